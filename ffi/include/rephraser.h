@@ -0,0 +1,52 @@
+/*
+ * C interface to Rephraser
+ *
+ * Link against librephraser_ffi (built by `cargo build -p rephraser-ffi
+ * --release`). Every function may be called from any thread; each call
+ * blocks until it finishes, so call them off the main thread of a GUI.
+ *
+ * Strings are UTF-8 and NUL-terminated. Strings returned by these
+ * functions, including error messages, belong to the caller and must be
+ * released with rephraser_string_free. On failure, functions return NULL
+ * and, if `error` is not NULL, store a message in *error.
+ */
+
+#ifndef REPHRASER_H
+#define REPHRASER_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A loaded configuration with its provider clients */
+typedef struct RephraserHandle RephraserHandle;
+
+/*
+ * Load the configuration at `config_path`, or ~/.rephraser/config.toml if
+ * it is NULL. Release the handle with rephraser_free.
+ */
+RephraserHandle *rephraser_new(const char *config_path, char **error);
+
+/* Release a handle from rephraser_new; NULL is ignored */
+void rephraser_free(RephraserHandle *handle);
+
+/* Run `action` on `text` and return the response */
+char *rephraser_rephrase(const RephraserHandle *handle,
+                         const char *action,
+                         const char *text,
+                         char **error);
+
+/* The configured actions, as a JSON array of action objects */
+char *rephraser_list_actions(const RephraserHandle *handle, char **error);
+
+/* The configuration in use, as a JSON object */
+char *rephraser_config(const RephraserHandle *handle, char **error);
+
+/* Release a string returned by these functions; NULL is ignored */
+void rephraser_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* REPHRASER_H */
@@ -0,0 +1,237 @@
+//! C interface to Rephraser
+//!
+//! Exposes the blocking library API to native apps, such as a SwiftUI menu
+//! bar app, which can then link the core instead of running the CLI. The
+//! functions are declared in `include/rephraser.h`, with a module map for
+//! Swift next to it.
+//!
+//! Failures are reported by returning a null pointer and storing a message
+//! in the `error` out-parameter. Panics are caught at the boundary and
+//! reported the same way.
+
+use rephraser::blocking::Rephraser;
+use rephraser::config::ConfigManager;
+use rephraser::{RephraserError, Result};
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+/// A loaded configuration with its provider clients
+pub struct RephraserHandle {
+    rephraser: Rephraser,
+}
+
+/// Load the configuration at `config_path`, or the default one if it is
+/// null
+///
+/// # Safety
+/// `config_path` must be null or a NUL-terminated string, and `error` null
+/// or valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn rephraser_new(
+    config_path: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut RephraserHandle {
+    let handle = guard(error, || {
+        let manager = if config_path.is_null() {
+            ConfigManager::new()?
+        } else {
+            ConfigManager::with_path(PathBuf::from(str_arg(config_path, "config_path")?))
+        };
+        let rephraser = Rephraser::from_config(manager.shared()?)?;
+        Ok(RephraserHandle { rephraser })
+    });
+    handle.map_or(ptr::null_mut(), |handle| Box::into_raw(Box::new(handle)))
+}
+
+/// Release a handle from [`rephraser_new`]
+///
+/// # Safety
+/// `handle` must be null or a handle from [`rephraser_new`] that has not
+/// been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn rephraser_free(handle: *mut RephraserHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Run `action` on `text` and return the response
+///
+/// # Safety
+/// `handle` must be a live handle from [`rephraser_new`], `action` and
+/// `text` NUL-terminated strings, and `error` null or valid for writing a
+/// pointer.
+#[no_mangle]
+pub unsafe extern "C" fn rephraser_rephrase(
+    handle: *const RephraserHandle,
+    action: *const c_char,
+    text: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    string_result(guard(error, || {
+        let handle = handle_arg(handle)?;
+        let action = str_arg(action, "action")?;
+        let text = str_arg(text, "text")?;
+        handle.rephraser.rephrase(action, text)
+    }))
+}
+
+/// The configured actions, as a JSON array
+///
+/// # Safety
+/// `handle` must be a live handle from [`rephraser_new`] and `error` null
+/// or valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn rephraser_list_actions(
+    handle: *const RephraserHandle,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    string_result(guard(error, || {
+        let actions = handle_arg(handle)?.rephraser.actions();
+        Ok(serde_json::to_string(actions)?)
+    }))
+}
+
+/// The configuration in use, as a JSON object
+///
+/// # Safety
+/// As for [`rephraser_list_actions`].
+#[no_mangle]
+pub unsafe extern "C" fn rephraser_config(
+    handle: *const RephraserHandle,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    string_result(guard(error, || {
+        let config = handle_arg(handle)?.rephraser.config();
+        Ok(serde_json::to_string(config)?)
+    }))
+}
+
+/// Release a string returned by these functions
+///
+/// # Safety
+/// `string` must be null or a string returned by these functions that has
+/// not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn rephraser_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Run `f`, storing its error or panic in `error`
+unsafe fn guard<T>(error: *mut *mut c_char, f: impl FnOnce() -> Result<T>) -> Option<T> {
+    let result = catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(RephraserError::Other(format!(
+            "Rephraser panicked: {}",
+            message
+        )))
+    });
+    match result {
+        Ok(value) => Some(value),
+        Err(e) => {
+            if !error.is_null() {
+                *error = into_c_string(e.to_string());
+            }
+            None
+        }
+    }
+}
+
+/// Hand a string over to the caller, or null on failure
+fn string_result(result: Option<String>) -> *mut c_char {
+    result.map_or(ptr::null_mut(), into_c_string)
+}
+
+/// Strings can't hold NUL bytes in C; responses never should either
+fn into_c_string(string: String) -> *mut c_char {
+    let string = string.replace('\0', "");
+    CString::new(string).unwrap_or_default().into_raw()
+}
+
+unsafe fn str_arg<'a>(string: *const c_char, name: &str) -> Result<&'a str> {
+    if string.is_null() {
+        return Err(RephraserError::Input(format!("{} is null", name)));
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|_| RephraserError::Input(format!("{} is not valid UTF-8", name)))
+}
+
+unsafe fn handle_arg<'a>(handle: *const RephraserHandle) -> Result<&'a RephraserHandle> {
+    handle
+        .as_ref()
+        .ok_or_else(|| RephraserError::Input("handle is null".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rephraser::config::Config;
+
+    unsafe fn take(string: *mut c_char) -> String {
+        assert!(!string.is_null());
+        let owned = CStr::from_ptr(string).to_str().unwrap().to_string();
+        rephraser_string_free(string);
+        owned
+    }
+
+    #[test]
+    fn test_rephrase_through_c() {
+        let mut config = Config::default();
+        config.llm.provider = "mock".to_string();
+        config.history.enabled = false;
+        config.usage.enabled = false;
+        config.daemon.enabled = false;
+        let path = std::env::temp_dir().join(format!("rephraser-ffi-{}.toml", std::process::id()));
+        std::fs::write(&path, toml::to_string(&config).unwrap()).unwrap();
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let mut error = ptr::null_mut();
+            let handle = rephraser_new(c_path.as_ptr(), &mut error);
+            assert!(!handle.is_null() && error.is_null());
+
+            let (action, text) = (
+                CString::new("polite").unwrap(),
+                CString::new("hello").unwrap(),
+            );
+            let response = take(rephraser_rephrase(
+                handle,
+                action.as_ptr(),
+                text.as_ptr(),
+                &mut error,
+            ));
+            assert!(!response.is_empty());
+
+            let actions: serde_json::Value =
+                serde_json::from_str(&take(rephraser_list_actions(handle, &mut error))).unwrap();
+            assert!(actions
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|a| a["name"] == "polite"));
+            let config: serde_json::Value =
+                serde_json::from_str(&take(rephraser_config(handle, &mut error))).unwrap();
+            assert_eq!(config["llm"]["provider"], "mock");
+
+            let missing = CString::new("nope").unwrap();
+            let response = rephraser_rephrase(handle, missing.as_ptr(), text.as_ptr(), &mut error);
+            assert!(response.is_null());
+            assert!(take(error).contains("nope"));
+
+            let response = rephraser_rephrase(handle, ptr::null(), text.as_ptr(), ptr::null_mut());
+            assert!(response.is_null());
+
+            rephraser_free(handle);
+        }
+        let _ = std::fs::remove_file(path);
+    }
+}
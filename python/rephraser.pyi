@@ -0,0 +1,19 @@
+"""Run Rephraser actions from Python"""
+
+import os
+from typing import Any, Dict, List, Optional, Union
+
+class RephraserError(Exception):
+    """Raised when loading the configuration or running an action fails"""
+
+class Rephraser:
+    """A loaded configuration with its provider clients"""
+
+    def __init__(self, config_path: Optional[Union[str, os.PathLike]] = None) -> None:
+        """Load the configuration at `config_path`, or the default one"""
+    def rephrase(self, action: str, text: str) -> str:
+        """Run `action` on `text` and return the response"""
+    def list_actions(self) -> List[Dict[str, Any]]:
+        """The configured actions"""
+    def config(self) -> Dict[str, Any]:
+        """The configuration in use"""
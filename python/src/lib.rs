@@ -0,0 +1,97 @@
+//! Python bindings to Rephraser
+//!
+//! Exposes the blocking library API as the `rephraser` Python module, so
+//! notebooks and scripts run the same configured actions as the CLI:
+//!
+//! ```python
+//! import rephraser
+//!
+//! r = rephraser.Rephraser()  # ~/.rephraser/config.toml
+//! print(r.rephrase("polite", "明日の会議、遅れます"))
+//! ```
+//!
+//! Built with maturin (`maturin develop` in this directory) rather than as
+//! part of the Cargo workspace, since it links against the Python it is
+//! installed into.
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use rephraser::blocking::Rephraser;
+use rephraser::config::ConfigManager;
+use serde::Serialize;
+use std::path::PathBuf;
+
+create_exception!(
+    rephraser,
+    RephraserError,
+    PyException,
+    "Raised when loading the configuration or running an action fails"
+);
+
+/// A loaded configuration with its provider clients
+#[pyclass(name = "Rephraser", module = "rephraser", frozen)]
+struct PyRephraser {
+    inner: Rephraser,
+}
+
+#[pymethods]
+impl PyRephraser {
+    /// Load the configuration at `config_path`, or the default one
+    #[new]
+    #[pyo3(signature = (config_path = None))]
+    fn new(config_path: Option<PathBuf>) -> PyResult<Self> {
+        let manager = match config_path {
+            Some(path) => ConfigManager::with_path(path),
+            None => ConfigManager::new().map_err(to_py_err)?,
+        };
+        let config = manager.shared().map_err(to_py_err)?;
+        let inner = Rephraser::from_config(config).map_err(to_py_err)?;
+        Ok(Self { inner })
+    }
+
+    /// Run `action` on `text` and return the response
+    ///
+    /// Other Python threads keep running while the provider answers.
+    fn rephrase(&self, py: Python<'_>, action: &str, text: &str) -> PyResult<String> {
+        py.allow_threads(|| self.inner.rephrase(action, text))
+            .map_err(to_py_err)
+    }
+
+    /// The configured actions, as a list of dicts
+    fn list_actions<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_python(py, self.inner.actions())
+    }
+
+    /// The configuration in use, as a dict
+    fn config<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_python(py, self.inner.config())
+    }
+
+    fn __repr__(&self) -> String {
+        let llm = &self.inner.config().llm;
+        format!(
+            "Rephraser(provider={:?}, model={:?})",
+            llm.provider, llm.model
+        )
+    }
+}
+
+/// Convert `value` to Python objects by way of JSON
+fn to_python<'py>(py: Python<'py>, value: &impl Serialize) -> PyResult<Bound<'py, PyAny>> {
+    let json =
+        serde_json::to_string(value).map_err(|e| to_py_err(rephraser::RephraserError::from(e)))?;
+    py.import_bound("json")?.call_method1("loads", (json,))
+}
+
+fn to_py_err(e: rephraser::RephraserError) -> PyErr {
+    RephraserError::new_err(e.to_string())
+}
+
+#[pymodule]
+#[pyo3(name = "rephraser")]
+fn module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyRephraser>()?;
+    m.add("RephraserError", m.py().get_type_bound::<RephraserError>())?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+"""Tests of the Python module, run with pytest after `maturin develop`"""
+
+import pathlib
+import re
+
+import pytest
+
+import rephraser
+
+EXAMPLE_CONFIG = pathlib.Path(__file__).parents[2] / "examples" / "example_config.toml"
+
+
+@pytest.fixture
+def config_path(tmp_path, monkeypatch):
+    # History and usage go to the home directory
+    monkeypatch.setenv("HOME", str(tmp_path))
+    config = re.sub(
+        r'^provider = ".*"$',
+        'provider = "mock"',
+        EXAMPLE_CONFIG.read_text(),
+        count=1,
+        flags=re.MULTILINE,
+    )
+    path = tmp_path / "config.toml"
+    path.write_text(config)
+    return path
+
+
+def test_rephrase(config_path):
+    r = rephraser.Rephraser(config_path)
+    assert "お元気でしょうか" in r.rephrase("polite", "hello")
+    assert repr(r) == 'Rephraser(provider="mock", model="gpt-4o-mini")'
+
+
+def test_list_actions_and_config(config_path):
+    r = rephraser.Rephraser(str(config_path))
+    assert "polite" in [action["name"] for action in r.list_actions()]
+    assert r.config()["llm"]["provider"] == "mock"
+
+
+def test_errors(config_path, tmp_path):
+    r = rephraser.Rephraser(config_path)
+    with pytest.raises(rephraser.RephraserError, match="nope"):
+        r.rephrase("nope", "hello")
+
+    (tmp_path / "broken.toml").write_text("[llm")
+    with pytest.raises(rephraser.RephraserError):
+        rephraser.Rephraser(tmp_path / "broken.toml")
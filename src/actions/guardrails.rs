@@ -0,0 +1,183 @@
+//! Response guardrails
+//!
+//! Checks LLM responses against an action's validation rules and retries
+//! with a corrective instruction when a rule is violated.
+
+use crate::config::ValidationRules;
+use crate::error::{RephraserError, Result};
+use crate::llm::{Completion, LlmClient, TokenUsage};
+use regex::Regex;
+use tracing::warn;
+
+impl ValidationRules {
+    /// Check a response against the rules
+    ///
+    /// # Returns
+    /// * `Ok(Vec<String>)` - Human-readable violations (empty when valid)
+    ///
+    /// # Errors
+    /// * If `must_match` is not a valid regular expression
+    pub fn violations(&self, response: &str) -> Result<Vec<String>> {
+        let mut violations = Vec::new();
+
+        if let Some(pattern) = &self.must_match {
+            let re = Regex::new(pattern).map_err(|e| {
+                RephraserError::Config(format!("Invalid must_match pattern '{}': {}", pattern, e))
+            })?;
+            if !re.is_match(response) {
+                violations.push(format!("the answer must match the pattern `{}`", pattern));
+            }
+        }
+
+        for phrase in &self.must_not_contain {
+            if response.contains(phrase.as_str()) {
+                violations.push(format!("the answer must not contain \"{}\"", phrase));
+            }
+        }
+
+        if let Some(max_words) = self.max_words {
+            let words = response.split_whitespace().count();
+            if words > max_words {
+                violations.push(format!(
+                    "the answer must be at most {} words (it was {})",
+                    max_words, words
+                ));
+            }
+        }
+
+        Ok(violations)
+    }
+}
+
+/// Complete a prompt, retrying until the response satisfies the rules
+///
+/// # Arguments
+/// * `client` - LLM client to use
+/// * `prompt` - Rendered prompt
+/// * `rules` - Validation rules, or `None` to skip validation
+///
+/// # Returns
+/// * `Ok(Completion)` - The accepted response, with usage summed over all attempts
+///
+/// # Errors
+/// * LLM errors
+/// * `RephraserError::Validation` if every attempt violates the rules
+pub async fn complete_validated(
+    client: &dyn LlmClient,
+    prompt: &str,
+    rules: Option<&ValidationRules>,
+) -> Result<Completion> {
+    let rules = match rules {
+        Some(rules) => rules,
+        None => return client.complete_with_usage(prompt).await,
+    };
+
+    let mut current_prompt = prompt.to_string();
+    let mut attempt = 0;
+    let mut usage = TokenUsage::default();
+
+    loop {
+        let completion = client.complete_with_usage(&current_prompt).await?;
+        usage += completion.usage;
+        let violations = rules.violations(&completion.text)?;
+
+        if violations.is_empty() {
+            return Ok(Completion {
+                usage,
+                ..completion
+            });
+        }
+
+        if attempt >= rules.max_retries {
+            return Err(RephraserError::Validation(violations.join("; ")));
+        }
+
+        attempt += 1;
+        warn!(
+            "Response violated guardrails ({}), retrying ({}/{})",
+            violations.join("; "),
+            attempt,
+            rules.max_retries
+        );
+        current_prompt = corrective_prompt(prompt, &violations);
+    }
+}
+
+/// Append a corrective instruction to the original prompt
+fn corrective_prompt(prompt: &str, violations: &[String]) -> String {
+    let mut corrected = prompt.to_string();
+    corrected.push_str("\n\nYour previous answer was rejected:\n");
+    for violation in violations {
+        corrected.push_str(&format!("- {}\n", violation));
+    }
+    corrected.push_str("Answer again, following these requirements.");
+    corrected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLlmClient;
+
+    #[test]
+    fn test_violations() {
+        let rules = ValidationRules {
+            must_match: Some("^Dear".to_string()),
+            must_not_contain: vec!["As an AI".to_string()],
+            max_words: Some(3),
+            max_retries: 0,
+        };
+
+        assert!(rules.violations("Dear Bob, thanks").unwrap().is_empty());
+        assert_eq!(rules.violations("As an AI, I cannot").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let rules = ValidationRules {
+            must_match: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(rules.violations("text").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_succeeds_after_correction() {
+        let mut client = MockLlmClient::new();
+        client.set_default_response("As an AI, here you go");
+        client.add_response("rejected", "Here you go");
+
+        let rules = ValidationRules {
+            must_not_contain: vec!["As an AI".to_string()],
+            max_retries: 1,
+            ..Default::default()
+        };
+
+        let result = complete_validated(&client, "prompt", Some(&rules))
+            .await
+            .unwrap();
+        assert_eq!(result.text, "Here you go");
+    }
+
+    #[test]
+    fn test_default_matches_empty_table() {
+        let parsed: ValidationRules = toml::from_str("").unwrap();
+        assert_eq!(parsed.max_retries, ValidationRules::default().max_retries);
+        assert_eq!(parsed.max_retries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_exhausted() {
+        let mut client = MockLlmClient::new();
+        client.set_default_response("As an AI, here you go");
+
+        let rules = ValidationRules {
+            must_not_contain: vec!["As an AI".to_string()],
+            max_retries: 2,
+            ..Default::default()
+        };
+
+        let result = complete_validated(&client, "prompt", Some(&rules)).await;
+        assert!(matches!(result, Err(RephraserError::Validation(_))));
+    }
+}
@@ -0,0 +1,166 @@
+//! Shell command hooks around actions
+//!
+//! A pre-hook receives the input on stdin and its output is transformed
+//! instead, e.g. to spell-check the text first. A post-hook receives the
+//! result on stdin, e.g. to publish it with `gh gist create`. An action's own
+//! hooks take precedence over the ones under `[hooks]`.
+
+use crate::config::Config;
+use crate::error::{RephraserError, Result};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Environment variable telling hooks which action is running
+pub const ACTION_ENV: &str = "REPHRASER_ACTION";
+
+/// Hooks that apply to one action
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hooks {
+    pub pre: Option<String>,
+    pub post: Option<String>,
+}
+
+impl Hooks {
+    /// Hooks of `action`, falling back to the global ones
+    pub fn for_action(config: &Config, action: &str) -> Self {
+        let action_config = config.actions.iter().find(|a| a.name == action);
+        let pick = |own: Option<&Option<String>>, global: &Option<String>| {
+            own.and_then(Option::clone).or_else(|| global.clone())
+        };
+        Self {
+            pre: pick(action_config.map(|a| &a.pre_hook), &config.hooks.pre_hook),
+            post: pick(action_config.map(|a| &a.post_hook), &config.hooks.post_hook),
+        }
+    }
+
+    /// Pass the input through the pre-hook, if any
+    ///
+    /// # Errors
+    /// * If the hook cannot be run or exits unsuccessfully
+    pub async fn before(&self, action: &str, input: String) -> Result<String> {
+        let Some(command) = &self.pre else {
+            return Ok(input);
+        };
+        debug!("Running pre-hook: {}", command);
+
+        let output = run(command, action, &input, Stdio::piped())
+            .await
+            .map_err(|e| RephraserError::Input(format!("Pre-hook failed: {}", e)))?;
+        String::from_utf8(output)
+            .map_err(|_| RephraserError::Input("Pre-hook output is not valid UTF-8".to_string()))
+    }
+
+    /// Pipe the result into the post-hook, if any
+    ///
+    /// The hook's output goes to stderr so it never mixes with results
+    /// printed on stdout. Failures are logged but don't fail the action,
+    /// whose result has already been produced.
+    pub async fn after(&self, action: &str, result: &str) {
+        let Some(command) = &self.post else {
+            return;
+        };
+        debug!("Running post-hook: {}", command);
+
+        if let Err(e) = run(command, action, result, Stdio::from(std::io::stderr())).await {
+            warn!("Post-hook failed: {}", e);
+        }
+    }
+}
+
+/// Run `command` with a shell, writing `input` to its stdin
+///
+/// Returns what the command printed, if `stdout` is piped. The hook runs
+/// as a child process the runtime waits on, so a slow hook doesn't hold up
+/// other requests.
+async fn run(command: &str, action: &str, input: &str, stdout: Stdio) -> std::io::Result<Vec<u8>> {
+    let mut child = shell(command)
+        .env(ACTION_ENV, action)
+        .stdin(Stdio::piped())
+        .stdout(stdout)
+        .spawn()?;
+
+    // Write while waiting so a hook that prints before it has read all of
+    // its input cannot deadlock; stdin is closed once written
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let write = async move {
+        // A hook may exit without reading its input
+        let _ = stdin.write_all(input.as_bytes()).await;
+    };
+    let ((), output) = tokio::join!(write, child.wait_with_output());
+    let output = output?;
+
+    if !output.status.success() {
+        return Err(std::io::Error::other(format!(
+            "`{}` exited with {}",
+            command, output.status
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_hooks_override_global() {
+        let mut config = Config::default();
+        config.hooks.pre_hook = Some("global-pre".to_string());
+        config.hooks.post_hook = Some("global-post".to_string());
+        config.actions[0].pre_hook = Some("own-pre".to_string());
+        let action = config.actions[0].name.clone();
+
+        let hooks = Hooks::for_action(&config, &action);
+        assert_eq!(hooks.pre.as_deref(), Some("own-pre"));
+        assert_eq!(hooks.post.as_deref(), Some("global-post"));
+    }
+
+    #[tokio::test]
+    async fn test_pre_hook_replaces_input() {
+        let hooks = Hooks {
+            pre: Some(r#"tr a-z A-Z; printf ' (%s)' "$REPHRASER_ACTION""#.to_string()),
+            post: None,
+        };
+        assert_eq!(
+            hooks.before("polite", "hello".to_string()).await.unwrap(),
+            "HELLO (polite)"
+        );
+
+        let failing = Hooks {
+            pre: Some("exit 3".to_string()),
+            post: None,
+        };
+        assert!(failing.before("polite", "hello".to_string()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hooks_do_not_block_the_runtime() {
+        let hooks = Hooks {
+            pre: Some("sleep 1; cat".to_string()),
+            post: None,
+        };
+        // On this single-threaded runtime, a blocking hook would keep the
+        // timer from firing until it exits
+        tokio::select! {
+            biased;
+            _ = tokio::time::sleep(std::time::Duration::from_millis(50)) => {}
+            _ = hooks.before("polite", "hello".to_string()) => panic!("hook blocked"),
+        }
+    }
+}
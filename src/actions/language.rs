@@ -0,0 +1,218 @@
+//! Language detection for the `{language}` template variable
+//!
+//! Templates can tell the model which language to answer in ("Reply in
+//! {language}") without an extra LLM call to find out. Detection is local
+//! and cheap: the writing system decides most languages, and for text in
+//! Latin script the most frequent common words decide between the
+//! languages listed in [`LATIN_WORDS`].
+
+/// Value of `{language}` when the language can't be told
+pub const UNKNOWN: &str = "the same language as the text";
+
+/// Common short words of languages written in Latin script
+const LATIN_WORDS: &[(&str, &[&str])] = &[
+    (
+        "English",
+        &[
+            "the", "and", "is", "are", "of", "to", "in", "that", "it", "you", "for", "this",
+            "with", "was", "have", "not",
+        ],
+    ),
+    (
+        "French",
+        &[
+            "le", "la", "les", "et", "est", "des", "une", "un", "du", "que", "pour", "dans", "pas",
+            "je", "vous", "nous",
+        ],
+    ),
+    (
+        "German",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "ich", "sie", "mit", "zu",
+            "den", "auf", "wir", "auch",
+        ],
+    ),
+    (
+        "Spanish",
+        &[
+            "el", "la", "los", "las", "y", "es", "que", "de", "en", "un", "una", "por", "para",
+            "con", "no", "está",
+        ],
+    ),
+    (
+        "Portuguese",
+        &[
+            "o", "os", "as", "e", "é", "que", "de", "em", "um", "uma", "para", "com", "não",
+            "você", "do", "da",
+        ],
+    ),
+    (
+        "Italian",
+        &[
+            "il", "lo", "gli", "e", "è", "che", "di", "un", "una", "per", "con", "non", "sono",
+            "della", "mi", "ho",
+        ],
+    ),
+    (
+        "Dutch",
+        &[
+            "de", "het", "een", "en", "is", "van", "ik", "je", "niet", "dat", "op", "te", "met",
+            "zijn", "voor", "wij",
+        ],
+    ),
+];
+
+/// Writing systems told apart by [`detect`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+    Hangul,
+    Kana,
+    Han,
+}
+
+/// The language `text` is written in, as an English name such as
+/// "Japanese", or `None` if it can't be told
+pub fn detect(text: &str) -> Option<&'static str> {
+    let mut counts = [0usize; 10];
+    for c in text.chars() {
+        if let Some(script) = script(c) {
+            counts[script as usize] += 1;
+        }
+    }
+
+    // Japanese mixes kana with kanji, which alone would mean Chinese
+    if counts[Script::Kana as usize] > 0 {
+        counts[Script::Kana as usize] += counts[Script::Han as usize];
+        counts[Script::Han as usize] = 0;
+    }
+
+    let (index, &count) = counts.iter().enumerate().max_by_key(|&(_, count)| *count)?;
+    if count == 0 {
+        return None;
+    }
+    let language = match SCRIPTS[index] {
+        Script::Latin => return latin_language(text),
+        Script::Cyrillic if text.contains(['і', 'ї', 'є', 'ґ']) => "Ukrainian",
+        Script::Cyrillic => "Russian",
+        Script::Greek => "Greek",
+        Script::Arabic => "Arabic",
+        Script::Hebrew => "Hebrew",
+        Script::Devanagari => "Hindi",
+        Script::Thai => "Thai",
+        Script::Hangul => "Korean",
+        Script::Kana => "Japanese",
+        Script::Han => "Chinese",
+    };
+    Some(language)
+}
+
+/// [`Script`]s in the order of their discriminants
+const SCRIPTS: [Script; 10] = [
+    Script::Latin,
+    Script::Cyrillic,
+    Script::Greek,
+    Script::Arabic,
+    Script::Hebrew,
+    Script::Devanagari,
+    Script::Thai,
+    Script::Hangul,
+    Script::Kana,
+    Script::Han,
+];
+
+fn script(c: char) -> Option<Script> {
+    let script = match c {
+        'a'..='z' | 'A'..='Z' | '\u{C0}'..='\u{24F}' => Script::Latin,
+        '\u{400}'..='\u{4FF}' => Script::Cyrillic,
+        '\u{370}'..='\u{3FF}' => Script::Greek,
+        '\u{600}'..='\u{6FF}' => Script::Arabic,
+        '\u{590}'..='\u{5FF}' => Script::Hebrew,
+        '\u{900}'..='\u{97F}' => Script::Devanagari,
+        '\u{E00}'..='\u{E7F}' => Script::Thai,
+        '\u{AC00}'..='\u{D7AF}' | '\u{1100}'..='\u{11FF}' => Script::Hangul,
+        '\u{3040}'..='\u{30FF}' | '\u{FF66}'..='\u{FF9F}' => Script::Kana,
+        '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' => Script::Han,
+        _ => return None,
+    };
+    Some(script)
+}
+
+/// The language of Latin-script text with the most common words in it
+///
+/// Many common words are shared (French and Dutch "je", Spanish and
+/// Portuguese "que"), so ties go to the language listed first in
+/// [`LATIN_WORDS`].
+fn latin_language(text: &str) -> Option<&'static str> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    // `max_by_key` keeps the last of equal maxima, so go through the list
+    // backwards
+    let (language, hits) = LATIN_WORDS
+        .iter()
+        .rev()
+        .map(|(language, common)| {
+            let hits = words
+                .iter()
+                .filter(|word| common.contains(&word.as_str()))
+                .count();
+            (*language, hits)
+        })
+        .max_by_key(|&(_, hits)| hits)?;
+    (hits > 0).then_some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_by_script() {
+        assert_eq!(detect("明日の会議に遅れます"), Some("Japanese"));
+        assert_eq!(detect("我明天开会会迟到"), Some("Chinese"));
+        assert_eq!(detect("내일 회의에 늦습니다"), Some("Korean"));
+        assert_eq!(detect("Я опоздаю на встречу"), Some("Russian"));
+        assert_eq!(detect("Я запізнюся на зустріч, і все"), Some("Ukrainian"));
+        // Latin words in Japanese text don't make it English
+        assert_eq!(detect("明日のmeetingに遅れます"), Some("Japanese"));
+    }
+
+    #[test]
+    fn test_detect_latin_languages() {
+        assert_eq!(detect("I will be late for the meeting"), Some("English"));
+        assert_eq!(detect("Je serai en retard pour la réunion"), Some("French"));
+        assert_eq!(
+            detect("Ich komme zu spät, das tut mir leid"),
+            Some("German")
+        );
+        assert_eq!(
+            detect("Llegaré tarde a la reunión por el tráfico"),
+            Some("Spanish")
+        );
+    }
+
+    #[test]
+    fn test_ties_go_to_the_first_listed() {
+        // "je" is both French and Dutch
+        assert_eq!(detect("Je t'aime"), Some("French"));
+        // "que" and "de" are both Spanish and Portuguese
+        assert_eq!(detect("que de"), Some("Spanish"));
+    }
+
+    #[test]
+    fn test_undetectable() {
+        assert_eq!(detect(""), None);
+        assert_eq!(detect("1234 !?"), None);
+        assert_eq!(detect("Xyzzy"), None);
+    }
+}
@@ -1,7 +1,20 @@
 //! Action module
 
+pub mod guardrails;
+#[cfg(feature = "native")]
+pub mod hooks;
+pub mod language;
+pub mod proofread;
+#[cfg(feature = "native")]
+pub mod registry;
 pub mod resolver;
+#[cfg(feature = "native")]
+pub mod selector;
 pub mod template;
 
+#[cfg(feature = "native")]
+pub use registry::ActionRegistry;
 pub use resolver::ActionResolver;
+#[cfg(feature = "native")]
+pub use selector::ActionSelector;
 pub use template::TemplateEngine;
@@ -0,0 +1,234 @@
+//! The built-in proofreading mode
+//!
+//! Rather than having the model rewrite the whole text, `rephraser
+//! proofread` asks it for a list of corrections in JSON, each with the
+//! position of the mistake, the original and suggested wording and the
+//! reason, and shows them as a report or an inline diff. The text itself
+//! is left as written.
+
+use crate::actions::language;
+use crate::config::ValidationRules;
+use crate::error::{RephraserError, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// One mistake and how to fix it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Correction {
+    /// Where the mistake starts, in characters from the start of the text
+    pub position: usize,
+    /// The mistaken text, as written
+    pub original: String,
+    /// What to write instead
+    pub suggestion: String,
+    /// Why the original is wrong
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    corrections: Vec<Correction>,
+}
+
+/// Prompt asking for the corrections to `text`, explained in its language
+pub fn prompt(text: &str) -> String {
+    let language = language::detect(text).unwrap_or(language::UNKNOWN);
+    format!(
+        r#"You proofread text. Find the spelling, grammar, punctuation and word choice mistakes in the text below. Do not rewrite passages that are correct or change the style.
+
+Answer with JSON only, in this form:
+{{"corrections": [{{"position": <index of the first character of the mistake, counting characters from 0>, "original": "<the mistaken text, exactly as written>", "suggestion": "<the corrected text>", "reason": "<a short explanation in {language}>"}}]}}
+
+Answer {{"corrections": []}} if there are no mistakes.
+
+Text:
+{text}"#
+    )
+}
+
+/// Guardrails retrying responses that aren't a list of corrections
+pub fn rules() -> ValidationRules {
+    ValidationRules {
+        must_match: Some(r#""corrections"\s*:"#.to_string()),
+        ..ValidationRules::default()
+    }
+}
+
+/// Read the corrections in a response to [`prompt`]
+///
+/// Models often miscount, so each correction is moved to the occurrence of
+/// its original wording nearest to the position given. Corrections whose
+/// wording isn't in the text, that change nothing or that overlap an
+/// earlier one are dropped.
+///
+/// # Errors
+/// * [`RephraserError::Validation`] if the response holds no corrections
+///   in JSON
+pub fn parse(response: &str, text: &str) -> Result<Vec<Correction>> {
+    // Models like to wrap JSON in a code block or a sentence
+    let json = match (response.find('{'), response.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &response[start..=end],
+        _ => response,
+    };
+    let response: Response = serde_json::from_str(json).map_err(|e| {
+        RephraserError::Validation(format!("The corrections are not valid JSON: {}", e))
+    })?;
+
+    let mut corrections: Vec<Correction> = response
+        .corrections
+        .into_iter()
+        .filter(|c| !c.original.is_empty() && c.original != c.suggestion)
+        .filter_map(|mut correction| {
+            let Some(position) = locate(text, &correction.original, correction.position) else {
+                debug!(
+                    "Dropping correction of '{}', which is not in the text",
+                    correction.original
+                );
+                return None;
+            };
+            correction.position = position;
+            Some(correction)
+        })
+        .collect();
+    corrections.sort_by_key(|c| c.position);
+
+    let mut end = 0;
+    corrections.retain(|c| {
+        let keep = c.position >= end;
+        if keep {
+            end = c.position + c.original.chars().count();
+        }
+        keep
+    });
+    Ok(corrections)
+}
+
+/// Character position of the occurrence of `original` nearest to `near`
+fn locate(text: &str, original: &str, near: usize) -> Option<usize> {
+    text.match_indices(original)
+        .map(|(byte, _)| text[..byte].chars().count())
+        .min_by_key(|&position| position.abs_diff(near))
+}
+
+/// Byte offset of a character position in `text`
+fn byte_offset(text: &str, position: usize) -> usize {
+    text.char_indices()
+        .nth(position)
+        .map_or(text.len(), |(byte, _)| byte)
+}
+
+/// Rebuild `text`, writing each correction with `write`
+fn rewrite(
+    text: &str,
+    corrections: &[Correction],
+    write: impl Fn(&Correction) -> String,
+) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut copied = 0;
+    for correction in corrections {
+        let start = byte_offset(text, correction.position);
+        result.push_str(&text[copied..start]);
+        result.push_str(&write(correction));
+        copied = start + correction.original.len();
+    }
+    result.push_str(&text[copied..]);
+    result
+}
+
+/// `text` with the corrections applied
+pub fn apply(text: &str, corrections: &[Correction]) -> String {
+    rewrite(text, corrections, |c| c.suggestion.clone())
+}
+
+/// `text` with each correction shown inline, in the `[-original-]{+suggestion+}`
+/// notation of word diffs
+pub fn diff(text: &str, corrections: &[Correction]) -> String {
+    rewrite(text, corrections, |c| {
+        format!("[-{}-]{{+{}+}}", c.original, c.suggestion)
+    })
+}
+
+/// A list of the corrections with their line, column and reason, followed
+/// by the corrected text
+pub fn report(text: &str, corrections: &[Correction]) -> String {
+    if corrections.is_empty() {
+        return "No corrections needed.".to_string();
+    }
+
+    let mut report = String::new();
+    for correction in corrections {
+        let before = &text[..byte_offset(text, correction.position)];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        report.push_str(&format!(
+            "{}:{}: {} → {}",
+            line, column, correction.original, correction.suggestion
+        ));
+        if !correction.reason.is_empty() {
+            report.push_str(&format!(" ({})", correction.reason));
+        }
+        report.push('\n');
+    }
+    report.push_str("\nCorrected text:\n");
+    report.push_str(&apply(text, corrections));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "I recieve teh mail.\nTeh end";
+
+    fn corrections() -> Vec<Correction> {
+        let response = r#"Here you go:
+```json
+{"corrections": [
+  {"position": 14, "original": "teh", "suggestion": "the", "reason": "Misspelling"},
+  {"position": 2, "original": "recieve", "suggestion": "receive", "reason": "i before e"},
+  {"position": 3, "original": "ecieve", "suggestion": "eceive"},
+  {"position": 0, "original": "mail", "suggestion": "mail"},
+  {"position": 5, "original": "missing", "suggestion": "gone"}
+]}
+```"#;
+        parse(response, TEXT).unwrap()
+    }
+
+    #[test]
+    fn test_parse_relocates_and_drops() {
+        let corrections = corrections();
+        // "teh" is moved from the miscounted 14 to 10; the overlapping,
+        // unchanged and missing corrections are dropped
+        let found: Vec<_> = corrections
+            .iter()
+            .map(|c| (c.position, c.original.as_str()))
+            .collect();
+        assert_eq!(found, [(2, "recieve"), (10, "teh")]);
+
+        assert!(parse("Sorry, I can't.", TEXT).is_err());
+        assert_eq!(parse(r#"{"corrections": []}"#, TEXT).unwrap(), []);
+    }
+
+    #[test]
+    fn test_render() {
+        let corrections = corrections();
+        assert_eq!(apply(TEXT, &corrections), "I receive the mail.\nTeh end");
+        assert_eq!(
+            diff(TEXT, &corrections),
+            "I [-recieve-]{+receive+} [-teh-]{+the+} mail.\nTeh end"
+        );
+
+        let report = report(TEXT, &corrections);
+        assert!(report.starts_with("1:3: recieve → receive (i before e)\n1:11: teh → the"));
+        assert!(report.ends_with("Corrected text:\nI receive the mail.\nTeh end"));
+    }
+
+    #[test]
+    fn test_prompt_in_text_language() {
+        let prompt = prompt("明日の会議に遅れます");
+        assert!(prompt.contains("explanation in Japanese"));
+        assert!(prompt.ends_with("Text:\n明日の会議に遅れます"));
+        assert!(rules().violations(&prompt).unwrap().is_empty());
+    }
+}
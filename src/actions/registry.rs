@@ -0,0 +1,258 @@
+//! Remote action registry
+//!
+//! Fetches a JSON index of community prompt actions, caches it locally and
+//! checks each action's checksum before installation. The checksum comes
+//! from the same index as the action, so it catches corrupted or truncated
+//! downloads but does not prove who published the action; trust rests on
+//! the registry URL and HTTPS.
+
+use crate::config::{ActionConfig, RegistryConfig};
+use crate::error::{RephraserError, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+/// Registry index document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    pub actions: Vec<RegistryEntry>,
+}
+
+/// A single action published in the registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub name: String,
+    pub display_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub prompt_template: String,
+
+    /// Hex-encoded SHA-256 of `prompt_template`
+    pub sha256: String,
+}
+
+impl RegistryEntry {
+    /// Check the prompt template against the checksum in the index
+    ///
+    /// This detects corruption, not tampering: whoever can change the
+    /// template in the index can change its checksum too.
+    ///
+    /// # Errors
+    /// * If the checksum does not match
+    pub fn verify(&self) -> Result<()> {
+        let actual = sha256_hex(&self.prompt_template);
+        if !actual.eq_ignore_ascii_case(self.sha256.trim()) {
+            return Err(RephraserError::Registry(format!(
+                "Checksum mismatch for action '{}' (expected {}, got {})",
+                self.name, self.sha256, actual
+            )));
+        }
+        Ok(())
+    }
+
+    /// Convert into an action configuration
+    pub fn into_action(self) -> ActionConfig {
+        ActionConfig {
+            name: self.name,
+            display_name: self.display_name,
+            description: self.description,
+            tags: Vec::new(),
+            prompt_template: self.prompt_template,
+            provider: None,
+            model: None,
+            validate: None,
+            privacy: None,
+            pre_hook: None,
+            post_hook: None,
+            script: None,
+        }
+    }
+}
+
+/// Action registry client
+pub struct ActionRegistry {
+    client: Client,
+    url: String,
+    cache_path: PathBuf,
+    cache_ttl: Duration,
+}
+
+impl ActionRegistry {
+    /// Create a new registry client
+    ///
+    /// # Arguments
+    /// * `config` - Registry configuration
+    /// * `cache_dir` - Directory where the index is cached
+    pub fn new(config: &RegistryConfig, cache_dir: PathBuf) -> Self {
+        Self {
+            client: Client::new(),
+            url: config.url.clone(),
+            cache_path: cache_dir.join("registry.json"),
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+        }
+    }
+
+    /// Get the registry index
+    ///
+    /// Uses the cached copy while it is fresh. When the remote fetch fails,
+    /// a stale cached copy is used instead.
+    pub async fn index(&self) -> Result<RegistryIndex> {
+        if self.cache_is_fresh() {
+            if let Ok(index) = self.read_cache() {
+                debug!("Using cached registry index");
+                return Ok(index);
+            }
+        }
+
+        debug!("Fetching registry index from {}", self.url);
+        match self.fetch().await {
+            Ok(index) => {
+                // A cache write failure should not prevent browsing
+                let _ = self.write_cache(&index);
+                Ok(index)
+            }
+            Err(e) => {
+                warn!("Registry fetch failed ({}), falling back to cache", e);
+                self.read_cache().map_err(|_| e)
+            }
+        }
+    }
+
+    /// Find an action by name, with its checksum checked
+    ///
+    /// # Errors
+    /// * If the action is not in the registry
+    /// * If the checksum does not match
+    pub async fn find(&self, name: &str) -> Result<RegistryEntry> {
+        let index = self.index().await?;
+        let entry = index
+            .actions
+            .into_iter()
+            .find(|a| a.name == name)
+            .ok_or_else(|| {
+                RephraserError::Registry(format!("Action '{}' not found in registry", name))
+            })?;
+
+        entry.verify()?;
+        Ok(entry)
+    }
+
+    /// Fetch the index from the remote URL
+    async fn fetch(&self) -> Result<RegistryIndex> {
+        let response = self.client.get(&self.url).send().await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(RephraserError::Registry(format!(
+                "Failed to fetch registry index ({})",
+                status
+            )));
+        }
+
+        let body = response.text().await?;
+        serde_json::from_str(&body)
+            .map_err(|e| RephraserError::Registry(format!("Invalid registry index: {}", e)))
+    }
+
+    fn cache_is_fresh(&self) -> bool {
+        fs::metadata(&self.cache_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < self.cache_ttl)
+    }
+
+    fn read_cache(&self) -> Result<RegistryIndex> {
+        let content = fs::read_to_string(&self.cache_path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    fn write_cache(&self, index: &RegistryIndex) -> Result<()> {
+        if let Some(parent) = self.cache_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.cache_path, serde_json::to_string_pretty(index)?)?;
+        Ok(())
+    }
+}
+
+/// Compute the hex-encoded SHA-256 digest of a string
+pub fn sha256_hex(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(template: &str, sha256: String) -> RegistryEntry {
+        RegistryEntry {
+            name: "translate".to_string(),
+            display_name: "翻訳".to_string(),
+            description: None,
+            prompt_template: template.to_string(),
+            sha256,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rephraser-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_verify_checksum() {
+        let template = "Translate: {text}";
+        assert!(entry(template, sha256_hex(template)).verify().is_ok());
+        assert!(entry(template, sha256_hex("tampered")).verify().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_and_cache() {
+        let template = "Translate: {text}";
+        let index = RegistryIndex {
+            actions: vec![entry(template, sha256_hex(template))],
+        };
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/index.json")
+            .with_status(200)
+            .with_body(serde_json::to_string(&index).unwrap())
+            .expect(1)
+            .create_async()
+            .await;
+
+        let config = RegistryConfig {
+            url: format!("{}/index.json", server.url()),
+            cache_ttl_secs: 3600,
+        };
+        let cache_dir = temp_dir("registry");
+        let registry = ActionRegistry::new(&config, cache_dir.clone());
+
+        let found = registry.find("translate").await.unwrap();
+        assert_eq!(found.prompt_template, template);
+
+        // Second lookup is served from the cache
+        assert!(registry.find("missing").await.is_err());
+        mock.assert_async().await;
+
+        let _ = fs::remove_dir_all(cache_dir);
+    }
+}
@@ -1,14 +1,17 @@
 //! Action resolution
 
+use crate::actions::language;
 use crate::actions::template::TemplateEngine;
-use crate::config::{ActionConfig, Config};
+use crate::config::{ActionConfig, Config, TonesConfig};
 use crate::error::{RephraserError, Result};
+use tracing::{debug, trace};
 
 /// Action resolver
 ///
 /// Resolves action names to prompt templates and performs variable substitution
 pub struct ActionResolver {
     actions: Vec<ActionConfig>,
+    tones: TonesConfig,
 }
 
 impl ActionResolver {
@@ -16,6 +19,7 @@ impl ActionResolver {
     pub fn new(config: &Config) -> Self {
         Self {
             actions: config.actions.clone(),
+            tones: config.tones.clone(),
         }
     }
 
@@ -31,6 +35,9 @@ impl ActionResolver {
 
     /// Resolve an action and render its prompt with the given text
     ///
+    /// With a tone selected, its instruction is put where the template has
+    /// `{tone}`, or before the prompt if it has none.
+    ///
     /// # Arguments
     /// * `action_name` - Name of the action to resolve
     /// * `text` - Text to process
@@ -40,6 +47,7 @@ impl ActionResolver {
     ///
     /// # Errors
     /// * If the action is not found
+    /// * If the selected tone is not configured
     /// * If template rendering fails
     pub fn resolve(&self, action_name: &str, text: &str) -> Result<String> {
         let action = self
@@ -48,8 +56,46 @@ impl ActionResolver {
 
         let mut engine = TemplateEngine::new();
         engine.set("text", text);
+        if action.prompt_template.contains("{language}") {
+            let language = language::detect(text).unwrap_or(language::UNKNOWN);
+            debug!("Detected input language: {}", language);
+            engine.set("language", language);
+        }
+        let tone = self.tone()?;
+        let tone_variable = action.prompt_template.contains("{tone}");
+        if tone_variable {
+            engine.set("tone", tone.unwrap_or_default());
+        }
+
+        let mut prompt = engine.render(&action.prompt_template)?;
+        if let (Some(tone), false) = (tone, tone_variable) {
+            prompt = format!("{}\n\n{}", tone, prompt);
+        }
+        debug!(
+            "Resolved action '{}': {} input chars, {} prompt chars",
+            action_name,
+            text.chars().count(),
+            prompt.chars().count()
+        );
+        trace!("Prompt:\n{}", prompt);
+
+        Ok(prompt)
+    }
 
-        engine.render(&action.prompt_template)
+    /// Instruction of the selected tone, if any
+    fn tone(&self) -> Result<Option<&str>> {
+        let Some(name) = &self.tones.default else {
+            return Ok(None);
+        };
+        let instruction = self.tones.instruction(name).ok_or_else(|| {
+            RephraserError::Config(format!(
+                "Unknown tone '{}' (available: {})",
+                name,
+                self.tones.names().join(", ")
+            ))
+        })?;
+        debug!("Using tone '{}'", name);
+        Ok(Some(instruction))
     }
 }
 
@@ -68,6 +114,49 @@ mod tests {
         assert!(prompt.contains("丁寧な表現"));
     }
 
+    #[test]
+    fn test_language_variable() {
+        let mut config = Config::default();
+        config.actions[0].prompt_template = "Reply in {language}:\n{text}".to_string();
+        let resolver = ActionResolver::new(&config);
+        let name = &config.actions[0].name;
+
+        let prompt = resolver.resolve(name, "明日の会議に遅れます").unwrap();
+        assert!(prompt.starts_with("Reply in Japanese:"));
+        let prompt = resolver.resolve(name, "42").unwrap();
+        assert!(prompt.starts_with("Reply in the same language as the text:"));
+    }
+
+    #[test]
+    fn test_tone() {
+        let mut config = Config::default();
+        config.tones.default = Some("formal".to_string());
+        config
+            .tones
+            .presets
+            .insert("pirate".to_string(), "Talk like a pirate.".to_string());
+        let resolver = ActionResolver::new(&config);
+        let prompt = resolver.resolve("polite", "Hello").unwrap();
+        assert!(prompt.starts_with("Use a formal, professional tone.\n\n"));
+        assert!(prompt.contains("Hello"));
+
+        config.tones.default = Some("pirate".to_string());
+        config.actions[0].prompt_template = "{tone} Rewrite:\n{text}".to_string();
+        let resolver = ActionResolver::new(&config);
+        let name = &config.actions[0].name;
+        let prompt = resolver.resolve(name, "Hello").unwrap();
+        assert_eq!(prompt, "Talk like a pirate. Rewrite:\nHello");
+
+        config.tones.default = None;
+        let resolver = ActionResolver::new(&config);
+        assert_eq!(resolver.resolve(name, "Hello").unwrap(), " Rewrite:\nHello");
+
+        config.tones.default = Some("sarcastic".to_string());
+        let resolver = ActionResolver::new(&config);
+        let err = resolver.resolve(name, "Hello").unwrap_err().to_string();
+        assert!(err.contains("available: assertive, casual, formal, friendly, pirate"));
+    }
+
     #[test]
     fn test_action_not_found() {
         let config = Config::default();
@@ -0,0 +1,120 @@
+//! Automatic action selection
+
+use crate::config::ActionConfig;
+use crate::error::{RephraserError, Result};
+
+/// Action selector
+///
+/// Builds a classification prompt from the available actions and maps the
+/// LLM's answer back to one of them
+pub struct ActionSelector<'a> {
+    actions: &'a [ActionConfig],
+}
+
+impl<'a> ActionSelector<'a> {
+    /// Create a new selector over the given actions
+    pub fn new(actions: &'a [ActionConfig]) -> Self {
+        Self { actions }
+    }
+
+    /// Build the prompt asking the LLM to pick the most appropriate action
+    ///
+    /// # Errors
+    /// * If there are no actions to choose from
+    pub fn build_prompt(&self, text: &str) -> Result<String> {
+        if self.actions.is_empty() {
+            return Err(RephraserError::Config(
+                "No actions configured to choose from".to_string(),
+            ));
+        }
+
+        let mut prompt = String::from(
+            "Choose the single most appropriate action for the text below.\n\
+             Answer with the action name only, exactly as written.\n\nActions:\n",
+        );
+
+        for action in self.actions {
+            let description = action
+                .description
+                .as_deref()
+                .unwrap_or(&action.display_name);
+            prompt.push_str(&format!("- {}: {}\n", action.name, description));
+        }
+
+        prompt.push_str(&format!("\nText:\n{}\n\nAction:", text));
+
+        Ok(prompt)
+    }
+
+    /// Map the LLM's answer to an action
+    ///
+    /// Accepts an exact name (ignoring case, quotes and trailing punctuation)
+    /// and falls back to the first action name mentioned in the answer.
+    ///
+    /// # Errors
+    /// * If the answer does not name any known action
+    pub fn parse_choice(&self, answer: &str) -> Result<&'a ActionConfig> {
+        let cleaned = answer
+            .trim()
+            .trim_matches(|c: char| c == '"' || c == '\'' || c == '`' || c == '.')
+            .to_lowercase();
+
+        if let Some(action) = self
+            .actions
+            .iter()
+            .find(|a| a.name.to_lowercase() == cleaned)
+        {
+            return Ok(action);
+        }
+
+        self.actions
+            .iter()
+            .filter_map(|a| cleaned.find(&a.name.to_lowercase()).map(|pos| (pos, a)))
+            .min_by_key(|(pos, _)| *pos)
+            .map(|(_, a)| a)
+            .ok_or_else(|| RephraserError::ActionNotFound(answer.trim().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_build_prompt_lists_actions() {
+        let config = Config::default();
+        let selector = ActionSelector::new(&config.actions);
+
+        let prompt = selector.build_prompt("Hello").unwrap();
+        assert!(prompt.contains("- polite:"));
+        assert!(prompt.contains("- summarize:"));
+        assert!(prompt.contains("Hello"));
+    }
+
+    #[test]
+    fn test_build_prompt_without_actions() {
+        let selector = ActionSelector::new(&[]);
+        assert!(selector.build_prompt("Hello").is_err());
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        let config = Config::default();
+        let selector = ActionSelector::new(&config.actions);
+
+        assert_eq!(selector.parse_choice("polite").unwrap().name, "polite");
+        assert_eq!(
+            selector.parse_choice(" `Summarize`.\n").unwrap().name,
+            "summarize"
+        );
+        assert_eq!(
+            selector
+                .parse_choice("I would pick organize here")
+                .unwrap()
+                .name,
+            "organize"
+        );
+        assert!(selector.parse_choice("translate").is_err());
+    }
+}
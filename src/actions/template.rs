@@ -1,16 +1,27 @@
 //! Prompt template engine
 
 use crate::error::{RephraserError, Result};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// Simple template engine for prompt templates
 ///
-/// Supports variable substitution like {text}, {language}, etc.
-pub struct TemplateEngine {
-    variables: HashMap<String, String>,
+/// Supports variable substitution like {text}, {language}, etc. Values may
+/// be borrowed, so a large input set as {text} is copied only once, into
+/// the rendered result.
+pub struct TemplateEngine<'a> {
+    variables: HashMap<Cow<'a, str>, Cow<'a, str>>,
 }
 
-impl TemplateEngine {
+/// A piece of a parsed template
+enum Piece<'t> {
+    /// Text copied as-is
+    Literal(&'t str),
+    /// A `{name}` placeholder, with the placeholder text itself
+    Variable { name: &'t str, raw: &'t str },
+}
+
+impl<'a> TemplateEngine<'a> {
     /// Create a new template engine
     pub fn new() -> Self {
         Self {
@@ -19,13 +30,24 @@ impl TemplateEngine {
     }
 
     /// Set a variable value
-    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+    ///
+    /// Pass a `&str` to borrow the value instead of copying it, or a
+    /// `String` to hand it over.
+    pub fn set(
+        &mut self,
+        key: impl Into<Cow<'a, str>>,
+        value: impl Into<Cow<'a, str>>,
+    ) -> &mut Self {
         self.variables.insert(key.into(), value.into());
         self
     }
 
     /// Render a template with the current variables
     ///
+    /// The template is parsed in a single pass, so substituted values are
+    /// never scanned for placeholders themselves. The result is written into
+    /// a buffer sized up front, since values can be several megabytes.
+    ///
     /// # Arguments
     /// * `template` - Template string with variables like {text}
     ///
@@ -35,34 +57,21 @@ impl TemplateEngine {
     /// # Errors
     /// * If a variable in the template is not set
     pub fn render(&self, template: &str) -> Result<String> {
-        let mut result = template.to_string();
-
-        // Find all variables in the template
+        let mut size = 0;
         let mut missing_vars = Vec::new();
-
-        for (key, value) in &self.variables {
-            let placeholder = format!("{{{}}}", key);
-            if result.contains(&placeholder) {
-                result = result.replace(&placeholder, value);
-            }
-        }
-
-        // Check for unresolved variables
-        let mut chars = result.chars().peekable();
-        while let Some(c) = chars.next() {
-            if c == '{' {
-                let mut var_name = String::new();
-                while let Some(&next_char) = chars.peek() {
-                    if next_char == '}' {
-                        chars.next();
-                        if !var_name.is_empty() && !self.variables.contains_key(&var_name) {
-                            missing_vars.push(var_name.clone());
+        for piece in pieces(template) {
+            size += match piece {
+                Piece::Literal(text) => text.len(),
+                Piece::Variable { name, raw } => match self.variables.get(name) {
+                    Some(value) => value.len(),
+                    None => {
+                        if !name.is_empty() {
+                            missing_vars.push(name);
                         }
-                        break;
+                        raw.len()
                     }
-                    var_name.push(chars.next().unwrap());
-                }
-            }
+                },
+            };
         }
 
         if !missing_vars.is_empty() {
@@ -72,16 +81,63 @@ impl TemplateEngine {
             )));
         }
 
+        let mut result = String::with_capacity(size);
+        for piece in pieces(template) {
+            match piece {
+                Piece::Literal(text) => result.push_str(text),
+                Piece::Variable { name, raw } => {
+                    // Only "{}" can be missing here, and is kept as written
+                    result.push_str(self.variables.get(name).map_or(raw, |value| value))
+                }
+            }
+        }
+
         Ok(result)
     }
 }
 
-impl Default for TemplateEngine {
+impl Default for TemplateEngine<'_> {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Split a template into literal text and placeholders
+///
+/// A "{" without a closing "}" is literal text.
+fn pieces(template: &str) -> impl Iterator<Item = Piece<'_>> {
+    let mut rest = template;
+    let mut pending = None;
+    std::iter::from_fn(move || {
+        if let Some(piece) = pending.take() {
+            return Some(piece);
+        }
+        if rest.is_empty() {
+            return None;
+        }
+
+        let Some((start, end)) = rest
+            .find('{')
+            .and_then(|start| Some((start, start + 1 + rest[start + 1..].find('}')?)))
+        else {
+            return Some(Piece::Literal(std::mem::take(&mut rest)));
+        };
+        let variable = Piece::Variable {
+            name: &rest[start + 1..end],
+            raw: &rest[start..=end],
+        };
+        let literal = &rest[..start];
+        rest = &rest[end + 1..];
+
+        if literal.is_empty() {
+            Some(variable)
+        } else {
+            pending = Some(variable);
+            Some(Piece::Literal(literal))
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,6 +167,39 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_braces_in_values_are_kept() {
+        let mut engine = TemplateEngine::new();
+        engine.set("text", "fn main() { println!(\"{x}\") }");
+
+        let result = engine.render("Code: {text}").unwrap();
+        assert_eq!(result, "Code: fn main() { println!(\"{x}\") }");
+    }
+
+    #[test]
+    fn test_borrowed_values() {
+        let text = "大".repeat(1000);
+        let rendered = {
+            let mut engine = TemplateEngine::new();
+            engine
+                .set("text", text.as_str())
+                .set("tone", String::from("polite"));
+            engine.render("Make {text} {tone}, {text}").unwrap()
+        };
+        assert_eq!(rendered, format!("Make {} polite, {}", text, text));
+        // Sized up front, never grown
+        assert_eq!(rendered.capacity(), rendered.len());
+    }
+
+    #[test]
+    fn test_unclosed_and_empty_braces() {
+        let mut engine = TemplateEngine::new();
+        engine.set("text", "x");
+        assert_eq!(engine.render("{} {text} {oops").unwrap(), "{} x {oops");
+        assert_eq!(engine.render("{text}{text}").unwrap(), "xx");
+        assert_eq!(engine.render("").unwrap(), "");
+    }
+
     #[test]
     fn test_no_variables() {
         let engine = TemplateEngine::new();
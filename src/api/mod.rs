@@ -0,0 +1,305 @@
+//! Library API for apps embedding Rephraser
+//!
+//! [`Rephraser`] runs actions the way `rephraser rephrase` does, with the
+//! same prompts, provider clients (cache, privacy filter, fallback and so
+//! on), guardrails, hooks, history and output template, so that apps such
+//! as a GUI don't have to put that pipeline together themselves:
+//!
+//! ```no_run
+//! # async fn example() -> rephraser::Result<()> {
+//! use rephraser::Rephraser;
+//!
+//! let rephraser = Rephraser::load()?;
+//! let polite = rephraser.rephrase("polite", "明日の会議、遅れます").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Progress UIs, logging and analytics can follow each run by adding an
+//! [`Observer`] with [`Rephraser::with_observer`].
+
+mod observer;
+mod stream;
+
+pub use observer::Observer;
+
+use crate::config::{ActionConfig, Config, ConfigManager};
+use crate::error::{RephraserError, Result};
+use crate::llm::TokenUsage;
+use crate::pipeline::{self, check_provider, ActionRun, LlmOverrides};
+use std::sync::Arc;
+use std::time::Duration;
+use stream::{TokenSender, TokenStream};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
+
+/// Runs actions on text with a configuration
+#[derive(Clone)]
+pub struct Rephraser {
+    config: Arc<Config>,
+    observers: Vec<Arc<dyn Observer>>,
+}
+
+/// Result of running an action, with the details of the request
+#[derive(Debug, Clone)]
+pub struct Rephrased {
+    pub action: String,
+    /// The response, decorated with the output template if one is set
+    pub text: String,
+    pub provider: String,
+    pub model: String,
+    pub usage: TokenUsage,
+    pub duration: Duration,
+}
+
+impl Rephraser {
+    /// Use `config` instead of the config file
+    ///
+    /// # Errors
+    /// * If the client of the configured provider can't be built, such as
+    ///   for lack of an API key
+    pub fn from_config(config: impl Into<Arc<Config>>) -> Result<Self> {
+        let config = config.into();
+        check_provider(&config)?;
+        Ok(Self {
+            config,
+            observers: Vec::new(),
+        })
+    }
+
+    /// Use the config file, `~/.rephraser/config.toml`
+    ///
+    /// # Errors
+    /// * If the config file can't be read, or as for [`Rephraser::from_config`]
+    pub fn load() -> Result<Self> {
+        Self::from_config(ConfigManager::new()?.shared()?)
+    }
+
+    /// Report the progress of every run to `observer`, besides any
+    /// observers added before
+    ///
+    /// With observers, responses are streamed from providers that support
+    /// it, so that `on_token` sees them as they are generated.
+    pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// The configuration in use
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The configured actions
+    pub fn actions(&self) -> &[ActionConfig] {
+        &self.config.actions
+    }
+
+    /// Run `action` on `text` and return the response
+    ///
+    /// # Errors
+    /// * If the action doesn't exist, its hooks or the LLM request fail, or
+    ///   the response fails its guardrails
+    pub async fn rephrase(&self, action: &str, text: &str) -> Result<String> {
+        self.run(action, text).await.map(|rephrased| rephrased.text)
+    }
+
+    /// Run `action` on `text`, yielding the response in pieces as the
+    /// provider generates them
+    ///
+    /// Providers that don't stream, cached responses and actions with
+    /// guardrails yield the response in one piece. The output template is
+    /// not applied. A failure ends the stream with an error; dropping the
+    /// stream cancels the request. Observers see the run as with
+    /// [`Rephraser::run`].
+    ///
+    /// # Panics
+    /// * If called outside a Tokio runtime
+    pub fn rephrase_stream(
+        &self,
+        action: &str,
+        text: &str,
+    ) -> impl Stream<Item = Result<String>> + Send + Unpin + 'static {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let rephraser = self
+            .clone()
+            .with_observer(Arc::new(TokenSender(sender.clone())));
+        let (action, text) = (action.to_string(), text.to_string());
+        let task = tokio::spawn(async move {
+            if let Err(e) = rephraser.run(&action, &text).await {
+                let _ = sender.send(Err(e));
+            }
+        });
+        TokenStream::new(UnboundedReceiverStream::new(receiver), task)
+    }
+
+    /// Run `action` on `text`, returning the response with the provider,
+    /// model, token usage and duration of the request
+    ///
+    /// # Errors
+    /// * As for [`Rephraser::rephrase`]
+    pub async fn run(&self, action: &str, text: &str) -> Result<Rephrased> {
+        let llm = LlmOverrides::default();
+        if self.observers.is_empty() {
+            return Rephrased::from_run(pipeline::run(&self.config, action, text, &llm).await?);
+        }
+
+        let observers = Observers(&self.observers);
+        let result = pipeline::observe(&self.config, action, text, &llm, &observers)
+            .await
+            .and_then(Rephrased::from_run);
+        match &result {
+            Ok(rephrased) => observers.on_response(rephrased),
+            Err(e) => observers.on_error(action, e),
+        }
+        result
+    }
+}
+
+impl Rephrased {
+    fn from_run(run: ActionRun) -> Result<Self> {
+        let (provider, model) = run.provider_and_model();
+        Ok(Self {
+            text: run.output()?,
+            provider: provider.to_string(),
+            model: model.to_string(),
+            usage: run.completion.usage,
+            duration: run.duration,
+            action: run.action,
+        })
+    }
+}
+
+/// Passes events on to every observer
+struct Observers<'a>(&'a [Arc<dyn Observer>]);
+
+impl Observer for Observers<'_> {
+    fn on_prompt_built(&self, action: &str, prompt: &str) {
+        for observer in self.0 {
+            observer.on_prompt_built(action, prompt);
+        }
+    }
+
+    fn on_request_start(&self, action: &str, provider: &str, model: &str) {
+        for observer in self.0 {
+            observer.on_request_start(action, provider, model);
+        }
+    }
+
+    fn on_token(&self, action: &str, token: &str) {
+        for observer in self.0 {
+            observer.on_token(action, token);
+        }
+    }
+
+    fn on_response(&self, response: &Rephrased) {
+        for observer in self.0 {
+            observer.on_response(response);
+        }
+    }
+
+    fn on_error(&self, action: &str, error: &RephraserError) {
+        for observer in self.0 {
+            observer.on_error(action, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Observer writing down the events it sees
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl Observer for Recorder {
+        fn on_prompt_built(&self, action: &str, _prompt: &str) {
+            self.0.lock().unwrap().push(format!("prompt {}", action));
+        }
+
+        fn on_request_start(&self, _action: &str, provider: &str, _model: &str) {
+            self.0.lock().unwrap().push(format!("start {}", provider));
+        }
+
+        fn on_token(&self, _action: &str, _token: &str) {
+            self.0.lock().unwrap().push("token".to_string());
+        }
+
+        fn on_response(&self, response: &Rephrased) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("response {}", response.action));
+        }
+
+        fn on_error(&self, action: &str, _error: &RephraserError) {
+            self.0.lock().unwrap().push(format!("error {}", action));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rephrase() {
+        let rephraser = Rephraser::from_config(Config::mock()).unwrap();
+        let polite = rephraser.rephrase("polite", "hello").await.unwrap();
+        assert!(!polite.is_empty());
+
+        let rephrased = rephraser.run("polite", "hello").await.unwrap();
+        assert_eq!(rephrased.provider, "mock");
+        assert_eq!(rephrased.text, polite);
+
+        let err = rephraser.rephrase("nope", "hello").await.unwrap_err();
+        assert!(matches!(err, RephraserError::ActionNotFound(_)));
+    }
+
+    #[tokio::test]
+    async fn test_output_template() {
+        let mut config = Config::mock();
+        config.output.template = Some("[{action}] {result}".to_string());
+        let rephraser = Rephraser::from_config(config).unwrap();
+        let polite = rephraser.rephrase("polite", "hello").await.unwrap();
+        assert!(polite.starts_with("[polite] "));
+    }
+
+    #[tokio::test]
+    async fn test_observer() {
+        let recorder = Arc::new(Recorder::default());
+        let rephraser = Rephraser::from_config(Config::mock())
+            .unwrap()
+            .with_observer(Arc::clone(&recorder) as Arc<dyn Observer>);
+
+        rephraser.rephrase("polite", "hello").await.unwrap();
+        let events = std::mem::take(&mut *recorder.0.lock().unwrap());
+        assert_eq!(events.first().unwrap(), "prompt polite");
+        assert_eq!(events[1], "start mock");
+        assert!(events.contains(&"token".to_string()));
+        assert_eq!(events.last().unwrap(), "response polite");
+
+        assert!(rephraser.rephrase("nope", "hello").await.is_err());
+        assert_eq!(*recorder.0.lock().unwrap(), ["error nope"]);
+    }
+
+    #[tokio::test]
+    async fn test_rephrase_stream() {
+        use tokio_stream::StreamExt;
+
+        let rephraser = Rephraser::from_config(Config::mock()).unwrap();
+        let tokens: Vec<String> = rephraser
+            .rephrase_stream("polite", "hello")
+            .collect::<Result<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            tokens.concat(),
+            rephraser.rephrase("polite", "hello").await.unwrap()
+        );
+
+        let mut failed = rephraser.rephrase_stream("nope", "hello");
+        assert!(matches!(
+            failed.next().await,
+            Some(Err(RephraserError::ActionNotFound(_)))
+        ));
+        assert!(failed.next().await.is_none());
+    }
+}
@@ -0,0 +1,32 @@
+//! Lifecycle events of action runs
+
+use crate::api::Rephrased;
+use crate::error::RephraserError;
+
+/// Receives the events of the actions a [`Rephraser`](crate::Rephraser)
+/// runs, for progress UIs, logging or analytics
+///
+/// Every method does nothing by default, so implementations only handle the
+/// events they need. Events arrive in this order: `on_prompt_built`,
+/// `on_request_start`, any number of `on_token`, then `on_response` or
+/// `on_error`. Errors before the request, such as an unknown action, skip
+/// straight to `on_error`.
+pub trait Observer: Send + Sync {
+    /// The prompt for `action` was built from its template and the text
+    fn on_prompt_built(&self, _action: &str, _prompt: &str) {}
+
+    /// The request for `action` is about to be sent to `provider`
+    fn on_request_start(&self, _action: &str, _provider: &str, _model: &str) {}
+
+    /// A piece of the response arrived
+    ///
+    /// Providers that don't stream, cached responses and actions with
+    /// guardrails deliver the response in one piece.
+    fn on_token(&self, _action: &str, _token: &str) {}
+
+    /// The action finished
+    fn on_response(&self, _response: &Rephrased) {}
+
+    /// The action failed
+    fn on_error(&self, _action: &str, _error: &RephraserError) {}
+}
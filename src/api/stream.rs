@@ -0,0 +1,52 @@
+//! Responses delivered as they are generated
+
+use crate::api::Observer;
+use crate::error::Result;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
+
+/// Pieces of a response, from [`Rephraser::rephrase_stream`]
+///
+/// Dropping the stream cancels the request.
+///
+/// [`Rephraser::rephrase_stream`]: crate::Rephraser::rephrase_stream
+pub(super) struct TokenStream {
+    tokens: UnboundedReceiverStream<Result<String>>,
+    task: JoinHandle<()>,
+}
+
+impl TokenStream {
+    pub(super) fn new(
+        tokens: UnboundedReceiverStream<Result<String>>,
+        task: JoinHandle<()>,
+    ) -> Self {
+        Self { tokens, task }
+    }
+}
+
+impl Stream for TokenStream {
+    type Item = Result<String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.tokens).poll_next(cx)
+    }
+}
+
+impl Drop for TokenStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Observer sending the tokens of a run into a stream
+pub(super) struct TokenSender(pub(super) UnboundedSender<Result<String>>);
+
+impl Observer for TokenSender {
+    fn on_token(&self, _action: &str, token: &str) {
+        let _ = self.0.send(Ok(token.to_string()));
+    }
+}
@@ -0,0 +1,224 @@
+//! JSON Lines audit log
+//!
+//! Each LLM request is appended as one JSON object per line with the prompt
+//! sent, the response or error received, and the provider and model. Text
+//! matching the configured patterns is redacted before it is written; with
+//! `record_text = false` only SHA-256 hashes of the text are kept.
+
+use crate::actions::registry::sha256_hex;
+use crate::config::manager::expand_home;
+use crate::config::AuditConfig;
+use crate::error::{RephraserError, Result};
+use regex::Regex;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Replacement for redacted text
+const REDACTED: &str = "[REDACTED]";
+
+/// One exchange with a provider
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub provider: String,
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_sha256: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Audit log file with its redaction settings
+pub struct AuditLog {
+    path: PathBuf,
+    record_text: bool,
+    redactions: Vec<Regex>,
+}
+
+impl AuditLog {
+    /// Audit log described by `config`
+    ///
+    /// # Errors
+    /// * If a redaction pattern is not a valid regular expression
+    pub fn from_config(config: &AuditConfig) -> Result<Self> {
+        let redactions = config
+            .redact
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).map_err(|e| {
+                    RephraserError::Config(format!(
+                        "Invalid audit redaction pattern '{}': {}",
+                        pattern, e
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            path: expand_home(&config.path),
+            record_text: config.record_text,
+            redactions,
+        })
+    }
+
+    /// Check that the log can be appended to
+    ///
+    /// Called before a request is sent, so that nothing reaches a provider
+    /// without being recorded.
+    pub fn check_writable(&self) -> Result<()> {
+        self.open().map(|_| ())
+    }
+
+    /// Build the entry for an exchange, applying redaction
+    pub fn entry(
+        &self,
+        provider: &str,
+        model: &str,
+        prompt: &str,
+        outcome: std::result::Result<&str, &RephraserError>,
+        duration_ms: u64,
+    ) -> AuditEntry {
+        let (response, error) = match outcome {
+            Ok(response) => (Some(self.redact(response)), None),
+            Err(e) => (None, Some(self.redact(&e.to_string()))),
+        };
+        let prompt = self.redact(prompt);
+
+        let (prompt, prompt_sha256, response, response_sha256) = if self.record_text {
+            (Some(prompt), None, response, None)
+        } else {
+            let response_sha256 = response.as_deref().map(sha256_hex);
+            (None, Some(sha256_hex(&prompt)), None, response_sha256)
+        };
+
+        AuditEntry {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt,
+            prompt_sha256,
+            response,
+            response_sha256,
+            error,
+            duration_ms,
+        }
+    }
+
+    /// Append an entry as one line
+    pub fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        // A single write keeps lines from concurrent processes intact
+        self.open()?.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Replace every match of the redaction patterns
+    fn redact(&self, text: &str) -> String {
+        self.redactions.iter().fold(text.to_string(), |text, re| {
+            re.replace_all(&text, REDACTED).into_owned()
+        })
+    }
+
+    fn open(&self) -> Result<std::fs::File> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(&self.path).map_err(|e| {
+            RephraserError::Other(format!(
+                "Failed to open the audit log {}: {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(path: &std::path::Path) -> AuditConfig {
+        AuditConfig {
+            enabled: true,
+            path: path.to_string_lossy().into_owned(),
+            record_text: true,
+            redact: vec![r"[\w.]+@[\w.]+".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_append_redacted() {
+        let path =
+            std::env::temp_dir().join(format!("rephraser-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = AuditLog::from_config(&config(&path)).unwrap();
+
+        let entry = log.entry(
+            "openai",
+            "gpt-4o-mini",
+            "Mail bob@example.com",
+            Ok("Done"),
+            42,
+        );
+        log.append(&entry).unwrap();
+        let err = RephraserError::Timeout(std::time::Duration::from_secs(5));
+        log.append(&log.entry("openai", "gpt-4o-mini", "again", Err(&err), 5000))
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["prompt"], "Mail [REDACTED]");
+        assert_eq!(lines[0]["response"], "Done");
+        assert_eq!(lines[0]["duration_ms"], 42);
+        assert!(lines[1]["error"].as_str().unwrap().contains("timed out"));
+        assert!(lines[1].get("response").is_none());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_hashes_only() {
+        let path = std::env::temp_dir().join("unused-audit.jsonl");
+        let log = AuditLog::from_config(&AuditConfig {
+            record_text: false,
+            ..config(&path)
+        })
+        .unwrap();
+
+        let entry = log.entry("openai", "gpt-4o-mini", "secret", Ok("reply"), 1);
+        assert_eq!(entry.prompt, None);
+        assert_eq!(entry.prompt_sha256, Some(sha256_hex("secret")));
+        assert_eq!(entry.response_sha256, Some(sha256_hex("reply")));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let path = std::env::temp_dir().join("unused-audit.jsonl");
+        let result = AuditLog::from_config(&AuditConfig {
+            redact: vec!["(".to_string()],
+            ..config(&path)
+        });
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,5 @@
+//! Append-only audit log of the text sent to providers
+
+pub mod log;
+
+pub use log::{AuditEntry, AuditLog};
@@ -0,0 +1,235 @@
+//! Batches of LLM calls with bounded concurrency
+//!
+//! Used wherever many items go to the LLM at once (`rephraser batch`,
+//! `rephrase-files`, `compare` and the server's `POST /batch`): at most
+//! `concurrency` items run at the same time, an item that fails because the
+//! network or provider is unavailable is retried with a growing delay, and
+//! the results come back in the order of the items.
+
+use crate::config::BatchConfig;
+use crate::error::Result;
+use crate::llm::idempotency;
+use crate::output::progress::ProgressCounter;
+use futures_util::stream::{self, StreamExt};
+use std::future::Future;
+use std::time::Duration;
+use tracing::debug;
+
+/// Runs a function on each item of a batch
+#[derive(Debug, Clone)]
+pub struct Batch {
+    concurrency: usize,
+    retries: u32,
+    retry_delay: Duration,
+    /// Message of the progress bar, if one is shown
+    progress: Option<String>,
+}
+
+impl Batch {
+    pub fn from_config(config: &BatchConfig) -> Self {
+        Self {
+            concurrency: config.concurrency.max(1),
+            retries: config.retries,
+            retry_delay: Duration::from_millis(config.retry_delay_ms),
+            progress: None,
+        }
+    }
+
+    /// Use `concurrency` instead of the configured limit, if given
+    pub fn with_concurrency(mut self, concurrency: Option<usize>) -> Self {
+        if let Some(concurrency) = concurrency {
+            self.concurrency = concurrency.max(1);
+        }
+        self
+    }
+
+    /// Show a progress bar with `message` on stderr while running
+    pub fn with_progress(mut self, message: impl Into<String>, enabled: bool) -> Self {
+        self.progress = enabled.then(|| message.into());
+        self
+    }
+
+    /// Run `f` on every item and collect the results in item order
+    ///
+    /// A failed item does not stop the others.
+    pub async fn run<'a, I, T, F, Fut>(&self, items: &'a [I], f: F) -> BatchResults<T>
+    where
+        F: Fn(&'a I) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let progress = self
+            .progress
+            .as_ref()
+            .map(|message| ProgressCounter::start(message.clone(), items.len(), true));
+        let (f, progress) = (&f, &progress);
+
+        // Futures do nothing until polled, so creating them all up front is
+        // cheap; collecting first also keeps the stream's type free of
+        // closures, which would stop it from being `Send` in server handlers
+        let tasks: Vec<_> = items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| async move {
+                let result = self.run_item(|| f(item)).await;
+                if let Some(progress) = progress {
+                    progress.inc();
+                }
+                (index, result)
+            })
+            .collect();
+        let mut results: Vec<(usize, Result<T>)> = stream::iter(tasks)
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        results.sort_by_key(|(index, _)| *index);
+
+        BatchResults {
+            results: results.into_iter().map(|(_, result)| result).collect(),
+        }
+    }
+
+    /// Run one item, retrying while the provider is unavailable
+    ///
+    /// Every attempt runs with the same idempotency key, so providers can
+    /// tell a retried request from a new one.
+    async fn run_item<T, Fut>(&self, attempt: impl Fn() -> Fut) -> Result<T>
+    where
+        Fut: Future<Output = Result<T>>,
+    {
+        let key = idempotency::new_key();
+        let mut delay = self.retry_delay;
+        let mut retries = 0;
+        loop {
+            match idempotency::scope(Some(key.clone()), attempt()).await {
+                // An open circuit won't close before the retries run out
+                Err(e) if e.is_retryable() && !e.is_circuit_open() && retries < self.retries => {
+                    debug!("Retrying batch item in {:?}: {}", delay, e);
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Results of a batch, in the order of its items
+#[derive(Debug)]
+pub struct BatchResults<T> {
+    results: Vec<Result<T>>,
+}
+
+impl<T> BatchResults<T> {
+    /// Number of items that failed
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|result| result.is_err()).count()
+    }
+
+    /// One line per failed item, naming it with `label` (given the item's
+    /// index), under a count of the failures; `None` if every item succeeded
+    pub fn error_summary(&self, label: impl Fn(usize) -> String) -> Option<String> {
+        let failed = self.failed();
+        if failed == 0 {
+            return None;
+        }
+
+        let mut summary = crate::t!("batch-failed", failed = failed, total = self.results.len());
+        for (index, result) in self.results.iter().enumerate() {
+            if let Err(e) = result {
+                summary.push_str(&format!("\n  {}: {}", label(index), e));
+            }
+        }
+        Some(summary)
+    }
+
+    pub fn into_results(self) -> Vec<Result<T>> {
+        self.results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RephraserError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn batch(concurrency: usize, retries: u32) -> Batch {
+        Batch::from_config(&BatchConfig {
+            concurrency,
+            retries,
+            retry_delay_ms: 0,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_bounded_concurrency_keeps_order() {
+        let running = AtomicUsize::new(0);
+        let most = AtomicUsize::new(0);
+        let items: Vec<u64> = (0..10).collect();
+
+        let results = batch(3, 0)
+            .run(&items, |item| {
+                let (running, most) = (&running, &most);
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    // Later items finish first
+                    tokio::time::sleep(Duration::from_millis(20 - item * 2)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(item * 10)
+                }
+            })
+            .await;
+
+        assert_eq!(most.load(Ordering::SeqCst), 3);
+        let values: Vec<u64> = results
+            .into_results()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(values, (0..10).map(|i| i * 10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_retries_unavailable_items() {
+        let attempts = AtomicUsize::new(0);
+        let results = batch(2, 2)
+            .run(&["flaky", "broken"], |item| {
+                let attempts = &attempts;
+                async move {
+                    match *item {
+                        "flaky" if attempts.fetch_add(1, Ordering::SeqCst) < 2 => {
+                            Err(RephraserError::LlmServiceError("overloaded".to_string()))
+                        }
+                        "flaky" => Ok(()),
+                        _ => Err(RephraserError::Input("bad".to_string())),
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(results.failed(), 1);
+        let summary = results
+            .error_summary(|index| format!("item {}", index))
+            .unwrap();
+        assert!(
+            summary.ends_with("\n  item 1: Input error: bad"),
+            "{}",
+            summary
+        );
+        assert!(results.into_results()[0].is_ok());
+
+        // Retries are limited
+        let attempts = AtomicUsize::new(0);
+        let results = batch(1, 1)
+            .run(&[()], |_| async {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err::<(), _>(RephraserError::Timeout(Duration::from_secs(1)))
+            })
+            .await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(results.failed(), 1);
+    }
+}
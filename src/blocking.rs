@@ -0,0 +1,123 @@
+//! Blocking library API
+//!
+//! [`Rephraser`] wraps the asynchronous [`crate::Rephraser`] with a runtime
+//! of its own, for scripts and other callers that don't use async Rust:
+//!
+//! ```no_run
+//! # fn example() -> rephraser::Result<()> {
+//! let rephraser = rephraser::blocking::Rephraser::load()?;
+//! let polite = rephraser.rephrase("polite", "明日の会議、遅れます")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Its methods must not be called from async code, where they would block
+//! the runtime; use [`crate::Rephraser`] there instead.
+
+use crate::api::{Observer, Rephrased};
+use crate::config::{ActionConfig, Config};
+use crate::error::Result;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio_stream::StreamExt;
+
+/// Runs actions on text, blocking until they finish
+pub struct Rephraser {
+    inner: crate::Rephraser,
+    runtime: Runtime,
+}
+
+impl Rephraser {
+    /// Use `config` instead of the config file
+    ///
+    /// # Errors
+    /// * As for [`crate::Rephraser::from_config`], or if the runtime can't
+    ///   be started
+    pub fn from_config(config: impl Into<Arc<Config>>) -> Result<Self> {
+        Self::new(crate::Rephraser::from_config(config)?)
+    }
+
+    /// Use the config file, `~/.rephraser/config.toml`
+    ///
+    /// # Errors
+    /// * As for [`crate::Rephraser::load`], or if the runtime can't be
+    ///   started
+    pub fn load() -> Result<Self> {
+        Self::new(crate::Rephraser::load()?)
+    }
+
+    fn new(inner: crate::Rephraser) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Report the progress of every run to `observer`, as with
+    /// [`crate::Rephraser::with_observer`]
+    pub fn with_observer(mut self, observer: Arc<dyn Observer>) -> Self {
+        self.inner = self.inner.with_observer(observer);
+        self
+    }
+
+    /// The configuration in use
+    pub fn config(&self) -> &Config {
+        self.inner.config()
+    }
+
+    /// The configured actions
+    pub fn actions(&self) -> &[ActionConfig] {
+        self.inner.actions()
+    }
+
+    /// Run `action` on `text` and return the response
+    ///
+    /// # Errors
+    /// * As for [`crate::Rephraser::rephrase`]
+    pub fn rephrase(&self, action: &str, text: &str) -> Result<String> {
+        self.runtime.block_on(self.inner.rephrase(action, text))
+    }
+
+    /// Run `action` on `text`, returning the response with the details of
+    /// the request
+    ///
+    /// # Errors
+    /// * As for [`crate::Rephraser::run`]
+    pub fn run(&self, action: &str, text: &str) -> Result<Rephrased> {
+        self.runtime.block_on(self.inner.run(action, text))
+    }
+
+    /// Run `action` on `text`, iterating over the pieces of the response as
+    /// the provider generates them
+    ///
+    /// Works like [`crate::Rephraser::rephrase_stream`]; the request makes
+    /// progress while the iterator is waited on, and dropping the iterator
+    /// cancels it.
+    pub fn rephrase_iter<'a>(
+        &'a self,
+        action: &str,
+        text: &str,
+    ) -> impl Iterator<Item = Result<String>> + 'a {
+        let mut tokens = {
+            let _entered = self.runtime.enter();
+            self.inner.rephrase_stream(action, text)
+        };
+        std::iter::from_fn(move || self.runtime.block_on(tokens.next()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_rephrase() {
+        let rephraser = Rephraser::from_config(Config::mock()).unwrap();
+        let polite = rephraser.rephrase("polite", "hello").unwrap();
+        assert_eq!(rephraser.run("polite", "hello").unwrap().text, polite);
+
+        let tokens: Result<Vec<String>> = rephraser.rephrase_iter("polite", "hello").collect();
+        assert_eq!(tokens.unwrap().concat(), polite);
+        assert!(rephraser.rephrase("nope", "hello").is_err());
+    }
+}
@@ -0,0 +1,5 @@
+//! On-disk cache of LLM responses
+
+pub mod store;
+
+pub use store::{CacheStats, ResponseCache};
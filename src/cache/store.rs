@@ -0,0 +1,248 @@
+//! File-backed response cache
+//!
+//! Each response is stored as a JSON file named after the hash of everything
+//! that determines it: provider, model, parameters and prompt. Entries expire
+//! after a TTL, and the oldest ones are evicted once the directory grows past
+//! its size limit.
+
+use crate::actions::registry::sha256_hex;
+use crate::config::models::LlmParameters;
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Extension of cache entry files
+const ENTRY_EXTENSION: &str = "json";
+
+/// Response cache in a directory
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_bytes: u64,
+}
+
+/// Number and total size of cached responses
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// Contents of a cache entry file
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    text: String,
+}
+
+/// A cache entry file with the metadata eviction needs
+struct EntryFile {
+    path: PathBuf,
+    modified: SystemTime,
+    bytes: u64,
+}
+
+impl ResponseCache {
+    /// Cache in `dir`, keeping entries for `ttl` and at most `max_bytes`
+    pub fn new(dir: &Path, ttl: Duration, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            ttl,
+            max_bytes,
+        }
+    }
+
+    /// Cache key of a request
+    ///
+    /// Fields are separated by NUL so that, e.g., a model name ending in the
+    /// first characters of the prompt cannot produce the same key.
+    pub fn key(provider: &str, model: &str, parameters: &LlmParameters, prompt: &str) -> String {
+        sha256_hex(&format!(
+            "{}\0{}\0{}\0{}\0{}",
+            provider, model, parameters.temperature, parameters.max_tokens, prompt
+        ))
+    }
+
+    /// Cached response for `key`, if present and not expired
+    ///
+    /// Expired entries are removed.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        let path = self.entry_path(key);
+        let modified = match fs::metadata(&path) {
+            Ok(metadata) => metadata.modified()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if self.is_expired(modified) {
+            let _ = fs::remove_file(&path);
+            return Ok(None);
+        }
+
+        let entry: Entry = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        Ok(Some(entry.text))
+    }
+
+    /// Store a response, then evict entries beyond the TTL and size limit
+    pub fn put(&self, key: &str, text: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let json = serde_json::to_string(&Entry {
+            text: text.to_string(),
+        })?;
+
+        // Write then rename, so concurrent runs never read a partial entry
+        let path = self.entry_path(key);
+        let partial = path.with_extension(format!("{}.{}", ENTRY_EXTENSION, std::process::id()));
+        fs::write(&partial, json)?;
+        // Responses are as private as the text they were generated from
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&partial, fs::Permissions::from_mode(0o600))?;
+        }
+        fs::rename(&partial, &path)?;
+
+        self.evict()
+    }
+
+    /// Number and total size of the cached responses
+    pub fn stats(&self) -> Result<CacheStats> {
+        let entries = self.entries()?;
+        Ok(CacheStats {
+            entries: entries.len(),
+            bytes: entries.iter().map(|e| e.bytes).sum(),
+        })
+    }
+
+    /// Remove every cached response, returning how many there were
+    pub fn clear(&self) -> Result<usize> {
+        let entries = self.entries()?;
+        for entry in &entries {
+            fs::remove_file(&entry.path)?;
+        }
+        Ok(entries.len())
+    }
+
+    /// Remove expired entries, then the oldest until within the size limit
+    fn evict(&self) -> Result<()> {
+        let mut entries = self.entries()?;
+        entries.sort_by_key(|e| e.modified);
+
+        let mut total: u64 = entries.iter().map(|e| e.bytes).sum();
+        for entry in entries {
+            if total <= self.max_bytes && !self.is_expired(entry.modified) {
+                break;
+            }
+            fs::remove_file(&entry.path)?;
+            total -= entry.bytes;
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, modified: SystemTime) -> bool {
+        modified.elapsed().is_ok_and(|age| age >= self.ttl)
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(key).with_extension(ENTRY_EXTENSION)
+    }
+
+    /// Entry files in the cache directory
+    fn entries(&self) -> Result<Vec<EntryFile>> {
+        let dir = match fs::read_dir(&self.dir) {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for item in dir {
+            let path = item?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(ENTRY_EXTENSION) {
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            entries.push(EntryFile {
+                path,
+                modified: metadata.modified()?,
+                bytes: metadata.len(),
+            });
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(name: &str, ttl: Duration, max_bytes: u64) -> ResponseCache {
+        let dir =
+            std::env::temp_dir().join(format!("rephraser-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        ResponseCache::new(&dir, ttl, max_bytes)
+    }
+
+    #[test]
+    fn test_key() {
+        let parameters = LlmParameters::default();
+        let key = ResponseCache::key("openai", "gpt-4o-mini", &parameters, "hello");
+        assert_eq!(key.len(), 64);
+        assert_eq!(
+            key,
+            ResponseCache::key("openai", "gpt-4o-mini", &parameters, "hello")
+        );
+
+        let warmer = LlmParameters {
+            temperature: 1.0,
+            ..LlmParameters::default()
+        };
+        assert_ne!(
+            key,
+            ResponseCache::key("openai", "gpt-4o-mini", &warmer, "hello")
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("openai", "gpt-4o", &parameters, "hello")
+        );
+        assert_ne!(
+            key,
+            ResponseCache::key("anthropic", "gpt-4o-mini", &parameters, "hello")
+        );
+    }
+
+    #[test]
+    fn test_put_and_get() {
+        let cache = temp_cache("get", Duration::from_secs(3600), 1 << 20);
+        assert!(cache.get("a").unwrap().is_none());
+
+        cache.put("a", "first").unwrap();
+        assert_eq!(cache.get("a").unwrap().as_deref(), Some("first"));
+        assert_eq!(cache.stats().unwrap().entries, 1);
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert!(cache.get("a").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_expired_entries() {
+        let cache = temp_cache("ttl", Duration::ZERO, 1 << 20);
+        cache.put("a", "first").unwrap();
+        assert!(cache.get("a").unwrap().is_none());
+        assert_eq!(cache.stats().unwrap(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_size_eviction() {
+        let cache = temp_cache("size", Duration::from_secs(3600), 150);
+        cache.put("a", &"x".repeat(80)).unwrap();
+        // Keep modification times apart so the oldest entry is well defined
+        std::thread::sleep(Duration::from_millis(20));
+        cache.put("b", &"y".repeat(80)).unwrap();
+
+        // Both entries together exceed the limit, so the older one goes
+        assert!(cache.get("a").unwrap().is_none());
+        assert!(cache.get("b").unwrap().is_some());
+        cache.clear().unwrap();
+    }
+}
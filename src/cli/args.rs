@@ -1,6 +1,10 @@
 //! CLI argument definitions
 
-use clap::{Parser, Subcommand};
+use crate::config::OutputMethod;
+use crate::pipeline::LlmOverrides;
+use crate::usage::Grouping;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(name = "rephraser")]
@@ -8,21 +12,274 @@ use clap::{Parser, Subcommand};
 #[command(version = "0.1.0")]
 #[command(about = "macOS text transformation tool with LLM integration", long_about = None)]
 pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// Options accepted by every command
+#[derive(Args, Debug, Clone, Default)]
+pub struct GlobalArgs {
+    /// Suppress progress display and informational messages
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Show diagnostic logs on stderr (-v for debug, -vv for trace)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+}
+
+/// Per-run overrides of the LLM settings
+#[derive(Args, Debug, Clone, Default)]
+pub struct LlmArgs {
+    /// Provider for this run ("openai", "anthropic", "mock" or a plugin)
+    #[arg(short, long, value_name = "PROVIDER")]
+    pub provider: Option<String>,
+
+    /// Model for this run (e.g., "gpt-4o", "claude-3-5-sonnet-20241022")
+    #[arg(short, long, value_name = "MODEL")]
+    pub model: Option<String>,
+
+    /// Temperature for this run (0.0-2.0)
+    #[arg(short, long, value_name = "TEMP", value_parser = parse_temperature)]
+    pub temperature: Option<f32>,
+
+    /// Cancel an LLM request that takes longer than this many seconds; each
+    /// guardrail retry is a new request with its own limit
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: Option<u64>,
+
+    /// Neither use nor store cached responses for this run
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Generate a fresh response even if one is cached, replacing it
+    #[arg(long, conflicts_with = "no_cache")]
+    pub refresh: bool,
+
+    /// Attribute this run's spend to a project or client in `rephraser cost`
+    #[arg(long, value_name = "TAG")]
+    pub tag: Option<String>,
+
+    /// Tone for this run's actions, from [tones] (e.g., "formal", "casual",
+    /// "friendly", "assertive")
+    #[arg(long, value_name = "TONE")]
+    pub tone: Option<String>,
+}
+
+impl From<&LlmArgs> for LlmOverrides {
+    fn from(llm: &LlmArgs) -> Self {
+        Self {
+            provider: llm.provider.clone(),
+            model: llm.model.clone(),
+            temperature: llm.temperature,
+            timeout: llm.timeout,
+            no_cache: llm.no_cache,
+            refresh: llm.refresh,
+            tag: llm.tag.clone(),
+            tone: llm.tone.clone(),
+        }
+    }
+}
+
+fn parse_temperature(value: &str) -> Result<f32, String> {
+    let temperature: f32 = value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))?;
+    if !(0.0..=2.0).contains(&temperature) {
+        return Err("temperature must be between 0.0 and 2.0".to_string());
+    }
+    Ok(temperature)
+}
+
+/// Where the text to transform comes from
+#[derive(Args, Debug, Clone, Default)]
+pub struct InputArgs {
+    /// Text to transform; reads from stdin if omitted or "-"
+    #[arg(value_name = "TEXT")]
+    pub text: Option<String>,
+
+    /// Read the text to transform from the clipboard
+    #[arg(long, conflicts_with_all = ["text", "file"])]
+    pub from_clipboard: bool,
+
+    /// Read the text selected in the frontmost app (requires Accessibility permission)
+    #[arg(long, conflicts_with_all = ["text", "file", "from_clipboard"])]
+    pub from_selection: bool,
+
+    /// Read the text to transform from a file
+    #[arg(short, long, value_name = "PATH", conflicts_with = "text")]
+    pub file: Option<PathBuf>,
+
+    /// Stream the file in chunks of `chunk_chars` and condense the results, for files of any size
+    #[arg(long, requires = "file")]
+    pub chunked: bool,
+}
+
+impl InputArgs {
+    /// Whether the text comes from somewhere other than the positional argument
+    fn has_source(&self) -> bool {
+        self.text.is_some() || self.file.is_some() || self.from_clipboard || self.from_selection
+    }
+}
+
+/// Work out which actions `rephrase` should run
+///
+/// Clap assigns the first positional argument to ACTION, so with
+/// `rephrase -a summarize -a polite "text"` the text ends up there. When
+/// `-a` is used and no other input is given, neither by flag nor piped to
+/// stdin, that argument is moved to the input instead; otherwise it is run
+/// first, before the `-a` actions.
+pub fn rephrase_actions(
+    action: Option<String>,
+    actions: Vec<String>,
+    input: &mut InputArgs,
+    stdin_piped: bool,
+) -> Vec<String> {
+    match action {
+        Some(text) if !actions.is_empty() && !input.has_source() && !stdin_piped => {
+            input.text = Some(text);
+            actions
+        }
+        Some(action) => std::iter::once(action).chain(actions).collect(),
+        None => actions,
+    }
+}
+
+/// Output formats for launcher apps, selected with `rephrase --format`
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Copy the result and print a one-line summary for Raycast's HUD
+    Raycast,
+    /// Print Alfred Script Filter JSON
+    Alfred,
+}
+
+/// How `proofread` shows the corrections it finds
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProofreadFormat {
+    /// List each correction with its line, column and reason
+    #[default]
+    Report,
+    /// Mark each correction inline, as [-original-]{+suggestion+}
+    Diff,
+    /// Print the corrections as a JSON array
+    Json,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Transform text using an action
     Rephrase {
+        /// Action name (e.g., "polite", "organize", "summarize")
+        #[arg(value_name = "ACTION", required_unless_present = "actions")]
+        action: Option<String>,
+
+        /// Run several actions on the same text (repeatable); a lone
+        /// positional argument is then taken as the text
+        #[arg(short = 'a', long = "action", value_name = "ACTION")]
+        actions: Vec<String>,
+
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        llm: LlmArgs,
+
+        /// Open the result in an editor instead of the configured output
+        #[arg(long, conflicts_with = "output")]
+        edit: bool,
+
+        /// Output method for this run (e.g., "stdout", "clipboard", "dialog")
+        #[arg(short, long, value_name = "METHOD")]
+        output: Option<OutputMethod>,
+
+        /// Print the rendered prompt and target model without calling the API
+        #[arg(long, conflicts_with_all = ["edit", "output"])]
+        dry_run: bool,
+
+        /// Print the result as a JSON object instead of using the output method
+        #[arg(long, conflicts_with_all = ["edit", "output", "dry_run"])]
+        json: bool,
+
+        /// Print the result for a launcher app instead of using the output method
+        #[arg(long, value_name = "FORMAT", conflicts_with_all = ["edit", "output", "dry_run", "json"])]
+        format: Option<OutputFormat>,
+    },
+
+    /// Transform the selected text in the frontmost app and paste the result back
+    Selection {
+        /// Action name (e.g., "polite", "organize", "summarize")
+        #[arg(value_name = "ACTION")]
+        action: String,
+    },
+
+    /// Send a prompt as-is to the LLM, without an action
+    Prompt {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        llm: LlmArgs,
+
+        /// Open the result in an editor instead of the configured output
+        #[arg(long, conflicts_with = "output")]
+        edit: bool,
+
+        /// Output method for this run (e.g., "stdout", "clipboard", "dialog")
+        #[arg(short, long, value_name = "METHOD")]
+        output: Option<OutputMethod>,
+    },
+
+    /// Check the text for mistakes and list each correction with its reason
+    Proofread {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        llm: LlmArgs,
+
+        /// How to show the corrections
+        #[arg(long, value_name = "FORMAT", default_value = "report")]
+        format: ProofreadFormat,
+
+        /// Output method for this run (defaults to printing to stdout)
+        #[arg(short, long, value_name = "METHOD")]
+        output: Option<OutputMethod>,
+    },
+
+    /// Run an action against several models concurrently and compare the results
+    Compare {
         /// Action name (e.g., "polite", "organize", "summarize")
         #[arg(value_name = "ACTION")]
         action: String,
 
-        /// Text to transform
-        #[arg(value_name = "TEXT")]
-        text: String,
+        #[command(flatten)]
+        input: InputArgs,
+
+        /// Model to compare, as "provider:model" or a bare model name
+        /// (repeatable; defaults to compare.models in the config)
+        #[arg(short, long = "model", value_name = "[PROVIDER:]MODEL")]
+        models: Vec<String>,
+    },
+
+    /// Let the LLM pick the most appropriate action and run it
+    Auto {
+        #[command(flatten)]
+        input: InputArgs,
+
+        #[command(flatten)]
+        llm: LlmArgs,
+
+        /// Open the result in an editor instead of the configured output
+        #[arg(long, conflicts_with = "output")]
+        edit: bool,
+
+        /// Output method for this run (e.g., "stdout", "clipboard", "dialog")
+        #[arg(short, long, value_name = "METHOD")]
+        output: Option<OutputMethod>,
     },
 
     /// Configuration management
@@ -32,7 +289,380 @@ pub enum Commands {
     },
 
     /// List available actions
-    ListActions,
+    ListActions {
+        /// Show descriptions, tags, provider overrides and template previews
+        #[arg(short, long)]
+        long: bool,
+
+        /// Print the actions as a JSON array
+        #[arg(long, conflicts_with = "long")]
+        json: bool,
+
+        /// Print the actions for a launcher app ("alfred")
+        #[arg(long, value_name = "FORMAT", conflicts_with_all = ["long", "json"])]
+        format: Option<OutputFormat>,
+    },
+
+    /// Re-run an action whenever a file changes, writing the result next to it
+    Watch {
+        /// Action name (e.g., "polite", "organize", "summarize")
+        #[arg(value_name = "ACTION")]
+        action: String,
+
+        /// File to watch
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Where to write the result (default: FILE with the action before the extension)
+        #[arg(long, value_name = "PATH")]
+        output_file: Option<PathBuf>,
+
+        /// Wait until the file has been unchanged this long before running
+        #[arg(long, value_name = "MS", default_value_t = 1000)]
+        debounce: u64,
+
+        #[command(flatten)]
+        llm: LlmArgs,
+    },
+
+    /// Run an action on each file, writing each result next to it (e.g. notes.polite.md)
+    RephraseFiles {
+        /// Action name (e.g., "polite", "organize", "summarize")
+        #[arg(value_name = "ACTION")]
+        action: String,
+
+        /// Text files to transform
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Show a notification when done, for use without a terminal
+        #[arg(long)]
+        notify: bool,
+
+        /// Most files to transform at the same time (default: `concurrency` under [batch])
+        #[arg(short, long, value_name = "N")]
+        jobs: Option<usize>,
+
+        #[command(flatten)]
+        llm: LlmArgs,
+    },
+
+    /// Run an action on each line of a file or stdin, printing the results as JSON Lines
+    Batch {
+        /// Action name (e.g., "polite", "organize", "summarize")
+        #[arg(value_name = "ACTION")]
+        action: String,
+
+        /// Read the lines from a file instead of stdin
+        #[arg(short, long, value_name = "PATH")]
+        file: Option<PathBuf>,
+
+        /// Most lines to transform at the same time (default: `concurrency` under [batch])
+        #[arg(short, long, value_name = "N")]
+        jobs: Option<usize>,
+
+        #[command(flatten)]
+        llm: LlmArgs,
+    },
+
+    /// Transform copied text automatically, following the [[clipboard_watch.rules]] in the config
+    WatchClipboard,
+
+    /// Run in the background, keeping LLM clients warm for other invocations
+    Daemon,
+
+    /// Serve actions over HTTP for browser extensions and other local apps
+    Serve {
+        /// Address to listen on (default: server.host, 127.0.0.1)
+        #[arg(long, value_name = "HOST")]
+        host: Option<String>,
+
+        /// Port to listen on (default: server.port, 8787)
+        #[arg(long, value_name = "PORT")]
+        port: Option<u16>,
+    },
+
+    /// Run a Model Context Protocol server on stdio, exposing actions as tools
+    Mcp,
+
+    /// Show a menu bar item with actions, recent results and a pause toggle (macOS)
+    MenuBar,
+
+    /// Raycast integration
+    Raycast {
+        #[command(subcommand)]
+        subcommand: RaycastCommands,
+    },
+
+    /// Run an action for Apple Shortcuts: reads {"action", "text"} JSON on stdin, prints JSON
+    Shortcut,
+
+    /// Handle a rephraser://rephrase?action=...&text=... URL
+    OpenUrl {
+        /// The URL to handle
+        #[arg(value_name = "URL")]
+        url: String,
+    },
+
+    /// Manage the app that handles rephraser:// URLs (macOS)
+    UrlHandler {
+        #[command(subcommand)]
+        subcommand: UrlHandlerCommands,
+    },
+
+    /// Add a Finder Quick Action that runs an action on the selected text files (macOS)
+    InstallQuickAction {
+        /// Action name (e.g., "polite", "organize", "summarize")
+        #[arg(value_name = "ACTION")]
+        action: String,
+    },
+
+    /// Start the daemon or menu bar item at login with a LaunchAgent (macOS)
+    Agent {
+        #[command(subcommand)]
+        subcommand: AgentCommands,
+    },
+
+    /// Re-run the most recent transformation
+    Redo {
+        /// Use this action instead of the recorded one
+        #[arg(short, long, value_name = "ACTION")]
+        action: Option<String>,
+
+        #[command(flatten)]
+        llm: LlmArgs,
+
+        /// Output method for this run (e.g., "stdout", "clipboard", "dialog")
+        #[arg(short, long, value_name = "METHOD")]
+        output: Option<OutputMethod>,
+    },
+
+    /// Restore the clipboard contents from before Rephraser last replaced them
+    Undo {
+        /// Also paste the restored text into the frontmost app (macOS)
+        #[arg(long)]
+        paste: bool,
+    },
+
+    /// Browse past transformations
+    #[command(args_conflicts_with_subcommands = true)]
+    History {
+        #[command(subcommand)]
+        subcommand: Option<HistoryCommands>,
+
+        /// Maximum number of entries to show
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Summarize token usage and estimated spend
+    Cost {
+        /// Only include requests since a date (YYYY-MM-DD) or a number of days ago (e.g., 7d)
+        #[arg(long, value_name = "WHEN")]
+        since: Option<String>,
+
+        /// Only include requests with this tag
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+
+        /// Group by "day", "model", "provider", "action" or "tag"
+        #[arg(long, value_name = "GROUP", default_value = "day")]
+        by: Grouping,
+    },
+
+    /// Show request counts, failure rates and latency percentiles
+    Stats {
+        /// Only include requests since a date (YYYY-MM-DD) or a number of days ago (e.g., 7d)
+        #[arg(long, value_name = "WHEN")]
+        since: Option<String>,
+
+        /// Group by "action", "provider", "model", "day" or "tag"
+        #[arg(long, value_name = "GROUP", default_value = "action")]
+        by: Grouping,
+    },
+
+    /// Read the log file in ~/.rephraser/logs
+    Logs {
+        #[command(subcommand)]
+        subcommand: LogsCommands,
+    },
+
+    /// Inspect or clear the response cache
+    Cache {
+        #[command(subcommand)]
+        subcommand: CacheCommands,
+    },
+
+    /// Manage API keys in the encrypted secrets file
+    Secrets {
+        #[command(subcommand)]
+        subcommand: SecretsCommands,
+    },
+
+    /// Run or inspect rephrase jobs queued while offline
+    Queue {
+        #[command(subcommand)]
+        subcommand: QueueCommands,
+    },
+
+    /// List or run the recurring jobs defined under [[schedules]]
+    Schedule {
+        #[command(subcommand)]
+        subcommand: ScheduleCommands,
+    },
+
+    /// Browse and install actions from the remote registry
+    Action {
+        #[command(subcommand)]
+        subcommand: ActionCommands,
+    },
+
+    /// Check the setup for problems, such as a missing Accessibility permission
+    Doctor,
+
+    /// Print the version
+    Version {
+        /// Check whether a newer release is available
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ActionCommands {
+    /// List actions available in the registry
+    Browse,
+
+    /// Install an action from the registry into the config file
+    Install {
+        /// Action name in the registry
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LogsCommands {
+    /// Print the most recent log lines
+    Tail {
+        /// Number of lines to print
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 20)]
+        lines: usize,
+
+        /// Keep printing new lines as they are logged
+        #[arg(short, long)]
+        follow: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// Show the number and total size of cached responses
+    Stats,
+
+    /// Delete every cached response
+    Clear,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SecretsCommands {
+    /// Store a secret, reading its value from stdin
+    Set {
+        /// Secret name, e.g. the provider ("openai") whose API key it is
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum QueueCommands {
+    /// List queued jobs, oldest first
+    List,
+
+    /// Run queued jobs, stopping if the provider is still unavailable
+    Run,
+
+    /// Delete every queued job
+    Clear,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ScheduleCommands {
+    /// List schedules with their next run time
+    List,
+
+    /// Run a schedule now
+    Run {
+        /// Schedule name
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RaycastCommands {
+    /// Write a Raycast script command for each action
+    Generate {
+        /// Directory to write the scripts to (default: raycast/ in the config directory)
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum UrlHandlerCommands {
+    /// Create ~/Applications/Rephraser URL Handler.app and register it for rephraser:// URLs
+    Install,
+
+    /// Unregister and delete the URL handler app
+    Uninstall,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AgentCommands {
+    /// Write the LaunchAgent plist and load it
+    Install {
+        /// Run the menu bar item instead of the daemon
+        #[arg(long)]
+        menu_bar: bool,
+
+        /// Copy this variable from the current environment into the agent, e.g. an API key
+        /// (repeatable)
+        #[arg(long = "env", value_name = "NAME")]
+        env: Vec<String>,
+    },
+
+    /// Unload the LaunchAgent and delete its plist
+    Uninstall,
+
+    /// Show whether the LaunchAgent is installed and running
+    Status,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommands {
+    /// Find entries whose action, input or output contain every word of the query
+    Search {
+        /// Words to look for
+        #[arg(value_name = "QUERY", required = true, num_args = 1..)]
+        query: Vec<String>,
+
+        /// Maximum number of entries to show
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Show an entry in full, with its prompt, cost and duration
+    Show {
+        #[arg(value_name = "ID")]
+        id: i64,
+    },
+
+    /// Copy the output of an entry to the clipboard
+    Copy {
+        #[arg(value_name = "ID")]
+        id: i64,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -57,3 +687,73 @@ pub enum ConfigCommands {
     /// Show configuration file path
     Path,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_rephrase(args: &[&str]) -> (Vec<String>, InputArgs) {
+        parse_rephrase_with_stdin(args, false)
+    }
+
+    fn parse_rephrase_with_stdin(args: &[&str], stdin_piped: bool) -> (Vec<String>, InputArgs) {
+        let args = std::iter::once("rephraser").chain(args.iter().copied());
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Commands::Rephrase {
+                action,
+                actions,
+                mut input,
+                ..
+            } => (
+                rephrase_actions(action, actions, &mut input, stdin_piped),
+                input,
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_rephrase_actions() {
+        let (actions, input) = parse_rephrase(&["rephrase", "polite", "hello"]);
+        assert_eq!(actions, ["polite"]);
+        assert_eq!(input.text.as_deref(), Some("hello"));
+
+        let (actions, input) =
+            parse_rephrase(&["rephrase", "-a", "summarize", "-a", "polite", "hello"]);
+        assert_eq!(actions, ["summarize", "polite"]);
+        assert_eq!(input.text.as_deref(), Some("hello"));
+
+        let (actions, input) = parse_rephrase(&["rephrase", "polite", "-a", "summarize", "hello"]);
+        assert_eq!(actions, ["polite", "summarize"]);
+        assert_eq!(input.text.as_deref(), Some("hello"));
+
+        let (actions, input) = parse_rephrase(&["rephrase", "-a", "polite", "-a", "organize"]);
+        assert_eq!(actions, ["polite", "organize"]);
+        assert!(input.text.is_none());
+
+        let (actions, input) = parse_rephrase(&["rephrase", "-a", "polite", "--from-selection"]);
+        assert_eq!(actions, ["polite"]);
+        assert!(input.from_selection);
+
+        // echo x | rephraser rephrase -a summarize polite
+        let (actions, input) =
+            parse_rephrase_with_stdin(&["rephrase", "-a", "summarize", "polite"], true);
+        assert_eq!(actions, ["polite", "summarize"]);
+        assert!(input.text.is_none());
+
+        assert!(Cli::try_parse_from(["rephraser", "rephrase"]).is_err());
+        assert!(
+            Cli::try_parse_from(["rephraser", "rephrase", "polite", "hi", "--from-selection"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_timeout_must_be_positive() {
+        let parse =
+            |timeout| Cli::try_parse_from(["rephraser", "rephrase", "hi", "--timeout", timeout]);
+        assert!(parse("0").is_err());
+        assert!(parse("10").is_ok());
+    }
+}
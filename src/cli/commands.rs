@@ -1,55 +1,2367 @@
 //! CLI command implementations
 
-use crate::actions::ActionResolver;
-use crate::config::ConfigManager;
-use crate::error::{RephraserError, Result};
-use crate::llm::{AnthropicClient, LlmClient, MockLlmClient, OpenAiClient};
-use crate::output::OutputHandler;
+use crate::actions::guardrails::complete_validated;
+use crate::actions::{proofread, ActionRegistry, ActionResolver};
+use crate::batch::Batch;
+use crate::cli::{GlobalArgs, InputArgs, LlmArgs, OutputFormat, ProofreadFormat};
+use crate::config::manager::expand_home;
+use crate::config::{ConfigManager, LlmConfig, OutputMethod, ScheduleConfig};
+use crate::error::{ErrorCode, RephraserError, Result};
+use crate::history::store::preview;
+use crate::history::{HistoryEntry, HistoryStore};
+use crate::input::clipboard_watch::find_rule;
+use crate::input::watch::output_path as watch_output_path;
+use crate::input::{
+    frontmost_app, is_trusted, read_clipboard, read_file, read_selection, read_stdin,
+    ClipboardWatcher, FileChunks, FileWatcher,
+};
+use crate::integrations::url_scheme::{self, UrlRequest};
+use crate::integrations::{alfred, launchd, quick_action};
+use crate::llm::idempotency;
+use crate::llm::pricing::estimate_cost;
+use crate::llm::tokens::estimate_tokens;
+use crate::llm::{ClientRegistry, LlmClient, TokenUsage};
+use crate::logging::LOG_FILE_NAME;
+use crate::output::clipboard;
+use crate::output::formatter::paste_clipboard;
+use crate::output::platform;
+#[cfg(all(target_os = "macos", feature = "menu-bar"))]
+use crate::output::platform::Notification;
+use crate::output::progress::Spinner;
+use crate::output::text;
+use crate::output::undo::{stash_clipboard, UndoStack};
+use crate::output::{DialogAction, OutputHandler};
+#[cfg(unix)]
+use crate::pipeline::provider_client_factory;
+use crate::pipeline::{
+    self, apply_llm_overrides, choose_action, complete_request, configure_llm, create_llm_client,
+    output_context, provider_clients, record_usage, response_cache, ActionRun, LlmOverrides,
+    PreparedAction, Request, AD_HOC_ACTION, BYTES_PER_MB, PROOFREAD_ACTION,
+};
+use crate::plugins::PluginRegistry;
+use crate::queue::{QueueStore, QueuedJob};
+use crate::schedule::Cron;
+use crate::secrets::EncryptedFileStore;
+use crate::shutdown::{Signals, DRAIN_TIMEOUT};
+use crate::t;
+use crate::update::{self, UpdateChecker};
+use crate::usage::store::parse_since;
+use crate::usage::{CostRow, Grouping, UsageStore};
+use chrono::{Local, Timelike};
+use serde::Serialize;
+use std::io::{IsTerminal, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+#[cfg(all(target_os = "macos", feature = "menu-bar"))]
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Number of template characters shown by `list-actions --long`
+const TEMPLATE_PREVIEW_LENGTH: usize = 60;
+
+/// Number of characters of restored text shown by `undo`
+const UNDO_PREVIEW_LENGTH: usize = 60;
+
+/// How often `logs tail --follow` checks the log file for new lines
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Number of input characters shown by `queue list`
+const QUEUE_PREVIEW_LENGTH: usize = 50;
+
+/// How long a command waits for a background update check to finish
+const UPDATE_CHECK_WAIT: Duration = Duration::from_secs(2);
 
 /// Execute the rephrase command
-pub async fn rephrase(action: &str, text: &str) -> Result<()> {
+///
+/// `llm` and `output` override the configured LLM settings and output
+/// method for this run
+pub async fn rephrase(
+    opts: &GlobalArgs,
+    action: &str,
+    input: &InputArgs,
+    llm: &LlmArgs,
+    output: Option<OutputMethod>,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
     // Load configuration
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    configure_llm(&mut config, action, llm);
+    let text = match &input.file {
+        Some(path) if input.chunked => condense_file(opts, &config, action, path, llm).await?,
+        _ => read_input(input, &config)?,
+    };
+    if let Some(method) = output {
+        config.output.method = method;
+    }
+
+    // Create LLM client based on config
+    let client = create_llm_client(&config)?;
+
+    // Resolve action, call LLM API and handle output
+    let request = Request::for_action(&config, action, text.clone()).await?;
+    match execute(opts, &config, client.as_ref(), &request).await {
+        Err(e) if config.queue.enabled && e.is_retryable() => {
+            debug!("Queueing '{}': {}", action, e);
+            QueueStore::new(&config_manager.queue_dir()).push(&QueuedJob {
+                action: action.to_string(),
+                text,
+                provider: llm.provider.clone(),
+                model: llm.model.clone(),
+                temperature: llm.temperature,
+                queued_at: Local::now(),
+            })?;
+            if !opts.quiet {
+                eprintln!("{}", t!("queued-job", action = action));
+            }
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Execute the rephrase command with several actions on the same text
+///
+/// The actions run concurrently; their results are delivered together,
+/// each labeled with its action
+pub async fn rephrase_many(
+    opts: &GlobalArgs,
+    actions: &[String],
+    input: &InputArgs,
+    llm: &LlmArgs,
+    output: Option<OutputMethod>,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    apply_llm_overrides(&mut config, llm);
+    let text = read_input(input, &config)?;
+    if let Some(method) = output {
+        config.output.method = method;
+    }
+
+    let client = create_llm_client(&config)?;
+    let label = actions.join(" + ");
+    let output_handler = OutputHandler::from_config(&config.output)
+        .with_plugins(&config.plugins)
+        .with_context(output_context(&config, client.as_ref(), &label));
+
+    loop {
+        let runs = run_actions(opts, &config, actions, &text, llm).await?;
+        let combined = combine_results(&config, runs)?;
+
+        if config.output.method != OutputMethod::Dialog {
+            return output_handler.handle(&combined);
+        }
+        match output_handler.show_dialog(&combined)? {
+            DialogAction::Copy(edited) => return output_handler.copy_to_clipboard(&edited),
+            DialogAction::Retry => continue,
+            DialogAction::Cancel => return Ok(()),
+        }
+    }
+}
+
+/// Render the results of several actions as one text
+///
+/// A single result is returned as-is; several are each labeled with their
+/// action
+fn combine_results(config: &crate::config::Config, runs: Vec<ActionRun>) -> Result<String> {
+    let labeled = runs.len() > 1;
+    let mut sections = Vec::new();
+    for run in runs {
+        let context = output_context(&run.config, run.client.as_ref(), &run.action);
+        let response = context.wrap(config.output.template.as_deref(), run.completion.text)?;
+        if labeled {
+            sections.push(format!("== {} ==\n{}", run.action, response));
+        } else {
+            sections.push(response);
+        }
+    }
+    Ok(sections.join("\n\n"))
+}
+
+/// Result of a rephrase, printed by `rephrase --json`
+#[derive(Debug, Serialize)]
+struct JsonOutput<'a> {
+    action: &'a str,
+    input: &'a str,
+    output: &'a str,
+    provider: &'a str,
+    model: &'a str,
+    tokens: TokenUsage,
+    duration_ms: u128,
+}
+
+/// Execute the rephrase command, printing the result as JSON on stdout
+///
+/// A single action prints one object; several actions print an array of
+/// objects in the order given. The configured output method is bypassed
+/// entirely
+pub async fn rephrase_json(
+    opts: &GlobalArgs,
+    actions: &[String],
+    input: &InputArgs,
+    llm: &LlmArgs,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let text = read_input(input, &config)?;
+
+    let runs = run_actions(opts, &config, actions, &text, llm).await?;
+    let outputs: Vec<JsonOutput> = runs
+        .iter()
+        .map(|run| JsonOutput {
+            action: &run.action,
+            input: &text,
+            output: &run.completion.text,
+            provider: run.provider_and_model().0,
+            model: run.provider_and_model().1,
+            tokens: run.completion.usage,
+            duration_ms: run.duration.as_millis(),
+        })
+        .collect();
+
+    match outputs.as_slice() {
+        [output] => println!("{}", serde_json::to_string(output)?),
+        _ => println!("{}", serde_json::to_string(&outputs)?),
+    }
+
+    Ok(())
+}
+
+/// Execute the rephrase command for a Raycast script command
+///
+/// The result is copied to the clipboard and a one-line summary is printed,
+/// which Raycast shows as a HUD in silent mode
+pub async fn rephrase_raycast(
+    opts: &GlobalArgs,
+    actions: &[String],
+    input: &InputArgs,
+    llm: &LlmArgs,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let text = read_input(input, &config)?;
+
+    let runs = run_actions(opts, &config, actions, &text, llm).await?;
+    let names: Vec<String> = runs
+        .iter()
+        .map(|run| {
+            output_context(&run.config, run.client.as_ref(), &run.action).action_display_name
+        })
+        .collect();
+    let combined = combine_results(&config, runs)?;
+
+    OutputHandler::from_config(&config.output).copy_to_clipboard(&combined)?;
+    println!("{}", t!("copied-results", names = names.join(" + ")));
+
+    Ok(())
+}
+
+/// Execute the rephrase command for an Alfred Script Filter
+///
+/// Prints one item per action result. Failures are shown as an item too,
+/// since Alfred does not display stderr
+pub async fn rephrase_alfred(
+    opts: &GlobalArgs,
+    actions: &[String],
+    input: &InputArgs,
+    llm: &LlmArgs,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let result = alfred_results(opts, actions, input, llm).await;
+    let items = match &result {
+        Ok(items) => items
+            .iter()
+            .map(|(label, output)| alfred::result_item(label, output))
+            .collect(),
+        Err(e) => vec![alfred::error_item(&e.to_string())],
+    };
+    println!(
+        "{}",
+        serde_json::to_string(&alfred::ScriptFilter::new(items))?
+    );
+
+    result.map(|_| ())
+}
+
+/// Run the actions, returning each display name with its result
+async fn alfred_results(
+    opts: &GlobalArgs,
+    actions: &[String],
+    input: &InputArgs,
+    llm: &LlmOverrides,
+) -> Result<Vec<(String, String)>> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let text = read_input(input, &config)?;
+
+    let runs = run_actions(opts, &config, actions, &text, llm).await?;
+    runs.into_iter()
+        .map(|run| {
+            let context = output_context(&run.config, run.client.as_ref(), &run.action);
+            let response = context.wrap(config.output.template.as_deref(), run.completion.text)?;
+            Ok((context.action_display_name, response))
+        })
+        .collect()
+}
+
+/// Show what the rephrase command would send, without calling the API
+pub async fn dry_run(actions: &[String], input: &InputArgs, llm: &LlmArgs) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let base_config = config_manager.load()?;
+    let text = read_input(input, &base_config)?;
+
+    for (index, action) in actions.iter().enumerate() {
+        let mut config = base_config.clone();
+        configure_llm(&mut config, action, llm);
+
+        let resolver = ActionResolver::new(&config);
+        let prompt = resolver.resolve(action, &text)?;
+
+        if actions.len() > 1 {
+            if index > 0 {
+                println!();
+            }
+            println!("== {} ==", action);
+        }
+        println!("Provider: {}", config.llm.provider);
+        println!("Model: {}", config.llm.model);
+        println!("Temperature: {}", config.llm.parameters.temperature);
+        println!("Estimated prompt tokens: ~{}", estimate_tokens(&prompt));
+        println!("Max response tokens: {}", config.llm.parameters.max_tokens);
+        if let Some(timeout) = config.llm.parameters.timeout_secs {
+            println!("Timeout: {}s", timeout);
+        }
+        println!();
+        println!("{}", prompt);
+    }
+
+    Ok(())
+}
+
+/// Execute the selection command
+///
+/// Reads the current selection, transforms it and pastes the result back
+pub async fn selection(opts: &GlobalArgs, action: &str) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    configure_llm(&mut config, action, &LlmOverrides::default());
+
+    // Fail early on unknown actions, before touching the clipboard
+    let resolver = ActionResolver::new(&config);
+    if resolver.find_action(action).is_none() {
+        return Err(RephraserError::ActionNotFound(action.to_string()));
+    }
+
+    // Pasting replaces the clipboard; keep what was there for `undo`
+    stash_clipboard(config.output.undo_depth);
+    let text = read_selection()?;
+
+    let client = create_llm_client(&config)?;
+    let request = Request::for_action(&config, action, text).await?;
+    let response = run_action(opts, &config, client.as_ref(), &request).await?;
+
+    let output_handler = OutputHandler::from_config(&config.output)
+        .with_context(output_context(&config, client.as_ref(), action))
+        .with_method(OutputMethod::Paste);
+    output_handler.handle(&response)?;
+    request.hooks.after(action, &response).await;
+
+    Ok(())
+}
+
+/// Execute the prompt command
+///
+/// Sends the text to the LLM as the prompt itself, with the standard output
+/// handling
+pub async fn prompt(
+    opts: &GlobalArgs,
+    input: &InputArgs,
+    llm: &LlmArgs,
+    output: Option<OutputMethod>,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    apply_llm_overrides(&mut config, llm);
+    let text = read_input(input, &config)?;
+    if let Some(method) = output {
+        config.output.method = method;
+    }
+
+    let client = create_llm_client(&config)?;
+    execute(opts, &config, client.as_ref(), &Request::ad_hoc(text)).await?;
+
+    Ok(())
+}
+
+/// Execute the proofread command
+///
+/// Asks for the mistakes in the text as structured corrections and shows
+/// them as a report, an inline diff or JSON, leaving the text itself alone
+pub async fn proofread(
+    opts: &GlobalArgs,
+    input: &InputArgs,
+    llm: &LlmArgs,
+    format: ProofreadFormat,
+    output: Option<OutputMethod>,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    apply_llm_overrides(&mut config, llm);
+    let text = read_input(input, &config)?;
+    config.output.method = output.unwrap_or(OutputMethod::Terminal);
+    // The corrections are parsed from the raw response
+    config.output.template = None;
+
+    let client = create_llm_client(&config)?;
+    let request = Request::proofread(text);
+    let response = run_action(opts, &config, client.as_ref(), &request).await?;
+    let corrections = proofread::parse(&response, &request.text)?;
+
+    let rendered = match format {
+        ProofreadFormat::Report => proofread::report(&request.text, &corrections),
+        ProofreadFormat::Diff => proofread::diff(&request.text, &corrections),
+        ProofreadFormat::Json => serde_json::to_string_pretty(&corrections)?,
+    };
+    OutputHandler::from_config(&config.output)
+        .with_plugins(&config.plugins)
+        .with_context(output_context(&config, client.as_ref(), PROOFREAD_ACTION))
+        .handle(&rendered)?;
+
+    Ok(())
+}
+
+/// Execute the compare command
+///
+/// Runs the action against every model as a batch and prints each result
+/// with its latency, token usage and estimated cost
+pub async fn compare(
+    opts: &GlobalArgs,
+    action: &str,
+    input: &InputArgs,
+    models: &[String],
+) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    configure_llm(&mut config, action, &LlmOverrides::default());
+    // Cached responses would make the latency comparison meaningless
+    config.cache.enabled = false;
+    let text = read_input(input, &config)?;
+
+    let specs = if models.is_empty() {
+        config.compare.models.clone()
+    } else {
+        models.to_vec()
+    };
+    if specs.len() < 2 {
+        return Err(RephraserError::Config(
+            "Specify at least two models with --model or compare.models".to_string(),
+        ));
+    }
+
+    let resolver = ActionResolver::new(&config);
+    let prompt = resolver.resolve(action, &text)?;
+    let rules = resolver
+        .find_action(action)
+        .and_then(|a| a.validate.clone());
+    let plugins = PluginRegistry::from_config(&config.plugins);
+    let llms: Vec<LlmConfig> = specs
+        .iter()
+        .map(|spec| {
+            config
+                .llm
+                .for_model_spec(spec, |provider| plugins.provider(provider).is_some())
+        })
+        .collect();
+
+    let batch = Batch::from_config(&config.batch).with_progress(
+        format!("Comparing {} models for {}", specs.len(), action),
+        !opts.quiet,
+    );
+    let results = batch
+        .run(&llms, |llm| {
+            let mut run_config = config.clone();
+            run_config.llm = llm.clone();
+            let (prompt, rules) = (&prompt, &rules);
+            async move {
+                let client = create_llm_client(&run_config)?;
+                let started = Instant::now();
+                let result = complete_validated(client.as_ref(), prompt, rules.as_ref()).await;
+                let elapsed = started.elapsed();
+                record_usage(
+                    &run_config,
+                    action,
+                    client.as_ref(),
+                    result.as_ref(),
+                    elapsed,
+                );
+                result.map(|completion| (completion, elapsed))
+            }
+        })
+        .await;
+
+    let columns: Vec<CompareColumn> = llms
+        .iter()
+        .zip(results.into_results())
+        .map(|(llm, result)| {
+            let label = format!("{}/{}", llm.provider, llm.model);
+            match result {
+                Ok((completion, elapsed)) => {
+                    let cost = estimate_cost(&llm.model, completion.usage)
+                        .map(|cost| format!("  ~${:.6}", cost))
+                        .unwrap_or_default();
+                    let stats = format!(
+                        "{} ms  {} → {} tokens{}",
+                        elapsed.as_millis(),
+                        completion.usage.prompt_tokens,
+                        completion.usage.completion_tokens,
+                        cost
+                    );
+                    CompareColumn {
+                        label,
+                        stats,
+                        text: completion.text,
+                    }
+                }
+                Err(e) => CompareColumn {
+                    label,
+                    stats: "failed".to_string(),
+                    text: e.to_string(),
+                },
+            }
+        })
+        .collect();
+
+    let width = std::io::stdout()
+        .is_terminal()
+        .then(|| console::Term::stdout().size_checked())
+        .flatten()
+        .map(|(_, columns)| usize::from(columns));
+    match width {
+        Some(width) if width >= columns_width(columns.len()) => {
+            for line in render_columns(&columns, width) {
+                println!("{}", line);
+            }
+        }
+        _ => print_stacked(&columns),
+    }
+
+    Ok(())
+}
+
+/// Narrowest column `compare` shows results in side by side
+const MIN_COMPARE_COLUMN: usize = 30;
+
+/// Separator between the columns of `compare`
+const COLUMN_SEPARATOR: &str = " │ ";
+
+/// Result of one model in `compare`
+struct CompareColumn {
+    label: String,
+    stats: String,
+    text: String,
+}
+
+/// Terminal width needed to show `count` results side by side
+fn columns_width(count: usize) -> usize {
+    count * MIN_COMPARE_COLUMN + count.saturating_sub(1) * text::width(COLUMN_SEPARATOR)
+}
+
+/// Print the results one after another
+fn print_stacked(columns: &[CompareColumn]) {
+    for column in columns {
+        println!("== {}  {}", column.label, column.stats);
+        println!("{}", column.text);
+        println!();
+    }
+}
+
+/// Lines showing the results next to each other, one column per model, in
+/// `width` terminal columns
+fn render_columns(columns: &[CompareColumn], width: usize) -> Vec<String> {
+    let separators = columns.len().saturating_sub(1) * text::width(COLUMN_SEPARATOR);
+    let column_width = (width - separators) / columns.len();
+
+    let cells: Vec<Vec<String>> = columns
+        .iter()
+        .map(|column| {
+            let mut lines: Vec<String> = text::wrap(&column.label, column_width)
+                .into_iter()
+                .chain(text::wrap(&column.stats, column_width))
+                .map(str::to_string)
+                .collect();
+            lines.push("─".repeat(column_width));
+            lines.extend(
+                text::wrap(&column.text, column_width)
+                    .into_iter()
+                    .map(str::to_string),
+            );
+            lines
+        })
+        .collect();
+
+    let rows = cells.iter().map(Vec::len).max().unwrap_or(0);
+    (0..rows)
+        .map(|row| {
+            let line: Vec<String> = cells
+                .iter()
+                .map(|lines| {
+                    let cell = lines.get(row).map_or("", String::as_str);
+                    let padding = column_width.saturating_sub(text::width(cell));
+                    format!("{}{}", cell, " ".repeat(padding))
+                })
+                .collect();
+            line.join(COLUMN_SEPARATOR).trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Execute the auto command
+///
+/// Asks the LLM to choose the best action for the text, then runs it
+pub async fn auto(
+    opts: &GlobalArgs,
+    input: &InputArgs,
+    llm: &LlmArgs,
+    output: Option<OutputMethod>,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    apply_llm_overrides(&mut config, llm);
+    let text = read_input(input, &config)?;
+    if let Some(method) = output {
+        config.output.method = method;
+    }
+
+    let client = create_llm_client(&config)?;
+    let prepared = {
+        let _spinner = Spinner::start(
+            format!(
+                "Choosing action ({}/{})",
+                client.provider_name(),
+                client.model_name()
+            ),
+            !opts.quiet,
+        );
+        choose_action(&config, client.as_ref(), &text, llm).await?
+    };
+    execute(
+        opts,
+        &prepared.config,
+        prepared.client.as_ref(),
+        &prepared.request,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// List all available actions
+///
+/// `long` adds details for each action; `json` prints the action
+/// definitions for frontends
+pub async fn list_actions(
+    opts: &GlobalArgs,
+    long: bool,
+    json: bool,
+    format: Option<OutputFormat>,
+) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+
+    let resolver = ActionResolver::new(&config);
+    let actions = resolver.list_actions();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(actions)?);
+        return Ok(());
+    }
+    match format {
+        Some(OutputFormat::Alfred) => {
+            let items = actions.iter().map(alfred::action_item).collect();
+            println!(
+                "{}",
+                serde_json::to_string(&alfred::ScriptFilter::new(items))?
+            );
+            return Ok(());
+        }
+        Some(OutputFormat::Raycast) => {
+            return Err(RephraserError::Input(
+                "list-actions does not support --format raycast; use `rephraser raycast generate`"
+                    .to_string(),
+            ));
+        }
+        None => {}
+    }
+
+    if !opts.quiet {
+        println!("{}", t!("available-actions"));
+        println!();
+    }
+
+    for action in actions {
+        println!("  {} ({})", action.name, action.display_name);
+        if !long {
+            continue;
+        }
+
+        if let Some(description) = &action.description {
+            println!("      {}", description);
+        }
+        if !action.tags.is_empty() {
+            println!("      Tags: {}", action.tags.join(", "));
+        }
+        if action.provider.is_some() || action.model.is_some() {
+            println!(
+                "      LLM: {}/{}",
+                action.provider.as_deref().unwrap_or(&config.llm.provider),
+                action.model.as_deref().unwrap_or(&config.llm.model)
+            );
+        }
+        println!(
+            "      Template: {}",
+            preview(&action.prompt_template, TEMPLATE_PREVIEW_LENGTH)
+        );
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Execute the watch command
+///
+/// Runs the action on the file now and again after every change, writing
+/// each result to `output_file`. Failed runs are reported and watching
+/// continues; stop with Ctrl-C.
+pub async fn watch(
+    opts: &GlobalArgs,
+    action: &str,
+    file: &Path,
+    output_file: Option<&Path>,
+    debounce: Duration,
+    llm: &LlmArgs,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    configure_llm(&mut config, action, llm);
+
+    // Fail early on unknown actions, before waiting for changes
+    if ActionResolver::new(&config).find_action(action).is_none() {
+        return Err(RephraserError::ActionNotFound(action.to_string()));
+    }
+
+    let client = create_llm_client(&config)?;
+    let output_file =
+        output_file.map_or_else(|| watch_output_path(file, action), Path::to_path_buf);
+    let mut watcher = FileWatcher::new(file, debounce);
+    let mut signals = Signals::new()?;
+    info(
+        opts,
+        format!(
+            "Watching {}, writing results to {} (Ctrl-C to stop)",
+            file.display(),
+            output_file.display()
+        ),
+    );
+
+    let mut last_text: Option<String> = None;
+    loop {
+        match read_file(file, config.input.max_file_size) {
+            // Saving without edits touches the file but needs no new result
+            Ok(text) if last_text.as_ref() == Some(&text) => {}
+            Ok(text) => {
+                let request = Request::for_action(&config, action, text.clone()).await?;
+                let run = run_action(opts, &config, client.as_ref(), &request);
+                let Some(result) = signals.finish(run).await else {
+                    return Ok(());
+                };
+                match result {
+                    Ok(response) => {
+                        std::fs::write(&output_file, &response).map_err(|e| {
+                            RephraserError::Output(format!(
+                                "Failed to write {}: {}",
+                                output_file.display(),
+                                e
+                            ))
+                        })?;
+                        info(opts, t!("updated-file", path = output_file.display()));
+                        request.hooks.after(action, &response).await;
+                        last_text = Some(text);
+                    }
+                    Err(e) => eprintln!("{}", t!("error", error = e)),
+                }
+            }
+            Err(e) => eprintln!("{}", t!("error", error = e)),
+        }
+        if signals.stopping() {
+            return Ok(());
+        }
+
+        tokio::select! {
+            _ = watcher.changed() => {}
+            _ = signals.recv() => return Ok(()),
+        }
+    }
+}
+
+/// One line of `rephraser batch` output
+#[derive(Debug, Serialize)]
+struct BatchLine<'a> {
+    line: usize,
+    input: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_code: Option<ErrorCode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retryable: Option<bool>,
+}
+
+/// Run an action on each line of the input as a batch
+///
+/// Blank lines are skipped. A JSON line per input line is printed in input
+/// order, with the output or the error; the first failure is returned at
+/// the end
+pub async fn batch(
+    opts: &GlobalArgs,
+    action: &str,
+    file: Option<&Path>,
+    jobs: Option<usize>,
+    llm: &LlmArgs,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config = ConfigManager::new()?.load()?;
+    let input = match file {
+        Some(path) => read_file(path, config.input.max_file_size)?,
+        None => read_stdin()?,
+    };
+    let lines: Vec<(usize, &str)> = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| (index + 1, line))
+        .collect();
+
+    let batch = Batch::from_config(&config.batch)
+        .with_concurrency(jobs)
+        .with_progress(format!("Running {}", action), !opts.quiet);
+    let results = batch
+        .run(&lines, |(_, text)| {
+            pipeline::run(&config, action, text, llm)
+        })
+        .await;
+    let summary = results.error_summary(|index| format!("line {}", lines[index].0));
+
+    let mut first_error = None;
+    for (&(line, input), result) in lines.iter().zip(results.into_results()) {
+        let json = match &result {
+            Ok(run) => serde_json::to_string(&BatchLine {
+                line,
+                input,
+                output: Some(&run.completion.text),
+                error: None,
+                error_code: None,
+                retryable: None,
+            })?,
+            Err(e) => serde_json::to_string(&BatchLine {
+                line,
+                input,
+                output: None,
+                error: Some(e.to_string()),
+                error_code: Some(e.code()),
+                retryable: Some(e.is_retryable()),
+            })?,
+        };
+        println!("{}", json);
+        if let Err(e) = result {
+            first_error.get_or_insert(e);
+        }
+    }
+    if let Some(summary) = summary {
+        eprintln!("{}", summary);
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Run an action on each file as a batch, writing each result next to it
+///
+/// A file that fails is reported and the rest are still processed; the
+/// first failure is returned at the end. With `notify`, a notification
+/// summarizes the outcome, for the Finder Quick Action
+pub async fn rephrase_files(
+    opts: &GlobalArgs,
+    action: &str,
+    files: &[PathBuf],
+    jobs: Option<usize>,
+    llm: &LlmArgs,
+    notify: bool,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config = ConfigManager::new()?.load()?;
+
+    let batch = Batch::from_config(&config.batch)
+        .with_concurrency(jobs)
+        .with_progress(format!("Running {}", action), !opts.quiet);
+    let results = batch
+        .run(files, |file| {
+            let config = &config;
+            async move {
+                let text = read_file(file, config.input.max_file_size)?;
+                let run = pipeline::run(config, action, &text, llm).await?;
+                let output_file = watch_output_path(file, action);
+                std::fs::write(&output_file, combine_results(config, vec![run])?).map_err(|e| {
+                    RephraserError::Output(format!(
+                        "Failed to write {}: {}",
+                        output_file.display(),
+                        e
+                    ))
+                })?;
+                Ok(output_file)
+            }
+        })
+        .await;
+
+    // A single failure is reported by the caller
+    let summary = results
+        .error_summary(|index| files[index].display().to_string())
+        .filter(|_| files.len() > 1);
+
+    let mut done = 0;
+    let mut first_error = None;
+    for result in results.into_results() {
+        match result {
+            Ok(output_file) => {
+                done += 1;
+                info(opts, t!("wrote-file", path = output_file.display()));
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    if let Some(summary) = summary {
+        eprintln!("{}", summary);
+    }
+
+    if notify {
+        let body = t!("rephrased-files", done = done, total = files.len());
+        let notified = platform::current().notify(&platform::Notification {
+            title: "Rephraser",
+            subtitle: first_error.as_ref().map(|e| e.to_string()).as_deref(),
+            body: &body,
+            copy_on_click: None,
+        });
+        if let Err(e) = notified {
+            warn!("Failed to show a notification: {}", e);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// Watch the clipboard and transform copied text matching a rule
+///
+/// Each new clipboard text is checked against `[[clipboard_watch.rules]]`;
+/// the first matching rule's action is applied and the result replaces the
+/// clipboard contents. Failures are reported and watching continues; stop
+/// with Ctrl-C.
+pub async fn watch_clipboard(opts: &GlobalArgs) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let rules = &config.clipboard_watch.rules;
+    if rules.is_empty() {
+        return Err(RephraserError::Config(
+            "No clipboard rules configured; add [[clipboard_watch.rules]] to the config file"
+                .to_string(),
+        ));
+    }
+    // Asking System Events costs a process launch, so only do it when needed
+    let needs_app = rules.iter().any(|rule| !rule.apps.is_empty());
+
+    let interval = Duration::from_millis(config.clipboard_watch.poll_interval_ms);
+    let mut watcher = ClipboardWatcher::new(interval);
+    let mut signals = Signals::new()?;
+    info(opts, t!("watching-clipboard"));
+
+    loop {
+        let text = tokio::select! {
+            text = watcher.changed() => text,
+            _ = signals.recv() => return Ok(()),
+        };
+
+        let app = if needs_app {
+            frontmost_app().ok()
+        } else {
+            None
+        };
+        let rule = match find_rule(rules, &text, app.as_deref()) {
+            Ok(Some(rule)) => rule,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("{}", t!("error", error = e));
+                continue;
+            }
+        };
+
+        match app.as_deref() {
+            Some(app) => info(
+                opts,
+                t!("applying-action-from-app", action = rule.action, app = app),
+            ),
+            None => info(opts, t!("applying-action", action = rule.action)),
+        }
+        match signals
+            .finish(copy_transformed(&config, &rule.action, &text))
+            .await
+        {
+            Some(Ok(())) => {}
+            Some(Err(e)) => eprintln!("{}", t!("error", error = e)),
+            None => return Ok(()),
+        }
+        if signals.stopping() {
+            return Ok(());
+        }
+        watcher.skip_current();
+    }
+}
+
+/// Execute the redo command
+///
+/// Replays the most recent history entry, optionally with a different
+/// action, provider or model
+pub async fn redo(
+    opts: &GlobalArgs,
+    action: Option<&str>,
+    llm: &LlmArgs,
+    output: Option<OutputMethod>,
+) -> Result<()> {
+    let llm = &LlmOverrides::from(llm);
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+    if let Some(method) = output {
+        config.output.method = method;
+    }
+
+    let store = HistoryStore::open(&config_manager.history_path())?;
+    let entry = store
+        .latest()?
+        .ok_or_else(|| RephraserError::Other("History is empty, nothing to redo".to_string()))?;
+    let text = entry.input.ok_or_else(|| {
+        RephraserError::Other(format!(
+            "History entry {} has no stored input and cannot be replayed",
+            entry.id
+        ))
+    })?;
+    let action = action.unwrap_or(&entry.action);
+    configure_llm(&mut config, action, llm);
+    // Replaying asks for a new response, not the one already given
+    config.cache.refresh = true;
+
+    let request = if action == AD_HOC_ACTION {
+        Request::ad_hoc(text)
+    } else {
+        Request::for_action(&config, action, text).await?
+    };
+    let client = create_llm_client(&config)?;
+    execute(opts, &config, client.as_ref(), &request).await?;
+
+    Ok(())
+}
+
+/// List the most recent history entries
+pub async fn history(opts: &GlobalArgs, limit: usize) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let store = HistoryStore::open(&config_manager.history_path())?;
+    print_history(opts, &store.list(None, limit)?);
+    Ok(())
+}
+
+/// Search the history for entries containing every word of `query`
+pub async fn history_search(opts: &GlobalArgs, query: &str, limit: usize) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let store = HistoryStore::open(&config_manager.history_path())?;
+    print_history(opts, &store.search(query, limit)?);
+    Ok(())
+}
+
+/// Show a history entry in full
+pub async fn history_show(id: i64) -> Result<()> {
+    let entry = history_entry(id)?;
+
+    println!("Entry:    {}", entry.id);
+    println!("Date:     {}", entry.created_at);
+    println!("Action:   {}", entry.action);
+    println!("Model:    {}/{}", entry.provider, entry.model);
+    if let Some(duration_ms) = entry.duration_ms {
+        println!("Duration: {} ms", duration_ms);
+    }
+    if let Some(cost) = entry.cost_usd {
+        println!("Cost:     ~${:.6}", cost);
+    }
+
+    println!();
+    println!("== Input ==");
+    println!("{}", entry.input.as_deref().unwrap_or(&entry.input_preview));
+    if let Some(prompt) = &entry.prompt {
+        println!();
+        println!("== Prompt ==");
+        println!("{}", prompt);
+    }
+    println!();
+    println!("== Output ==");
+    println!("{}", entry.output);
+
+    Ok(())
+}
+
+/// Copy the output of a history entry to the clipboard
+pub async fn history_copy(opts: &GlobalArgs, id: i64) -> Result<()> {
+    let entry = history_entry(id)?;
+    let config = ConfigManager::new()?.load()?;
+    OutputHandler::from_config(&config.output).copy_to_clipboard(&entry.output)?;
+    info(opts, t!("copied-history-entry", id = id));
+    Ok(())
+}
+
+fn history_entry(id: i64) -> Result<HistoryEntry> {
+    let config_manager = ConfigManager::new()?;
+    let store = HistoryStore::open(&config_manager.history_path())?;
+    store
+        .get(id)?
+        .ok_or_else(|| RephraserError::Other(format!("History entry {} not found", id)))
+}
+
+fn print_history(opts: &GlobalArgs, entries: &[HistoryEntry]) {
+    if entries.is_empty() {
+        info(opts, t!("no-history-entries"));
+        return;
+    }
+
+    for entry in entries {
+        println!(
+            "{:>5}  {}  {} ({}/{})",
+            entry.id, entry.created_at, entry.action, entry.provider, entry.model
+        );
+        println!("       > {}", entry.input_preview);
+        for line in entry.output.lines() {
+            println!("       {}", line);
+        }
+        println!();
+    }
+}
+
+/// Put the clipboard contents saved before the last overwrite back
+///
+/// With `paste`, the restored text is also pasted into the frontmost app,
+/// e.g. over a bad result that was pasted in place and is still selected
+pub async fn undo(opts: &GlobalArgs, paste: bool) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let stack = UndoStack::new(&config_manager.undo_path(), config.output.undo_depth);
+
+    let text = stack
+        .pop()?
+        .ok_or_else(|| RephraserError::Other("Nothing to undo".to_string()))?;
+    clipboard::write_text(&text)?;
+    if paste {
+        paste_clipboard()?;
+    }
+
+    info(
+        opts,
+        t!(
+            "restored-clipboard",
+            text = preview(&text, UNDO_PREVIEW_LENGTH)
+        ),
+    );
+    Ok(())
+}
+
+/// Summarize token usage and estimated spend from the usage store
+pub async fn cost(since: Option<&str>, tag: Option<&str>, grouping: Grouping) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let store = UsageStore::open(&config_manager.usage_path())?;
+
+    let today = chrono::Local::now().date_naive();
+    let since = since.map(|value| parse_since(value, today)).transpose()?;
+    let rows = store.cost_summary(since, tag, grouping)?;
+
+    let total = CostRow {
+        key: "Total".to_string(),
+        requests: rows.iter().map(|row| row.requests).sum(),
+        prompt_tokens: rows.iter().map(|row| row.prompt_tokens).sum(),
+        completion_tokens: rows.iter().map(|row| row.completion_tokens).sum(),
+        cost_usd: rows.iter().map(|row| row.cost_usd).sum(),
+    };
+
+    let title = grouping.title();
+    let width = rows
+        .iter()
+        .map(|row| row.key.chars().count())
+        .chain([title.len(), total.key.len()])
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "{:<width$}  {:>8}  {:>10}  {:>10}  {:>10}",
+        title, "Requests", "Prompt", "Completion", "Cost (USD)"
+    );
+    for row in rows.iter().chain(std::iter::once(&total)) {
+        println!(
+            "{:<width$}  {:>8}  {:>10}  {:>10}  {:>10.4}",
+            row.key, row.requests, row.prompt_tokens, row.completion_tokens, row.cost_usd
+        );
+    }
+
+    Ok(())
+}
+
+/// Report request counts, failure rates and latency percentiles from the usage store
+pub async fn stats(since: Option<&str>, grouping: Grouping) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let store = UsageStore::open(&config_manager.usage_path())?;
+
+    let today = chrono::Local::now().date_naive();
+    let since = since.map(|value| parse_since(value, today)).transpose()?;
+    let rows = store.performance_summary(since, grouping)?;
+
+    if rows.is_empty() {
+        println!("{}", t!("no-requests-recorded"));
+        return Ok(());
+    }
+
+    let title = grouping.title();
+    let width = rows
+        .iter()
+        .map(|row| row.key.chars().count())
+        .chain([title.len()])
+        .max()
+        .unwrap_or_default();
+    let latency =
+        |value: Option<u64>| value.map_or_else(|| "-".to_string(), |ms| format!("{} ms", ms));
+
+    println!(
+        "{:<width$}  {:>8}  {:>8}  {:>10}  {:>10}  {:>10}",
+        title, "Requests", "Failed", "p50", "p90", "p99"
+    );
+    for row in &rows {
+        println!(
+            "{:<width$}  {:>8}  {:>7.1}%  {:>10}  {:>10}  {:>10}",
+            row.key,
+            row.requests,
+            row.failure_rate() * 100.0,
+            latency(row.p50_ms),
+            latency(row.p90_ms),
+            latency(row.p99_ms)
+        );
+    }
+
+    Ok(())
+}
+
+/// Print the last `lines` lines of the log file
+///
+/// With `follow`, keep printing lines as they are appended (Ctrl-C to stop),
+/// starting over when the file is rotated
+pub async fn logs_tail(lines: usize, follow: bool) -> Result<()> {
+    let path = ConfigManager::new()?.log_dir().join(LOG_FILE_NAME);
+    let content = std::fs::read(&path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            RephraserError::Other(format!("No log file at {}", path.display()))
+        }
+        _ => e.into(),
+    })?;
+
+    let text = String::from_utf8_lossy(&content);
+    let skip = text.lines().count().saturating_sub(lines);
+    for line in text.lines().skip(skip) {
+        println!("{}", line);
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut offset = content.len() as u64;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(LOG_POLL_INTERVAL) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+
+        let Ok(mut file) = std::fs::File::open(&path) else {
+            continue;
+        };
+        let length = file.metadata()?.len();
+        if length < offset {
+            offset = 0;
+        }
+        if length == offset {
+            continue;
+        }
+
+        let mut appended = Vec::new();
+        file.seek(std::io::SeekFrom::Start(offset))?;
+        file.read_to_end(&mut appended)?;
+        offset += appended.len() as u64;
+        print!("{}", String::from_utf8_lossy(&appended));
+        std::io::stdout().flush()?;
+    }
+}
+
+/// Show the number and total size of cached responses
+pub async fn cache_stats() -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let stats = response_cache(&config_manager, &config).stats()?;
+
+    println!("Entries: {}", stats.entries);
+    println!(
+        "Size:    {:.1} MB of {} MB",
+        stats.bytes as f64 / BYTES_PER_MB as f64,
+        config.cache.max_size_mb
+    );
+    println!("TTL:     {} hours", config.cache.ttl_hours);
+    if !config.cache.enabled {
+        println!("{}", t!("caching-disabled"));
+    }
+    Ok(())
+}
+
+/// Delete every cached response
+pub async fn cache_clear(opts: &GlobalArgs) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let removed = response_cache(&config_manager, &config).clear()?;
+    info(opts, t!("removed-cached-responses", count = removed));
+    Ok(())
+}
+
+/// Store a secret in the encrypted secrets file, reading its value from
+/// stdin
+pub async fn secrets_set(opts: &GlobalArgs, name: &str) -> Result<()> {
+    let config = ConfigManager::new()?.load()?;
+
+    // Typed secrets are not echoed; piped ones are read as they come
+    let value = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(format!("Value for '{}': ", name))?
+    } else {
+        let mut value = String::new();
+        std::io::stdin().read_line(&mut value)?;
+        value
+    };
+    let value = value.trim_end_matches(['\r', '\n']);
+    if value.is_empty() {
+        return Err(RephraserError::Input("No value given".to_string()));
+    }
+
+    let store = EncryptedFileStore::from_config(&config.secrets);
+    store.set(name, value)?;
+    // Clients built with the old value would keep using it
+    ClientRegistry::global().clear();
+    info(
+        opts,
+        t!("stored-secret", name = name, file = config.secrets.file),
+    );
+    Ok(())
+}
+
+/// List queued jobs, oldest first
+pub async fn queue_list() -> Result<()> {
+    let jobs = QueueStore::new(&ConfigManager::new()?.queue_dir()).jobs()?;
+    if jobs.is_empty() {
+        println!("{}", t!("queue-empty"));
+        return Ok(());
+    }
+    for (_, job) in jobs {
+        println!(
+            "{}  {:<16} {}",
+            job.queued_at.format("%Y-%m-%d %H:%M"),
+            job.action,
+            preview(&job.text, QUEUE_PREVIEW_LENGTH)
+        );
+    }
+    Ok(())
+}
+
+/// Run queued jobs, printing each result
+///
+/// Stops at the first job that fails because the provider is still
+/// unavailable; jobs that fail for other reasons are kept and skipped
+pub async fn queue_run(opts: &GlobalArgs) -> Result<()> {
+    let config = ConfigManager::new()?.load()?;
+    let result = process_queue(opts, &config, |run| {
+        println!("== {} ==\n{}\n", run.action, run.completion.text);
+    })
+    .await;
+
+    match result {
+        Ok(0) => info(opts, t!("queue-empty")),
+        Ok(count) => info(opts, t!("ran-queued-jobs", count = count)),
+        Err(e) => {
+            let remaining = QueueStore::new(&ConfigManager::new()?.queue_dir())
+                .jobs()?
+                .len();
+            eprintln!("{}", t!("queue-still-unavailable", count = remaining));
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// Delete every queued job
+pub async fn queue_clear(opts: &GlobalArgs) -> Result<()> {
+    let removed = QueueStore::new(&ConfigManager::new()?.queue_dir()).clear()?;
+    info(opts, t!("removed-queued-jobs", count = removed));
+    Ok(())
+}
+
+/// Run queued jobs, oldest first, returning how many completed
+///
+/// Each completed job is removed from the queue, passed to `on_done` and
+/// announced with a notification.
+///
+/// # Errors
+/// Returns the error of the first job that fails because the provider is
+/// still unavailable; later jobs are left queued
+async fn process_queue(
+    opts: &GlobalArgs,
+    config: &crate::config::Config,
+    mut on_done: impl FnMut(&ActionRun),
+) -> Result<usize> {
+    let store = QueueStore::new(&ConfigManager::new()?.queue_dir());
+    let mut completed = 0;
+    for (path, job) in store.jobs()? {
+        let llm = LlmOverrides {
+            provider: job.provider.clone(),
+            model: job.model.clone(),
+            temperature: job.temperature,
+            ..LlmOverrides::default()
+        };
+        let actions = std::slice::from_ref(&job.action);
+        let runs = match run_actions(opts, config, actions, &job.text, &llm).await {
+            Ok(runs) => runs,
+            Err(e) if e.is_retryable() => return Err(e),
+            Err(e) => {
+                warn!("Queued '{}' job failed: {}", job.action, e);
+                if !opts.quiet {
+                    eprintln!("{}", t!("queue-job-failed", action = job.action, error = e));
+                }
+                continue;
+            }
+        };
+
+        store.remove(&path)?;
+        completed += 1;
+        for run in &runs {
+            on_done(run);
+            let notified = OutputHandler::from_config(&run.config.output)
+                .with_context(output_context(
+                    &run.config,
+                    run.client.as_ref(),
+                    &run.action,
+                ))
+                .with_method(OutputMethod::Notification)
+                .handle(&run.completion.text);
+            if let Err(e) = notified {
+                warn!("Failed to announce the queued '{}' job: {}", run.action, e);
+            }
+        }
+    }
+    Ok(completed)
+}
+
+/// Retry queued jobs every `retry_interval_secs`, for the daemon
+#[cfg(unix)]
+async fn retry_queue(config: crate::config::Config) {
+    let opts = GlobalArgs {
+        quiet: true,
+        ..GlobalArgs::default()
+    };
+    // The daemon must not send the queued requests to itself
+    let mut config = config;
+    config.daemon.enabled = false;
+
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(config.queue.retry_interval_secs.max(1)));
+    loop {
+        interval.tick().await;
+        match process_queue(&opts, &config, |_| {}).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("Ran {} queued jobs", count),
+            Err(e) => debug!("Queued jobs still unavailable: {}", e),
+        }
+    }
+}
+
+/// List schedules with their next run time
+pub async fn schedule_list() -> Result<()> {
+    let config = ConfigManager::new()?.load()?;
+    if config.schedules.is_empty() {
+        println!("{}", t!("no-schedules"));
+        return Ok(());
+    }
+
+    let now = Local::now().naive_local();
+    for schedule in &config.schedules {
+        let next = match Cron::parse(&schedule.cron) {
+            Ok(cron) => cron
+                .next_after(&now)
+                .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            Err(e) => e.to_string(),
+        };
+        println!(
+            "{:<16} {:<16} {:<12} {}",
+            schedule.name, schedule.cron, schedule.action, next
+        );
+    }
+    Ok(())
+}
+
+/// Run a schedule now, regardless of its cron expression
+pub async fn schedule_run(opts: &GlobalArgs, name: &str) -> Result<()> {
+    let config = ConfigManager::new()?.load()?;
+    let schedule = config
+        .schedules
+        .iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| RephraserError::Config(format!("No schedule named '{}'", name)))?;
+    run_schedule(opts, &config, schedule).await
+}
+
+/// Apply a schedule's action to its input and deliver the result
+async fn run_schedule(
+    opts: &GlobalArgs,
+    base_config: &crate::config::Config,
+    schedule: &ScheduleConfig,
+) -> Result<()> {
+    let text = match (&schedule.file, &schedule.text) {
+        (Some(file), _) => read_file(&expand_home(file), base_config.input.max_file_size)?,
+        (None, Some(text)) => text.clone(),
+        (None, None) => {
+            return Err(RephraserError::Config(format!(
+                "Schedule '{}' needs a file or text",
+                schedule.name
+            )))
+        }
+    };
+
+    let mut config = base_config.clone();
+    if let Some(method) = schedule.output.clone() {
+        config.output.method = method;
+    }
+    if let Some(path) = &schedule.note_path {
+        config.output.note.path = path.clone();
+    }
+
+    let actions = std::slice::from_ref(&schedule.action);
+    for run in run_actions(opts, &config, actions, &text, &LlmOverrides::default()).await? {
+        let context = output_context(&run.config, run.client.as_ref(), &run.action);
+        let response = context.wrap(config.output.template.as_deref(), run.completion.text)?;
+        OutputHandler::from_config(&config.output)
+            .with_plugins(&config.plugins)
+            .with_context(context)
+            .handle(&response)?;
+    }
+    Ok(())
+}
+
+/// Run each schedule whenever its cron expression matches, for the daemon
+#[cfg(unix)]
+async fn run_schedules(config: crate::config::Config, schedules: Vec<(ScheduleConfig, Cron)>) {
+    let opts = GlobalArgs {
+        quiet: true,
+        ..GlobalArgs::default()
+    };
+    // The daemon must not send the scheduled requests to itself
+    let mut config = config;
+    config.daemon.enabled = false;
+    let config = Arc::new(config);
+
+    let mut last_minute = None;
+    loop {
+        // Wake at the start of each minute
+        let now = Local::now();
+        let elapsed = Duration::new(now.second().into(), now.nanosecond() % 1_000_000_000);
+        tokio::time::sleep(Duration::from_secs(60).saturating_sub(elapsed)).await;
+
+        let minute = Local::now()
+            .naive_local()
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0));
+        if minute.is_none() || minute == last_minute {
+            continue;
+        }
+        last_minute = minute;
+
+        for (schedule, cron) in &schedules {
+            if !minute.is_some_and(|minute| cron.matches(&minute)) {
+                continue;
+            }
+            let (opts, config, schedule) = (opts.clone(), Arc::clone(&config), schedule.clone());
+            tokio::spawn(async move {
+                tracing::info!("Running schedule '{}'", schedule.name);
+                if let Err(e) = run_schedule(&opts, &config, &schedule).await {
+                    warn!("Schedule '{}' failed: {}", schedule.name, e);
+                }
+            });
+        }
+    }
+}
+
+/// Run the daemon in the foreground until interrupted
+///
+/// Other invocations send their LLM requests to it over a Unix socket,
+/// reusing its provider clients and open connections
+#[cfg(unix)]
+pub async fn daemon(opts: &GlobalArgs) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    // Report invalid cron expressions before the daemon starts
+    let schedules = config
+        .schedules
+        .iter()
+        .map(|schedule| Ok((schedule.clone(), Cron::parse(&schedule.cron)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let socket_path = config_manager.socket_path();
+    let listener = bind_daemon_socket(&socket_path)?;
+    info(opts, t!("daemon-listening", path = socket_path.display()));
+    tracing::info!("Daemon listening on {}", socket_path.display());
+
+    let queue = config
+        .queue
+        .enabled
+        .then(|| tokio::spawn(retry_queue(config.clone())));
+    let scheduler =
+        (!schedules.is_empty()).then(|| tokio::spawn(run_schedules(config.clone(), schedules)));
+    let factory = provider_client_factory(config);
+    let mut signals = Signals::new()?;
+    let result = crate::daemon::serve(listener, factory, Arc::default(), signals.recv()).await;
+    for task in [queue, scheduler].into_iter().flatten() {
+        task.abort();
+    }
+
+    let _ = std::fs::remove_file(&socket_path);
+    result
+}
+
+/// Listen on the daemon socket, replacing a stale one
+///
+/// # Errors
+/// Fails if another daemon is already listening
+#[cfg(unix)]
+fn bind_daemon_socket(socket_path: &Path) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if socket_path.exists() {
+        if std::os::unix::net::UnixStream::connect(socket_path).is_ok() {
+            return Err(RephraserError::Other(format!(
+                "A daemon is already listening on {}",
+                socket_path.display()
+            )));
+        }
+        // Left behind by a daemon that didn't shut down cleanly
+        std::fs::remove_file(socket_path)?;
+    }
+    if let Some(parent) = socket_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(socket_path)?;
+    std::fs::set_permissions(socket_path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+pub async fn daemon(_opts: &GlobalArgs) -> Result<()> {
+    Err(RephraserError::Other(
+        "The daemon requires Unix domain sockets, which this platform lacks".to_string(),
+    ))
+}
+
+/// Show the menu bar status item until "Quit" is chosen
+///
+/// Unless a daemon is already running, the menu bar app also serves as the
+/// daemon, and its pause toggle makes the daemon refuse requests
+#[cfg(all(target_os = "macos", feature = "menu-bar"))]
+pub async fn menu_bar(opts: &GlobalArgs) -> Result<()> {
     let config_manager = ConfigManager::new()?;
     let config = config_manager.load()?;
+    let paused = Arc::new(AtomicBool::new(false));
 
-    // Resolve action to prompt
-    let resolver = ActionResolver::new(&config);
-    let prompt = resolver.resolve(action, text)?;
+    match bind_daemon_socket(&config_manager.socket_path()) {
+        Ok(listener) => {
+            let factory = provider_client_factory(config.clone());
+            // Runs until "Quit" ends the process
+            tokio::spawn(crate::daemon::serve(
+                listener,
+                factory,
+                Arc::clone(&paused),
+                std::future::pending(),
+            ));
+        }
+        Err(e) => warn!("Not serving as the daemon: {}", e),
+    }
 
-    // Create LLM client based on config
-    let client = create_llm_client(&config)?;
+    info(opts, t!("menu-bar-running"));
+    let handler = MenuBarState {
+        config,
+        runtime: tokio::runtime::Handle::current(),
+        paused,
+    };
+    // AppKit must run on the main thread, which this task occupies
+    tokio::task::block_in_place(|| crate::menubar::run(Box::new(handler)))
+}
 
-    // Call LLM API
-    let response = client.complete(&prompt).await?;
+#[cfg(not(all(target_os = "macos", feature = "menu-bar")))]
+pub async fn menu_bar(_opts: &GlobalArgs) -> Result<()> {
+    Err(RephraserError::Other(
+        "The menu bar requires macOS and a build with the 'menu-bar' feature".to_string(),
+    ))
+}
 
-    // Handle output
-    let output_handler = OutputHandler::new(config.output.method);
-    output_handler.handle(&response)?;
+/// Number of recent results listed in the menu bar
+#[cfg(all(target_os = "macos", feature = "menu-bar"))]
+const MENU_BAR_RECENT: usize = 5;
+
+/// Menu bar behavior: actions transform the clipboard in place
+#[cfg(all(target_os = "macos", feature = "menu-bar"))]
+struct MenuBarState {
+    config: crate::config::Config,
+    runtime: tokio::runtime::Handle,
+    paused: Arc<AtomicBool>,
+}
+
+#[cfg(all(target_os = "macos", feature = "menu-bar"))]
+impl crate::menubar::MenuBarHandler for MenuBarState {
+    fn actions(&self) -> Vec<(String, String)> {
+        self.config
+            .actions
+            .iter()
+            .map(|action| (action.name.clone(), action.display_name.clone()))
+            .collect()
+    }
+
+    fn recent(&self) -> Vec<(String, String)> {
+        let entries = ConfigManager::new()
+            .and_then(|manager| HistoryStore::open(&manager.history_path()))
+            .and_then(|store| store.list(None, MENU_BAR_RECENT));
+
+        match entries {
+            Ok(entries) => entries
+                .into_iter()
+                .map(|entry| {
+                    let label = format!("{}: {}", entry.action, preview(&entry.output, 40));
+                    (label, entry.output)
+                })
+                .collect(),
+            Err(e) => {
+                warn!("Failed to read history: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn run_action(&self, name: &str) {
+        let config = self.config.clone();
+        let action = name.to_string();
+        self.runtime.spawn(async move {
+            if let Err(e) = transform_clipboard(&config, &action).await {
+                let message = e.to_string();
+                let _ = crate::output::platform::current().notify(&Notification {
+                    title: "Rephraser",
+                    body: &message,
+                    ..Notification::default()
+                });
+            }
+        });
+    }
+
+    fn copy(&self, text: &str) {
+        let output_handler = OutputHandler::from_config(&self.config.output);
+        if let Err(e) = output_handler.copy_to_clipboard(text) {
+            warn!("Failed to copy to the clipboard: {}", e);
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+}
+
+/// Run an action on the clipboard contents and copy the result back
+#[cfg(all(target_os = "macos", feature = "menu-bar"))]
+async fn transform_clipboard(config: &crate::config::Config, action: &str) -> Result<()> {
+    let text = read_clipboard()?;
+    copy_transformed(config, action, &text).await
+}
+
+/// Run an action on text and copy the result to the clipboard
+async fn copy_transformed(config: &crate::config::Config, action: &str, text: &str) -> Result<()> {
+    let opts = GlobalArgs {
+        quiet: true,
+        ..GlobalArgs::default()
+    };
+    let run = run_actions(
+        &opts,
+        config,
+        &[action.to_string()],
+        text,
+        &LlmOverrides::default(),
+    )
+    .await?
+    .pop()
+    .ok_or_else(|| RephraserError::Other("No result".to_string()))?;
+
+    let context = output_context(&run.config, run.client.as_ref(), action);
+    let response = context.wrap(run.config.output.template.as_deref(), run.completion.text)?;
+    OutputHandler::from_config(&run.config.output)
+        .with_context(context)
+        .with_method(OutputMethod::Clipboard)
+        .handle(&response)
+}
+
+/// Serve actions over HTTP until interrupted
+///
+/// `host` and `port` override the `[server]` configuration
+pub async fn serve(opts: &GlobalArgs, host: Option<&str>, port: Option<u16>) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.shared()?;
+
+    let token = match &config.server.token_env {
+        Some(name) => Some(std::env::var(name).map_err(|_| {
+            RephraserError::Config(format!("Environment variable '{}' not found", name))
+        })?),
+        None => None,
+    };
+    let address = format!(
+        "{}:{}",
+        host.unwrap_or(&config.server.host),
+        port.unwrap_or(config.server.port)
+    );
+
+    let listener = tokio::net::TcpListener::bind(&address).await?;
+    info(
+        opts,
+        format!(
+            "Listening on http://{}{} (Ctrl-C to stop)",
+            listener.local_addr()?,
+            if token.is_some() {
+                " with bearer-token auth"
+            } else {
+                ""
+            }
+        ),
+    );
+
+    let router = super::server::router(Arc::new(super::server::ServerState::new(config, token)));
+    let mut signals = Signals::new()?;
+    let stopping = Arc::new(tokio::sync::Notify::new());
+    // Client addresses are needed for their rate limits
+    let router = router.into_make_service_with_connect_info::<std::net::SocketAddr>();
+    let server = axum::serve(listener, router).with_graceful_shutdown({
+        let stopping = Arc::clone(&stopping);
+        async move {
+            signals.recv().await;
+            stopping.notify_one();
+        }
+    });
+
+    // Stop accepting connections on a signal, then give the requests in
+    // flight a moment to finish
+    tokio::select! {
+        result = std::future::IntoFuture::into_future(server) => result?,
+        _ = async {
+            stopping.notified().await;
+            tokio::time::sleep(DRAIN_TIMEOUT).await;
+        } => warn!("Abandoning requests still running after {:?}", DRAIN_TIMEOUT),
+    }
 
     Ok(())
 }
 
-/// List all available actions
-pub async fn list_actions() -> Result<()> {
+/// Run an action for Apple Shortcuts
+///
+/// Reads `{"action": ..., "text": ...}` (plus optional `provider`, `model`
+/// and `temperature`) from stdin and prints the same JSON object as the
+/// HTTP server's `POST /rephrase`, or an error object
+pub async fn shortcut(opts: &GlobalArgs) -> Result<()> {
+    use super::server::ErrorBody;
+
+    let result = shortcut_run(opts).await;
+    match &result {
+        Ok(response) => println!("{}", serde_json::to_string(response)?),
+        Err(e) => println!("{}", serde_json::to_string(&ErrorBody::from(e))?),
+    }
+
+    result.map(|_| ())
+}
+
+async fn shortcut_run(opts: &GlobalArgs) -> Result<super::server::RephraseResponse> {
+    let mut body = String::new();
+    std::io::stdin().read_to_string(&mut body)?;
+    let request: super::server::RephraseRequest = serde_json::from_str(&body)
+        .map_err(|e| RephraserError::Input(format!("Invalid shortcut request: {}", e)))?;
+
     let config_manager = ConfigManager::new()?;
     let config = config_manager.load()?;
+    let actions = std::slice::from_ref(&request.action);
+    let run = run_actions(
+        opts,
+        &config,
+        actions,
+        &request.text,
+        &request.llm_overrides(),
+    )
+    .await?
+    .pop()
+    .ok_or_else(|| RephraserError::Other("No result".to_string()))?;
 
-    let resolver = ActionResolver::new(&config);
-    let actions = resolver.list_actions();
+    Ok(run.into())
+}
 
-    println!("Available actions:");
-    println!();
+/// Handle a `rephraser://rephrase?action=...` URL
+///
+/// The request is checked against `[url_scheme]` first. The result goes to
+/// the configured output method, or the one given by `output`. For an
+/// x-callback-url, it is sent back through the `x-success` URL instead
+pub async fn open_url(opts: &GlobalArgs, url: &str) -> Result<()> {
+    let request = UrlRequest::parse(url)?;
+    request.check(&ConfigManager::new()?.load()?.url_scheme)?;
+    let input = InputArgs {
+        from_clipboard: false,
+        from_selection: false,
+        chunked: false,
+        text: Some(request.text.clone()),
+        file: None,
+    };
+    let Some(callbacks) = &request.callbacks else {
+        return rephrase(
+            opts,
+            &request.action,
+            &input,
+            &LlmArgs::default(),
+            request.output,
+        )
+        .await;
+    };
 
-    for action in actions {
-        println!("  {} ({})", action.name, action.display_name);
+    match run_for_callback(opts, &request, &input).await {
+        Ok(result) => {
+            if let Some(success) = &callbacks.success {
+                platform::open_url(&url_scheme::callback_url(success, &[("result", &result)]))?;
+            }
+            Ok(())
+        }
+        Err(e) => {
+            if let Some(error) = &callbacks.error {
+                let code = e.exit_code().to_string();
+                let message = e.to_string();
+                let params = [("errorCode", code.as_str()), ("errorMessage", &message)];
+                platform::open_url(&url_scheme::callback_url(error, &params))?;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Run the action of an x-callback-url request and return its result
+///
+/// The result is only delivered locally when the URL names an output method
+async fn run_for_callback(
+    opts: &GlobalArgs,
+    request: &UrlRequest,
+    input: &InputArgs,
+) -> Result<String> {
+    let mut config = ConfigManager::new()?.load()?;
+    let text = read_input(input, &config)?;
+    if let Some(method) = request.output.clone() {
+        config.output.method = method;
+    }
+
+    let actions = std::slice::from_ref(&request.action);
+    let mut result = String::new();
+    for run in run_actions(opts, &config, actions, &text, &LlmOverrides::default()).await? {
+        let context = output_context(&run.config, run.client.as_ref(), &run.action);
+        result = context.wrap(config.output.template.as_deref(), run.completion.text)?;
+        if request.output.is_some() {
+            OutputHandler::from_config(&config.output)
+                .with_plugins(&config.plugins)
+                .with_context(context)
+                .handle(&result)?;
+        }
+    }
+    Ok(result)
+}
+
+/// Install the applet that handles `rephraser://` URLs
+pub async fn url_handler_install(opts: &GlobalArgs) -> Result<()> {
+    let app = url_scheme::handler_app_path()?;
+    url_scheme::install_handler(&app, &std::env::current_exe()?)?;
+    info(opts, t!("installed-path", path = app.display()));
+    info(opts, t!("url-handler-try"));
+    Ok(())
+}
+
+/// Remove the `rephraser://` URL handler applet
+pub async fn url_handler_uninstall(opts: &GlobalArgs) -> Result<()> {
+    let app = url_scheme::handler_app_path()?;
+    url_scheme::uninstall_handler(&app)?;
+    info(opts, t!("removed-path", path = app.display()));
+    Ok(())
+}
+
+/// Install a Finder Quick Action for an action
+///
+/// Reinstalling replaces the previous workflow
+pub async fn install_quick_action(opts: &GlobalArgs, action: &str) -> Result<()> {
+    let config = ConfigManager::new()?.load()?;
+    let display_name = ActionResolver::new(&config)
+        .find_action(action)
+        .map(|a| a.display_name.clone())
+        .ok_or_else(|| RephraserError::ActionNotFound(action.to_string()))?;
+
+    let menu_name = format!("Rephraser - {}", display_name);
+    let path = quick_action::workflow_path(&menu_name)?;
+    let command = quick_action::shell_command(&std::env::current_exe()?, action);
+    quick_action::install(&path, &menu_name, &command)?;
+    info(opts, t!("installed-path", path = path.display()));
+    info(opts, t!("quick-action-try", name = menu_name));
+    Ok(())
+}
+
+/// Install and load the LaunchAgent
+///
+/// Reinstalling replaces the previous agent. Variables named in `env` are
+/// copied into the plist, which is then only readable by the user
+pub async fn agent_install(opts: &GlobalArgs, menu_bar: bool, env: &[String]) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let log_dir = config_manager.log_dir();
+    std::fs::create_dir_all(&log_dir)?;
+
+    let environment = env
+        .iter()
+        .map(|name| {
+            std::env::var(name)
+                .map(|value| (name.clone(), value))
+                .map_err(|_| {
+                    RephraserError::Config(format!("Environment variable '{}' not found", name))
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let spec = launchd::AgentSpec {
+        program_arguments: vec![
+            std::env::current_exe()?.to_string_lossy().into_owned(),
+            if menu_bar { "menu-bar" } else { "daemon" }.to_string(),
+        ],
+        log_path: log_dir.join("agent.log"),
+        environment,
+    };
+
+    let plist_path = launchd::plist_path()?;
+    if plist_path.exists() && launchd::status()?.is_some() {
+        launchd::unload(&plist_path)?;
+    }
+    if let Some(parent) = plist_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&plist_path, launchd::plist(&spec))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&plist_path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    launchd::load(&plist_path)?;
+
+    info(opts, t!("installed-path", path = plist_path.display()));
+    info(opts, t!("agent-logs", path = spec.log_path.display()));
+    Ok(())
+}
+
+/// Unload the LaunchAgent and delete its plist
+pub async fn agent_uninstall(opts: &GlobalArgs) -> Result<()> {
+    let plist_path = launchd::plist_path()?;
+    if !plist_path.exists() {
+        info(opts, t!("agent-not-installed"));
+        return Ok(());
+    }
+
+    if launchd::status()?.is_some() {
+        launchd::unload(&plist_path)?;
+    }
+    std::fs::remove_file(&plist_path)?;
+
+    info(opts, t!("removed-path", path = plist_path.display()));
+    Ok(())
+}
+
+/// Show whether the LaunchAgent is installed and running
+pub async fn agent_status() -> Result<()> {
+    let plist_path = launchd::plist_path()?;
+    if plist_path.exists() {
+        println!("Installed: {}", plist_path.display());
+    } else {
+        println!("Installed: no");
+    }
+
+    match launchd::status()? {
+        Some(launchd::AgentStatus { pid: Some(pid), .. }) => println!("Running: yes (pid {})", pid),
+        Some(launchd::AgentStatus {
+            last_exit_status: Some(status),
+            ..
+        }) => println!("Running: no (last exit status {})", status),
+        Some(_) => println!("Running: no"),
+        None => println!("Running: not loaded"),
+    }
+
+    Ok(())
+}
+
+/// Execute the doctor command
+///
+/// Prints one line per check. On macOS, a missing Accessibility permission
+/// also brings up the system prompt to grant it.
+pub async fn doctor() -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let path = config_manager.config_path();
+    match config_manager.load() {
+        Ok(_) if path.exists() => println!("Config: ok ({})", path.display()),
+        Ok(_) => println!("Config: not found, using the defaults (see `rephraser config init`)"),
+        Err(e) => println!("Config: {}", e),
+    }
+
+    match is_trusted(false) {
+        Ok(true) => println!("Accessibility: authorized"),
+        Ok(false) => {
+            let _ = is_trusted(true);
+            println!(
+                "Accessibility: not authorized; `selection`, `--from-selection` and paste \
+                 output need it. Allow the app running Rephraser under System Settings > \
+                 Privacy & Security > Accessibility"
+            );
+        }
+        Err(_) if !cfg!(target_os = "macos") => {
+            println!("Accessibility: not needed on this platform")
+        }
+        Err(e) => println!("Accessibility: {}", e),
+    }
+
+    if let Ok(config) = config_manager.load() {
+        let checks = crate::llm::health::probe_all(provider_clients(&config)).await;
+        for health in checks {
+            match (health.latency_ms, health.last_error) {
+                (Some(latency_ms), _) => println!(
+                    "Provider {}/{}: ok ({} ms)",
+                    health.provider, health.model, latency_ms
+                ),
+                (None, error) => println!(
+                    "Provider {}/{}: {}",
+                    health.provider,
+                    health.model,
+                    error.unwrap_or_default()
+                ),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Run a Model Context Protocol server on stdin/stdout until stdin closes
+///
+/// Each configured action is exposed as a tool. Nothing but protocol
+/// messages may be written to stdout; logs go to stderr.
+pub async fn mcp() -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let config_manager = ConfigManager::new()?;
+    let server = super::mcp::McpServer::new(config_manager.shared()?);
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle_line(&line).await {
+            stdout.write_all(response.as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a Raycast script command for each configured action
+///
+/// The scripts call this binary by its absolute path, since Raycast does
+/// not run them with the login shell's `PATH`
+pub async fn raycast_generate(opts: &GlobalArgs, dir: Option<&Path>) -> Result<()> {
+    use crate::integrations::raycast;
+
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let dir = dir.map_or_else(
+        || config_manager.config_dir().join("raycast"),
+        Path::to_path_buf,
+    );
+    let exe = std::env::current_exe()?;
+
+    std::fs::create_dir_all(&dir)?;
+    for action in &config.actions {
+        let path = dir.join(raycast::script_file_name(action));
+        std::fs::write(&path, raycast::script_command(action, &exe))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        info(opts, t!("wrote-file", path = path.display()));
+    }
+
+    info(opts, t!("raycast-add-directory", dir = dir.display()));
+
+    Ok(())
+}
+
+/// Browse actions in the remote registry
+pub async fn action_browse(opts: &GlobalArgs) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+
+    let registry = ActionRegistry::new(&config.registry, config_manager.config_dir().join("cache"));
+    let index = registry.index().await?;
+
+    if !opts.quiet {
+        println!("{}", t!("registry-actions"));
+        println!();
+    }
+
+    for entry in &index.actions {
+        let installed = config.actions.iter().any(|a| a.name == entry.name);
+        println!(
+            "  {} ({}){}",
+            entry.name,
+            entry.display_name,
+            if installed { " [installed]" } else { "" }
+        );
+        if let Some(description) = &entry.description {
+            println!("      {}", description);
+        }
+    }
+
+    Ok(())
+}
+
+/// Install an action from the remote registry
+pub async fn action_install(opts: &GlobalArgs, name: &str) -> Result<()> {
+    let config_manager = ConfigManager::new()?;
+    let mut config = config_manager.load()?;
+
+    if config.actions.iter().any(|a| a.name == name) {
+        return Err(RephraserError::Config(format!(
+            "Action '{}' is already defined",
+            name
+        )));
+    }
+
+    let registry = ActionRegistry::new(&config.registry, config_manager.config_dir().join("cache"));
+    let entry = registry.find(name).await?;
+
+    config.actions.push(entry.into_action());
+    config_manager.save(&config)?;
+
+    info(opts, t!("installed-action", name = name));
+
+    Ok(())
+}
+
+/// Print the version, and with `check` look up the latest release
+pub async fn version(opts: &GlobalArgs, check: bool) -> Result<()> {
+    println!("rephraser {}", update::CURRENT_VERSION);
+    if !check {
+        return Ok(());
     }
 
+    let config_manager = ConfigManager::new()?;
+    let config = config_manager.load()?;
+    let checker = UpdateChecker::new(&config.updates, config_manager.update_state_path());
+    let state = checker.check().await?;
+    match state.newer_version() {
+        Some(latest) => println!("{}", update::notice(latest)),
+        None => info(opts, t!("up-to-date")),
+    }
     Ok(())
 }
 
+/// Look up the latest release in the background if the last check is a
+/// day old
+///
+/// Nothing is checked with `--quiet`, when stderr is not a terminal (e.g.
+/// when run by a launcher), or with `check = false` under `[updates]`.
+pub fn spawn_update_check(opts: &GlobalArgs) -> Option<UpdateCheck> {
+    if opts.quiet || !std::io::stderr().is_terminal() {
+        return None;
+    }
+    let config_manager = ConfigManager::new().ok()?;
+    let config = config_manager.load().ok()?;
+    if !config.updates.check {
+        return None;
+    }
+
+    let checker = Arc::new(UpdateChecker::new(
+        &config.updates,
+        config_manager.update_state_path(),
+    ));
+    let task = checker.is_due().then(|| {
+        let checker = Arc::clone(&checker);
+        tokio::spawn(async move {
+            if let Err(e) = checker.check().await {
+                debug!("Update check failed: {}", e);
+            }
+        })
+    });
+    Some(UpdateCheck { checker, task })
+}
+
+/// Update check started by [`spawn_update_check`]
+pub struct UpdateCheck {
+    checker: Arc<UpdateChecker>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl UpdateCheck {
+    /// Print a notice on stderr if a newer release is known
+    ///
+    /// Waits briefly for a running check, which is abandoned (and retried
+    /// on a later run) if it takes longer.
+    pub async fn finish(self) {
+        if let Some(task) = self.task {
+            let _ = tokio::time::timeout(UPDATE_CHECK_WAIT, task).await;
+        }
+        if let Some(latest) = self.checker.state().newer_version() {
+            eprintln!("{}", update::notice(latest));
+        }
+    }
+}
+
 /// Initialize configuration
-pub async fn config_init() -> Result<()> {
+pub async fn config_init(opts: &GlobalArgs) -> Result<()> {
     let config_manager = ConfigManager::new()?;
 
     if config_manager.exists() {
@@ -61,27 +2373,34 @@ pub async fn config_init() -> Result<()> {
 
     config_manager.init()?;
 
-    println!(
-        "Configuration initialized at: {:?}",
-        config_manager.config_path()
-    );
-    println!();
-    println!("Edit the file to customize your settings.");
-    println!("Don't forget to set your API key environment variable!");
+    if !opts.quiet {
+        println!(
+            "{}",
+            t!(
+                "config-initialized",
+                path = format!("{:?}", config_manager.config_path())
+            )
+        );
+        println!();
+        println!("{}", t!("config-edit-hint"));
+        println!("{}", t!("config-api-key-hint"));
+    }
 
     Ok(())
 }
 
 /// Show current configuration
-pub async fn config_show() -> Result<()> {
+pub async fn config_show(opts: &GlobalArgs) -> Result<()> {
     let config_manager = ConfigManager::new()?;
     let config = config_manager.load()?;
 
     let toml_str = toml::to_string_pretty(&config)
         .map_err(|e| RephraserError::Config(format!("Failed to serialize config: {}", e)))?;
 
-    println!("Current configuration:");
-    println!();
+    if !opts.quiet {
+        println!("{}", t!("current-configuration"));
+        println!();
+    }
     println!("{}", toml_str);
 
     Ok(())
@@ -92,7 +2411,7 @@ pub async fn config_set(key: &str, value: &str) -> Result<()> {
     // TODO: Implement config value setting
     // This requires parsing the key path and updating nested values
     println!("[TODO] Set {} = {}", key, value);
-    println!("For now, please edit the config file directly.");
+    println!("{}", t!("config-set-unsupported"));
 
     Ok(())
 }
@@ -105,43 +2424,381 @@ pub async fn config_path() -> Result<()> {
     Ok(())
 }
 
-/// Create an LLM client based on configuration
-fn create_llm_client(config: &crate::config::Config) -> Result<Arc<dyn LlmClient>> {
-    match config.llm.provider.as_str() {
-        "openai" => {
-            let api_key = std::env::var(&config.llm.api_key_env).map_err(|_| {
-                RephraserError::Config(format!(
-                    "Environment variable '{}' not found",
-                    config.llm.api_key_env
-                ))
-            })?;
+/// Print an informational message, unless `--quiet` is set
+fn info(opts: &GlobalArgs, message: impl std::fmt::Display) {
+    if !opts.quiet {
+        println!("{}", message);
+    }
+}
 
-            Ok(Arc::new(OpenAiClient::new(
-                api_key,
-                config.llm.model.clone(),
-                config.llm.parameters.temperature,
-                config.llm.parameters.max_tokens,
-            )))
+/// Read the text to transform
+///
+/// Uses TEXT or `--file` if given; "-" reads stdin. Otherwise the selection
+/// is read with `--from-selection`, the clipboard when requested by flag or
+/// config, stdin otherwise.
+fn read_input(input: &InputArgs, config: &crate::config::Config) -> Result<String> {
+    if input.chunked {
+        return Err(RephraserError::Input(
+            "--chunked only works with --file and a single action".to_string(),
+        ));
+    }
+    if let Some(path) = &input.file {
+        return read_file(path, config.input.max_file_size);
+    }
+
+    match input.text.as_deref() {
+        Some("-") => read_stdin(),
+        Some(text) => Ok(text.to_string()),
+        None if input.from_selection => read_selection(),
+        None if input.from_clipboard || config.input.from_clipboard => {
+            let text = read_clipboard()?;
+            if text.trim().is_empty() {
+                return Err(RephraserError::Input("Clipboard is empty".to_string()));
+            }
+            Ok(text)
         }
-        "anthropic" => {
-            let api_key = std::env::var(&config.llm.api_key_env).map_err(|_| {
-                RephraserError::Config(format!(
-                    "Environment variable '{}' not found",
-                    config.llm.api_key_env
-                ))
-            })?;
+        None => read_stdin(),
+    }
+}
 
-            Ok(Arc::new(AnthropicClient::new(
-                api_key,
-                config.llm.model.clone(),
-                config.llm.parameters.temperature,
-                config.llm.parameters.max_tokens,
-            )))
+/// Condense a file of any size until it fits in one chunk
+///
+/// The file is streamed in chunks of `chunk_chars`, a few at a time, so
+/// only those chunks and the results so far are held in memory. The action
+/// runs on each chunk and the joined results are chunked again until they
+/// fit; the caller then runs the action once more on what is returned. A
+/// file that already fits in one chunk is returned unchanged.
+async fn condense_file(
+    opts: &GlobalArgs,
+    config: &crate::config::Config,
+    action: &str,
+    path: &Path,
+    llm: &LlmOverrides,
+) -> Result<String> {
+    let max_chars = config.input.chunk_chars.max(1);
+    let mut chunks = FileChunks::open(path, max_chars)?;
+    let Some(first) = chunks.next().transpose()? else {
+        return Err(RephraserError::Input(format!(
+            "{} is empty",
+            path.display()
+        )));
+    };
+    let Some(second) = chunks.next().transpose()? else {
+        return Ok(first);
+    };
+
+    let _spinner = Spinner::start(format!("Condensing {}", path.display()), !opts.quiet);
+    let batch = Batch::from_config(&config.batch);
+    let window = config.batch.concurrency.max(1);
+    let run = |window: Vec<String>| {
+        let batch = &batch;
+        async move {
+            let results = batch
+                .run(&window, |chunk| async move {
+                    pipeline::run(config, action, chunk, llm).await
+                })
+                .await;
+            results
+                .into_results()
+                .into_iter()
+                .map(|result| result.map(|run| run.completion.text))
+                .collect::<Result<Vec<_>>>()
+        }
+    };
+
+    // First round, streamed from the file
+    let chunks = [Ok(first), Ok(second)].into_iter().chain(chunks);
+    let mut text = condense_streamed(chunks, window, run).await?.join("\n\n");
+
+    // Further rounds over the results until they fit in one chunk
+    while text.chars().count() > max_chars {
+        debug!("Condensing {} characters of results", text.chars().count());
+        let mut condensed = Vec::new();
+        let chunks: Vec<String> = text::chunks(&text, max_chars).map(str::to_string).collect();
+        for window in chunks.chunks(window) {
+            condensed.extend(run(window.to_vec()).await?);
+        }
+        let shorter = condensed.join("\n\n");
+        if shorter.len() >= text.len() {
+            return Err(RephraserError::Input(format!(
+                "'{}' does not shorten the text; use an action like summarize with --chunked",
+                action
+            )));
+        }
+        text = shorter;
+    }
+    Ok(text)
+}
+
+/// Run chunks through `run` `window` at a time, as they are read, so that
+/// at most `window` chunks are held in memory
+async fn condense_streamed<F, Fut>(
+    chunks: impl Iterator<Item = Result<String>>,
+    window: usize,
+    run: F,
+) -> Result<Vec<String>>
+where
+    F: Fn(Vec<String>) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<String>>>,
+{
+    let mut condensed = Vec::new();
+    let mut pending = Vec::new();
+    for chunk in chunks {
+        pending.push(chunk?);
+        if pending.len() >= window {
+            condensed.extend(run(std::mem::take(&mut pending)).await?);
+        }
+    }
+    if !pending.is_empty() {
+        condensed.extend(run(pending).await?);
+    }
+    Ok(condensed)
+}
+
+/// Run several actions on the same text concurrently
+///
+/// Each action uses its own provider/model overrides, then `llm`. Results
+/// are returned in the order of `actions`; if any action fails, the first
+/// failure is returned
+pub(super) async fn run_actions(
+    opts: &GlobalArgs,
+    base_config: &crate::config::Config,
+    actions: &[String],
+    text: &str,
+    llm: &LlmOverrides,
+) -> Result<Vec<ActionRun>> {
+    let mut tasks = Vec::new();
+    for action in actions {
+        let prepared = PreparedAction::new(base_config, action, text, llm).await?;
+        let key = idempotency::current();
+        tasks.push(tokio::spawn(idempotency::scope(key, prepared.run(None))));
+    }
+
+    let message = match actions {
+        [action] => format!("Running {}", action),
+        _ => format!("Running {} actions", actions.len()),
+    };
+    let spinner = Spinner::start(message, !opts.quiet);
+    let mut runs = Vec::new();
+    for task in tasks {
+        let run = task
+            .await
+            .map_err(|e| RephraserError::Other(format!("Action task failed: {}", e)))??;
+        runs.push(run);
+    }
+    drop(spinner);
+
+    Ok(runs)
+}
+
+/// Run one action, passing the response to `on_token` as it arrives
+///
+/// Guardrails need the complete response before any of it is used, so for
+/// actions with validation rules the validated response is passed in one
+/// piece
+pub(super) async fn stream_action(
+    base_config: &crate::config::Config,
+    action: &str,
+    text: &str,
+    llm: &LlmOverrides,
+    on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+) -> Result<ActionRun> {
+    PreparedAction::new(base_config, action, text, llm)
+        .await?
+        .run(Some(on_token))
+        .await
+}
+
+/// Run a request and deliver its result
+///
+/// With the terminal method on a TTY, tokens are printed as they arrive
+/// instead of waiting for the complete response
+async fn execute(
+    opts: &GlobalArgs,
+    config: &crate::config::Config,
+    client: &dyn LlmClient,
+    request: &Request,
+) -> Result<()> {
+    // Guardrails and output templates need the complete response before
+    // anything is shown
+    let stream = config.output.method == OutputMethod::Terminal
+        && std::io::stdout().is_terminal()
+        && client.supports_streaming()
+        && request.rules.is_none()
+        && config.output.template.is_none();
+
+    if !stream {
+        let response = run_action(opts, config, client, request).await?;
+        return deliver(opts, config, client, request, response).await;
+    }
+
+    let mut print_token = |token: &str| {
+        print!("{}", token);
+        let _ = std::io::stdout().flush();
+    };
+    let (response, _) = complete_request(config, client, request, Some(&mut print_token)).await?;
+    println!();
+    let response = response.text;
+
+    OutputHandler::from_config(&config.output)
+        .with_context(output_context(config, client, &request.action))
+        .finish_terminal_output(&response)?;
+    request.hooks.after(&request.action, &response).await;
+    Ok(())
+}
+
+/// Call the LLM for a request and enforce its guardrails
+///
+/// The returned response is decorated with the output template, if any
+async fn run_action(
+    opts: &GlobalArgs,
+    config: &crate::config::Config,
+    client: &dyn LlmClient,
+    request: &Request,
+) -> Result<String> {
+    let action = request.action.as_str();
+
+    let (response, _) = {
+        let _spinner = Spinner::start(
+            format!(
+                "Running {} ({}/{})",
+                action,
+                client.provider_name(),
+                client.model_name()
+            ),
+            !opts.quiet,
+        );
+        complete_request(config, client, request, None).await?
+    };
+
+    output_context(config, client, action).wrap(config.output.template.as_deref(), response.text)
+}
+
+/// Deliver a response via the configured output method, then pass it to
+/// the post-hook
+///
+/// For the dialog method, "Retry" generates a new response and shows it
+/// again, and the post-hook only sees the response that is copied
+async fn deliver(
+    opts: &GlobalArgs,
+    config: &crate::config::Config,
+    client: &dyn LlmClient,
+    request: &Request,
+    response: String,
+) -> Result<()> {
+    let output_handler = OutputHandler::from_config(&config.output)
+        .with_plugins(&config.plugins)
+        .with_context(output_context(config, client, &request.action));
+
+    if config.output.method != OutputMethod::Dialog {
+        output_handler.handle(&response)?;
+        request.hooks.after(&request.action, &response).await;
+        return Ok(());
+    }
+
+    let mut response = response;
+    loop {
+        match output_handler.show_dialog(&response)? {
+            DialogAction::Copy(edited) => {
+                output_handler.copy_to_clipboard(&edited)?;
+                request.hooks.after(&request.action, &edited).await;
+                return Ok(());
+            }
+            DialogAction::Retry => {
+                response = run_action(opts, config, client, request).await?;
+            }
+            DialogAction::Cancel => return Ok(()),
         }
-        "mock" => Ok(Arc::new(MockLlmClient::new())),
-        _ => Err(RephraserError::Config(format!(
-            "Unknown provider: {}",
-            config.llm.provider
-        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[tokio::test]
+    async fn test_condense_streamed() {
+        // Each chunk is condensed before the next one is read
+        let read = std::cell::Cell::new(0);
+        let chunks = ["a", "b", "c"].into_iter().map(|chunk| {
+            read.set(read.get() + 1);
+            Ok(chunk.to_string())
+        });
+        let runs = std::cell::RefCell::new(Vec::new());
+        let condensed = condense_streamed(chunks, 1, |window| {
+            runs.borrow_mut().push((read.get(), window.len()));
+            async move { Ok(window.iter().map(|chunk| chunk.to_uppercase()).collect()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(condensed, ["A", "B", "C"]);
+        assert_eq!(runs.into_inner(), [(1, 1), (2, 1), (3, 1)]);
+
+        let chunks = ["a", "b", "c"].map(|chunk| Ok(chunk.to_string()));
+        let windows = std::cell::RefCell::new(Vec::new());
+        condense_streamed(chunks.into_iter(), 2, |window| {
+            windows.borrow_mut().push(window.len());
+            async move { Ok(window) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(windows.into_inner(), [2, 1]);
+    }
+
+    #[test]
+    fn test_action_llm_overrides() {
+        let mut base = Config::default();
+        base.actions[0].provider = Some("anthropic".to_string());
+        base.actions[0].model = Some("claude-3-5-haiku-20241022".to_string());
+        let (overridden, plain) = (base.actions[0].name.clone(), base.actions[1].name.clone());
+
+        // The action's provider and model replace the [llm] settings...
+        let mut config = base.clone();
+        configure_llm(&mut config, &overridden, &LlmOverrides::default());
+        assert_eq!(config.llm.provider, "anthropic");
+        assert_eq!(config.llm.model, "claude-3-5-haiku-20241022");
+
+        // ...only for that action...
+        let mut config = base.clone();
+        configure_llm(&mut config, &plain, &LlmOverrides::default());
+        assert_eq!(config.llm.provider, base.llm.provider);
+        assert_eq!(config.llm.model, base.llm.model);
+
+        // ...and flags given for the run take precedence
+        let mut config = base.clone();
+        let llm = LlmOverrides {
+            model: Some("claude-3-5-sonnet-20241022".to_string()),
+            ..LlmOverrides::default()
+        };
+        configure_llm(&mut config, &overridden, &llm);
+        assert_eq!(config.llm.provider, "anthropic");
+        assert_eq!(config.llm.model, "claude-3-5-sonnet-20241022");
+    }
+
+    #[test]
+    fn test_render_columns() {
+        let column = |label: &str, text: &str| CompareColumn {
+            label: label.to_string(),
+            stats: "12 ms".to_string(),
+            text: text.to_string(),
+        };
+        let columns = [
+            column("mock/a", "Short."),
+            column("mock/b", "A longer answer that wraps"),
+        ];
+        assert_eq!(columns_width(2), 63);
+
+        let lines = render_columns(&columns, 33);
+        assert_eq!(
+            lines,
+            [
+                "mock/a          │ mock/b",
+                "12 ms           │ 12 ms",
+                "─────────────── │ ───────────────",
+                "Short.          │ A longer answer",
+                "                │ that wraps",
+            ]
+        );
     }
 }
@@ -0,0 +1,323 @@
+//! gRPC interface of the HTTP server
+//!
+//! Implements the `rephraser.v1.Rephraser` service from
+//! `proto/rephraser.proto` on the server's router, so REST and gRPC share
+//! one address: gRPC clients connect over HTTP/2 without TLS ("h2c"), which
+//! the server accepts alongside HTTP/1.1. Requests go through the same
+//! bearer token check as the REST routes.
+
+mod proto;
+
+use super::commands::stream_action;
+use super::limits::Slot;
+use super::server::{run_request, stream_task, RephraseResponse, ServerState};
+use crate::error::{ErrorCode, RephraserError};
+use axum::body::{Body, Bytes};
+use axum::extract::{Extension, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use http_body::Frame;
+use http_body_util::StreamBody;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio_stream::{Stream, StreamExt};
+
+const CONTENT_TYPE: &str = "application/grpc";
+
+/// gRPC status codes
+mod code {
+    pub const OK: u32 = 0;
+    pub const INVALID_ARGUMENT: u32 = 3;
+    pub const DEADLINE_EXCEEDED: u32 = 4;
+    pub const NOT_FOUND: u32 = 5;
+    pub const RESOURCE_EXHAUSTED: u32 = 8;
+    pub const FAILED_PRECONDITION: u32 = 9;
+    pub const INTERNAL: u32 = 13;
+    pub const UNAVAILABLE: u32 = 14;
+}
+
+/// Routes of the gRPC service
+pub(super) fn routes() -> Router<Arc<ServerState>> {
+    Router::new()
+        .route("/rephraser.v1.Rephraser/Rephrase", post(rephrase))
+        .route("/rephraser.v1.Rephraser/ListActions", post(list_actions))
+        .route("/rephraser.v1.Rephraser/Stream", post(stream))
+}
+
+async fn rephrase(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let request = match read_message(&headers, &body).and_then(proto::decode_rephrase_request) {
+        Ok(request) => request,
+        Err(e) => return error_response(&e),
+    };
+
+    match run_request(&state.config, request).await {
+        Ok(response) => unary(proto::encode_rephrase_response(&response)),
+        Err(e) => error_response(&e),
+    }
+}
+
+async fn list_actions(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    // The request message has no fields
+    if let Err(e) = read_message(&headers, &body) {
+        return error_response(&e);
+    }
+    unary(proto::encode_list_actions_response(&state.config.actions))
+}
+
+/// Run the request, sending each piece of text as a `token` message as it
+/// arrives, followed by a `done` message or an error status
+async fn stream(
+    State(state): State<Arc<ServerState>>,
+    slot: Option<Extension<Slot>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let request = match read_message(&headers, &body).and_then(proto::decode_rephrase_request) {
+        Ok(request) => request,
+        Err(e) => return error_response(&e),
+    };
+
+    let frames = stream_task(slot.map(|Extension(slot)| slot), |sender| async move {
+        let llm = request.llm_overrides();
+        let tokens = sender.clone();
+        let mut on_token = move |text: &str| {
+            let _ = tokens.send(Frame::data(frame(&proto::encode_token(text))));
+        };
+
+        let result = stream_action(
+            &state.config,
+            &request.action,
+            &request.text,
+            &llm,
+            &mut on_token,
+        )
+        .await;
+        let trailers = match result {
+            Ok(run) => {
+                let done = proto::encode_done(&RephraseResponse::from(run));
+                let _ = sender.send(Frame::data(frame(&done)));
+                status(code::OK, "")
+            }
+            Err(e) => error_status(&e),
+        };
+        let _ = sender.send(Frame::trailers(trailers));
+    });
+
+    response(frames)
+}
+
+/// The request message, unwrapped from its length prefix
+fn read_message<'a>(headers: &HeaderMap, body: &'a [u8]) -> crate::error::Result<&'a [u8]> {
+    if !is_grpc(headers) {
+        return Err(RephraserError::Input(format!(
+            "Expected content type {}",
+            CONTENT_TYPE
+        )));
+    }
+
+    let Some((&[compressed, ref length @ ..], message)) = body.split_first_chunk::<5>() else {
+        return Err(RephraserError::Input("Malformed gRPC message".to_string()));
+    };
+    if compressed != 0 {
+        return Err(RephraserError::Input(
+            "Compressed gRPC messages are not supported".to_string(),
+        ));
+    }
+    if u32::from_be_bytes(*length) as usize != message.len() {
+        return Err(RephraserError::Input("Truncated gRPC message".to_string()));
+    }
+    Ok(message)
+}
+
+/// Prefix a message with its compression flag and length
+fn frame(message: &[u8]) -> Bytes {
+    let mut framed = Vec::with_capacity(message.len() + 5);
+    framed.push(0);
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    framed.into()
+}
+
+/// A response carrying one message and an OK status
+fn unary(message: Vec<u8>) -> Response {
+    response(tokio_stream::iter([
+        Frame::data(frame(&message)),
+        Frame::trailers(status(code::OK, "")),
+    ]))
+}
+
+fn response(frames: impl Stream<Item = Frame<Bytes>> + Send + 'static) -> Response {
+    let body = Body::new(StreamBody::new(frames.map(Ok::<_, Infallible>)));
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], body).into_response()
+}
+
+/// Whether a request with `headers` is a gRPC call
+pub(super) fn is_grpc(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with(CONTENT_TYPE))
+}
+
+/// A response that carries only an error status, in its headers
+pub(super) fn error_response(error: &RephraserError) -> Response {
+    let mut headers = error_status(error);
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE));
+    (StatusCode::OK, headers).into_response()
+}
+
+fn error_status(error: &RephraserError) -> HeaderMap {
+    status(code_for(error), &error.to_string())
+}
+
+/// `grpc-status` and `grpc-message` metadata
+fn status(code: u32, message: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("grpc-status", HeaderValue::from(code));
+    if !message.is_empty() {
+        if let Ok(value) = HeaderValue::from_str(&percent_encode(message)) {
+            headers.insert("grpc-message", value);
+        }
+    }
+    headers
+}
+
+/// gRPC status code for an error
+fn code_for(error: &RephraserError) -> u32 {
+    match error.code() {
+        ErrorCode::ActionNotFound => code::NOT_FOUND,
+        ErrorCode::Input => code::INVALID_ARGUMENT,
+        ErrorCode::Validation => code::FAILED_PRECONDITION,
+        ErrorCode::RateLimit => code::RESOURCE_EXHAUSTED,
+        ErrorCode::Timeout => code::DEADLINE_EXCEEDED,
+        ErrorCode::Auth
+        | ErrorCode::LlmApi
+        | ErrorCode::BadRequest
+        | ErrorCode::ServiceUnavailable
+        | ErrorCode::Network
+        | ErrorCode::Offline
+        | ErrorCode::CircuitOpen => code::UNAVAILABLE,
+        ErrorCode::Config | ErrorCode::Registry | ErrorCode::Output | ErrorCode::Other => {
+            code::INTERNAL
+        }
+    }
+}
+
+/// Percent-encode a status message as the gRPC protocol requires
+fn percent_encode(message: &str) -> String {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        if (0x20..0x7f).contains(&byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::proto::{fields, Encoder, Value};
+    use super::*;
+    use crate::cli::server::tests::start;
+
+    async fn call(address: &str, method: &str, message: Vec<u8>) -> reqwest::Response {
+        reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap()
+            .post(format!("{}/rephraser.v1.Rephraser/{}", address, method))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(frame(&message).to_vec())
+            .send()
+            .await
+            .unwrap()
+    }
+
+    /// Messages in a response body
+    fn messages(mut body: &[u8]) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+        while body.len() >= 5 {
+            let size = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+            messages.push(body[5..5 + size].to_vec());
+            body = &body[5 + size..];
+        }
+        messages
+    }
+
+    fn field<'a>(message: &'a [u8], number: u64) -> Option<Value<'a>> {
+        fields(message)
+            .unwrap()
+            .into_iter()
+            .find(|(n, _)| *n == number)
+            .map(|(_, value)| value)
+    }
+
+    fn request(action: &str) -> Vec<u8> {
+        Encoder::default()
+            .string(1, action)
+            .string(2, "hello")
+            .finish()
+    }
+
+    #[tokio::test]
+    async fn test_unary_calls() {
+        let address = start(None).await;
+
+        let response = call(&address, "Rephrase", request("polite")).await;
+        assert_eq!(response.headers()["content-type"], CONTENT_TYPE);
+        let body = messages(&response.bytes().await.unwrap());
+        assert_eq!(body.len(), 1);
+        assert_eq!(field(&body[0], 1), Some(Value::Bytes(b"polite")));
+        assert_eq!(field(&body[0], 3), Some(Value::Bytes(b"mock")));
+        let Some(Value::Bytes(output)) = field(&body[0], 2) else {
+            panic!("no output");
+        };
+        assert!(String::from_utf8_lossy(output).contains("お元気でしょうか"));
+
+        let response = call(&address, "Rephrase", request("nope")).await;
+        assert_eq!(response.headers()["grpc-status"], "5");
+
+        let response = call(&address, "ListActions", Vec::new()).await;
+        let body = messages(&response.bytes().await.unwrap());
+        let names: Vec<Value> = fields(&body[0])
+            .unwrap()
+            .into_iter()
+            .filter_map(|(_, action)| match action {
+                Value::Bytes(action) => field(action, 1),
+                _ => None,
+            })
+            .collect();
+        assert!(names.contains(&Value::Bytes(b"polite")));
+    }
+
+    #[tokio::test]
+    async fn test_stream() {
+        let address = start(None).await;
+        let response = call(&address, "Stream", request("polite")).await;
+        let body = messages(&response.bytes().await.unwrap());
+
+        let (done, tokens) = body.split_last().unwrap();
+        assert!(!tokens.is_empty());
+        assert!(tokens.iter().all(|token| field(token, 1).is_some()));
+        assert!(field(done, 2).is_some());
+    }
+
+    #[test]
+    fn test_percent_encode() {
+        assert_eq!(percent_encode("100% ok"), "100%25 ok");
+        assert_eq!(percent_encode("é\n"), "%C3%A9%0A");
+    }
+}
@@ -0,0 +1,371 @@
+//! Protobuf encoding of the messages in `proto/rephraser.proto`
+//!
+//! Only the wire types those messages use are handled. Unknown fields are
+//! skipped when decoding, as protobuf requires.
+
+use crate::cli::server::{RephraseRequest, RephraseResponse};
+use crate::config::ActionConfig;
+use crate::error::{RephraserError, Result};
+
+const VARINT: u64 = 0;
+const FIXED64: u64 = 1;
+const LEN: u64 = 2;
+const FIXED32: u64 = 5;
+
+/// A decoded field value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Builds an encoded message
+#[derive(Debug, Default)]
+pub struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    /// A string field, left out when empty as proto3 does
+    pub fn string(self, field: u64, value: &str) -> Self {
+        if value.is_empty() {
+            self
+        } else {
+            self.bytes(field, value.as_bytes())
+        }
+    }
+
+    /// A length-delimited field, always written
+    pub fn bytes(mut self, field: u64, value: &[u8]) -> Self {
+        put_varint(&mut self.buf, field << 3 | LEN);
+        put_varint(&mut self.buf, value.len() as u64);
+        self.buf.extend_from_slice(value);
+        self
+    }
+
+    /// An unsigned integer field, left out when zero
+    pub fn uint64(mut self, field: u64, value: u64) -> Self {
+        if value != 0 {
+            put_varint(&mut self.buf, field << 3 | VARINT);
+            put_varint(&mut self.buf, value);
+        }
+        self
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Split an encoded message into its fields
+///
+/// # Errors
+/// * If the message is malformed
+pub fn fields(mut data: &[u8]) -> Result<Vec<(u64, Value<'_>)>> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let key = take_varint(&mut data)?;
+        let value = match key & 0x7 {
+            VARINT => Value::Varint(take_varint(&mut data)?),
+            FIXED64 => Value::Fixed64(u64::from_le_bytes(
+                take(&mut data, 8)?.try_into().unwrap_or_default(),
+            )),
+            LEN => {
+                let len = usize::try_from(take_varint(&mut data)?).map_err(|_| malformed())?;
+                Value::Bytes(take(&mut data, len)?)
+            }
+            FIXED32 => Value::Fixed32(u32::from_le_bytes(
+                take(&mut data, 4)?.try_into().unwrap_or_default(),
+            )),
+            _ => return Err(malformed()),
+        };
+        fields.push((key >> 3, value));
+    }
+    Ok(fields)
+}
+
+fn take_varint(data: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or_else(malformed)?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+    Err(malformed())
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(malformed());
+    }
+    let (head, rest) = data.split_at(len);
+    *data = rest;
+    Ok(head)
+}
+
+fn malformed() -> RephraserError {
+    RephraserError::Input("Malformed protobuf message".to_string())
+}
+
+fn string(value: Value<'_>) -> Result<String> {
+    match value {
+        Value::Bytes(bytes) => String::from_utf8(bytes.to_vec())
+            .map_err(|_| RephraserError::Input("String field is not valid UTF-8".to_string())),
+        _ => Err(malformed()),
+    }
+}
+
+/// Decode a `RephraseRequest`
+///
+/// # Errors
+/// * If the message is malformed or has no action
+pub fn decode_rephrase_request(data: &[u8]) -> Result<RephraseRequest> {
+    let mut request = RephraseRequest {
+        action: String::new(),
+        text: String::new(),
+        provider: None,
+        model: None,
+        temperature: None,
+        no_cache: false,
+        refresh: false,
+    };
+    for (field, value) in fields(data)? {
+        match (field, value) {
+            (1, value) => request.action = string(value)?,
+            (2, value) => request.text = string(value)?,
+            (3, value) => request.provider = Some(string(value)?),
+            (4, value) => request.model = Some(string(value)?),
+            (5, Value::Fixed32(bits)) => request.temperature = Some(f32::from_bits(bits)),
+            (5, _) => return Err(malformed()),
+            (6, Value::Varint(value)) => request.no_cache = value != 0,
+            (7, Value::Varint(value)) => request.refresh = value != 0,
+            (6 | 7, _) => return Err(malformed()),
+            _ => {}
+        }
+    }
+
+    if request.action.is_empty() {
+        return Err(RephraserError::Input("No action given".to_string()));
+    }
+    Ok(request)
+}
+
+/// Encode a `RephraseResponse`
+pub fn encode_rephrase_response(response: &RephraseResponse) -> Vec<u8> {
+    let tokens = Encoder::default()
+        .uint64(1, response.tokens.prompt_tokens as u64)
+        .uint64(2, response.tokens.completion_tokens as u64)
+        .finish();
+    Encoder::default()
+        .string(1, &response.action)
+        .string(2, &response.output)
+        .string(3, &response.provider)
+        .string(4, &response.model)
+        .bytes(5, &tokens)
+        .uint64(6, u64::try_from(response.duration_ms).unwrap_or(u64::MAX))
+        .finish()
+}
+
+/// Encode a `ListActionsResponse`
+pub fn encode_list_actions_response(actions: &[ActionConfig]) -> Vec<u8> {
+    actions
+        .iter()
+        .fold(Encoder::default(), |message, action| {
+            let encoded = action.tags.iter().fold(
+                Encoder::default()
+                    .string(1, &action.name)
+                    .string(2, &action.display_name)
+                    .string(3, action.description.as_deref().unwrap_or_default()),
+                |encoder, tag| encoder.bytes(4, tag.as_bytes()),
+            );
+            message.bytes(1, &encoded.finish())
+        })
+        .finish()
+}
+
+/// Encode a `StreamResponse` carrying a piece of text
+pub fn encode_token(text: &str) -> Vec<u8> {
+    Encoder::default().bytes(1, text.as_bytes()).finish()
+}
+
+/// Encode a `StreamResponse` carrying the complete result
+pub fn encode_done(response: &RephraseResponse) -> Vec<u8> {
+    Encoder::default()
+        .bytes(2, &encode_rephrase_response(response))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::server::RephraseResponse;
+    use crate::llm::TokenUsage;
+    use prost::Message;
+    use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, Value as Field};
+
+    /// Descriptor of `message` in proto/rephraser.proto
+    fn schema(message: &str) -> MessageDescriptor {
+        let proto = concat!(env!("CARGO_MANIFEST_DIR"), "/proto");
+        let files = protox::compile(["rephraser.proto"], [proto]).unwrap();
+        DescriptorPool::from_file_descriptor_set(files)
+            .unwrap()
+            .get_message_by_name(&format!("rephraser.v1.{}", message))
+            .unwrap()
+    }
+
+    fn response() -> RephraseResponse {
+        RephraseResponse {
+            action: "polite".to_string(),
+            output: "こんにちは".to_string(),
+            provider: "mock".to_string(),
+            model: "mock-model-v1".to_string(),
+            tokens: TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 300,
+            },
+            duration_ms: 812,
+        }
+    }
+
+    #[test]
+    fn test_request_matches_schema() {
+        let mut message = DynamicMessage::new(schema("RephraseRequest"));
+        let set = |message: &mut DynamicMessage, name: &str, value: Field| {
+            message.try_set_field_by_name(name, value).unwrap();
+        };
+        set(&mut message, "action", Field::String("polite".to_string()));
+        set(
+            &mut message,
+            "text",
+            Field::String("こんにちは".to_string()),
+        );
+        set(
+            &mut message,
+            "provider",
+            Field::String("anthropic".to_string()),
+        );
+        set(&mut message, "model", Field::String("claude".to_string()));
+        set(&mut message, "temperature", Field::F32(0.25));
+        set(&mut message, "no_cache", Field::Bool(true));
+        set(&mut message, "refresh", Field::Bool(true));
+
+        let request = decode_rephrase_request(&message.encode_to_vec()).unwrap();
+        assert_eq!(request.action, "polite");
+        assert_eq!(request.text, "こんにちは");
+        assert_eq!(request.provider.as_deref(), Some("anthropic"));
+        assert_eq!(request.model.as_deref(), Some("claude"));
+        assert_eq!(request.temperature, Some(0.25));
+        assert!(request.no_cache);
+        assert!(request.refresh);
+    }
+
+    #[test]
+    fn test_responses_match_schema() {
+        let decode = |name: &str, data: Vec<u8>| {
+            DynamicMessage::decode(schema(name), data.as_slice()).unwrap()
+        };
+        let field = |message: &DynamicMessage, name: &str| {
+            message.get_field_by_name(name).unwrap().into_owned()
+        };
+
+        let decoded = decode("RephraseResponse", encode_rephrase_response(&response()));
+        assert_eq!(
+            field(&decoded, "action"),
+            Field::String("polite".to_string())
+        );
+        assert_eq!(
+            field(&decoded, "output"),
+            Field::String("こんにちは".to_string())
+        );
+        assert_eq!(
+            field(&decoded, "provider"),
+            Field::String("mock".to_string())
+        );
+        assert_eq!(
+            field(&decoded, "model"),
+            Field::String("mock-model-v1".to_string())
+        );
+        assert_eq!(field(&decoded, "duration_ms"), Field::U64(812));
+        let tokens = field(&decoded, "tokens");
+        let tokens = tokens.as_message().unwrap();
+        assert_eq!(field(tokens, "prompt_tokens"), Field::U64(12));
+        assert_eq!(field(tokens, "completion_tokens"), Field::U64(300));
+
+        let token = decode("StreamResponse", encode_token("Hel"));
+        assert_eq!(field(&token, "token"), Field::String("Hel".to_string()));
+        let done = decode("StreamResponse", encode_done(&response()));
+        assert!(!done.has_field_by_name("token"));
+        let done = field(&done, "done");
+        assert_eq!(
+            field(done.as_message().unwrap(), "output"),
+            Field::String("こんにちは".to_string())
+        );
+
+        let actions = crate::config::Config::default().actions;
+        let decoded = decode(
+            "ListActionsResponse",
+            encode_list_actions_response(&actions),
+        );
+        let listed = field(&decoded, "actions");
+        let listed = listed.as_list().unwrap();
+        assert_eq!(listed.len(), actions.len());
+        let first = listed[0].as_message().unwrap();
+        assert_eq!(field(first, "name"), Field::String(actions[0].name.clone()));
+        assert_eq!(
+            field(first, "display_name"),
+            Field::String(actions[0].display_name.clone())
+        );
+    }
+
+    #[test]
+    fn test_decode_rephrase_request() {
+        let mut data = Encoder::default()
+            .string(1, "polite")
+            .string(2, "こんにちは")
+            .string(4, "gpt-4o")
+            .uint64(9, 300)
+            .finish();
+        data.push(5 << 3 | 5);
+        data.extend_from_slice(&0.5f32.to_le_bytes());
+
+        let request = decode_rephrase_request(&data).unwrap();
+        assert_eq!(request.action, "polite");
+        assert_eq!(request.text, "こんにちは");
+        assert_eq!(request.provider, None);
+        assert_eq!(request.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(request.temperature, Some(0.5));
+
+        assert!(decode_rephrase_request(&data[..data.len() - 1]).is_err());
+        assert!(decode_rephrase_request(&[]).is_err());
+    }
+
+    #[test]
+    fn test_varints() {
+        for value in [0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let data = Encoder::default().uint64(1, value).finish();
+            let expected = if value == 0 {
+                vec![]
+            } else {
+                vec![(1, Value::Varint(value))]
+            };
+            assert_eq!(fields(&data).unwrap(), expected);
+        }
+        assert_eq!(
+            Encoder::default().uint64(1, 300).finish(),
+            [0x08, 0xac, 0x02]
+        );
+    }
+}
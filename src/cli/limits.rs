@@ -0,0 +1,270 @@
+//! Request limits of the HTTP server
+//!
+//! Every request an app sends to `rephraser serve` may turn into a paid LLM
+//! call, so a misbehaving client could run through the API quota or, by
+//! piling up requests, memory. Authenticated requests therefore go through
+//! three limits, all answered with `429 Too Many Requests` and a
+//! `Retry-After` header when exceeded:
+//!
+//! * each client address gets `requests_per_minute` requests, with every
+//!   text of a batch counting as one
+//! * at most `max_concurrent_requests` run at the same time
+//! * at most `max_queued_requests` wait for one of those slots
+
+use super::server::{ErrorBody, ServerState};
+use crate::config::ServerConfig;
+use crate::error::RephraserError;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use http_body::{Frame, SizeHint};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+
+/// Client addresses tracked before idle ones are forgotten
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+/// Limits shared by all requests to the server
+pub(super) struct Limits {
+    slots: Arc<Semaphore>,
+    waiting: AtomicUsize,
+    max_queued: usize,
+    per_minute: u32,
+    allowances: Mutex<HashMap<IpAddr, Allowance>>,
+}
+
+/// Requests a client may still send, refilled continuously
+struct Allowance {
+    requests: f64,
+    updated: Instant,
+}
+
+impl Limits {
+    pub(super) fn from_config(config: &ServerConfig) -> Self {
+        Self {
+            slots: Arc::new(Semaphore::new(config.max_concurrent_requests.max(1))),
+            waiting: AtomicUsize::new(0),
+            max_queued: config.max_queued_requests,
+            per_minute: config.requests_per_minute,
+            allowances: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request from `client`, or tell how long until it may send
+    /// another
+    fn allow(&self, client: IpAddr) -> Result<(), Duration> {
+        self.allow_many(client, 1)
+    }
+
+    /// Count `requests` requests from `client` at once, or tell how long
+    /// until it may send them
+    ///
+    /// Nothing is counted when they are not allowed.
+    pub(super) fn allow_many(&self, client: IpAddr, requests: u32) -> Result<(), Duration> {
+        if self.per_minute == 0 || requests == 0 {
+            return Ok(());
+        }
+
+        let capacity = f64::from(self.per_minute);
+        let per_sec = capacity / 60.0;
+        let now = Instant::now();
+        let refill = |allowance: &Allowance| {
+            let elapsed = now.duration_since(allowance.updated).as_secs_f64();
+            (allowance.requests + elapsed * per_sec).min(capacity)
+        };
+
+        let mut allowances = self.allowances.lock().unwrap_or_else(|e| e.into_inner());
+        if allowances.len() >= MAX_TRACKED_CLIENTS {
+            allowances.retain(|_, allowance| refill(allowance) < capacity);
+        }
+        let allowance = allowances.entry(client).or_insert(Allowance {
+            requests: capacity,
+            updated: now,
+        });
+        allowance.requests = refill(allowance);
+        allowance.updated = now;
+        let requests = f64::from(requests);
+        if allowance.requests >= requests {
+            allowance.requests -= requests;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (requests - allowance.requests) / per_sec,
+            ))
+        }
+    }
+
+    /// Wait for a free slot, unless too many requests are waiting already
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        if let Ok(permit) = Arc::clone(&self.slots).try_acquire_owned() {
+            return Some(permit);
+        }
+
+        let waiting = self.waiting.fetch_add(1, Ordering::SeqCst);
+        let permit = if waiting < self.max_queued {
+            Arc::clone(&self.slots).acquire_owned().await.ok()
+        } else {
+            None
+        };
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+        permit
+    }
+}
+
+/// Address of the client that sent a request, from the request extensions
+#[derive(Clone, Copy)]
+pub(super) struct Client(pub(super) IpAddr);
+
+/// A slot for a running request
+///
+/// Shared by the response body and any task still working on the request,
+/// which gets it from the request extensions, and freed when both are done.
+#[derive(Clone)]
+pub(super) struct Slot {
+    _permit: Arc<OwnedSemaphorePermit>,
+}
+
+/// Apply the limits to a request
+///
+/// The slot is held until the response body has been sent, so streamed
+/// responses count as running until they end, and by any task that
+/// produces the response (see [`Slot`]).
+pub(super) async fn limit(
+    State(state): State<Arc<ServerState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |info| info.0.ip());
+    let grpc = super::grpc::is_grpc(request.headers());
+    request.extensions_mut().insert(Client(client));
+
+    if let Err(retry_in) = state.limits.allow(client) {
+        debug!("Rate limited {}", client);
+        let message = format!("too many requests from {}", client);
+        return too_many_requests(message, retry_in, grpc);
+    }
+
+    let Some(permit) = state.limits.acquire().await else {
+        let message = "too many requests in progress".to_string();
+        return too_many_requests(message, Duration::from_secs(1), grpc);
+    };
+
+    let slot = Slot {
+        _permit: Arc::new(permit),
+    };
+    request.extensions_mut().insert(slot.clone());
+    next.run(request)
+        .await
+        .map(|body| Body::new(HeldBody { body, _slot: slot }))
+}
+
+/// Reject a request, in the protocol it was made in
+pub(super) fn too_many_requests(message: String, retry_in: Duration, grpc: bool) -> Response {
+    let error = RephraserError::LlmRateLimit(message);
+    let mut response = if grpc {
+        super::grpc::error_response(&error)
+    } else {
+        (StatusCode::TOO_MANY_REQUESTS, Json(ErrorBody::from(&error))).into_response()
+    };
+    let secs = retry_in.as_secs_f64().ceil().max(1.0) as u64;
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+    response
+}
+
+/// Response body holding on to a slot until it is dropped
+struct HeldBody {
+    body: Body,
+    _slot: Slot,
+}
+
+impl http_body::Body for HeldBody {
+    type Data = <Body as http_body::Body>::Data;
+    type Error = <Body as http_body::Body>::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Pin::new(&mut self.body).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(max_concurrent: usize, max_queued: usize, per_minute: u32) -> Limits {
+        Limits::from_config(&ServerConfig {
+            max_concurrent_requests: max_concurrent,
+            max_queued_requests: max_queued,
+            requests_per_minute: per_minute,
+            ..ServerConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_rate_limit_per_client() {
+        let limited = limits(1, 0, 2);
+        let (first, second) = (IpAddr::from([10, 0, 0, 1]), IpAddr::from([10, 0, 0, 2]));
+        assert!(limited.allow(first).is_ok());
+        assert!(limited.allow(first).is_ok());
+        let retry_in = limited.allow(first).unwrap_err();
+        assert!(retry_in > Duration::from_secs(25) && retry_in <= Duration::from_secs(30));
+        assert!(limited.allow(second).is_ok());
+
+        assert!(limits(1, 0, 0).allow(first).is_ok());
+    }
+
+    #[test]
+    fn test_allow_many() {
+        let limited = limits(1, 0, 10);
+        let client = IpAddr::from([10, 0, 0, 1]);
+        assert!(limited.allow_many(client, 8).is_ok());
+        // Too many at once counts none of them
+        assert!(limited.allow_many(client, 3).is_err());
+        assert!(limited.allow_many(client, 2).is_ok());
+        assert!(limited.allow(client).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bounded_queue() {
+        let limits = Arc::new(limits(1, 1, 0));
+        let running = limits.acquire().await.unwrap();
+
+        // One request may wait for the slot, the next is turned away
+        let queued = tokio::spawn({
+            let limits = Arc::clone(&limits);
+            async move { limits.acquire().await.is_some() }
+        });
+        while limits.waiting.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert!(limits.acquire().await.is_none());
+
+        drop(running);
+        assert!(queued.await.unwrap());
+    }
+}
@@ -0,0 +1,235 @@
+//! Model Context Protocol server mode
+//!
+//! Speaks JSON-RPC 2.0 over stdio, one message per line, and exposes each
+//! configured action as a tool taking a single `text` argument.
+
+use super::commands::run_actions;
+use crate::cli::GlobalArgs;
+use crate::config::{ActionConfig, Config};
+use crate::pipeline::LlmOverrides;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Protocol revision implemented by this server
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// MCP server exposing the configured actions as tools
+pub struct McpServer {
+    config: Arc<Config>,
+}
+
+impl McpServer {
+    pub fn new(config: impl Into<Arc<Config>>) -> Self {
+        Self {
+            config: config.into(),
+        }
+    }
+
+    /// Handle one line of input, returning the line to answer with
+    ///
+    /// Notifications get no answer.
+    pub async fn handle_line(&self, line: &str) -> Option<String> {
+        let response = match serde_json::from_str::<Value>(line) {
+            Ok(message) => self.handle(&message).await?,
+            Err(e) => error_response(&Value::Null, PARSE_ERROR, &e.to_string()),
+        };
+        Some(response.to_string())
+    }
+
+    /// Handle a parsed message
+    async fn handle(&self, message: &Value) -> Option<Value> {
+        // Messages without an id are notifications, e.g. "notifications/initialized"
+        let id = message.get("id")?;
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": {
+                    "name": "rephraser",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({
+                "tools": self.config.actions.iter().map(tool).collect::<Vec<_>>(),
+            })),
+            "tools/call" => self.call_tool(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    /// Run the action named by a `tools/call` request
+    ///
+    /// Failures of the action itself are reported as a tool result with
+    /// `isError` so the host can show them to the model.
+    async fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+        let text = params
+            .pointer("/arguments/text")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "Missing 'text' argument".to_string()))?;
+
+        let opts = GlobalArgs {
+            quiet: true,
+            ..GlobalArgs::default()
+        };
+        let result = run_actions(
+            &opts,
+            &self.config,
+            &[name.to_string()],
+            text,
+            &LlmOverrides::default(),
+        )
+        .await;
+
+        Ok(match result {
+            Ok(mut runs) => {
+                let output = runs
+                    .pop()
+                    .map(|run| run.completion.text)
+                    .unwrap_or_default();
+                json!({ "content": [{ "type": "text", "text": output }], "isError": false })
+            }
+            Err(e) => {
+                json!({ "content": [{ "type": "text", "text": e.to_string() }], "isError": true })
+            }
+        })
+    }
+}
+
+/// Tool definition for an action
+fn tool(action: &ActionConfig) -> Value {
+    let description = action
+        .description
+        .clone()
+        .unwrap_or_else(|| action.display_name.clone());
+
+    json!({
+        "name": action.name,
+        "description": description,
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "text": { "type": "string", "description": "Text to transform" },
+            },
+            "required": ["text"],
+        },
+    })
+}
+
+fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> McpServer {
+        McpServer::new(Config::mock())
+    }
+
+    async fn request(server: &McpServer, message: Value) -> Value {
+        let line = server
+            .handle_line(&message.to_string())
+            .await
+            .expect("a response");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_initialize_and_list_tools() {
+        let server = server();
+
+        let response = request(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"}),
+        )
+        .await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["protocolVersion"], PROTOCOL_VERSION);
+
+        let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+        assert!(server
+            .handle_line(&notification.to_string())
+            .await
+            .is_none());
+
+        let response = request(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+        )
+        .await;
+        let tools = response["result"]["tools"].as_array().unwrap();
+        let polite = tools.iter().find(|t| t["name"] == "polite").unwrap();
+        assert_eq!(polite["inputSchema"]["required"][0], "text");
+    }
+
+    #[tokio::test]
+    async fn test_call_tool() {
+        let server = server();
+
+        let response = request(
+            &server,
+            json!({
+                "jsonrpc": "2.0", "id": "a", "method": "tools/call",
+                "params": {"name": "polite", "arguments": {"text": "hello"}},
+            }),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], false);
+        assert!(response["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("お元気でしょうか"));
+
+        let response = request(
+            &server,
+            json!({
+                "jsonrpc": "2.0", "id": "b", "method": "tools/call",
+                "params": {"name": "nope", "arguments": {"text": "hello"}},
+            }),
+        )
+        .await;
+        assert_eq!(response["result"]["isError"], true);
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let server = server();
+
+        let response = request(
+            &server,
+            json!({"jsonrpc": "2.0", "id": 3, "method": "resources/list"}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let line = server.handle_line("{not json").await.unwrap();
+        let response: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+    }
+}
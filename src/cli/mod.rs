@@ -2,5 +2,13 @@
 
 pub mod args;
 pub mod commands;
+mod grpc;
+mod limits;
+mod mcp;
+mod server;
 
-pub use args::{Cli, Commands, ConfigCommands};
+pub use args::{
+    rephrase_actions, ActionCommands, AgentCommands, CacheCommands, Cli, Commands, ConfigCommands,
+    GlobalArgs, HistoryCommands, InputArgs, LlmArgs, LogsCommands, OutputFormat, ProofreadFormat,
+    QueueCommands, RaycastCommands, ScheduleCommands, SecretsCommands, UrlHandlerCommands,
+};
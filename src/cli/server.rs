@@ -0,0 +1,789 @@
+//! HTTP server mode
+//!
+//! Exposes the configured actions to browser extensions and other apps on
+//! the machine:
+//!
+//! * `POST /rephrase` - run an action on `{"action": ..., "text": ...}`;
+//!   with `Accept: text/event-stream` the response is streamed as
+//!   server-sent events
+//! * `POST /batch` - run an action on each of `{"action": ..., "texts": [...]}`
+//!   with the `[batch]` concurrency; answers `{"results": [...]}` with a
+//!   response or error body per text, in order, or 413 for more than
+//!   `max_batch_texts` texts
+//! * `GET /actions` - list the configured actions
+//! * `GET /metrics` - request metrics in the Prometheus text format, or as
+//!   JSON with `Accept: application/json`; only with `[metrics]` enabled
+//! * `GET /health` - liveness check with the health of each configured
+//!   provider, never authenticated
+//!
+//! The same address serves the gRPC interface in `proto/rephraser.proto`
+//! (see [`super::grpc`]). Authenticated requests are subject to the
+//! concurrency and rate limits in [`super::limits`].
+
+use super::commands::stream_action;
+use super::limits::{limit, too_many_requests, Client, Limits, Slot};
+use crate::batch::Batch;
+use crate::config::{ActionConfig, Config, ConfigManager};
+use crate::error::{ErrorCode, RephraserError};
+use crate::llm::health::HealthChecks;
+use crate::llm::TokenUsage;
+use crate::metrics::MetricsStore;
+use crate::pipeline::{self, provider_clients, ActionRun, LlmOverrides};
+use axum::extract::{Extension, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+
+/// Shared state of the server
+pub struct ServerState {
+    pub(super) config: Arc<Config>,
+    /// Bearer token clients must send, if authentication is enabled
+    token: Option<String>,
+    /// Latest provider health checks, shared by `/health` requests
+    health: HealthChecks,
+    /// Concurrency and rate limits of authenticated requests
+    pub(super) limits: Limits,
+}
+
+impl ServerState {
+    pub fn new(config: impl Into<Arc<Config>>, token: Option<String>) -> Self {
+        let config = config.into();
+        Self {
+            limits: Limits::from_config(&config.server),
+            config,
+            token,
+            health: HealthChecks::default(),
+        }
+    }
+}
+
+/// Body of `POST /rephrase`, also read by `rephraser shortcut`
+#[derive(Debug, Deserialize)]
+pub(super) struct RephraseRequest {
+    pub(super) action: String,
+    pub(super) text: String,
+    #[serde(default)]
+    pub(super) provider: Option<String>,
+    #[serde(default)]
+    pub(super) model: Option<String>,
+    #[serde(default)]
+    pub(super) temperature: Option<f32>,
+    /// Neither use nor store a cached response
+    #[serde(default)]
+    pub(super) no_cache: bool,
+    /// Ignore a cached response but store the new one
+    #[serde(default)]
+    pub(super) refresh: bool,
+}
+
+/// Successful response of `POST /rephrase`, also printed by `rephraser shortcut`
+#[derive(Debug, Serialize)]
+pub(super) struct RephraseResponse {
+    pub(super) action: String,
+    pub(super) output: String,
+    pub(super) provider: String,
+    pub(super) model: String,
+    pub(super) tokens: TokenUsage,
+    pub(super) duration_ms: u128,
+}
+
+impl From<ActionRun> for RephraseResponse {
+    fn from(run: ActionRun) -> Self {
+        let (provider, model) = run.provider_and_model();
+        Self {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            action: run.action,
+            output: run.completion.text,
+            tokens: run.completion.usage,
+            duration_ms: run.duration.as_millis(),
+        }
+    }
+}
+
+/// Body of `POST /batch`
+#[derive(Debug, Deserialize)]
+struct BatchRequest {
+    action: String,
+    texts: Vec<String>,
+    #[serde(default)]
+    provider: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    temperature: Option<f32>,
+    #[serde(default)]
+    no_cache: bool,
+    #[serde(default)]
+    refresh: bool,
+}
+
+/// Response of `POST /batch`
+#[derive(Debug, Serialize)]
+struct BatchResponse {
+    results: Vec<BatchItem>,
+}
+
+/// Result for one text of a batch
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum BatchItem {
+    Done(RephraseResponse),
+    Failed(ErrorBody),
+}
+
+/// Data of a `token` event
+#[derive(Debug, Serialize)]
+struct TokenEvent<'a> {
+    text: &'a str,
+}
+
+/// Error response body
+#[derive(Debug, Serialize)]
+pub(super) struct ErrorBody {
+    error: String,
+    /// The CLI exit code for the same failure
+    code: i32,
+    error_code: ErrorCode,
+    /// Whether sending the same request again later may succeed
+    retryable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<&'static str>,
+}
+
+impl ErrorBody {
+    fn new(error: String, code: ErrorCode) -> Self {
+        Self {
+            error,
+            code: code.exit_code(),
+            error_code: code,
+            retryable: code.is_retryable(),
+            hint: code.hint(),
+        }
+    }
+}
+
+impl From<&RephraserError> for ErrorBody {
+    fn from(error: &RephraserError) -> Self {
+        Self::new(error.to_string(), error.code())
+    }
+}
+
+/// A failed request, rendered as JSON with a matching status code
+struct ApiError(RephraserError);
+
+impl From<RephraserError> for ApiError {
+    fn from(error: RephraserError) -> Self {
+        Self(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (status_for(&self.0), Json(ErrorBody::from(&self.0))).into_response()
+    }
+}
+
+/// HTTP status for an error
+fn status_for(error: &RephraserError) -> StatusCode {
+    match error.code() {
+        ErrorCode::ActionNotFound => StatusCode::NOT_FOUND,
+        ErrorCode::Input => StatusCode::BAD_REQUEST,
+        ErrorCode::Validation => StatusCode::UNPROCESSABLE_ENTITY,
+        ErrorCode::RateLimit => StatusCode::TOO_MANY_REQUESTS,
+        ErrorCode::Timeout => StatusCode::GATEWAY_TIMEOUT,
+        ErrorCode::CircuitOpen => StatusCode::SERVICE_UNAVAILABLE,
+        ErrorCode::Auth
+        | ErrorCode::LlmApi
+        | ErrorCode::BadRequest
+        | ErrorCode::ServiceUnavailable
+        | ErrorCode::Network
+        | ErrorCode::Offline => StatusCode::BAD_GATEWAY,
+        ErrorCode::Config | ErrorCode::Registry | ErrorCode::Output | ErrorCode::Other => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Build the server's routes
+pub fn router(state: Arc<ServerState>) -> Router {
+    let authenticated = Router::new()
+        .route("/rephrase", post(rephrase))
+        .route("/batch", post(batch))
+        .route("/actions", get(actions))
+        .route("/metrics", get(metrics))
+        .merge(super::grpc::routes())
+        .route_layer(middleware::from_fn_with_state(Arc::clone(&state), limit))
+        .route_layer(middleware::from_fn_with_state(
+            Arc::clone(&state),
+            authorize,
+        ));
+
+    Router::new()
+        .merge(authenticated)
+        .route("/health", get(health))
+        .with_state(state)
+}
+
+/// Reject requests without the expected bearer token
+async fn authorize(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(expected) = &state.token else {
+        return next.run(request).await;
+    };
+
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(ErrorBody::new(
+                "Missing or invalid bearer token".to_string(),
+                ErrorCode::Auth,
+            )),
+        )
+            .into_response(),
+    }
+}
+
+/// Compare secrets without leaking the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+async fn rephrase(
+    State(state): State<Arc<ServerState>>,
+    slot: Option<Extension<Slot>>,
+    headers: HeaderMap,
+    Json(request): Json<RephraseRequest>,
+) -> Response {
+    if wants_event_stream(&headers) {
+        rephrase_stream(state, slot.map(|Extension(slot)| slot), request).into_response()
+    } else {
+        rephrase_json(state, request).await.into_response()
+    }
+}
+
+/// Whether the client asked for server-sent events
+fn wants_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+/// Run the request and answer with the complete result
+async fn rephrase_json(
+    state: Arc<ServerState>,
+    request: RephraseRequest,
+) -> Result<Json<RephraseResponse>, ApiError> {
+    Ok(Json(run_request(&state.config, request).await?))
+}
+
+/// Run the action of a request
+pub(super) async fn run_request(
+    config: &Config,
+    request: RephraseRequest,
+) -> crate::error::Result<RephraseResponse> {
+    let llm = request.llm_overrides();
+    let run = pipeline::run(config, &request.action, &request.text, &llm).await?;
+    Ok(run.into())
+}
+
+/// Run the action on every text of the request as a batch
+///
+/// Each text counts as a request against the client's allowance; the
+/// request itself was counted as the first.
+async fn batch(
+    State(state): State<Arc<ServerState>>,
+    Extension(Client(client)): Extension<Client>,
+    Json(request): Json<BatchRequest>,
+) -> Response {
+    let max_texts = state.config.server.max_batch_texts;
+    if request.texts.len() > max_texts {
+        let error = RephraserError::Input(format!(
+            "A batch can have at most {} texts, got {}",
+            max_texts,
+            request.texts.len()
+        ));
+        return (StatusCode::PAYLOAD_TOO_LARGE, Json(ErrorBody::from(&error))).into_response();
+    }
+    let more = u32::try_from(request.texts.len().saturating_sub(1)).unwrap_or(u32::MAX);
+    if let Err(retry_in) = state.limits.allow_many(client, more) {
+        let message = format!("too many requests from {}", client);
+        return too_many_requests(message, retry_in, false);
+    }
+
+    let llm = LlmOverrides {
+        provider: request.provider,
+        model: request.model,
+        temperature: request.temperature,
+        no_cache: request.no_cache,
+        refresh: request.refresh,
+        ..LlmOverrides::default()
+    };
+    let results = Batch::from_config(&state.config.batch)
+        .run(&request.texts, |text| {
+            pipeline::run(&state.config, &request.action, text, &llm)
+        })
+        .await;
+
+    let results = results
+        .into_results()
+        .into_iter()
+        .map(|result| match result {
+            Ok(run) => BatchItem::Done(run.into()),
+            Err(e) => BatchItem::Failed(ErrorBody::from(&e)),
+        })
+        .collect();
+    Json(BatchResponse { results }).into_response()
+}
+
+/// Run the request, streaming the response as server-sent events
+///
+/// Each piece of text is sent as a `token` event (`{"text": ...}`) as it
+/// arrives, followed by a `done` event with the same body as a non-streamed
+/// response, or an `error` event with an error body.
+fn rephrase_stream(
+    state: Arc<ServerState>,
+    slot: Option<Slot>,
+    request: RephraseRequest,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = stream_task(slot, |sender| async move {
+        let llm = request.llm_overrides();
+        let tokens = sender.clone();
+        let mut on_token = move |text: &str| {
+            let _ = tokens.send(event("token", &TokenEvent { text }));
+        };
+
+        let result = stream_action(
+            &state.config,
+            &request.action,
+            &request.text,
+            &llm,
+            &mut on_token,
+        )
+        .await;
+        let last = match result {
+            Ok(run) => event("done", &RephraseResponse::from(run)),
+            Err(e) => event("error", &ErrorBody::from(&e)),
+        };
+        let _ = sender.send(last);
+    });
+
+    Sse::new(events.map(Ok)).keep_alive(KeepAlive::default())
+}
+
+/// Run `task` in the background, streaming what it sends
+///
+/// The task keeps the request's slot until it ends, and is aborted when the
+/// stream is dropped because the client went away, so an abandoned request
+/// neither keeps calling the LLM nor runs beyond the concurrency limit.
+pub(super) fn stream_task<T, F>(
+    slot: Option<Slot>,
+    task: impl FnOnce(mpsc::UnboundedSender<T>) -> F,
+) -> TaskStream<T>
+where
+    T: Send + 'static,
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let running = task(sender);
+    let handle = tokio::spawn(async move {
+        let _slot = slot;
+        running.await;
+    });
+    TaskStream {
+        receiver: UnboundedReceiverStream::new(receiver),
+        task: handle.abort_handle(),
+    }
+}
+
+/// Messages of a task started with [`stream_task`]
+pub(super) struct TaskStream<T> {
+    receiver: UnboundedReceiverStream<T>,
+    task: AbortHandle,
+}
+
+impl<T> Stream for TaskStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl<T> Drop for TaskStream<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// A named event with JSON data
+fn event(name: &str, data: &impl Serialize) -> Event {
+    let event = Event::default().event(name);
+    match serde_json::to_string(data) {
+        Ok(json) => event.data(json),
+        Err(_) => event,
+    }
+}
+
+impl RephraseRequest {
+    /// LLM overrides given in the request
+    pub(super) fn llm_overrides(&self) -> LlmOverrides {
+        LlmOverrides {
+            provider: self.provider.clone(),
+            model: self.model.clone(),
+            temperature: self.temperature,
+            no_cache: self.no_cache,
+            refresh: self.refresh,
+            ..LlmOverrides::default()
+        }
+    }
+}
+
+async fn actions(State(state): State<Arc<ServerState>>) -> Json<Vec<ActionConfig>> {
+    Json(state.config.actions.clone())
+}
+
+async fn metrics(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    if !state.config.metrics.enabled {
+        let error = RephraserError::Config(
+            "Metrics are disabled; set `enabled = true` under [metrics]".to_string(),
+        );
+        return Ok((StatusCode::NOT_FOUND, Json(ErrorBody::from(&error))).into_response());
+    }
+
+    let metrics = MetricsStore::new(&ConfigManager::new()?.metrics_path()).load();
+    let wants_json = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"));
+    Ok(if wants_json {
+        Json(metrics).into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            metrics.to_prometheus(),
+        )
+            .into_response()
+    })
+}
+
+/// How long `/health` reuses the results of its provider checks, so that
+/// polling it doesn't send a request to every provider each time
+const HEALTH_MAX_AGE: Duration = Duration::from_secs(60);
+
+async fn health(State(state): State<Arc<ServerState>>) -> Json<serde_json::Value> {
+    let providers = state
+        .health
+        .check(|| provider_clients(&state.config), HEALTH_MAX_AGE)
+        .await;
+    Json(serde_json::json!({
+        "status": "ok",
+        "version": env!("CARGO_PKG_VERSION"),
+        "providers": providers,
+    }))
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Start a server with the mock provider on a free port
+    pub(crate) async fn start(token: Option<&str>) -> String {
+        start_with(token, Config::mock()).await
+    }
+
+    async fn start_with(token: Option<&str>, config: Config) -> String {
+        let state = Arc::new(ServerState::new(config, token.map(str::to_string)));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(state)).await });
+        address
+    }
+
+    #[tokio::test]
+    async fn test_rephrase_and_errors() {
+        let address = start(None).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("{}/rephrase", address))
+            .json(&serde_json::json!({"action": "polite", "text": "hello"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["action"], "polite");
+        assert_eq!(body["provider"], "mock");
+        assert!(body["output"]
+            .as_str()
+            .unwrap()
+            .contains("お元気でしょうか"));
+
+        let response = client
+            .post(format!("{}/rephrase", address))
+            .json(&serde_json::json!({"action": "nope", "text": "hello"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["code"], crate::error::exit_code::ACTION_NOT_FOUND);
+        assert_eq!(body["error_code"], "action_not_found");
+        assert_eq!(body["retryable"], false);
+        assert!(body["hint"].as_str().unwrap().contains("list-actions"));
+
+        // Metrics are opt-in
+        let response = client
+            .get(format!("{}/metrics", address))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    #[test]
+    fn test_cache_overrides() {
+        let request: RephraseRequest =
+            serde_json::from_str(r#"{"action": "polite", "text": "hi", "refresh": true}"#).unwrap();
+        let llm = request.llm_overrides();
+        assert!(llm.refresh);
+        assert!(!llm.no_cache);
+    }
+
+    #[tokio::test]
+    async fn test_batch() {
+        let address = start(None).await;
+        let client = reqwest::Client::new();
+        let batch = |action: &str| {
+            client
+                .post(format!("{}/batch", address))
+                .json(&serde_json::json!({"action": action, "texts": ["hello", "bye"]}))
+                .send()
+        };
+
+        let response = batch("polite").await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["action"], "polite");
+        assert!(results[1]["output"].is_string());
+
+        // Failures are reported per text
+        let body: serde_json::Value = batch("nope").await.unwrap().json().await.unwrap();
+        assert_eq!(
+            body["results"][1]["code"],
+            crate::error::exit_code::ACTION_NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let address = start(None).await;
+        let body = reqwest::Client::new()
+            .post(format!("{}/rephrase", address))
+            .header("Accept", "text/event-stream")
+            .json(&serde_json::json!({"action": "polite", "text": "hello"}))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        let token = body.find("event: token").expect("token event");
+        let done = body.find("event: done").expect("done event");
+        assert!(token < done);
+        assert!(body.contains("お元気でしょうか"));
+
+        let body = reqwest::Client::new()
+            .post(format!("{}/rephrase", address))
+            .header("Accept", "text/event-stream")
+            .json(&serde_json::json!({"action": "nope", "text": "hello"}))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(body.contains("event: error"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_task_stops_with_client() {
+        let (started_tx, started) = tokio::sync::oneshot::channel();
+        let (finished_tx, finished) = tokio::sync::oneshot::channel::<()>();
+        let mut stream = stream_task(None, |sender| async move {
+            // Dropped, closing `finished`, when the task is aborted
+            let _finished = finished_tx;
+            let _ = sender.send("token");
+            let _ = started_tx.send(());
+            std::future::pending::<()>().await;
+        });
+
+        assert_eq!(stream.next().await, Some("token"));
+        started.await.unwrap();
+        drop(stream);
+        assert!(finished.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let mut config = Config::mock();
+        config.server.requests_per_minute = 1;
+        let address = start_with(None, config).await;
+        let client = reqwest::Client::new();
+
+        let first = client
+            .get(format!("{}/actions", address))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(first.status(), 200);
+        let second = client
+            .get(format!("{}/actions", address))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(second.status(), 429);
+        assert_eq!(second.headers()["retry-after"], "60");
+        let body: serde_json::Value = second.json().await.unwrap();
+        assert_eq!(body["code"], crate::error::exit_code::RATE_LIMIT);
+        assert_eq!(body["retryable"], true);
+
+        // Health checks are never limited
+        let health = client
+            .get(format!("{}/health", address))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(health.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_batch_limits() {
+        let mut config = Config::mock();
+        config.server.requests_per_minute = 3;
+        config.server.max_batch_texts = 2;
+        let address = start_with(None, config).await;
+        let client = reqwest::Client::new();
+        let batch = |texts: &[&str]| {
+            client
+                .post(format!("{}/batch", address))
+                .json(&serde_json::json!({"action": "polite", "texts": texts}))
+                .send()
+        };
+
+        let too_large = batch(&["a", "b", "c"]).await.unwrap();
+        assert_eq!(too_large.status(), 413);
+
+        // Each text counts as a request: the rejected batch took one, this
+        // one the other two
+        let done = batch(&["a", "b"]).await.unwrap();
+        assert_eq!(done.status(), 200);
+        let body: serde_json::Value = done.json().await.unwrap();
+        assert_eq!(body["results"].as_array().unwrap().len(), 2);
+
+        let limited = batch(&["a"]).await.unwrap();
+        assert_eq!(limited.status(), 429);
+        assert!(limited.headers().contains_key("retry-after"));
+    }
+
+    #[tokio::test]
+    async fn test_batch_charges_every_text() {
+        let mut config = Config::mock();
+        config.server.requests_per_minute = 2;
+        let address = start_with(None, config).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("{}/batch", address))
+            .json(&serde_json::json!({"action": "polite", "texts": ["a", "b", "c"]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 429);
+
+        // The rejected texts were not counted
+        let actions = client
+            .get(format!("{}/actions", address))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(actions.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_bearer_token() {
+        let address = start(Some("secret")).await;
+        let client = reqwest::Client::new();
+
+        let health = client
+            .get(format!("{}/health", address))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(health.status(), 200);
+        let health: serde_json::Value = health.json().await.unwrap();
+        assert_eq!(health["providers"][0]["provider"], "mock");
+        assert_eq!(health["providers"][0]["ok"], true);
+
+        let anonymous = client
+            .get(format!("{}/actions", address))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(anonymous.status(), 401);
+
+        let wrong = client
+            .get(format!("{}/actions", address))
+            .bearer_auth("guess")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong.status(), 401);
+
+        let actions: Vec<serde_json::Value> = client
+            .get(format!("{}/actions", address))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert!(actions.iter().any(|action| action["name"] == "polite"));
+    }
+}
@@ -2,8 +2,15 @@
 
 use crate::config::models::Config;
 use crate::error::{RephraserError, Result};
+use crate::llm::ClientRegistry;
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tracing::debug;
+
+/// Configurations loaded by this process, by the path they came from
+static SHARED: OnceLock<Mutex<HashMap<PathBuf, Arc<Config>>>> = OnceLock::new();
 
 /// Configuration manager
 pub struct ConfigManager {
@@ -34,14 +41,116 @@ impl ConfigManager {
         &self.config_path
     }
 
+    /// Get the directory containing the config file
+    ///
+    /// Other local state (caches, history) lives alongside the config file
+    pub fn config_dir(&self) -> PathBuf {
+        self.config_path
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default()
+    }
+
+    /// Get the history database path
+    pub fn history_path(&self) -> PathBuf {
+        self.config_dir().join("history.sqlite")
+    }
+
+    /// Get the usage database path
+    pub fn usage_path(&self) -> PathBuf {
+        self.config_dir().join("usage.sqlite")
+    }
+
+    /// Get the path of the saved metrics
+    pub fn metrics_path(&self) -> PathBuf {
+        self.config_dir().join("metrics.json")
+    }
+
+    /// Get the directory of queued jobs
+    pub fn queue_dir(&self) -> PathBuf {
+        self.config_dir().join("queue")
+    }
+
+    /// Get the log file directory
+    pub fn log_dir(&self) -> PathBuf {
+        self.config_dir().join("logs")
+    }
+
+    /// Get the response cache directory
+    pub fn cache_dir(&self) -> PathBuf {
+        self.config_dir().join("cache")
+    }
+
+    /// Get the path of the saved clipboard contents for `undo`
+    pub fn undo_path(&self) -> PathBuf {
+        self.config_dir().join("clipboard-undo.json")
+    }
+
+    /// Get the path where the result of the last update check is kept
+    pub fn update_state_path(&self) -> PathBuf {
+        self.config_dir().join("update-check.json")
+    }
+
+    /// Get the path of the saved circuit breaker state
+    pub fn circuit_path(&self) -> PathBuf {
+        self.config_dir().join("circuits.json")
+    }
+
+    /// Get the directory of texts that clicked notifications copy
+    pub fn notification_dir(&self) -> PathBuf {
+        self.config_dir().join("notifications")
+    }
+
+    /// Get the daemon's socket path
+    pub fn socket_path(&self) -> PathBuf {
+        self.config_dir().join("daemon.sock")
+    }
+
     /// Load configuration from file
     ///
-    /// If the file doesn't exist, returns default configuration
+    /// If the file doesn't exist, returns default configuration. Returns a
+    /// copy of [`ConfigManager::shared`], so the file is only read again
+    /// after [`ConfigManager::invalidate`].
     pub fn load(&self) -> Result<Config> {
+        self.shared().map(Arc::unwrap_or_clone)
+    }
+
+    /// Configuration shared by everything in this process
+    ///
+    /// The file is read and parsed on first use; later calls for the same
+    /// path return the same `Config` until it is saved or invalidated.
+    /// Errors are not cached.
+    pub fn shared(&self) -> Result<Arc<Config>> {
+        let mut shared = lock_shared();
+        if let Some(config) = shared.get(&self.config_path) {
+            return Ok(Arc::clone(config));
+        }
+
+        let config = Arc::new(self.read()?);
+        shared.insert(self.config_path.clone(), Arc::clone(&config));
+        Ok(config)
+    }
+
+    /// Forget the shared configuration, so the next load reads the file
+    /// again (e.g. after it was edited by hand)
+    ///
+    /// Provider clients built from it are forgotten as well.
+    pub fn invalidate(&self) {
+        lock_shared().remove(&self.config_path);
+        ClientRegistry::global().clear();
+    }
+
+    /// Read and parse the config file, bypassing the shared configuration
+    fn read(&self) -> Result<Config> {
         if !self.config_path.exists() {
+            debug!(
+                "No config file at {}, using defaults",
+                self.config_path.display()
+            );
             return Ok(Config::default());
         }
 
+        debug!("Loading config from {}", self.config_path.display());
         let content = fs::read_to_string(&self.config_path)?;
         let config: Config = toml::from_str(&content)?;
 
@@ -61,6 +170,7 @@ impl ConfigManager {
             .map_err(|e| RephraserError::Config(format!("Failed to serialize config: {}", e)))?;
 
         fs::write(&self.config_path, content)?;
+        self.invalidate();
 
         Ok(())
     }
@@ -89,6 +199,23 @@ impl ConfigManager {
     }
 }
 
+/// The shared configurations, even if another thread panicked while holding
+/// the lock (entries are only ever replaced whole)
+fn lock_shared() -> MutexGuard<'static, HashMap<PathBuf, Arc<Config>>> {
+    SHARED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Expand a leading `~/` in a configured path to the home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
 impl Default for ConfigManager {
     fn default() -> Self {
         Self::new().expect("Failed to create default ConfigManager")
@@ -98,7 +225,6 @@ impl Default for ConfigManager {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::env;
 
     #[test]
     fn test_config_default() {
@@ -107,6 +233,136 @@ mod tests {
         assert_eq!(config.actions.len(), 3);
     }
 
+    #[test]
+    fn test_output_method_from_str() {
+        use crate::config::OutputMethod;
+        assert_eq!(
+            "clipboard".parse::<OutputMethod>().unwrap(),
+            OutputMethod::Clipboard
+        );
+        assert_eq!(
+            "stdout".parse::<OutputMethod>().unwrap(),
+            OutputMethod::Terminal
+        );
+        assert_eq!(
+            "notes_app".parse::<OutputMethod>().unwrap(),
+            OutputMethod::NotesApp
+        );
+        assert_eq!(
+            "slack".parse::<OutputMethod>().unwrap(),
+            OutputMethod::Slack
+        );
+        assert_eq!(
+            "discord".parse::<OutputMethod>().unwrap(),
+            OutputMethod::Discord
+        );
+        assert_eq!(
+            "email".parse::<OutputMethod>().unwrap(),
+            OutputMethod::Email
+        );
+        assert!("fax".parse::<OutputMethod>().is_err());
+
+        let plugin = OutputMethod::Plugin("obsidian".to_string());
+        assert_eq!("plugin:obsidian".parse::<OutputMethod>().unwrap(), plugin);
+        assert!("plugin:".parse::<OutputMethod>().is_err());
+        let mut config = Config::default();
+        config.output.method = plugin.clone();
+        let saved = toml::to_string(&config).unwrap();
+        assert!(saved.contains("method = \"plugin:obsidian\""));
+        assert_eq!(
+            toml::from_str::<Config>(&saved).unwrap().output.method,
+            plugin
+        );
+    }
+
+    #[test]
+    fn test_set_provider() {
+        let mut llm = Config::default().llm;
+        llm.model = "gpt-4o".to_string();
+
+        llm.set_provider("openai");
+        assert_eq!(llm.model, "gpt-4o");
+
+        llm.set_provider("anthropic");
+        assert_eq!(llm.provider, "anthropic");
+        assert_eq!(llm.api_key_env, "ANTHROPIC_API_KEY");
+        assert!(llm.model.starts_with("claude"));
+    }
+
+    #[test]
+    fn test_for_model_spec() {
+        let llm = Config::default().llm;
+
+        let no_plugins = |_: &str| false;
+
+        let bare = llm.for_model_spec("gpt-4o", no_plugins);
+        assert_eq!(
+            (bare.provider.as_str(), bare.model.as_str()),
+            ("openai", "gpt-4o")
+        );
+
+        let qualified = llm.for_model_spec("anthropic:claude-3-5-haiku-20241022", no_plugins);
+        assert_eq!(qualified.provider, "anthropic");
+        assert_eq!(qualified.model, "claude-3-5-haiku-20241022");
+        assert_eq!(qualified.api_key_env, "ANTHROPIC_API_KEY");
+
+        // Other providers are recognized when configured...
+        let plugin = llm.for_model_spec("ollama:llama3:8b", |p| p == "ollama");
+        assert_eq!(
+            (plugin.provider.as_str(), plugin.model.as_str()),
+            ("ollama", "llama3:8b")
+        );
+        // ...and a colon is otherwise part of the model name
+        let colon = llm.for_model_spec("llama3:8b", no_plugins);
+        assert_eq!(
+            (colon.provider.as_str(), colon.model.as_str()),
+            ("openai", "llama3:8b")
+        );
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_home("~/notes/a.md"), home.join("notes/a.md"));
+        assert_eq!(expand_home("/tmp/a.md"), PathBuf::from("/tmp/a.md"));
+    }
+
+    #[test]
+    fn test_shared_config() {
+        let dir = std::env::temp_dir().join(format!("rephraser-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let manager = ConfigManager::with_path(dir.join("config.toml"));
+        let with_concurrency = |concurrency| {
+            let mut config = Config::default();
+            config.batch.concurrency = concurrency;
+            toml::to_string(&config).unwrap()
+        };
+        fs::write(manager.config_path(), with_concurrency(1)).unwrap();
+
+        let config = manager.shared().unwrap();
+        assert_eq!(config.batch.concurrency, 1);
+
+        // Edits on disk are not seen until invalidated
+        fs::write(manager.config_path(), with_concurrency(2)).unwrap();
+        assert!(Arc::ptr_eq(&config, &manager.shared().unwrap()));
+        assert_eq!(manager.load().unwrap().batch.concurrency, 1);
+        manager.invalidate();
+        assert_eq!(manager.shared().unwrap().batch.concurrency, 2);
+
+        // Saving replaces it
+        let mut edited = manager.load().unwrap();
+        edited.batch.concurrency = 3;
+        manager.save(&edited).unwrap();
+        assert_eq!(manager.shared().unwrap().batch.concurrency, 3);
+
+        // Another path is read from its own file
+        let other = ConfigManager::with_path(dir.join("missing.toml"));
+        assert_eq!(other.load().unwrap().llm.provider, "openai");
+        assert_eq!(manager.shared().unwrap().batch.concurrency, 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = Config::default();
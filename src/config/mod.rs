@@ -1,7 +1,18 @@
 //! Configuration module
 
+#[cfg(feature = "native")]
 pub mod manager;
 pub mod models;
 
+#[cfg(feature = "native")]
 pub use manager::ConfigManager;
-pub use models::{ActionConfig, Config, LlmConfig, OutputConfig, OutputMethod};
+pub use models::{
+    ActionConfig, AuditConfig, BatchConfig, CacheConfig, CircuitConfig, ClipboardRule,
+    ClipboardWatchConfig, CompareConfig, Config, DaemonConfig, DiscordConfig, EmailConfig,
+    HistoryConfig, HooksConfig, InputConfig, Language, LlmConfig, LoggingConfig, MetricsConfig,
+    NoteConfig, NotesAppConfig, NotificationConfig, OfflineConfig, OutputConfig, OutputMethod,
+    PluginCommand, PluginsConfig, PrivacyConfig, PrivacyDetector, QueueConfig, RegistryConfig,
+    ScheduleConfig, ScriptingConfig, SecretBackend, SecretRef, SecretsConfig, ServerConfig,
+    SlackConfig, SpeechConfig, TonesConfig, UiConfig, UpdatesConfig, UrlSchemeConfig, UsageConfig,
+    ValidationRules,
+};
@@ -1,6 +1,7 @@
 //! Configuration data structures
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Main configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,65 +9,1426 @@ pub struct Config {
     pub llm: LlmConfig,
     pub output: OutputConfig,
     pub actions: Vec<ActionConfig>,
+
+    /// Input acquisition defaults
+    #[serde(default)]
+    pub input: InputConfig,
+
+    /// Local history of transformations
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    /// Token usage and spend tracking
+    #[serde(default)]
+    pub usage: UsageConfig,
+
+    /// Request counts and latency metrics
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// Cache of LLM responses
+    #[serde(default)]
+    pub cache: CacheConfig,
+
+    /// Log file
+    #[serde(default)]
+    pub logging: LoggingConfig,
+
+    /// Audit log of prompts and responses
+    #[serde(default)]
+    pub audit: AuditConfig,
+
+    /// Masking of sensitive text sent to providers
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+
+    /// Where provider API keys are read from
+    #[serde(default)]
+    pub secrets: SecretsConfig,
+
+    /// Providers implemented by external programs
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+
+    /// Shell commands run before and after actions
+    #[serde(default)]
+    pub hooks: HooksConfig,
+
+    /// Script actions
+    #[serde(default)]
+    pub scripting: ScriptingConfig,
+
+    /// Defaults for the compare command
+    #[serde(default)]
+    pub compare: CompareConfig,
+
+    /// Remote action registry
+    #[serde(default)]
+    pub registry: RegistryConfig,
+
+    /// Checks for newer releases
+    #[serde(default)]
+    pub updates: UpdatesConfig,
+
+    /// Command line interface
+    #[serde(default)]
+    pub ui: UiConfig,
+
+    /// Background daemon
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+
+    /// Queue of jobs that failed while offline
+    #[serde(default)]
+    pub queue: QueueConfig,
+
+    /// Provider used when the configured one can't be reached
+    #[serde(default)]
+    pub offline: OfflineConfig,
+
+    /// Failing fast while a provider keeps failing
+    #[serde(default)]
+    pub circuit: CircuitConfig,
+
+    /// Concurrency and retries of batch commands
+    #[serde(default)]
+    pub batch: BatchConfig,
+
+    /// HTTP server mode
+    #[serde(default)]
+    pub server: ServerConfig,
+
+    /// Rules for `watch-clipboard`
+    #[serde(default)]
+    pub clipboard_watch: ClipboardWatchConfig,
+
+    /// What `rephraser://` URLs may do
+    #[serde(default)]
+    pub url_scheme: UrlSchemeConfig,
+
+    /// Tone presets for `--tone`
+    #[serde(default)]
+    pub tones: TonesConfig,
+
+    /// Recurring jobs run by the daemon
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<ScheduleConfig>,
+}
+
+/// LLM provider configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmConfig {
+    /// Provider name: "openai", "anthropic", "mock" or a provider plugin
+    pub provider: String,
+
+    /// Model name (e.g., "gpt-4o-mini", "claude-3-sonnet-20240229")
+    pub model: String,
+
+    /// Environment variable name containing the API key
+    pub api_key_env: String,
+
+    /// LLM parameters
+    #[serde(default)]
+    pub parameters: LlmParameters,
+
+    /// Extra headers sent with every request to OpenAI or Anthropic, e.g.
+    /// for a gateway that routes or attributes requests by header
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl LlmConfig {
+    /// Copy of this configuration for a "provider:model" or bare "model" spec
+    ///
+    /// The part before a colon is taken as the provider if it is built in,
+    /// the current provider or one `is_provider` accepts, such as a plugin;
+    /// otherwise the whole spec is a model name, since names like
+    /// "llama3:8b" contain colons too. A bare model name keeps the current
+    /// provider.
+    pub fn for_model_spec(&self, spec: &str, is_provider: impl Fn(&str) -> bool) -> Self {
+        let mut llm = self.clone();
+        match spec.split_once(':') {
+            Some((provider, model))
+                if matches!(provider, "openai" | "anthropic" | "mock")
+                    || provider == self.provider
+                    || is_provider(provider) =>
+            {
+                llm.set_provider(provider);
+                llm.model = model.to_string();
+            }
+            _ => llm.model = spec.to_string(),
+        }
+        llm
+    }
+
+    /// Switch to another provider
+    ///
+    /// The model and API key variable are reset to the provider's defaults
+    /// unless the provider is unchanged.
+    pub fn set_provider(&mut self, provider: &str) {
+        if self.provider == provider {
+            return;
+        }
+
+        let (model, api_key_env) = match provider {
+            "openai" => ("gpt-4o-mini", "OPENAI_API_KEY"),
+            "anthropic" => ("claude-3-5-haiku-20241022", "ANTHROPIC_API_KEY"),
+            _ => (self.model.as_str(), self.api_key_env.as_str()),
+        };
+
+        self.model = model.to_string();
+        self.api_key_env = api_key_env.to_string();
+        self.provider = provider.to_string();
+    }
+}
+
+/// LLM API parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmParameters {
+    #[serde(default = "default_temperature")]
+    pub temperature: f32,
+
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: usize,
+
+    /// Cancel LLM requests that take longer than this many seconds
+    ///
+    /// The limit is per request: an action whose guardrails retry may take
+    /// up to `max_retries + 1` times as long in total.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl Default for LlmParameters {
+    fn default() -> Self {
+        Self {
+            temperature: default_temperature(),
+            max_tokens: default_max_tokens(),
+            timeout_secs: None,
+        }
+    }
+}
+
+fn default_temperature() -> f32 {
+    0.7
+}
+
+fn default_max_tokens() -> usize {
+    500
+}
+
+/// Input configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputConfig {
+    /// Read the input from the clipboard when no TEXT is given, instead of
+    /// from stdin
+    #[serde(default)]
+    pub from_clipboard: bool,
+
+    /// Largest file accepted by `--file`, in bytes
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: u64,
+
+    /// Characters sent per request when a file is processed in chunks with
+    /// `--chunked`
+    #[serde(default = "default_chunk_chars")]
+    pub chunk_chars: usize,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        Self {
+            from_clipboard: false,
+            max_file_size: default_max_file_size(),
+            chunk_chars: default_chunk_chars(),
+        }
+    }
+}
+
+fn default_max_file_size() -> u64 {
+    1024 * 1024
+}
+
+fn default_chunk_chars() -> usize {
+    8000
+}
+
+/// History configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    /// Record every transformation in ~/.rephraser/history.sqlite
+    #[serde(default = "default_history_enabled")]
+    pub enabled: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_history_enabled(),
+        }
+    }
+}
+
+fn default_history_enabled() -> bool {
+    true
+}
+
+/// Usage tracking configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageConfig {
+    /// Record token usage, estimated cost and latency of every request in
+    /// ~/.rephraser/usage.sqlite (no text is stored)
+    #[serde(default = "default_usage_enabled")]
+    pub enabled: bool,
+
+    /// Tag recorded with every request, e.g. the project or client it is
+    /// for; overridden per run by `--tag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+impl Default for UsageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_usage_enabled(),
+            tag: None,
+        }
+    }
+}
+
+fn default_usage_enabled() -> bool {
+    true
+}
+
+/// Metrics configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Count requests and record their latency per provider and action in
+    /// ~/.rephraser/metrics.json
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// File the metrics are also written to in the Prometheus text format,
+    /// e.g. for node_exporter's textfile collector
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub textfile: Option<String>,
+}
+
+/// Response cache configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheConfig {
+    /// Reuse responses to identical requests from ~/.rephraser/cache
+    #[serde(default = "default_cache_enabled")]
+    pub enabled: bool,
+
+    /// Discard cached responses older than this many hours
+    #[serde(default = "default_cache_ttl_hours")]
+    pub ttl_hours: u64,
+
+    /// Evict the oldest responses once the cache exceeds this many megabytes
+    #[serde(default = "default_cache_max_size_mb")]
+    pub max_size_mb: u64,
+
+    /// Ignore cached responses but still store new ones (`--refresh`)
+    #[serde(skip)]
+    pub refresh: bool,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_cache_enabled(),
+            ttl_hours: default_cache_ttl_hours(),
+            max_size_mb: default_cache_max_size_mb(),
+            refresh: false,
+        }
+    }
+}
+
+fn default_cache_enabled() -> bool {
+    true
+}
+
+fn default_cache_ttl_hours() -> u64 {
+    24 * 7
+}
+
+fn default_cache_max_size_mb() -> u64 {
+    50
+}
+
+/// Audit log configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Append every prompt sent to a provider, with its response, to a JSON
+    /// Lines file; requests fail if the file cannot be written
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Audit log file; supports a leading `~/`
+    #[serde(default = "default_audit_path")]
+    pub path: String,
+
+    /// Record the text itself; when false, only SHA-256 hashes are recorded
+    #[serde(default = "default_audit_record_text")]
+    pub record_text: bool,
+
+    /// Regular expressions whose matches are replaced with "[REDACTED]"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact: Vec<String>,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_audit_path(),
+            record_text: default_audit_record_text(),
+            redact: Vec::new(),
+        }
+    }
+}
+
+fn default_audit_path() -> String {
+    "~/.rephraser/audit.jsonl".to_string()
+}
+
+fn default_audit_record_text() -> bool {
+    true
+}
+
+/// Privacy filter configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    /// Replace sensitive text with placeholders before it is sent to the
+    /// provider, and restore it in the response
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Built-in detectors to apply
+    #[serde(default = "default_privacy_detect")]
+    pub detect: Vec<PrivacyDetector>,
+
+    /// Additional regular expressions to mask
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            detect: default_privacy_detect(),
+            patterns: Vec::new(),
+        }
+    }
+}
+
+fn default_privacy_detect() -> Vec<PrivacyDetector> {
+    vec![
+        PrivacyDetector::Email,
+        PrivacyDetector::Phone,
+        PrivacyDetector::ApiKey,
+    ]
+}
+
+/// Kind of sensitive text recognized by the privacy filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyDetector {
+    Email,
+    Phone,
+    ApiKey,
+}
+
+/// API key storage configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    /// Where each provider's API key is stored, keyed by provider name;
+    /// providers not listed here read the variable named by `api_key_env`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, SecretRef>,
+
+    /// Keychain service of `keychain` secrets
+    #[serde(default = "default_secrets_keychain_service")]
+    pub keychain_service: String,
+
+    /// Encrypted file holding `encrypted_file` secrets; supports a leading `~/`
+    #[serde(default = "default_secrets_file")]
+    pub file: String,
+
+    /// Environment variable holding the passphrase of the encrypted file
+    #[serde(default = "default_secrets_passphrase_env")]
+    pub passphrase_env: String,
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self {
+            providers: BTreeMap::new(),
+            keychain_service: default_secrets_keychain_service(),
+            file: default_secrets_file(),
+            passphrase_env: default_secrets_passphrase_env(),
+        }
+    }
+}
+
+fn default_secrets_keychain_service() -> String {
+    "rephraser".to_string()
+}
+
+fn default_secrets_file() -> String {
+    "~/.rephraser/secrets.enc".to_string()
+}
+
+fn default_secrets_passphrase_env() -> String {
+    "REPHRASER_SECRETS_PASSPHRASE".to_string()
+}
+
+/// Location of one secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretRef {
+    pub store: SecretBackend,
+
+    /// Name of the secret within the store: the environment variable, the
+    /// Keychain account or the entry in the encrypted file (all defaulting
+    /// to the provider's usual name), or an `op://` reference for 1Password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Kind of store a secret is read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    Env,
+    Keychain,
+    EncryptedFile,
+    #[serde(rename = "1password")]
+    OnePassword,
+}
+
+/// Provider plugin configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginsConfig {
+    /// Programs implementing providers, keyed by provider name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, PluginCommand>,
+
+    /// Programs receiving results, keyed by the name used in the
+    /// `plugin:<name>` output method
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub sinks: BTreeMap<String, PluginCommand>,
+
+    /// Look for a `rephraser-provider-<name>` or `rephraser-sink-<name>`
+    /// program on PATH for plugins that are not configured here
+    #[serde(default = "default_plugins_discover")]
+    pub discover: bool,
+}
+
+impl Default for PluginsConfig {
+    fn default() -> Self {
+        Self {
+            providers: BTreeMap::new(),
+            sinks: BTreeMap::new(),
+            discover: default_plugins_discover(),
+        }
+    }
+}
+
+fn default_plugins_discover() -> bool {
+    true
+}
+
+/// Program to run for a plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginCommand {
+    /// Path or name of the executable; supports a leading `~/`
+    pub command: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+/// Shell command hooks applying to every action
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Shell command the input is piped into before an action; its output
+    /// is transformed instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_hook: Option<String>,
+
+    /// Shell command the result is piped into after an action
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_hook: Option<String>,
+}
+
+/// Script action configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptingConfig {
+    /// Maximum number of LLM requests one script run may make
+    #[serde(default = "default_scripting_max_llm_calls")]
+    pub max_llm_calls: usize,
+
+    /// Maximum number of operations one script run may perform, guarding
+    /// against endless loops
+    #[serde(default = "default_scripting_max_operations")]
+    pub max_operations: u64,
+
+    /// Script of the action being run, set from the action at runtime
+    #[serde(skip)]
+    pub script: Option<String>,
+}
+
+impl Default for ScriptingConfig {
+    fn default() -> Self {
+        Self {
+            max_llm_calls: default_scripting_max_llm_calls(),
+            max_operations: default_scripting_max_operations(),
+            script: None,
+        }
+    }
+}
+
+fn default_scripting_max_llm_calls() -> usize {
+    10
+}
+
+fn default_scripting_max_operations() -> u64 {
+    1_000_000
+}
+
+/// Log file configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// Append logs to ~/.rephraser/logs/rephraser.log
+    #[serde(default = "default_logging_file")]
+    pub file: bool,
+
+    /// Level of Rephraser's messages in the log file ("error", "warn",
+    /// "info", "debug" or "trace")
+    #[serde(default = "default_logging_level")]
+    pub level: String,
+
+    /// Levels for individual modules, e.g. `"rephraser::daemon" = "debug"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modules: BTreeMap<String, String>,
+
+    /// Rotate the log file once it exceeds this many megabytes
+    #[serde(default = "default_logging_max_size_mb")]
+    pub max_size_mb: u64,
+
+    /// Number of rotated log files to keep
+    #[serde(default = "default_logging_max_files")]
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file: default_logging_file(),
+            level: default_logging_level(),
+            modules: BTreeMap::new(),
+            max_size_mb: default_logging_max_size_mb(),
+            max_files: default_logging_max_files(),
+        }
+    }
+}
+
+fn default_logging_file() -> bool {
+    true
+}
+
+fn default_logging_level() -> String {
+    "info".to_string()
+}
+
+fn default_logging_max_size_mb() -> u64 {
+    5
+}
+
+fn default_logging_max_files() -> usize {
+    3
+}
+
+/// Daemon configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonConfig {
+    /// Send LLM requests through `rephraser daemon` when it is running
+    #[serde(default = "default_daemon_enabled")]
+    pub enabled: bool,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_daemon_enabled(),
+        }
+    }
+}
+
+fn default_daemon_enabled() -> bool {
+    true
+}
+
+/// Fallback used while offline
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OfflineConfig {
+    /// Provider to send requests to when the configured one can't be reached
+    /// because this machine appears to be offline, typically a local model
+    /// served by a provider plugin (e.g. "ollama")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_provider: Option<String>,
+
+    /// Model for the fallback provider; the configured model if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_model: Option<String>,
+}
+
+impl OfflineConfig {
+    /// LLM configuration of the fallback for `llm`, if one is set
+    pub fn fallback_llm(&self, llm: &LlmConfig) -> Option<LlmConfig> {
+        let provider = self.fallback_provider.as_deref()?;
+        let mut fallback = llm.clone();
+        fallback.set_provider(provider);
+        if let Some(model) = &self.fallback_model {
+            fallback.model = model.clone();
+        }
+        Some(fallback)
+    }
+}
+
+/// Circuit breaker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitConfig {
+    /// Stop sending requests to a provider after it keeps failing
+    #[serde(default = "default_circuit_enabled")]
+    pub enabled: bool,
+
+    /// Consecutive failures (network errors, timeouts, rate limits, server
+    /// errors) after which requests to the provider stop
+    #[serde(default = "default_circuit_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// How long requests stop for, in seconds, before one is let through to
+    /// see whether the provider has recovered
+    #[serde(default = "default_circuit_cooldown")]
+    pub cooldown_secs: u64,
+}
+
+impl Default for CircuitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_circuit_enabled(),
+            failure_threshold: default_circuit_failure_threshold(),
+            cooldown_secs: default_circuit_cooldown(),
+        }
+    }
+}
+
+fn default_circuit_enabled() -> bool {
+    true
+}
+
+fn default_circuit_failure_threshold() -> u32 {
+    3
+}
+
+fn default_circuit_cooldown() -> u64 {
+    60
+}
+
+/// Offline queue configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueConfig {
+    /// Save `rephrase` jobs that fail because the network or provider is
+    /// unavailable, to run them later with `rephraser queue run`
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// How often the daemon retries queued jobs, in seconds
+    #[serde(default = "default_queue_retry_interval")]
+    pub retry_interval_secs: u64,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retry_interval_secs: default_queue_retry_interval(),
+        }
+    }
+}
+
+fn default_queue_retry_interval() -> u64 {
+    300
+}
+
+/// Batch configuration, for commands that run many LLM calls at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchConfig {
+    /// Most LLM calls in flight at the same time
+    #[serde(default = "default_batch_concurrency")]
+    pub concurrency: usize,
+
+    /// How often an item is retried when the network or provider is
+    /// unavailable
+    #[serde(default = "default_batch_retries")]
+    pub retries: u32,
+
+    /// Wait before the first retry of an item, in milliseconds; doubled for
+    /// each further retry
+    #[serde(default = "default_batch_retry_delay")]
+    pub retry_delay_ms: u64,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: default_batch_concurrency(),
+            retries: default_batch_retries(),
+            retry_delay_ms: default_batch_retry_delay(),
+        }
+    }
+}
+
+fn default_batch_concurrency() -> usize {
+    4
+}
+
+fn default_batch_retries() -> u32 {
+    2
+}
+
+fn default_batch_retry_delay() -> u64 {
+    1000
+}
+
+/// A job the daemon runs on a cron schedule
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScheduleConfig {
+    /// Name used in logs and by `rephraser schedule run`
+    pub name: String,
+
+    /// When to run: five cron fields (minute, hour, day of month, month,
+    /// day of week) in local time, or @hourly, @daily, @weekly, @monthly
+    pub cron: String,
+
+    /// Action applied to the input
+    pub action: String,
+
+    /// File to read the input from; supports a leading `~/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+
+    /// Input text, used when no file is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    /// Output method for the result (default: the `[output]` method)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputMethod>,
+
+    /// Markdown file the "note" method appends to, instead of
+    /// `[output.note]` path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_path: Option<String>,
 }
 
-/// LLM provider configuration
+/// HTTP server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LlmConfig {
-    /// Provider name: "openai", "anthropic"
-    pub provider: String,
+pub struct ServerConfig {
+    /// Address to listen on; keep the loopback address unless other
+    /// machines need access
+    #[serde(default = "default_server_host")]
+    pub host: String,
 
-    /// Model name (e.g., "gpt-4o-mini", "claude-3-sonnet-20240229")
-    pub model: String,
+    #[serde(default = "default_server_port")]
+    pub port: u16,
 
-    /// Environment variable name containing the API key
-    pub api_key_env: String,
+    /// Environment variable holding a bearer token that clients must send;
+    /// no authentication when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_env: Option<String>,
 
-    /// LLM parameters
-    #[serde(default)]
-    pub parameters: LlmParameters,
+    /// Most requests handled at the same time
+    #[serde(default = "default_server_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+
+    /// Most requests waiting for one of those to finish; more are turned
+    /// away with 429 Too Many Requests
+    #[serde(default = "default_server_max_queued_requests")]
+    pub max_queued_requests: usize,
+
+    /// Requests each client address may send per minute; 0 for no limit
+    #[serde(default = "default_server_requests_per_minute")]
+    pub requests_per_minute: u32,
+
+    /// Most texts in one `/batch` request; each counts as a request
+    /// against `requests_per_minute`
+    #[serde(default = "default_server_max_batch_texts")]
+    pub max_batch_texts: usize,
 }
 
-/// LLM API parameters
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: default_server_host(),
+            port: default_server_port(),
+            token_env: None,
+            max_concurrent_requests: default_server_max_concurrent_requests(),
+            max_queued_requests: default_server_max_queued_requests(),
+            requests_per_minute: default_server_requests_per_minute(),
+            max_batch_texts: default_server_max_batch_texts(),
+        }
+    }
+}
+
+fn default_server_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_server_port() -> u16 {
+    8787
+}
+
+fn default_server_max_concurrent_requests() -> usize {
+    8
+}
+
+fn default_server_max_queued_requests() -> usize {
+    32
+}
+
+fn default_server_requests_per_minute() -> u32 {
+    120
+}
+
+fn default_server_max_batch_texts() -> usize {
+    100
+}
+
+/// Clipboard watcher configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LlmParameters {
-    #[serde(default = "default_temperature")]
-    pub temperature: f32,
+pub struct ClipboardWatchConfig {
+    /// How often the clipboard is checked for new contents, in milliseconds
+    #[serde(default = "default_clipboard_poll_interval_ms")]
+    pub poll_interval_ms: u64,
 
-    #[serde(default = "default_max_tokens")]
-    pub max_tokens: usize,
+    /// Rules tried in order; the first match decides the action
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ClipboardRule>,
 }
 
-impl Default for LlmParameters {
+impl Default for ClipboardWatchConfig {
     fn default() -> Self {
         Self {
-            temperature: default_temperature(),
-            max_tokens: default_max_tokens(),
+            poll_interval_ms: default_clipboard_poll_interval_ms(),
+            rules: Vec::new(),
         }
     }
 }
 
-fn default_temperature() -> f32 {
-    0.7
+fn default_clipboard_poll_interval_ms() -> u64 {
+    500
 }
 
-fn default_max_tokens() -> usize {
-    500
+/// `rephraser://` URL configuration
+///
+/// Any app or web page can open a URL, so by default URLs can neither send
+/// results to other apps nor use outputs that leave the machine.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UrlSchemeConfig {
+    /// URL schemes x-callback-url requests may send results to, such as
+    /// "drafts"; http and https are never allowed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub callback_schemes: Vec<String>,
+
+    /// Let URLs ask for any output method; otherwise only clipboard,
+    /// notification and terminal are allowed
+    #[serde(default)]
+    pub allow_all_outputs: bool,
+}
+
+/// When to transform copied text, and with which action
+///
+/// Every condition that is set must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClipboardRule {
+    /// Action applied to matching text
+    pub action: String,
+
+    /// Only text copied while one of these apps was frontmost (e.g., "Slack")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<String>,
+
+    /// Regular expression the text must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// Minimum length of the text in characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+
+    /// Maximum length of the text in characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+}
+
+/// Compare command configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompareConfig {
+    /// Models compared when none are given on the command line, as
+    /// "provider:model" or a bare model name for the configured provider
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
 }
 
 /// Output method configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutputConfig {
-    /// Output method: "clipboard", "notification", "dialog"
+    /// Output method: "clipboard", "notification", "dialog", "paste", "speak",
+    /// "note", "editor", "terminal", "preview", "notes_app", "slack",
+    /// "discord", "email"
     pub method: OutputMethod,
+
+    /// Template applied to the result before output, e.g.
+    /// "{result}\n\n— rewritten by {model}"; supports {result}, {action},
+    /// {action_display_name}, {provider}, {model}, {date} and {time}
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+
+    /// With the terminal method, also copy the result to the clipboard
+    #[serde(default)]
+    pub copy_terminal_output: bool,
+
+    /// Render Markdown results and copy them as rich text (HTML) alongside
+    /// the plain text
+    #[serde(default)]
+    pub rich_clipboard: bool,
+
+    /// Sound played when a clipboard output finishes: a name from
+    /// /System/Library/Sounds (e.g. "Glass") or "beep"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_sound: Option<String>,
+
+    /// Settings for the "notification" output method
+    #[serde(default)]
+    pub notification: NotificationConfig,
+
+    /// Settings for the "speak" output method
+    #[serde(default)]
+    pub speech: SpeechConfig,
+
+    /// Settings for the "note" output method
+    #[serde(default)]
+    pub note: NoteConfig,
+
+    /// Settings for the "notes_app" output method
+    #[serde(default)]
+    pub notes_app: NotesAppConfig,
+
+    /// Settings for the "slack" output method
+    #[serde(default)]
+    pub slack: SlackConfig,
+
+    /// Settings for the "discord" output method
+    #[serde(default)]
+    pub discord: DiscordConfig,
+
+    /// Settings for the "email" output method
+    #[serde(default)]
+    pub email: EmailConfig,
+
+    /// Number of previous clipboard contents kept for `rephraser undo`
+    /// (0 disables saving them)
+    #[serde(default = "default_undo_depth")]
+    pub undo_depth: usize,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            method: OutputMethod::default(),
+            template: None,
+            copy_terminal_output: false,
+            rich_clipboard: false,
+            completion_sound: None,
+            notification: NotificationConfig::default(),
+            speech: SpeechConfig::default(),
+            note: NoteConfig::default(),
+            notes_app: NotesAppConfig::default(),
+            slack: SlackConfig::default(),
+            discord: DiscordConfig::default(),
+            email: EmailConfig::default(),
+            undo_depth: default_undo_depth(),
+        }
+    }
+}
+
+fn default_undo_depth() -> usize {
+    10
+}
+
+/// Notes.app output configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotesAppConfig {
+    /// Folder the note is stored in (created if missing)
+    #[serde(default = "default_notes_app_folder")]
+    pub folder: String,
+
+    /// Note title; results are appended to an existing note with this title.
+    /// Supports {action}, {action_display_name}, {provider}, {model}, {date}
+    /// and {time}
+    #[serde(default = "default_notes_app_title")]
+    pub title: String,
+}
+
+impl Default for NotesAppConfig {
+    fn default() -> Self {
+        Self {
+            folder: default_notes_app_folder(),
+            title: default_notes_app_title(),
+        }
+    }
+}
+
+fn default_notes_app_folder() -> String {
+    "Rephraser".to_string()
+}
+
+fn default_notes_app_title() -> String {
+    "Rephraser {date}".to_string()
+}
+
+/// Slack output configuration
+///
+/// Results are posted with an incoming webhook, or with a bot token to any
+/// channel or DM the bot can write to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlackConfig {
+    /// Environment variable holding an incoming webhook URL
+    #[serde(default = "default_slack_webhook_url_env")]
+    pub webhook_url_env: String,
+
+    /// Environment variable holding a bot token (xoxb-...), used instead of
+    /// the webhook when set
+    #[serde(default = "default_slack_token_env")]
+    pub token_env: String,
+
+    /// Channel name or ID, or user ID for a DM; required with a bot token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+
+    /// Context line posted above the result. Supports {action},
+    /// {action_display_name}, {provider}, {model}, {date} and {time}
+    #[serde(default = "default_slack_context")]
+    pub context: String,
+}
+
+impl Default for SlackConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url_env: default_slack_webhook_url_env(),
+            token_env: default_slack_token_env(),
+            channel: None,
+            context: default_slack_context(),
+        }
+    }
+}
+
+fn default_slack_webhook_url_env() -> String {
+    "SLACK_WEBHOOK_URL".to_string()
+}
+
+fn default_slack_token_env() -> String {
+    "SLACK_BOT_TOKEN".to_string()
+}
+
+fn default_slack_context() -> String {
+    "Rephraser · {action_display_name}".to_string()
+}
+
+/// Discord output configuration
+///
+/// Results are posted as embeds with an incoming webhook, split into
+/// several messages when they are too long for one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscordConfig {
+    /// Environment variable holding the webhook URL
+    #[serde(default = "default_discord_webhook_url_env")]
+    pub webhook_url_env: String,
+
+    /// Embed title. Supports {action}, {action_display_name}, {provider},
+    /// {model}, {date} and {time}
+    #[serde(default = "default_discord_title")]
+    pub title: String,
+
+    /// Name the messages are posted under, instead of the webhook's own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// Embed color as an RGB integer (e.g. 0x5865F2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<u32>,
+}
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url_env: default_discord_webhook_url_env(),
+            title: default_discord_title(),
+            username: None,
+            color: None,
+        }
+    }
+}
+
+fn default_discord_webhook_url_env() -> String {
+    "DISCORD_WEBHOOK_URL".to_string()
+}
+
+fn default_discord_title() -> String {
+    "{action_display_name}".to_string()
+}
+
+/// Email draft output configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmailConfig {
+    /// Recipient address filled into the draft
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+
+    /// Subject line. Supports {action}, {action_display_name}, {provider},
+    /// {model}, {date} and {time}
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+
+    /// Open a mailto: link in the default mail app instead of using
+    /// Mail.app (always done outside macOS)
+    #[serde(default)]
+    pub mailto: bool,
+}
+
+/// Append-to-note output configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteConfig {
+    /// Markdown file to append to; supports {date}, {year}, {month}, {day}
+    /// and a leading `~/`
+    #[serde(default = "default_note_path")]
+    pub path: String,
+
+    /// Heading written before each entry; supports {date}, {time} and {action}
+    #[serde(default = "default_note_header")]
+    pub header: String,
+}
+
+impl Default for NoteConfig {
+    fn default() -> Self {
+        Self {
+            path: default_note_path(),
+            header: default_note_header(),
+        }
+    }
+}
+
+fn default_note_path() -> String {
+    "~/Documents/Rephraser/{date}.md".to_string()
+}
+
+fn default_note_header() -> String {
+    "## {time} {action}".to_string()
+}
+
+/// Notification output configuration
+///
+/// Title and subtitle support {action}, {action_display_name}, {provider},
+/// {model}, {date} and {time}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Notification title
+    #[serde(default = "default_notification_title")]
+    pub title: String,
+
+    /// Optional notification subtitle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitle: Option<String>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            title: default_notification_title(),
+            subtitle: None,
+        }
+    }
+}
+
+fn default_notification_title() -> String {
+    "Rephraser".to_string()
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Spoken output configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpeechConfig {
+    /// Voice name passed to `say -v` (e.g., "Kyoko"); system default if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+
+    /// Speaking rate in words per minute; system default if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum OutputMethod {
     Clipboard,
+    #[default]
     Notification,
     Dialog,
+    /// Copy to clipboard, then paste into the frontmost application
+    Paste,
+    /// Read the result aloud with `say`
+    Speak,
+    /// Append the result to a Markdown note
+    Note,
+    /// Open the result in `$EDITOR` or the default text editor
+    Editor,
+    /// Print the result to stdout, streaming tokens when on a TTY
+    #[serde(alias = "stdout")]
+    Terminal,
+    /// Render the result as Markdown in a QuickLook/browser preview
+    Preview,
+    /// Create or append to a note in Notes.app
+    #[serde(rename = "notes_app")]
+    NotesApp,
+    /// Post the result to a Slack channel or DM
+    Slack,
+
+    /// Post the result to a Discord channel through a webhook
+    Discord,
+
+    /// Open a new email draft containing the result
+    Email,
+
+    /// Send the result to a sink plugin, written `plugin:<name>`
+    #[serde(untagged, with = "sink_plugin")]
+    Plugin(String),
+}
+
+/// `plugin:<name>` form of [`OutputMethod::Plugin`]
+mod sink_plugin {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    const PREFIX: &str = "plugin:";
+
+    pub fn serialize<S: Serializer>(name: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{}{}", PREFIX, name))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        let method = String::deserialize(deserializer)?;
+        match method.strip_prefix(PREFIX) {
+            Some(name) if !name.is_empty() => Ok(name.to_string()),
+            _ => Err(de::Error::custom(format!(
+                "unknown output method '{}' (sink plugins are written `{}<name>`)",
+                method, PREFIX
+            ))),
+        }
+    }
+}
+
+impl std::str::FromStr for OutputMethod {
+    type Err = String;
+
+    /// Parse an output method using its config file name (e.g., "clipboard")
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        use serde::de::value::{Error, StrDeserializer};
+        Self::deserialize(StrDeserializer::<Error>::new(s)).map_err(|e| e.to_string())
+    }
+}
+
+/// Remote action registry configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistryConfig {
+    /// URL of the JSON action index
+    #[serde(default = "default_registry_url")]
+    pub url: String,
+
+    /// How long a cached index stays fresh, in seconds
+    #[serde(default = "default_registry_cache_ttl")]
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for RegistryConfig {
+    fn default() -> Self {
+        Self {
+            url: default_registry_url(),
+            cache_ttl_secs: default_registry_cache_ttl(),
+        }
+    }
+}
+
+fn default_registry_url() -> String {
+    "https://raw.githubusercontent.com/HirotakaInoue/Rephraser/main/registry/index.json".to_string()
+}
+
+fn default_registry_cache_ttl() -> u64 {
+    86400
+}
+
+/// Update check configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdatesConfig {
+    /// Look for a newer release at most once a day and print a notice when
+    /// there is one
+    #[serde(default = "default_updates_check")]
+    pub check: bool,
+
+    /// URL of the latest release in the GitHub releases API format
+    #[serde(default = "default_updates_url")]
+    pub url: String,
+}
+
+impl Default for UpdatesConfig {
+    fn default() -> Self {
+        Self {
+            check: default_updates_check(),
+            url: default_updates_url(),
+        }
+    }
+}
+
+fn default_updates_check() -> bool {
+    true
+}
+
+fn default_updates_url() -> String {
+    "https://api.github.com/repos/HirotakaInoue/Rephraser/releases/latest".to_string()
+}
+
+/// Command line interface configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UiConfig {
+    /// Language of CLI messages: "auto" (from the locale), "en" or "ja"
+    #[serde(default)]
+    pub language: Language,
+}
+
+/// Language of CLI messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    /// Taken from LC_ALL, LC_MESSAGES or LANG
+    #[default]
+    Auto,
+
+    /// English
+    En,
+
+    /// Japanese
+    Ja,
 }
 
 /// Action configuration
@@ -78,8 +1440,143 @@ pub struct ActionConfig {
     /// Display name (shown in UI)
     pub display_name: String,
 
-    /// Prompt template with variables like {text}
+    /// Short description of what the action does (used by `auto` selection)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Tags for grouping actions in frontends (e.g., "writing", "translate")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+
+    /// Prompt template with variables like {text}; optional for script
+    /// actions, which receive the rendered template as their input
+    #[serde(default = "default_prompt_template")]
     pub prompt_template: String,
+
+    /// Provider used for this action instead of `llm.provider`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+
+    /// Model used for this action instead of `llm.model`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Response guardrails checked before output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validate: Option<ValidationRules>,
+
+    /// Turn the privacy filter on or off for this action instead of
+    /// `privacy.enabled`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy: Option<bool>,
+
+    /// Shell command run on the input before the action; its output
+    /// replaces the input. Overrides `hooks.pre_hook`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_hook: Option<String>,
+
+    /// Shell command the result is piped into after the action. Overrides
+    /// `hooks.post_hook`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_hook: Option<String>,
+
+    /// Rhai script implementing the action (requires the `scripting`
+    /// feature); supports a leading `~/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
+}
+
+fn default_prompt_template() -> String {
+    "{text}".to_string()
+}
+
+/// Tones available without configuration, as name and instruction
+const BUILTIN_TONES: &[(&str, &str)] = &[
+    (
+        "assertive",
+        "Use a confident, assertive tone: state things directly, without hedging or apologizing.",
+    ),
+    (
+        "casual",
+        "Use a casual, relaxed tone, as between colleagues who know each other well.",
+    ),
+    ("formal", "Use a formal, professional tone."),
+    ("friendly", "Use a warm, friendly tone."),
+];
+
+/// Tone presets, selected per run with `--tone`
+///
+/// Each entry is an instruction added to the prompt of any action, so one
+/// action covers every tone. The entries add to, or replace, the built-in
+/// "formal", "casual", "friendly" and "assertive" tones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TonesConfig {
+    /// Tone applied to every action unless `--tone` picks another
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+
+    /// Tone instructions by name
+    #[serde(flatten)]
+    pub presets: BTreeMap<String, String>,
+}
+
+impl TonesConfig {
+    /// The instruction of a configured or built-in tone
+    pub fn instruction(&self, name: &str) -> Option<&str> {
+        self.presets.get(name).map(String::as_str).or_else(|| {
+            BUILTIN_TONES
+                .iter()
+                .find(|(tone, _)| *tone == name)
+                .map(|(_, instruction)| *instruction)
+        })
+    }
+
+    /// Names of all tones, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.presets.keys().map(String::as_str).collect();
+        names.extend(BUILTIN_TONES.iter().map(|(tone, _)| *tone));
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+/// Response guardrails for an action
+///
+/// When a response violates a rule, the request is retried with a
+/// corrective instruction appended to the prompt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationRules {
+    /// Regular expression the response must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub must_match: Option<String>,
+
+    /// Phrases the response must not contain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub must_not_contain: Vec<String>,
+
+    /// Maximum number of words in the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_words: Option<usize>,
+
+    /// Number of retries before giving up
+    #[serde(default = "default_max_retries")]
+    pub max_retries: usize,
+}
+
+fn default_max_retries() -> usize {
+    2
+}
+
+impl Default for ValidationRules {
+    fn default() -> Self {
+        Self {
+            must_match: None,
+            must_not_contain: Vec::new(),
+            max_words: None,
+            max_retries: default_max_retries(),
+        }
+    }
 }
 
 impl Default for Config {
@@ -90,46 +1587,112 @@ impl Default for Config {
                 model: "gpt-4o-mini".to_string(),
                 api_key_env: "OPENAI_API_KEY".to_string(),
                 parameters: LlmParameters::default(),
+                headers: BTreeMap::new(),
             },
-            output: OutputConfig {
-                method: OutputMethod::Notification,
-            },
+            output: OutputConfig::default(),
             actions: default_actions(),
+            input: InputConfig::default(),
+            history: HistoryConfig::default(),
+            usage: UsageConfig::default(),
+            metrics: MetricsConfig::default(),
+            cache: CacheConfig::default(),
+            logging: LoggingConfig::default(),
+            audit: AuditConfig::default(),
+            privacy: PrivacyConfig::default(),
+            secrets: SecretsConfig::default(),
+            plugins: PluginsConfig::default(),
+            hooks: HooksConfig::default(),
+            scripting: ScriptingConfig::default(),
+            compare: CompareConfig::default(),
+            registry: RegistryConfig::default(),
+            updates: UpdatesConfig::default(),
+            ui: UiConfig::default(),
+            daemon: DaemonConfig::default(),
+            queue: QueueConfig::default(),
+            offline: OfflineConfig::default(),
+            circuit: CircuitConfig::default(),
+            batch: BatchConfig::default(),
+            server: ServerConfig::default(),
+            clipboard_watch: ClipboardWatchConfig::default(),
+            url_scheme: UrlSchemeConfig::default(),
+            tones: TonesConfig::default(),
+            schedules: Vec::new(),
         }
     }
 }
 
+#[cfg(all(test, feature = "native"))]
+impl Config {
+    /// Configuration for tests: the mock provider, recording nothing and
+    /// not going through the daemon
+    pub(crate) fn mock() -> Self {
+        let mut config = Self::default();
+        config.llm.provider = "mock".to_string();
+        config.history.enabled = false;
+        config.usage.enabled = false;
+        config.daemon.enabled = false;
+        config
+    }
+}
+
 fn default_actions() -> Vec<ActionConfig> {
     vec![
         ActionConfig {
             name: "polite".to_string(),
             display_name: "丁寧に".to_string(),
+            description: Some("Rewrite the text in a polite, respectful tone".to_string()),
+            tags: vec!["writing".to_string(), "tone".to_string()],
             prompt_template: r#"以下のテキストを丁寧な表現に変換してください。元の意味を保ったまま、敬語や丁寧語を適切に使用してください。
 
 テキスト:
 {text}
 
 丁寧な表現:"#.to_string(),
+            provider: None,
+            model: None,
+            validate: None,
+            privacy: None,
+            pre_hook: None,
+            post_hook: None,
+            script: None,
         },
         ActionConfig {
             name: "organize".to_string(),
             display_name: "整理する".to_string(),
+            description: Some("Organize the text into a logical, readable structure".to_string()),
+            tags: vec!["writing".to_string(), "structure".to_string()],
             prompt_template: r#"以下のテキストを論理的に整理し、読みやすく構造化してください。
 
 テキスト:
 {text}
 
 整理されたテキスト:"#.to_string(),
+            provider: None,
+            model: None,
+            validate: None,
+            privacy: None,
+            pre_hook: None,
+            post_hook: None,
+            script: None,
         },
         ActionConfig {
             name: "summarize".to_string(),
             display_name: "要約".to_string(),
+            description: Some("Summarize the text concisely".to_string()),
+            tags: vec!["summary".to_string()],
             prompt_template: r#"以下のテキストを簡潔に要約してください。
 
 テキスト:
 {text}
 
 要約:"#.to_string(),
+            provider: None,
+            model: None,
+            validate: None,
+            privacy: None,
+            pre_hook: None,
+            post_hook: None,
+            script: None,
         },
     ]
 }
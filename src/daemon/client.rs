@@ -0,0 +1,70 @@
+//! LLM client that forwards requests to a running daemon
+
+use crate::config::LlmConfig;
+use crate::daemon::protocol::{read_message, write_message, DaemonRequest, DaemonResponse};
+use crate::error::{ErrorCode, RephraserError, Result};
+use crate::llm::client::{Completion, LlmClient};
+use crate::llm::idempotency;
+use async_trait::async_trait;
+use std::path::{Path, PathBuf};
+use tokio::io::BufReader;
+use tokio::net::UnixStream;
+
+/// Client that completes prompts through the daemon
+pub struct DaemonClient {
+    socket_path: PathBuf,
+    llm: LlmConfig,
+}
+
+impl DaemonClient {
+    /// Client for the daemon listening on `socket_path`, if one is running
+    ///
+    /// Returns `None` when nothing accepts connections on the socket, so the
+    /// caller can fall back to calling the provider directly.
+    pub fn connect(socket_path: &Path, llm: &LlmConfig) -> Option<Self> {
+        std::os::unix::net::UnixStream::connect(socket_path).ok()?;
+        Some(Self {
+            socket_path: socket_path.to_path_buf(),
+            llm: llm.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl LlmClient for DaemonClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self.complete_with_usage(prompt).await?.text)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        let stream = UnixStream::connect(&self.socket_path).await?;
+        let (reader, mut writer) = stream.into_split();
+
+        let request = DaemonRequest {
+            llm: self.llm.clone(),
+            prompt: prompt.to_string(),
+            idempotency_key: idempotency::current(),
+        };
+        write_message(&mut writer, &request).await?;
+
+        match read_message(&mut BufReader::new(reader)).await? {
+            DaemonResponse::Completion { text, usage } => Ok(Completion::new(text, usage)),
+            DaemonResponse::Error {
+                exit_code,
+                code,
+                message,
+            } => {
+                let code = code.unwrap_or_else(|| ErrorCode::from_exit_code(exit_code));
+                Err(RephraserError::Remote { code, message })
+            }
+        }
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.llm.provider
+    }
+
+    fn model_name(&self) -> &str {
+        &self.llm.model
+    }
+}
@@ -0,0 +1,13 @@
+//! Background daemon serving LLM requests over a Unix domain socket
+//!
+//! The daemon keeps provider clients (and their connection pools) warm
+//! across invocations. The CLI hands its LLM calls to a running daemon
+//! through [`DaemonClient`]; everything else (input, output, history)
+//! still happens in the CLI process.
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+pub use client::DaemonClient;
+pub use server::serve;
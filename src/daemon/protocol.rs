@@ -0,0 +1,122 @@
+//! Wire format between the CLI and the daemon
+//!
+//! Each connection carries one request and one response, each a single
+//! line of JSON.
+
+use crate::config::LlmConfig;
+use crate::error::{ErrorCode, RephraserError, Result};
+use crate::llm::TokenUsage;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+/// A completion request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonRequest {
+    /// Provider, model and parameters to complete with
+    pub llm: LlmConfig,
+    pub prompt: String,
+    /// Idempotency key of the client's scope, for the daemon to send with
+    /// the request (see [`crate::llm::idempotency`])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+/// The daemon's answer to a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonResponse {
+    Completion {
+        text: String,
+        usage: TokenUsage,
+    },
+    Error {
+        exit_code: i32,
+        /// Missing from daemons started before error codes existed
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        code: Option<ErrorCode>,
+        message: String,
+    },
+}
+
+impl DaemonResponse {
+    /// Response reporting `error`, keeping its code
+    pub fn from_error(error: &RephraserError) -> Self {
+        Self::Error {
+            exit_code: error.exit_code(),
+            code: Some(error.code()),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Write a message as one line of JSON
+pub async fn write_message<W, T>(writer: &mut W, message: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read a message from one line of JSON
+pub async fn read_message<R, T>(reader: &mut R) -> Result<T>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Err(RephraserError::Other(
+            "Daemon connection closed unexpectedly".to_string(),
+        ));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn test_message_round_trip() {
+        let request = DaemonRequest {
+            llm: Config::default().llm,
+            prompt: "Be polite:\nhello".to_string(),
+            idempotency_key: None,
+        };
+
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &request).await.unwrap();
+        assert_eq!(buffer.iter().filter(|&&b| b == b'\n').count(), 1);
+
+        let decoded: DaemonRequest = read_message(&mut BufReader::new(buffer.as_slice()))
+            .await
+            .unwrap();
+        assert_eq!(decoded.prompt, request.prompt);
+        assert_eq!(decoded.llm.model, request.llm.model);
+    }
+
+    #[test]
+    fn test_error_response_keeps_exit_code() {
+        let response = DaemonResponse::from_error(&RephraserError::LlmAuth("bad key".to_string()));
+        match response {
+            DaemonResponse::Error {
+                exit_code,
+                code,
+                message,
+            } => {
+                assert_eq!(exit_code, crate::error::exit_code::AUTH);
+                assert_eq!(code, Some(ErrorCode::Auth));
+                assert!(message.contains("bad key"));
+            }
+            _ => panic!("expected an error response"),
+        }
+    }
+}
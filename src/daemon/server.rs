@@ -0,0 +1,165 @@
+//! Daemon side of the Unix socket protocol
+
+use crate::config::LlmConfig;
+use crate::daemon::protocol::{read_message, write_message, DaemonRequest, DaemonResponse};
+use crate::error::{RephraserError, Result};
+use crate::llm::{idempotency, ClientRegistry, LlmClient};
+use crate::shutdown::drain;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// Builds a provider client for an LLM configuration
+pub type ClientFactory = dyn Fn(&LlmConfig) -> Result<Arc<dyn LlmClient>> + Send + Sync;
+
+/// Serve requests on `listener` until `shutdown` completes
+///
+/// Each connection is handled on its own task, so slow requests don't
+/// block others. While `paused` is set, requests are refused. On shutdown
+/// the listener is closed and requests in flight get a moment to finish.
+pub async fn serve(
+    listener: UnixListener,
+    factory: Arc<ClientFactory>,
+    paused: Arc<AtomicBool>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let clients = Arc::new(ClientRegistry::default());
+    let mut connections = JoinSet::new();
+    tokio::pin!(shutdown);
+
+    let result = loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => break Err(e.into()),
+            },
+            _ = &mut shutdown => break Ok(()),
+        };
+        // Forget connections that are done
+        while connections.try_join_next().is_some() {}
+
+        let clients = Arc::clone(&clients);
+        let factory = Arc::clone(&factory);
+        let paused = Arc::clone(&paused);
+        connections.spawn(async move {
+            if let Err(e) = handle(stream, &clients, factory.as_ref(), &paused).await {
+                warn!("Daemon connection failed: {}", e);
+            }
+        });
+    };
+
+    drop(listener);
+    drain(&mut connections).await;
+    result
+}
+
+/// Answer the single request on a connection
+async fn handle(
+    stream: UnixStream,
+    clients: &ClientRegistry,
+    factory: &ClientFactory,
+    paused: &AtomicBool,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    // Clients probe whether the daemon is running by connecting and
+    // hanging up straight away
+    if reader.fill_buf().await?.is_empty() {
+        return Ok(());
+    }
+
+    let request: DaemonRequest = read_message(&mut reader).await?;
+    info!(
+        "Daemon request for {}/{}",
+        request.llm.provider, request.llm.model
+    );
+
+    let result = if paused.load(Ordering::Relaxed) {
+        Err(RephraserError::Other("Rephraser is paused".to_string()))
+    } else {
+        match clients.get(&request.llm, factory) {
+            Ok(client) => {
+                let completion = client.complete_with_usage(&request.prompt);
+                idempotency::scope(request.idempotency_key, completion).await
+            }
+            Err(e) => Err(e),
+        }
+    };
+    let response = match result {
+        Ok(completion) => DaemonResponse::Completion {
+            text: completion.text,
+            usage: completion.usage,
+        },
+        Err(e) => {
+            warn!("Daemon request failed: {}", e);
+            DaemonResponse::from_error(&e)
+        }
+    };
+
+    write_message(&mut writer, &response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::daemon::DaemonClient;
+    use crate::llm::MockLlmClient;
+
+    #[tokio::test]
+    async fn test_client_round_trip() {
+        let socket =
+            std::env::temp_dir().join(format!("rephraser-daemon-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let factory: Arc<ClientFactory> = Arc::new(|llm: &LlmConfig| match llm.provider.as_str() {
+            "mock" => Ok(Arc::new(MockLlmClient::new()) as Arc<dyn LlmClient>),
+            other => Err(RephraserError::Config(format!(
+                "Unknown provider: {}",
+                other
+            ))),
+        });
+        let paused = Arc::new(AtomicBool::new(false));
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(listener, factory, Arc::clone(&paused), async {
+            let _ = stopped.await;
+        }));
+
+        let mut llm = Config::default().llm;
+        llm.provider = "mock".to_string();
+        let client = DaemonClient::connect(&socket, &llm).expect("daemon is listening");
+        let completion = client
+            .complete_with_usage("丁寧にしてください")
+            .await
+            .unwrap();
+        assert!(completion.text.contains("お元気でしょうか"));
+
+        paused.store(true, Ordering::Relaxed);
+        let err = client.complete("hello").await.unwrap_err();
+        assert!(err.to_string().contains("paused"));
+        paused.store(false, Ordering::Relaxed);
+
+        llm.provider = "nope".to_string();
+        let client = DaemonClient::connect(&socket, &llm).unwrap();
+        let err = client.complete("hello").await.unwrap_err();
+        assert_eq!(err.exit_code(), crate::error::exit_code::CONFIG);
+
+        // A request in flight when shutting down still gets its answer
+        llm.provider = "mock".to_string();
+        let client = DaemonClient::connect(&socket, &llm).unwrap();
+        let request = tokio::spawn(async move { client.complete("丁寧に").await });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        stop.send(()).unwrap();
+        assert!(request.await.unwrap().is_ok());
+        server.await.unwrap().unwrap();
+
+        let _ = std::fs::remove_file(&socket);
+        assert!(DaemonClient::connect(&socket, &llm).is_none());
+    }
+}
@@ -1,5 +1,10 @@
 //! Error types for Rephraser
 
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native")]
+use std::error::Error as _;
+#[cfg(feature = "native")]
+use std::io::ErrorKind;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -25,11 +30,40 @@ pub enum RephraserError {
     #[error("LLM service error: {0}")]
     LlmServiceError(String),
 
+    #[error("LLM request timed out after {0:?}")]
+    Timeout(std::time::Duration),
+
+    #[error("Registry error: {0}")]
+    Registry(String),
+
+    #[error("Response failed validation: {0}")]
+    Validation(String),
+
+    #[error("Input error: {0}")]
+    Input(String),
+
+    #[cfg(feature = "native")]
+    #[error("History error: {0}")]
+    History(#[from] rusqlite::Error),
+
     #[error("Output error: {0}")]
     Output(String),
 
+    #[cfg(feature = "native")]
     #[error("Network error: {0}")]
-    Network(#[from] reqwest::Error),
+    Network(#[source] reqwest::Error),
+
+    /// The provider could not be reached at all: its name did not resolve,
+    /// there was no route to it or connecting timed out
+    #[error("You appear to be offline (could not reach {0})")]
+    Offline(String),
+
+    /// Requests to the provider stopped after it kept failing
+    #[error("{provider} keeps failing; not sending it requests for another {retry_in:?}")]
+    CircuitOpen {
+        provider: String,
+        retry_in: std::time::Duration,
+    },
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -46,8 +80,357 @@ pub enum RephraserError {
     #[error("Invalid template: {0}")]
     InvalidTemplate(String),
 
+    /// An error reported by the daemon, with its original code
+    #[error("{message}")]
+    Remote { code: ErrorCode, message: String },
+
     #[error("{0}")]
     Other(String),
 }
 
+impl RephraserError {
+    /// Kind of failure, from which the exit code, retryability and hint
+    /// follow
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Config(_) | Self::Toml(_) | Self::InvalidTemplate(_) => ErrorCode::Config,
+            Self::ActionNotFound(_) => ErrorCode::ActionNotFound,
+            Self::LlmAuth(_) => ErrorCode::Auth,
+            Self::LlmRateLimit(_) => ErrorCode::RateLimit,
+            #[cfg(feature = "native")]
+            Self::Network(_) => ErrorCode::Network,
+            Self::Offline(_) => ErrorCode::Offline,
+            Self::Timeout(_) => ErrorCode::Timeout,
+            Self::CircuitOpen { .. } => ErrorCode::CircuitOpen,
+            Self::LlmServiceError(_) => ErrorCode::ServiceUnavailable,
+            Self::LlmApi(_) => ErrorCode::LlmApi,
+            Self::LlmBadRequest(_) => ErrorCode::BadRequest,
+            Self::Input(_) | Self::InputTooLong { .. } => ErrorCode::Input,
+            Self::Validation(_) => ErrorCode::Validation,
+            Self::Registry(_) => ErrorCode::Registry,
+            Self::Output(_) => ErrorCode::Output,
+            Self::Remote { code, .. } => *code,
+            #[cfg(feature = "native")]
+            Self::History(_) => ErrorCode::Other,
+            Self::Io(_) | Self::Serialization(_) | Self::Other(_) => ErrorCode::Other,
+        }
+    }
+
+    /// Process exit code for this error (see [`ErrorCode::exit_code`])
+    pub fn exit_code(&self) -> i32 {
+        self.code().exit_code()
+    }
+
+    /// Whether the request failed because the network or provider is
+    /// unavailable, so it may succeed if retried later
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_retryable()
+    }
+
+    /// What the user can do about this error, if anything
+    pub fn hint(&self) -> Option<&'static str> {
+        self.code().hint()
+    }
+
+    /// Whether the request failed because this machine appears to be offline
+    pub fn is_offline(&self) -> bool {
+        self.code() == ErrorCode::Offline
+    }
+
+    /// Whether the request was not sent because the provider keeps failing
+    pub fn is_circuit_open(&self) -> bool {
+        self.code() == ErrorCode::CircuitOpen
+    }
+}
+
+/// Kind of a failure
+///
+/// The single source of the exit code, whether retrying may help and the
+/// hint shown with an error, also reported in JSON error output and kept
+/// when errors travel from the daemon to the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Config,
+    ActionNotFound,
+    Auth,
+    RateLimit,
+    Network,
+    Offline,
+    Timeout,
+    CircuitOpen,
+    /// The provider reported a problem on its side
+    ServiceUnavailable,
+    LlmApi,
+    /// The provider rejected the request, e.g. for an unknown model
+    BadRequest,
+    Input,
+    Validation,
+    Registry,
+    Output,
+    Other,
+}
+
+impl ErrorCode {
+    /// Process exit code, so wrapping scripts can branch on the kind of
+    /// failure
+    ///
+    /// | Code | Meaning                                   |
+    /// |------|-------------------------------------------|
+    /// | 1    | Other error                               |
+    /// | 2    | Invalid command-line usage (from clap)    |
+    /// | 3    | Configuration error                       |
+    /// | 4    | Action not found                          |
+    /// | 5    | Authentication failure                    |
+    /// | 6    | Rate limit exceeded                       |
+    /// | 7    | Network error                             |
+    /// | 8    | Output failure                            |
+    /// | 9    | LLM API error                             |
+    /// | 10   | Input error                               |
+    /// | 11   | Response failed validation                |
+    /// | 12   | Registry error                            |
+    /// | 13   | LLM request timed out                     |
+    /// | 14   | Offline                                   |
+    /// | 15   | Provider keeps failing (circuit open)     |
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Config => exit_code::CONFIG,
+            Self::ActionNotFound => exit_code::ACTION_NOT_FOUND,
+            Self::Auth => exit_code::AUTH,
+            Self::RateLimit => exit_code::RATE_LIMIT,
+            Self::Network => exit_code::NETWORK,
+            Self::Offline => exit_code::OFFLINE,
+            Self::Timeout => exit_code::TIMEOUT,
+            Self::CircuitOpen => exit_code::CIRCUIT_OPEN,
+            Self::ServiceUnavailable | Self::LlmApi | Self::BadRequest => exit_code::LLM,
+            Self::Input => exit_code::INPUT,
+            Self::Validation => exit_code::VALIDATION,
+            Self::Registry => exit_code::REGISTRY,
+            Self::Output => exit_code::OUTPUT,
+            Self::Other => exit_code::OTHER,
+        }
+    }
+
+    /// Code for an exit code, for errors from daemons that only report the
+    /// latter
+    pub fn from_exit_code(code: i32) -> Self {
+        match code {
+            exit_code::CONFIG => Self::Config,
+            exit_code::ACTION_NOT_FOUND => Self::ActionNotFound,
+            exit_code::AUTH => Self::Auth,
+            exit_code::RATE_LIMIT => Self::RateLimit,
+            exit_code::NETWORK => Self::Network,
+            exit_code::OFFLINE => Self::Offline,
+            exit_code::TIMEOUT => Self::Timeout,
+            exit_code::CIRCUIT_OPEN => Self::CircuitOpen,
+            exit_code::LLM => Self::LlmApi,
+            exit_code::INPUT => Self::Input,
+            exit_code::VALIDATION => Self::Validation,
+            exit_code::REGISTRY => Self::Registry,
+            exit_code::OUTPUT => Self::Output,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether the network or provider was unavailable, so that the same
+    /// request may succeed later
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::RateLimit
+                | Self::Network
+                | Self::Offline
+                | Self::Timeout
+                | Self::CircuitOpen
+                | Self::ServiceUnavailable
+        )
+    }
+
+    /// What the user can do about errors of this kind
+    pub fn hint(self) -> Option<&'static str> {
+        let hint = match self {
+            Self::Config => "Check the config file; `rephraser config path` shows where it is",
+            Self::ActionNotFound => "Run `rephraser list-actions` to see the available actions",
+            Self::Auth => "Check the API key; `rephraser doctor` tests it",
+            Self::RateLimit => "Wait a moment and try again, or use another model",
+            Self::Network => "Check the network connection and try again",
+            Self::Offline => "Try again online, or set an [offline] fallback or enable [queue]",
+            Self::Timeout => "Try again, or raise timeout_secs under [llm.parameters]",
+            Self::CircuitOpen => "Wait for the cooldown; `rephraser doctor` checks the provider",
+            Self::ServiceUnavailable => "The provider is having trouble; try again later",
+            Self::BadRequest => "Check the model and parameters under [llm]",
+            Self::Validation => "Try again, or relax the action's validate rules",
+            Self::LlmApi | Self::Input | Self::Registry | Self::Output | Self::Other => {
+                return None
+            }
+        };
+        Some(hint)
+    }
+}
+
+#[cfg(feature = "native")]
+impl From<reqwest::Error> for RephraserError {
+    fn from(e: reqwest::Error) -> Self {
+        if !is_offline(&e) {
+            return Self::Network(e);
+        }
+        let host = e
+            .url()
+            .and_then(|url| url.host_str())
+            .unwrap_or("the provider")
+            .to_string();
+        Self::Offline(host)
+    }
+}
+
+/// Whether a request failed before reaching the server for lack of a
+/// network, rather than being refused or failing later
+#[cfg(feature = "native")]
+fn is_offline(e: &reqwest::Error) -> bool {
+    if !e.is_connect() {
+        return false;
+    }
+    if e.is_timeout() {
+        return true;
+    }
+
+    let mut source = e.source();
+    while let Some(error) = source {
+        if let Some(io) = error.downcast_ref::<std::io::Error>() {
+            if matches!(
+                io.kind(),
+                ErrorKind::NetworkUnreachable | ErrorKind::HostUnreachable | ErrorKind::NetworkDown
+            ) {
+                return true;
+            }
+        }
+        // Name resolution failures carry no error kind of their own
+        if error.to_string().starts_with("dns error") {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Process exit codes (see [`RephraserError::exit_code`])
+pub mod exit_code {
+    pub const OTHER: i32 = 1;
+    pub const USAGE: i32 = 2;
+    pub const CONFIG: i32 = 3;
+    pub const ACTION_NOT_FOUND: i32 = 4;
+    pub const AUTH: i32 = 5;
+    pub const RATE_LIMIT: i32 = 6;
+    pub const NETWORK: i32 = 7;
+    pub const OUTPUT: i32 = 8;
+    pub const LLM: i32 = 9;
+    pub const INPUT: i32 = 10;
+    pub const VALIDATION: i32 = 11;
+    pub const REGISTRY: i32 = 12;
+    pub const TIMEOUT: i32 = 13;
+    pub const OFFLINE: i32 = 14;
+    pub const CIRCUIT_OPEN: i32 = 15;
+}
+
 pub type Result<T> = std::result::Result<T, RephraserError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(
+            RephraserError::Config("x".into()).exit_code(),
+            exit_code::CONFIG
+        );
+        assert_eq!(RephraserError::ActionNotFound("x".into()).exit_code(), 4);
+        assert_eq!(RephraserError::LlmRateLimit("x".into()).exit_code(), 6);
+        assert_eq!(RephraserError::Other("x".into()).exit_code(), 1);
+        assert_eq!(
+            RephraserError::Timeout(std::time::Duration::from_secs(1)).exit_code(),
+            13
+        );
+        let open = RephraserError::CircuitOpen {
+            provider: "openai".into(),
+            retry_in: std::time::Duration::from_secs(42),
+        };
+        assert_eq!(open.exit_code(), 15);
+        assert!(open.is_retryable() && open.is_circuit_open());
+        assert!(open.to_string().contains("42s"));
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(RephraserError::LlmRateLimit("x".into()).is_retryable());
+        assert!(RephraserError::LlmServiceError("x".into()).is_retryable());
+        assert!(RephraserError::Remote {
+            code: ErrorCode::ServiceUnavailable,
+            message: "x".into(),
+        }
+        .is_retryable());
+        assert!(!RephraserError::LlmAuth("x".into()).is_retryable());
+        assert!(!RephraserError::LlmBadRequest("x".into()).is_retryable());
+    }
+
+    #[test]
+    fn test_codes_agree() {
+        let codes = [
+            ErrorCode::Config,
+            ErrorCode::ActionNotFound,
+            ErrorCode::Auth,
+            ErrorCode::RateLimit,
+            ErrorCode::Network,
+            ErrorCode::Offline,
+            ErrorCode::Timeout,
+            ErrorCode::CircuitOpen,
+            ErrorCode::LlmApi,
+            ErrorCode::Input,
+            ErrorCode::Validation,
+            ErrorCode::Registry,
+            ErrorCode::Output,
+            ErrorCode::Other,
+        ];
+        // Daemons that only send exit codes get the same code back
+        for code in codes {
+            assert_eq!(ErrorCode::from_exit_code(code.exit_code()), code);
+        }
+        assert_eq!(
+            ErrorCode::from_exit_code(exit_code::USAGE),
+            ErrorCode::Other
+        );
+
+        let auth = RephraserError::LlmAuth("x".into());
+        assert_eq!(auth.code(), ErrorCode::Auth);
+        assert!(auth.hint().unwrap().contains("API key"));
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::RateLimit).unwrap(),
+            "\"rate_limit\""
+        );
+    }
+
+    #[cfg(feature = "native")]
+    #[tokio::test]
+    async fn test_offline_detection() {
+        // .invalid names never resolve, with or without a network
+        let e = reqwest::get("http://rephraser.invalid/").await.unwrap_err();
+        let e = RephraserError::from(e);
+        assert!(e.is_offline(), "{:?}", e);
+        assert_eq!(e.exit_code(), exit_code::OFFLINE);
+        assert_eq!(
+            e.to_string(),
+            "You appear to be offline (could not reach rephraser.invalid)"
+        );
+
+        // A refused connection means the network is up
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        drop(listener);
+        let e = reqwest::get(format!("http://{}/", address))
+            .await
+            .unwrap_err();
+        let e = RephraserError::from(e);
+        assert!(matches!(e, RephraserError::Network(_)), "{:?}", e);
+        assert!(e.is_retryable());
+    }
+}
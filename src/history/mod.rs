@@ -0,0 +1,5 @@
+//! Local history of past transformations
+
+pub mod store;
+
+pub use store::{HistoryEntry, HistoryStore, NewEntry};
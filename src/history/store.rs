@@ -0,0 +1,463 @@
+//! SQLite-backed history store
+
+use crate::actions::registry::sha256_hex;
+use crate::error::Result;
+use crate::output::text::fit_chars;
+use rusqlite::{params, Connection, OptionalExtension, Row, TransactionBehavior};
+use std::path::Path;
+use std::time::Duration;
+
+/// Number of characters of the input kept as a preview
+const PREVIEW_LENGTH: usize = 80;
+
+/// Columns selected for a `HistoryEntry`
+const COLUMNS: &str =
+    "id, created_at, action, input_hash, input_preview, input, output, provider, model, \
+                       prompt, cost_usd, duration_ms";
+
+/// Shortest search term the full-text index can match; the trigram
+/// tokenizer indexes every run of three characters
+const MIN_INDEXED_TERM: usize = 3;
+
+/// A transformation to be recorded
+#[derive(Debug, Clone)]
+pub struct NewEntry<'a> {
+    pub action: &'a str,
+    pub input: &'a str,
+    pub output: &'a str,
+    pub provider: &'a str,
+    pub model: &'a str,
+    /// Prompt sent to the model
+    pub prompt: &'a str,
+    /// Estimated spend in USD, if the model's price is known
+    pub cost_usd: Option<f64>,
+    pub duration: Duration,
+}
+
+/// A recorded transformation
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub id: i64,
+    /// Local time, formatted as "YYYY-MM-DD HH:MM:SS"
+    pub created_at: String,
+    pub action: String,
+    /// Hex-encoded SHA-256 of the full input
+    pub input_hash: String,
+    pub input_preview: String,
+    /// Full input text
+    pub input: Option<String>,
+    pub output: String,
+    pub provider: String,
+    pub model: String,
+    /// Prompt sent to the model; `None` for entries recorded before it was stored
+    pub prompt: Option<String>,
+    pub cost_usd: Option<f64>,
+    pub duration_ms: Option<u64>,
+}
+
+/// History database
+pub struct HistoryStore {
+    conn: Connection,
+}
+
+/// Run the migration step to `version` unless the database is already
+/// there
+///
+/// The step and the version update are one transaction, so a failed or
+/// interrupted step leaves the database as it was, and another process
+/// opening the database at the same time waits and then skips the step.
+pub(crate) fn migration(conn: &mut Connection, version: i64, sql: &str) -> Result<()> {
+    let transaction = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let current: i64 = transaction.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if current < version {
+        transaction.execute_batch(sql)?;
+        transaction.execute_batch(&format!("PRAGMA user_version = {};", version))?;
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+impl HistoryStore {
+    /// Open (and create if needed) the history database at `path`
+    ///
+    /// # Errors
+    /// * If the parent directory cannot be created
+    /// * If the database cannot be opened or initialized
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut conn = Connection::open(path)?;
+        // Wait for another process that is migrating the database
+        conn.busy_timeout(Duration::from_secs(2))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                action TEXT NOT NULL,
+                input_hash TEXT NOT NULL,
+                input_preview TEXT NOT NULL,
+                input TEXT,
+                output TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL
+            );",
+        )?;
+
+        Self::migrate(&mut conn)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Bring an existing database up to the current schema
+    ///
+    /// Steps are numbered, and the last one run is stored in
+    /// `PRAGMA user_version`.
+    fn migrate(conn: &mut Connection) -> Result<()> {
+        // Request details, and a full-text index kept in sync by triggers.
+        // The trigram tokenizer matches substrings, so Japanese text
+        // without word breaks is searchable too
+        migration(
+            conn,
+            1,
+            "ALTER TABLE history ADD COLUMN prompt TEXT;
+             ALTER TABLE history ADD COLUMN cost_usd REAL;
+             ALTER TABLE history ADD COLUMN duration_ms INTEGER;
+
+             CREATE VIRTUAL TABLE history_fts USING fts5(
+                 action, input_preview, input, output,
+                 content = 'history', content_rowid = 'id', tokenize = 'trigram'
+             );
+             CREATE TRIGGER history_fts_insert AFTER INSERT ON history BEGIN
+                 INSERT INTO history_fts (rowid, action, input_preview, input, output)
+                 VALUES (new.id, new.action, new.input_preview, new.input, new.output);
+             END;
+             CREATE TRIGGER history_fts_delete AFTER DELETE ON history BEGIN
+                 INSERT INTO history_fts (history_fts, rowid, action, input_preview, input, output)
+                 VALUES ('delete', old.id, old.action, old.input_preview, old.input, old.output);
+             END;
+             INSERT INTO history_fts (history_fts) VALUES ('rebuild');",
+        )?;
+
+        Ok(())
+    }
+
+    /// Record a transformation and return its id
+    pub fn record(&self, entry: &NewEntry) -> Result<i64> {
+        let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        self.conn.execute(
+            "INSERT INTO history
+                (created_at, action, input_hash, input_preview, input, output, provider, model,
+                 prompt, cost_usd, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                created_at,
+                entry.action,
+                sha256_hex(entry.input),
+                preview(entry.input, PREVIEW_LENGTH),
+                entry.input,
+                entry.output,
+                entry.provider,
+                entry.model,
+                entry.prompt,
+                entry.cost_usd,
+                entry.duration.as_millis() as i64,
+            ],
+        )?;
+
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// List the most recent entries, newest first
+    ///
+    /// With `search`, only matching entries are returned (see [`Self::search`])
+    pub fn list(&self, search: Option<&str>, limit: usize) -> Result<Vec<HistoryEntry>> {
+        if let Some(query) = search {
+            return self.search(query, limit);
+        }
+
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {} FROM history ORDER BY id DESC LIMIT ?1",
+            COLUMNS
+        ))?;
+        let entries = statement
+            .query_map(params![limit as i64], from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Find entries whose action, input or output contain every word of
+    /// `query`, newest first
+    ///
+    /// Uses the full-text index; words shorter than three characters can't
+    /// be looked up there, so such queries scan for the whole query instead
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let indexed =
+            !words.is_empty() && words.iter().all(|w| w.chars().count() >= MIN_INDEXED_TERM);
+
+        let (sql, pattern) = if indexed {
+            let phrases: Vec<String> = words
+                .iter()
+                .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+                .collect();
+            (
+                format!(
+                    "SELECT {} FROM history
+                     WHERE id IN (SELECT rowid FROM history_fts WHERE history_fts MATCH ?1)
+                     ORDER BY id DESC
+                     LIMIT ?2",
+                    COLUMNS
+                ),
+                phrases.join(" "),
+            )
+        } else {
+            (
+                format!(
+                    "SELECT {} FROM history
+                     WHERE instr(action, ?1) > 0
+                        OR instr(coalesce(input, input_preview), ?1) > 0
+                        OR instr(output, ?1) > 0
+                     ORDER BY id DESC
+                     LIMIT ?2",
+                    COLUMNS
+                ),
+                query.to_string(),
+            )
+        };
+
+        let mut statement = self.conn.prepare(&sql)?;
+        let entries = statement
+            .query_map(params![pattern, limit as i64], from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Look up an entry by id
+    pub fn get(&self, id: i64) -> Result<Option<HistoryEntry>> {
+        let entry = self
+            .conn
+            .query_row(
+                &format!("SELECT {} FROM history WHERE id = ?1", COLUMNS),
+                params![id],
+                from_row,
+            )
+            .optional()?;
+
+        Ok(entry)
+    }
+
+    /// Get the most recent entry
+    pub fn latest(&self) -> Result<Option<HistoryEntry>> {
+        Ok(self.list(None, 1)?.into_iter().next())
+    }
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get(0)?,
+        created_at: row.get(1)?,
+        action: row.get(2)?,
+        input_hash: row.get(3)?,
+        input_preview: row.get(4)?,
+        input: row.get(5)?,
+        output: row.get(6)?,
+        provider: row.get(7)?,
+        model: row.get(8)?,
+        prompt: row.get(9)?,
+        cost_usd: row.get(10)?,
+        duration_ms: row.get::<_, Option<i64>>(11)?.map(|ms| ms as u64),
+    })
+}
+
+/// Collapse text to a single line of at most `max_chars` characters
+/// (plus an ellipsis when shortened), cut between grapheme clusters
+pub fn preview(text: &str, max_chars: usize) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= max_chars {
+        return line;
+    }
+    format!("{}…", &line[..fit_chars(&line, max_chars)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(action: &'a str, input: &'a str, output: &'a str) -> NewEntry<'a> {
+        NewEntry {
+            action,
+            input,
+            output,
+            provider: "mock",
+            model: "mock-model",
+            prompt: input,
+            cost_usd: None,
+            duration: Duration::from_millis(250),
+        }
+    }
+
+    #[test]
+    fn test_record_and_list() {
+        let path =
+            std::env::temp_dir().join(format!("rephraser-history-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = HistoryStore::open(&path).unwrap();
+
+        let first = store
+            .record(&entry(
+                "polite",
+                "こんにちは",
+                "こんにちは、お世話になっております。",
+            ))
+            .unwrap();
+        store
+            .record(&entry("summarize", "long\ntext", "short"))
+            .unwrap();
+
+        let all = store.list(None, 10).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].action, "summarize");
+        assert_eq!(all[0].input_preview, "long text");
+        assert_eq!(all[1].input_hash, sha256_hex("こんにちは"));
+
+        let found = store.list(Some("お世話"), 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, first);
+
+        assert_eq!(store.list(None, 1).unwrap().len(), 1);
+        assert!(store.get(first).unwrap().is_some());
+        assert!(store.get(999).unwrap().is_none());
+
+        let latest = store.latest().unwrap().unwrap();
+        assert_eq!(latest.input.as_deref(), Some("long\ntext"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_search() {
+        let path = std::env::temp_dir().join(format!(
+            "rephraser-history-fts-{}.sqlite",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let store = HistoryStore::open(&path).unwrap();
+
+        let greeting = store
+            .record(&entry(
+                "polite",
+                "こんにちは",
+                "こんにちは、お世話になっております。",
+            ))
+            .unwrap();
+        let meeting = store
+            .record(&entry(
+                "summarize",
+                "Meeting notes: ship the release",
+                "Ship it",
+            ))
+            .unwrap();
+
+        // Substrings of Japanese text, matched through the trigram index
+        let found = store.search("お世話", 10).unwrap();
+        assert_eq!(
+            found.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![greeting]
+        );
+
+        // Every word must match, in any column and case
+        assert_eq!(store.search("meeting RELEASE", 10).unwrap()[0].id, meeting);
+        assert!(store.search("meeting polite", 10).unwrap().is_empty());
+
+        // Terms too short for the index fall back to a scan
+        assert_eq!(store.search("it", 10).unwrap()[0].id, meeting);
+        assert_eq!(store.search("\"quoted\"", 10).unwrap().len(), 0);
+
+        let entry = store.get(meeting).unwrap().unwrap();
+        assert_eq!(
+            entry.prompt.as_deref(),
+            Some("Meeting notes: ship the release")
+        );
+        assert_eq!(entry.duration_ms, Some(250));
+        assert_eq!(entry.cost_usd, None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_migrates_old_schema() {
+        let path = std::env::temp_dir().join(format!(
+            "rephraser-history-v0-{}.sqlite",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE history (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    created_at TEXT NOT NULL,
+                    action TEXT NOT NULL,
+                    input_hash TEXT NOT NULL,
+                    input_preview TEXT NOT NULL,
+                    input TEXT,
+                    output TEXT NOT NULL,
+                    provider TEXT NOT NULL,
+                    model TEXT NOT NULL
+                );
+                INSERT INTO history (created_at, action, input_hash, input_preview, output, provider, model)
+                VALUES ('2024-01-01 00:00:00', 'polite', 'hash', 'old', 'out', 'mock', 'm');",
+            )
+            .unwrap();
+
+        let store = HistoryStore::open(&path).unwrap();
+        let old = store.latest().unwrap().unwrap();
+        assert_eq!(old.input, None);
+        assert_eq!(old.prompt, None);
+        // Existing entries are added to the search index
+        assert_eq!(store.search("old", 10).unwrap().len(), 1);
+
+        // Reopening an up-to-date database is a no-op
+        drop(store);
+        let store = HistoryStore::open(&path).unwrap();
+        store.record(&entry("polite", "new", "out")).unwrap();
+        assert_eq!(
+            store.latest().unwrap().unwrap().input.as_deref(),
+            Some("new")
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        let version = |conn: &Connection| -> i64 {
+            conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+                .unwrap()
+        };
+
+        let failing = "CREATE TABLE half (id INTEGER); SELECT * FROM missing;";
+        assert!(migration(&mut conn, 1, failing).is_err());
+        assert_eq!(version(&conn), 0);
+        assert!(conn.prepare("SELECT * FROM half").is_err());
+
+        migration(&mut conn, 1, "CREATE TABLE half (id INTEGER);").unwrap();
+        assert_eq!(version(&conn), 1);
+        // A step already run is skipped
+        migration(&mut conn, 1, "CREATE TABLE half (id INTEGER);").unwrap();
+    }
+
+    #[test]
+    fn test_preview_truncates() {
+        let long = "あ".repeat(100);
+        let result = preview(&long, PREVIEW_LENGTH);
+        assert_eq!(result.chars().count(), PREVIEW_LENGTH + 1);
+        assert!(result.ends_with('…'));
+    }
+}
@@ -0,0 +1,130 @@
+//! English messages
+
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    ("error", "Error: {error}"),
+    ("error-hint", "Hint: {hint}"),
+    (
+        "hint-config",
+        "Check the config file; `rephraser config path` shows where it is",
+    ),
+    (
+        "hint-action_not_found",
+        "Run `rephraser list-actions` to see the available actions",
+    ),
+    (
+        "hint-auth",
+        "Check the API key; `rephraser doctor` tests it",
+    ),
+    (
+        "hint-rate_limit",
+        "Wait a moment and try again, or use another model",
+    ),
+    ("hint-network", "Check the network connection and try again"),
+    (
+        "hint-offline",
+        "Try again online, or set an [offline] fallback or enable [queue]",
+    ),
+    (
+        "hint-timeout",
+        "Try again, or raise timeout_secs under [llm.parameters]",
+    ),
+    (
+        "hint-circuit_open",
+        "Wait for the cooldown; `rephraser doctor` checks the provider",
+    ),
+    (
+        "hint-service_unavailable",
+        "The provider is having trouble; try again later",
+    ),
+    (
+        "hint-bad_request",
+        "Check the model and parameters under [llm]",
+    ),
+    (
+        "hint-validation",
+        "Try again, or relax the action's validate rules",
+    ),
+    ("copied-results", "Copied {names} result to clipboard"),
+    ("available-actions", "Available actions:"),
+    ("registry-actions", "Registry actions:"),
+    ("installed-action", "Installed action '{name}'"),
+    ("updated-file", "Updated {path}"),
+    ("wrote-file", "Wrote {path}"),
+    ("rephrased-files", "{done} of {total} files done"),
+    (
+        "quick-action-try",
+        "Select text files in Finder and choose Quick Actions > {name}",
+    ),
+    ("installed-path", "Installed {path}"),
+    ("removed-path", "Removed {path}"),
+    (
+        "watching-clipboard",
+        "Watching the clipboard (Ctrl-C to stop)",
+    ),
+    ("applying-action", "Applying '{action}' to copied text"),
+    (
+        "applying-action-from-app",
+        "Applying '{action}' to text copied from {app}",
+    ),
+    ("copied-history-entry", "Copied entry {id} to the clipboard"),
+    ("no-history-entries", "No history entries"),
+    ("restored-clipboard", "Restored the clipboard: {text}"),
+    ("no-requests-recorded", "No requests recorded"),
+    ("caching-disabled", "(caching is disabled)"),
+    (
+        "removed-cached-responses",
+        "Removed {count} cached responses",
+    ),
+    ("stored-secret", "Stored '{name}' in {file}"),
+    ("daemon-listening", "Listening on {path} (Ctrl-C to stop)"),
+    (
+        "queued-job",
+        "The provider is unavailable; queued '{action}' for `rephraser queue run`",
+    ),
+    ("queue-empty", "No queued jobs"),
+    (
+        "queue-job-failed",
+        "Queued '{action}' failed and stays queued: {error}",
+    ),
+    (
+        "queue-still-unavailable",
+        "The provider is still unavailable; {count} jobs remain queued",
+    ),
+    ("batch-failed", "{failed} of {total} items failed:"),
+    ("ran-queued-jobs", "Ran {count} queued jobs"),
+    ("removed-queued-jobs", "Removed {count} queued jobs"),
+    (
+        "no-schedules",
+        "No schedules; add [[schedules]] entries to the config file",
+    ),
+    ("menu-bar-running", "Rephraser is running in the menu bar"),
+    (
+        "url-handler-try",
+        "Try it: open 'rephraser://rephrase?action=polite&text=hello'",
+    ),
+    ("agent-logs", "Logs: {path}"),
+    ("agent-not-installed", "The agent is not installed"),
+    (
+        "raycast-add-directory",
+        "Add {dir} as a script directory in Raycast (Settings > Extensions > Script Commands)",
+    ),
+    ("up-to-date", "You are using the latest version"),
+    (
+        "update-available",
+        "A new version of rephraser is available: {latest} (current: {current})",
+    ),
+    ("config-initialized", "Configuration initialized at: {path}"),
+    (
+        "config-edit-hint",
+        "Edit the file to customize your settings.",
+    ),
+    (
+        "config-api-key-hint",
+        "Don't forget to set your API key environment variable!",
+    ),
+    ("current-configuration", "Current configuration:"),
+    (
+        "config-set-unsupported",
+        "For now, please edit the config file directly.",
+    ),
+];
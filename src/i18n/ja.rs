@@ -0,0 +1,97 @@
+//! Japanese messages
+
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    ("error", "エラー: {error}"),
+    ("error-hint", "ヒント: {hint}"),
+    ("hint-config", "設定ファイルを確認してください。場所は `rephraser config path` で表示できます"),
+    ("hint-action_not_found", "`rephraser list-actions` で使えるアクションを確認してください"),
+    ("hint-auth", "API キーを確認してください。`rephraser doctor` でテストできます"),
+    ("hint-rate_limit", "しばらく待ってから再試行するか、別のモデルを使ってください"),
+    ("hint-network", "ネットワーク接続を確認してから再試行してください"),
+    ("hint-offline", "オンラインで再試行するか、[offline] のフォールバックまたは [queue] を設定してください"),
+    ("hint-timeout", "再試行するか、[llm.parameters] の timeout_secs を増やしてください"),
+    ("hint-circuit_open", "クールダウンを待ってください。`rephraser doctor` でプロバイダーを確認できます"),
+    ("hint-service_unavailable", "プロバイダー側で問題が発生しています。後で再試行してください"),
+    ("hint-bad_request", "[llm] のモデルとパラメーターを確認してください"),
+    ("hint-validation", "再試行するか、アクションの validate ルールを緩めてください"),
+    ("copied-results", "{names} の結果をクリップボードにコピーしました"),
+    ("available-actions", "利用できるアクション:"),
+    ("registry-actions", "レジストリのアクション:"),
+    ("installed-action", "アクション '{name}' をインストールしました"),
+    ("updated-file", "{path} を更新しました"),
+    ("wrote-file", "{path} を書き出しました"),
+    ("rephrased-files", "{total} 件中 {done} 件のファイルを処理しました"),
+    (
+        "quick-action-try",
+        "Finder でテキストファイルを選択し、クイックアクション > {name} を選んでください",
+    ),
+    ("installed-path", "{path} をインストールしました"),
+    ("removed-path", "{path} を削除しました"),
+    (
+        "watching-clipboard",
+        "クリップボードを監視しています (Ctrl-C で終了)",
+    ),
+    ("applying-action", "コピーされたテキストに '{action}' を適用します"),
+    (
+        "applying-action-from-app",
+        "{app} からコピーされたテキストに '{action}' を適用します",
+    ),
+    (
+        "copied-history-entry",
+        "履歴 {id} をクリップボードにコピーしました",
+    ),
+    ("no-history-entries", "履歴はありません"),
+    ("restored-clipboard", "クリップボードを元に戻しました: {text}"),
+    ("no-requests-recorded", "記録されたリクエストはありません"),
+    ("caching-disabled", "(キャッシュは無効です)"),
+    (
+        "removed-cached-responses",
+        "キャッシュされた応答を {count} 件削除しました",
+    ),
+    ("stored-secret", "'{name}' を {file} に保存しました"),
+    ("daemon-listening", "{path} で待ち受けています (Ctrl-C で終了)"),
+    (
+        "queued-job",
+        "プロバイダーに接続できないため、'{action}' をキューに追加しました (`rephraser queue run` で実行)",
+    ),
+    ("queue-empty", "キューにジョブはありません"),
+    ("queue-job-failed", "キューの '{action}' が失敗しました (キューに残します): {error}"),
+    (
+        "queue-still-unavailable",
+        "プロバイダーにまだ接続できません。{count} 件のジョブがキューに残っています",
+    ),
+    ("batch-failed", "{total} 件中 {failed} 件が失敗しました:"),
+    ("ran-queued-jobs", "キューのジョブを {count} 件実行しました"),
+    ("removed-queued-jobs", "キューのジョブを {count} 件削除しました"),
+    (
+        "no-schedules",
+        "スケジュールはありません。設定ファイルに [[schedules]] を追加してください",
+    ),
+    ("menu-bar-running", "Rephraser をメニューバーで実行しています"),
+    (
+        "url-handler-try",
+        "試すには: open 'rephraser://rephrase?action=polite&text=hello'",
+    ),
+    ("agent-logs", "ログ: {path}"),
+    ("agent-not-installed", "エージェントはインストールされていません"),
+    (
+        "raycast-add-directory",
+        "Raycast で {dir} をスクリプトディレクトリとして追加してください (Settings > Extensions > Script Commands)",
+    ),
+    ("up-to-date", "最新バージョンを使用しています"),
+    (
+        "update-available",
+        "rephraser の新しいバージョンがあります: {latest} (現在: {current})",
+    ),
+    ("config-initialized", "設定ファイルを作成しました: {path}"),
+    ("config-edit-hint", "ファイルを編集して設定をカスタマイズしてください。"),
+    (
+        "config-api-key-hint",
+        "API キーの環境変数の設定を忘れないでください!",
+    ),
+    ("current-configuration", "現在の設定:"),
+    (
+        "config-set-unsupported",
+        "現在は設定ファイルを直接編集してください。",
+    ),
+];
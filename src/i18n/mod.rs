@@ -0,0 +1,231 @@
+//! Translations of user-facing CLI messages
+//!
+//! Messages are looked up by key in the catalog of the current language,
+//! which is set under `[ui]` or, with "auto", taken from the locale
+//! environment variables. Messages missing from a catalog fall back to
+//! English. Placeholders are written `{name}` and filled with [`t!`]:
+//!
+//! ```
+//! use rephraser::t;
+//!
+//! let message = t!("installed-action", name = "translate");
+//! ```
+
+mod en;
+mod ja;
+
+use crate::config::{ConfigManager, Language};
+use crate::error::ErrorCode;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Language chosen at startup
+static CURRENT: OnceLock<Language> = OnceLock::new();
+
+/// Environment variables consulted for "auto", in order of precedence
+const LOCALE_VARS: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// Choose the language from the config file
+///
+/// Has no effect after the language has been chosen.
+pub fn init() {
+    let language = ConfigManager::new()
+        .and_then(|manager| manager.load())
+        .map(|config| config.ui.language)
+        .unwrap_or_default();
+    set_language(language);
+}
+
+/// Choose the language, resolving "auto" from the environment
+///
+/// Has no effect after the language has been chosen.
+pub fn set_language(language: Language) {
+    let _ = CURRENT.set(resolve(language));
+}
+
+/// Language messages are shown in (never "auto")
+pub fn language() -> Language {
+    *CURRENT.get_or_init(|| resolve(Language::Auto))
+}
+
+fn resolve(language: Language) -> Language {
+    match language {
+        Language::Auto => {
+            let locale = LOCALE_VARS
+                .iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|value| !value.is_empty());
+            language_for_locale(locale.as_deref().unwrap_or_default())
+        }
+        language => language,
+    }
+}
+
+/// Language for a POSIX locale such as "ja_JP.UTF-8"
+fn language_for_locale(locale: &str) -> Language {
+    if locale.starts_with("ja") {
+        Language::Ja
+    } else {
+        Language::En
+    }
+}
+
+/// Message for `key` in the current language
+///
+/// Unknown keys are returned as they are.
+pub fn message(key: &str) -> &str {
+    lookup(language(), key)
+}
+
+fn lookup(language: Language, key: &str) -> &str {
+    let catalog = match language {
+        Language::Ja => ja::MESSAGES,
+        Language::En | Language::Auto => en::MESSAGES,
+    };
+    find(catalog, key)
+        .or_else(|| find(en::MESSAGES, key))
+        .unwrap_or(key)
+}
+
+fn find(catalog: &[(&str, &'static str)], key: &str) -> Option<&'static str> {
+    catalog.iter().find(|(k, _)| *k == key).map(|(_, m)| *m)
+}
+
+/// What the user can do about errors of kind `code`, in the current
+/// language (see [`ErrorCode::hint`])
+pub fn hint(code: ErrorCode) -> Option<String> {
+    code.hint()?;
+    let key = format!("hint-{}", serde_json::to_value(code).ok()?.as_str()?);
+    Some(message(&key).to_string())
+}
+
+/// Message for `key` with its placeholders filled in
+pub fn format_message(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    fill(message(key), args)
+}
+
+/// Replace each `{name}` in `template` with its value in one pass, so
+/// braces in the values are left as they are
+fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start..];
+        let value = placeholder[1..].find('}').and_then(|end| {
+            let name = &placeholder[1..=end];
+            let value = args.iter().find(|(n, _)| *n == name)?.1;
+            Some((value, end + 2))
+        });
+        match value {
+            Some((value, length)) => {
+                result.push_str(&value.to_string());
+                rest = &placeholder[length..];
+            }
+            None => {
+                result.push('{');
+                rest = &placeholder[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Look up a message in the current language
+///
+/// `t!("key")` returns the message; `t!("key", name = value, ...)` returns
+/// it with each `{name}` replaced by `value`.
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::message($key)
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format_message(
+            $key,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),+],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogs_match() {
+        for (key, message) in en::MESSAGES {
+            let translated =
+                find(ja::MESSAGES, key).unwrap_or_else(|| panic!("'{}' is not translated", key));
+            let placeholders = |text: &str| {
+                let mut names: Vec<String> = text
+                    .split('{')
+                    .skip(1)
+                    .filter_map(|rest| rest.split_once('}').map(|(name, _)| name.to_string()))
+                    .collect();
+                names.sort();
+                names
+            };
+            assert_eq!(placeholders(message), placeholders(translated), "{}", key);
+        }
+        for (key, _) in ja::MESSAGES {
+            assert!(find(en::MESSAGES, key).is_some(), "unknown key '{}'", key);
+        }
+    }
+
+    #[test]
+    fn test_hints_match_error_codes() {
+        use ErrorCode::*;
+        let codes = [
+            Config,
+            ActionNotFound,
+            Auth,
+            RateLimit,
+            Network,
+            Offline,
+            Timeout,
+            CircuitOpen,
+            ServiceUnavailable,
+            LlmApi,
+            BadRequest,
+            Input,
+            Validation,
+            Registry,
+            Output,
+            Other,
+        ];
+        for code in codes {
+            let key = format!(
+                "hint-{}",
+                serde_json::to_value(code).unwrap().as_str().unwrap()
+            );
+            // The English catalog repeats the hints of JSON error output
+            assert_eq!(find(en::MESSAGES, &key), code.hint(), "{}", key);
+        }
+        let hints = en::MESSAGES
+            .iter()
+            .filter(|(key, _)| key.starts_with("hint-"));
+        assert_eq!(
+            hints.count(),
+            codes.iter().filter(|c| c.hint().is_some()).count()
+        );
+        assert!(lookup(Language::Ja, "hint-auth").contains("API キー"));
+    }
+
+    #[test]
+    fn test_lookup_and_fill() {
+        assert_eq!(language_for_locale("ja_JP.UTF-8"), Language::Ja);
+        assert_eq!(language_for_locale("C"), Language::En);
+
+        let message = fill(
+            lookup(Language::Ja, "installed-action"),
+            &[("name", &"translate")],
+        );
+        assert_eq!(message, "アクション 'translate' をインストールしました");
+
+        // Values are inserted as they are, even if they look like placeholders
+        let message = fill("{a} and {b}, {unknown}", &[("a", &"{b}"), ("b", &"two")]);
+        assert_eq!(message, "{b} and two, {unknown}");
+    }
+}
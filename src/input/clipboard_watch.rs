@@ -0,0 +1,174 @@
+//! Polling clipboard watcher for `watch-clipboard`
+
+use crate::config::ClipboardRule;
+use crate::error::{RephraserError, Result};
+use crate::input::read_clipboard;
+use regex::Regex;
+use std::time::Duration;
+
+impl ClipboardRule {
+    /// Whether the rule applies to `text` copied while `app` was frontmost
+    ///
+    /// A rule restricted to apps never matches when the app is unknown.
+    ///
+    /// # Errors
+    /// * If `pattern` is not a valid regular expression
+    pub fn matches(&self, text: &str, app: Option<&str>) -> Result<bool> {
+        if !self.apps.is_empty() {
+            let Some(app) = app else {
+                return Ok(false);
+            };
+            if !self.apps.iter().any(|a| a.eq_ignore_ascii_case(app)) {
+                return Ok(false);
+            }
+        }
+
+        let length = text.chars().count();
+        if self.min_length.is_some_and(|min| length < min)
+            || self.max_length.is_some_and(|max| length > max)
+        {
+            return Ok(false);
+        }
+
+        if let Some(pattern) = &self.pattern {
+            let re = Regex::new(pattern).map_err(|e| {
+                RephraserError::Config(format!(
+                    "Invalid clipboard rule pattern '{}': {}",
+                    pattern, e
+                ))
+            })?;
+            if !re.is_match(text) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// First rule that applies to `text` copied while `app` was frontmost
+pub fn find_rule<'a>(
+    rules: &'a [ClipboardRule],
+    text: &str,
+    app: Option<&str>,
+) -> Result<Option<&'a ClipboardRule>> {
+    for rule in rules {
+        if rule.matches(text, app)? {
+            return Ok(Some(rule));
+        }
+    }
+    Ok(None)
+}
+
+/// Watches the clipboard for new text by polling its contents
+///
+/// `pbpaste` exposes no change counter, so the text itself is compared.
+/// Copying the same text twice in a row is therefore not a change.
+pub struct ClipboardWatcher {
+    interval: Duration,
+    last: Option<String>,
+}
+
+impl ClipboardWatcher {
+    /// Start watching; the current contents are not reported as a change
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: read_clipboard().ok(),
+        }
+    }
+
+    /// Wait until the clipboard holds new, non-empty text and return it
+    ///
+    /// Read failures (e.g. while an image is on the clipboard) are ignored.
+    pub async fn changed(&mut self) -> String {
+        loop {
+            tokio::time::sleep(self.interval).await;
+            let Ok(text) = read_clipboard() else {
+                continue;
+            };
+            if text.trim().is_empty() || self.last.as_deref() == Some(text.as_str()) {
+                continue;
+            }
+            self.last = Some(text.clone());
+            return text;
+        }
+    }
+
+    /// Take the current contents as seen, e.g. after writing a result
+    ///
+    /// Keeps the watcher from treating its own output as a new copy.
+    pub fn skip_current(&mut self) {
+        self.last = read_clipboard().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule_for(action: &str) -> ClipboardRule {
+        ClipboardRule {
+            action: action.to_string(),
+            ..ClipboardRule::default()
+        }
+    }
+
+    #[test]
+    fn test_matches_apps() {
+        let rule = ClipboardRule {
+            apps: vec!["Slack".to_string()],
+            ..rule_for("polite")
+        };
+        assert!(rule.matches("hi", Some("slack")).unwrap());
+        assert!(!rule.matches("hi", Some("Mail")).unwrap());
+        assert!(!rule.matches("hi", None).unwrap());
+    }
+
+    #[test]
+    fn test_matches_length_and_pattern() {
+        let rule = ClipboardRule {
+            pattern: Some(r"^\s*TODO".to_string()),
+            min_length: Some(6),
+            max_length: Some(12),
+            ..rule_for("organize")
+        };
+        assert!(rule.matches("TODO: ship", None).unwrap());
+        assert!(!rule.matches("TODO", None).unwrap());
+        assert!(!rule.matches("TODO: ship it now", None).unwrap());
+        assert!(!rule.matches("DONE: ship", None).unwrap());
+
+        // Length counts characters, not bytes
+        let rule = ClipboardRule {
+            max_length: Some(5),
+            ..rule_for("polite")
+        };
+        assert!(rule.matches("こんにちは", None).unwrap());
+
+        let rule = ClipboardRule {
+            pattern: Some("(".to_string()),
+            ..rule_for("polite")
+        };
+        assert!(rule.matches("x", None).is_err());
+    }
+
+    #[test]
+    fn test_find_rule() {
+        let rules = vec![
+            ClipboardRule {
+                apps: vec!["Slack".to_string()],
+                ..rule_for("polite")
+            },
+            ClipboardRule {
+                min_length: Some(10),
+                ..rule_for("summarize")
+            },
+        ];
+
+        let found = find_rule(&rules, "hello", Some("Slack")).unwrap();
+        assert_eq!(found.map(|r| r.action.as_str()), Some("polite"));
+        let found = find_rule(&rules, "a longer piece of text", Some("Mail")).unwrap();
+        assert_eq!(found.map(|r| r.action.as_str()), Some("summarize"));
+        assert!(find_rule(&rules, "short", None).unwrap().is_none());
+    }
+}
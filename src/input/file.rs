@@ -0,0 +1,279 @@
+//! Input from a text file
+
+use crate::error::{RephraserError, Result};
+use crate::output::text::chunk_end;
+use encoding_rs::{Decoder, DecoderResult, Encoding, SHIFT_JIS, UTF_8};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Bytes read from the file at a time by [`FileChunks`]
+const READ_SIZE: usize = 64 * 1024;
+
+/// Read the input text from a file
+///
+/// The encoding is detected from the byte order mark; files without one are
+/// read as UTF-8, falling back to Shift_JIS.
+///
+/// # Errors
+/// Returns an error if:
+/// - The file is larger than `max_bytes`
+/// - The file could not be read
+/// - The contents are not valid text in a supported encoding
+/// - The file is empty
+pub fn read_file(path: &Path, max_bytes: u64) -> Result<String> {
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| RephraserError::Input(format!("Failed to read {}: {}", path.display(), e)))?;
+
+    if metadata.len() > max_bytes {
+        return Err(RephraserError::Input(format!(
+            "{} is too large (max {} bytes, got {})",
+            path.display(),
+            max_bytes,
+            metadata.len()
+        )));
+    }
+
+    let bytes = std::fs::read(path)
+        .map_err(|e| RephraserError::Input(format!("Failed to read {}: {}", path.display(), e)))?;
+
+    let text = decode(&bytes).ok_or_else(|| {
+        RephraserError::Input(format!(
+            "{} is not valid UTF-8, UTF-16 or Shift_JIS text",
+            path.display()
+        ))
+    })?;
+
+    if text.trim().is_empty() {
+        return Err(RephraserError::Input(format!(
+            "{} is empty",
+            path.display()
+        )));
+    }
+
+    Ok(text)
+}
+
+/// A text file read lazily, a chunk at a time
+///
+/// Only the chunk being assembled is held in memory, so files of any size
+/// can be processed. The encoding is detected like [`read_file`] does, from
+/// the start of the file. Chunks are at most `max_chars` characters long
+/// and end at a line break where possible, never inside a grapheme cluster.
+pub struct FileChunks {
+    path: PathBuf,
+    file: File,
+    decoder: Option<Decoder>,
+    /// Bytes read but not decoded yet
+    pending: Vec<u8>,
+    /// Decoded text not returned yet
+    text: String,
+    eof: bool,
+    max_chars: usize,
+}
+
+impl FileChunks {
+    /// Open `path` for reading in chunks of `max_chars` characters
+    ///
+    /// # Errors
+    /// Returns an error if the file could not be opened
+    pub fn open(path: &Path, max_chars: usize) -> Result<Self> {
+        let file = File::open(path).map_err(|e| read_error(path, e))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            decoder: None,
+            pending: Vec::new(),
+            text: String::new(),
+            eof: false,
+            max_chars: max_chars.max(1),
+        })
+    }
+
+    /// The next chunk, or `None` at the end of the file
+    ///
+    /// Chunks with nothing but whitespace are skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the file could not be read or is not valid text
+    pub fn next_chunk(&mut self) -> Result<Option<String>> {
+        loop {
+            // A chunk can be cut once more text than fits has been decoded,
+            // or at the end of the file
+            if self.eof || self.text.chars().nth(self.max_chars).is_some() {
+                if self.text.is_empty() {
+                    return Ok(None);
+                }
+                let end = if self.eof && self.text.chars().nth(self.max_chars).is_none() {
+                    self.text.len()
+                } else {
+                    chunk_end(&self.text, self.max_chars)
+                };
+                let chunk: String = self.text.drain(..end).collect();
+                if chunk.trim().is_empty() {
+                    continue;
+                }
+                return Ok(Some(chunk));
+            }
+            self.read_more()?;
+        }
+    }
+
+    /// Read and decode the next block of the file
+    fn read_more(&mut self) -> Result<()> {
+        let start = self.pending.len();
+        self.pending.resize(start + READ_SIZE, 0);
+        let read = self
+            .file
+            .read(&mut self.pending[start..])
+            .map_err(|e| read_error(&self.path, e))?;
+        self.pending.truncate(start + read);
+        self.eof = read == 0;
+
+        let decoder = match &mut self.decoder {
+            Some(decoder) => decoder,
+            None => {
+                // Wait for enough of the file to tell the encoding apart
+                if !self.eof && self.pending.len() < READ_SIZE {
+                    return Ok(());
+                }
+                let (encoding, bom_length) = detect_encoding(&self.pending, self.eof);
+                self.pending.drain(..bom_length);
+                self.decoder
+                    .insert(encoding.new_decoder_without_bom_handling())
+            }
+        };
+
+        let mut input = &self.pending[..];
+        loop {
+            self.text.reserve(
+                decoder
+                    .max_utf8_buffer_length_without_replacement(input.len())
+                    .unwrap_or(input.len() * 3),
+            );
+            let (result, consumed) =
+                decoder.decode_to_string_without_replacement(input, &mut self.text, self.eof);
+            input = &input[consumed..];
+            match result {
+                DecoderResult::InputEmpty => break,
+                DecoderResult::OutputFull => continue,
+                DecoderResult::Malformed(_, _) => {
+                    return Err(RephraserError::Input(format!(
+                        "{} is not valid UTF-8, UTF-16 or Shift_JIS text",
+                        self.path.display()
+                    )))
+                }
+            }
+        }
+        // The decoder keeps incomplete sequences itself
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+impl Iterator for FileChunks {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Result<String>> {
+        self.next_chunk().transpose()
+    }
+}
+
+fn read_error(path: &Path, error: std::io::Error) -> RephraserError {
+    RephraserError::Input(format!("Failed to read {}: {}", path.display(), error))
+}
+
+/// Encoding of a file from its first bytes, and the length of its byte
+/// order mark
+///
+/// Unless `complete`, the bytes may end in the middle of a UTF-8 sequence.
+fn detect_encoding(bytes: &[u8], complete: bool) -> (&'static Encoding, usize) {
+    if let Some(bom) = Encoding::for_bom(bytes) {
+        return bom;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => (UTF_8, 0),
+        Err(e) if e.error_len().is_none() && !complete => (UTF_8, 0),
+        Err(_) => (SHIFT_JIS, 0),
+    }
+}
+
+/// Decode file contents, detecting the encoding
+fn decode(bytes: &[u8]) -> Option<String> {
+    let (encoding, bom_length) = Encoding::for_bom(bytes).unwrap_or((UTF_8, 0));
+    let bytes = &bytes[bom_length..];
+
+    if bom_length > 0 || std::str::from_utf8(bytes).is_ok() {
+        return decode_strict(encoding, bytes);
+    }
+
+    decode_strict(SHIFT_JIS, bytes)
+}
+
+fn decode_strict(encoding: &'static Encoding, bytes: &[u8]) -> Option<String> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(|text| text.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_detects_encoding() {
+        assert_eq!(decode("こんにちは".as_bytes()).unwrap(), "こんにちは");
+        assert_eq!(decode(b"\xEF\xBB\xBFhello").unwrap(), "hello");
+        assert_eq!(decode(b"\xFF\xFEh\x00i\x00").unwrap(), "hi");
+
+        let (sjis, _, _) = SHIFT_JIS.encode("こんにちは");
+        assert_eq!(decode(&sjis).unwrap(), "こんにちは");
+    }
+
+    #[test]
+    fn test_read_file_size_guard() {
+        let path = std::env::temp_dir().join(format!("rephraser-input-{}.txt", std::process::id()));
+        std::fs::write(&path, "0123456789").unwrap();
+
+        assert_eq!(read_file(&path, 10).unwrap(), "0123456789");
+        assert!(read_file(&path, 9).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn read_chunks(name: &str, contents: &[u8], max_chars: usize) -> Result<Vec<String>> {
+        let path = std::env::temp_dir().join(format!(
+            "rephraser-chunks-{}-{}.txt",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, contents).unwrap();
+        let chunks = FileChunks::open(&path, max_chars).unwrap().collect();
+        std::fs::remove_file(&path).unwrap();
+        chunks
+    }
+
+    #[test]
+    fn test_file_chunks() {
+        // Longer than one read, with lines of 10 characters
+        let line = "あいうえお12345\n";
+        let text = line.repeat(6000);
+        assert!(text.len() > READ_SIZE);
+        let chunks = read_chunks("utf8", text.as_bytes(), 25).unwrap();
+        assert!(chunks.iter().all(|chunk| chunk == &line.repeat(2)));
+        assert_eq!(chunks.concat(), text);
+
+        let (sjis, _, _) = SHIFT_JIS.encode("こんにちは\n世界");
+        assert_eq!(
+            read_chunks("sjis", &sjis, 6).unwrap(),
+            ["こんにちは\n", "世界"]
+        );
+
+        assert_eq!(
+            read_chunks("utf16", b"\xFF\xFEh\x00i\x00", 6).unwrap(),
+            ["hi"]
+        );
+        assert!(read_chunks("empty", b"  \n", 6).unwrap().is_empty());
+        assert!(read_chunks("binary", b"\xFF\xFF\x00\x81", 6).is_err());
+    }
+}
@@ -0,0 +1,13 @@
+//! Input acquisition
+
+pub mod clipboard_watch;
+pub mod file;
+pub mod selection;
+pub mod stdin;
+pub mod watch;
+
+pub use clipboard_watch::ClipboardWatcher;
+pub use file::{read_file, FileChunks};
+pub use selection::{capture_selection, frontmost_app, is_trusted, read_clipboard, read_selection};
+pub use stdin::read_stdin;
+pub use watch::FileWatcher;
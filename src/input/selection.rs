@@ -0,0 +1,240 @@
+//! Selection capture from the frontmost application
+
+use crate::error::{RephraserError, Result};
+use crate::output::clipboard;
+use crate::output::formatter::check_macos_platform;
+use std::process::Command;
+use std::time::Duration;
+use tracing::debug;
+
+/// AppleScript that sends Cmd+C to the frontmost application
+const COPY_SCRIPT: &str = r#"tell application "System Events" to keystroke "c" using command down"#;
+
+/// AppleScript that prints the name of the frontmost application
+const FRONTMOST_APP_SCRIPT: &str = r#"tell application "System Events" to get name of first application process whose frontmost is true"#;
+
+/// AppleScript that prints the selected text of the focused UI element,
+/// through the Accessibility attributes exposed by System Events
+const SELECTED_TEXT_SCRIPT: &str = r#"tell application "System Events"
+	set frontApp to first application process whose frontmost is true
+	set focusedElement to value of attribute "AXFocusedUIElement" of frontApp
+	set selectedText to value of attribute "AXSelectedText" of focusedElement
+	if selectedText is missing value then return ""
+	return selectedText
+end tell"#;
+
+/// JavaScript for Automation that prints whether the calling process is
+/// trusted for Accessibility, showing the system prompt if `PROMPT` is true
+const TRUSTED_SCRIPT: &str = r#"ObjC.import("ApplicationServices");
+$.AXIsProcessTrustedWithOptions($({AXTrustedCheckOptionPrompt: PROMPT}))"#;
+
+/// Time to wait for the frontmost application to update the clipboard
+const COPY_DELAY: Duration = Duration::from_millis(200);
+
+/// osascript errors meaning the process lacks Accessibility permission
+const PERMISSION_ERRORS: [&str; 3] = ["(-1719)", "(-25211)", "not allowed assistive access"];
+
+/// Read the current clipboard contents
+///
+/// # Errors
+/// Returns an error if:
+/// - The platform is not macOS
+/// - The clipboard could not be read
+pub fn read_clipboard() -> Result<String> {
+    clipboard::read_text()
+}
+
+/// Copy the current selection of the frontmost application and return it
+///
+/// Clears the clipboard, simulates Cmd+C via System Events and reads the
+/// clipboard afterwards. Requires Accessibility permission for the calling
+/// process.
+///
+/// # Errors
+/// Returns an error if:
+/// - The platform is not macOS
+/// - The keystroke could not be sent
+/// - Nothing was selected
+pub fn capture_selection() -> Result<String> {
+    check_macos_platform()?;
+
+    // Clear the clipboard so an empty selection can be detected
+    clipboard::clear()?;
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(COPY_SCRIPT)
+        .output()
+        .map_err(|e| RephraserError::Input(format!("Failed to execute osascript: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if is_permission_error(&stderr) {
+            return Err(permission_error());
+        }
+        return Err(RephraserError::Input(format!(
+            "Failed to copy selection (check Accessibility permissions): {}",
+            stderr
+        )));
+    }
+
+    std::thread::sleep(COPY_DELAY);
+
+    let selection = read_clipboard()?;
+    if selection.trim().is_empty() {
+        return Err(RephraserError::Input("No text selected".to_string()));
+    }
+
+    Ok(selection)
+}
+
+/// Read the selected text of the frontmost application
+///
+/// Asks the focused UI element for its selection through the Accessibility
+/// API, which leaves the clipboard alone. Apps that do not expose their
+/// selection that way (e.g., many Electron apps) fall back to copying it
+/// with Cmd+C, after which the previous clipboard contents are restored.
+///
+/// Without Accessibility permission, the system prompt to grant it is shown
+/// and an error explaining where to do so is returned.
+///
+/// # Errors
+/// Returns an error if:
+/// - The platform is not macOS
+/// - Accessibility permission is missing
+/// - Nothing was selected
+pub fn read_selection() -> Result<String> {
+    check_macos_platform()?;
+
+    if let Some(text) = selected_text()? {
+        return Ok(text);
+    }
+
+    let previous = read_clipboard().ok();
+    let selection = capture_selection();
+    if let Some(previous) = previous {
+        let _ = clipboard::write_text(&previous);
+    }
+    selection
+}
+
+/// Selected text of the focused UI element, or `None` if the application
+/// does not expose it or nothing is selected
+fn selected_text() -> Result<Option<String>> {
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(SELECTED_TEXT_SCRIPT)
+        .output()
+        .map_err(|e| RephraserError::Input(format!("Failed to execute osascript: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if is_permission_error(&stderr) {
+            return Err(permission_error());
+        }
+        debug!("Accessibility API gave no selection: {}", stderr.trim());
+        return Ok(None);
+    }
+
+    // osascript adds a trailing newline
+    let text = String::from_utf8_lossy(&output.stdout);
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    Ok((!text.trim().is_empty()).then(|| text.to_string()))
+}
+
+/// Whether this process may use the Accessibility API
+///
+/// Accessibility permission is granted to the app that started Rephraser
+/// (e.g., Terminal), so that is the one checked. With `prompt`, the system
+/// dialog offering to open the settings is shown if it is not yet allowed.
+///
+/// # Errors
+/// Returns an error if the platform is not macOS or the check could not be
+/// run
+pub fn is_trusted(prompt: bool) -> Result<bool> {
+    check_macos_platform()?;
+
+    let output = Command::new("osascript")
+        .args(["-l", "JavaScript", "-e"])
+        .arg(TRUSTED_SCRIPT.replace("PROMPT", if prompt { "true" } else { "false" }))
+        .output()
+        .map_err(|e| RephraserError::Input(format!("Failed to execute osascript: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RephraserError::Input(format!(
+            "Failed to check Accessibility permission: {}",
+            stderr
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim() == "true")
+}
+
+fn is_permission_error(stderr: &str) -> bool {
+    PERMISSION_ERRORS.iter().any(|error| stderr.contains(error))
+}
+
+/// Show the system prompt for Accessibility permission and return the error
+/// to report
+fn permission_error() -> RephraserError {
+    let _ = is_trusted(true);
+    RephraserError::Input(
+        "Accessibility permission is required to read the selection. Allow the app running \
+         Rephraser under System Settings > Privacy & Security > Accessibility, then try again"
+            .to_string(),
+    )
+}
+
+/// Name of the frontmost application (e.g., "Slack")
+///
+/// # Errors
+/// Returns an error if the platform is not macOS or System Events could
+/// not be queried
+pub fn frontmost_app() -> Result<String> {
+    check_macos_platform()?;
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(FRONTMOST_APP_SCRIPT)
+        .output()
+        .map_err(|e| RephraserError::Input(format!("Failed to execute osascript: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RephraserError::Input(format!(
+            "Failed to get the frontmost application: {}",
+            stderr
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn test_capture_selection_fails_on_non_macos() {
+        assert!(capture_selection().is_err());
+        assert!(read_selection().is_err());
+        assert!(is_trusted(false).is_err());
+        assert!(read_clipboard().is_err());
+        assert!(frontmost_app().is_err());
+    }
+
+    #[test]
+    fn test_is_permission_error() {
+        assert!(is_permission_error(
+            "execution error: System Events got an error: osascript is not allowed assistive \
+             access. (-1719)"
+        ));
+        assert!(is_permission_error("execution error: (-25211)"));
+        assert!(!is_permission_error(
+            "execution error: System Events got an error: Can’t get attribute \
+             \"AXSelectedText\". (-1728)"
+        ));
+    }
+}
@@ -0,0 +1,55 @@
+//! Input from standard input
+
+use crate::error::{RephraserError, Result};
+use std::io::{IsTerminal, Read};
+
+/// Read the input text from stdin
+///
+/// # Errors
+/// Returns an error if:
+/// - stdin is a terminal (nothing was piped in)
+/// - stdin could not be read or is not valid UTF-8
+/// - The input is empty
+pub fn read_stdin() -> Result<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(RephraserError::Input(
+            "No input text: pass TEXT or pipe text via stdin".to_string(),
+        ));
+    }
+
+    read_from(stdin.lock())
+}
+
+/// Read all text from a reader, dropping trailing line breaks
+fn read_from(mut reader: impl Read) -> Result<String> {
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|e| RephraserError::Input(format!("Failed to read stdin: {}", e)))?;
+
+    let text = text.trim_end_matches(['\n', '\r']);
+    if text.trim().is_empty() {
+        return Err(RephraserError::Input(
+            "Input from stdin is empty".to_string(),
+        ));
+    }
+
+    Ok(text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_from_trims_trailing_newlines() {
+        let text = read_from("こんにちは\n世界\n\n".as_bytes()).unwrap();
+        assert_eq!(text, "こんにちは\n世界");
+    }
+
+    #[test]
+    fn test_read_from_rejects_empty_input() {
+        assert!(read_from(" \n".as_bytes()).is_err());
+    }
+}
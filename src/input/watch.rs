@@ -0,0 +1,129 @@
+//! Polling file watcher for `watch`
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often the watched file's metadata is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Modification time and size of a file, used to detect changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl Stamp {
+    /// Stamp of the file at `path`, or `None` if it doesn't exist
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Watches a file for changes by polling its metadata
+///
+/// Polling keeps working with editors that save by replacing the file, and
+/// with network and synced folders where change notifications are unreliable.
+pub struct FileWatcher {
+    path: PathBuf,
+    debounce: Duration,
+    last: Option<Stamp>,
+}
+
+impl FileWatcher {
+    /// Start watching `path`; its current state is not reported as a change
+    ///
+    /// A change is reported once the file has stayed unchanged for `debounce`
+    pub fn new(path: &Path, debounce: Duration) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            debounce,
+            last: Stamp::of(path),
+        }
+    }
+
+    /// Wait until the file changes and then settles
+    ///
+    /// Deleting the file is not a change; its reappearance is.
+    pub async fn changed(&mut self) {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let current = Stamp::of(&self.path);
+            if current.is_none() || current == self.last {
+                continue;
+            }
+
+            // Wait for a burst of writes (e.g. typing with autosave) to end
+            let mut settled = current;
+            loop {
+                tokio::time::sleep(self.debounce).await;
+                let next = Stamp::of(&self.path);
+                if next == settled {
+                    break;
+                }
+                settled = next;
+            }
+
+            if settled.is_some() && settled != self.last {
+                self.last = settled;
+                return;
+            }
+        }
+    }
+}
+
+/// Default path for the results of watching `path` with `action`
+///
+/// The action is inserted before the extension: `notes.md` becomes
+/// `notes.summarize.md`.
+pub fn output_path(path: &Path, action: &str) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, action, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, action),
+    };
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_path() {
+        assert_eq!(
+            output_path(Path::new("/tmp/notes.md"), "summarize"),
+            PathBuf::from("/tmp/notes.summarize.md")
+        );
+        assert_eq!(
+            output_path(Path::new("notes"), "polite"),
+            PathBuf::from("notes.polite")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_changed_after_write() {
+        let path = std::env::temp_dir().join(format!("rephraser-watch-{}.txt", std::process::id()));
+        std::fs::write(&path, "first").unwrap();
+        let mut watcher = FileWatcher::new(&path, Duration::from_millis(50));
+
+        let writer_path = path.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            std::fs::write(&writer_path, "second version").unwrap();
+        });
+
+        tokio::time::timeout(Duration::from_secs(5), watcher.changed())
+            .await
+            .expect("change was not detected");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,189 @@
+//! Alfred Script Filter output
+//!
+//! `list-actions --format alfred` lists the actions to pick from, and
+//! `rephrase --format alfred` shows the result. Result items set the
+//! workflow variable `mode` to `copy`, or to `paste` when ⌘ is held, so a
+//! workflow can route them to a Copy to Clipboard output with or without
+//! automatic pasting.
+
+use crate::config::ActionConfig;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Script Filter JSON document
+#[derive(Debug, Serialize)]
+pub struct ScriptFilter {
+    pub items: Vec<Item>,
+}
+
+impl ScriptFilter {
+    pub fn new(items: Vec<Item>) -> Self {
+        Self { items }
+    }
+}
+
+/// One row in Alfred's result list
+#[derive(Debug, Serialize)]
+pub struct Item {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    pub title: String,
+    pub subtitle: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autocomplete: Option<String>,
+    /// Words Alfred filters on when the workflow lets Alfred filter results
+    #[serde(rename = "match", skip_serializing_if = "Option::is_none")]
+    pub match_text: Option<String>,
+    pub valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<ItemText>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub mods: BTreeMap<String, Modifier>,
+}
+
+/// Text used by ⌘C and Large Type
+#[derive(Debug, Serialize)]
+pub struct ItemText {
+    pub copy: String,
+    pub largetype: String,
+}
+
+/// Behavior of an item while a modifier key is held
+#[derive(Debug, Serialize)]
+pub struct Modifier {
+    pub arg: String,
+    pub subtitle: String,
+    pub variables: BTreeMap<String, String>,
+}
+
+/// Item for picking an action; its argument is the action name
+pub fn action_item(action: &ActionConfig) -> Item {
+    let mut words = vec![action.name.clone(), action.display_name.clone()];
+    words.extend(action.tags.iter().cloned());
+
+    Item {
+        uid: Some(action.name.clone()),
+        title: action.display_name.clone(),
+        subtitle: action
+            .description
+            .clone()
+            .unwrap_or_else(|| action.name.clone()),
+        arg: Some(action.name.clone()),
+        autocomplete: Some(action.name.clone()),
+        match_text: Some(words.join(" ")),
+        valid: true,
+        text: None,
+        variables: BTreeMap::new(),
+        mods: BTreeMap::new(),
+    }
+}
+
+/// Item for the result of an action
+///
+/// ↩ copies the result; ⌘↩ copies and pastes it into the frontmost app.
+pub fn result_item(label: &str, output: &str) -> Item {
+    Item {
+        uid: None,
+        title: single_line(output),
+        subtitle: format!("{} · ↩ copy · ⌘↩ paste", label),
+        arg: Some(output.to_string()),
+        autocomplete: None,
+        match_text: None,
+        valid: true,
+        text: Some(ItemText {
+            copy: output.to_string(),
+            largetype: output.to_string(),
+        }),
+        variables: mode("copy"),
+        mods: BTreeMap::from([(
+            "cmd".to_string(),
+            Modifier {
+                arg: output.to_string(),
+                subtitle: format!("{} · paste into the frontmost app", label),
+                variables: mode("paste"),
+            },
+        )]),
+    }
+}
+
+/// Item reporting a failure; it cannot be actioned
+pub fn error_item(message: &str) -> Item {
+    Item {
+        uid: None,
+        title: single_line(message),
+        subtitle: "Rephraser failed".to_string(),
+        arg: None,
+        autocomplete: None,
+        match_text: None,
+        valid: false,
+        text: None,
+        variables: BTreeMap::new(),
+        mods: BTreeMap::new(),
+    }
+}
+
+fn mode(value: &str) -> BTreeMap<String, String> {
+    BTreeMap::from([("mode".to_string(), value.to_string())])
+}
+
+/// Titles are shown on a single line
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn to_value(items: Vec<Item>) -> Value {
+        serde_json::to_value(ScriptFilter::new(items)).unwrap()
+    }
+
+    #[test]
+    fn test_action_item() {
+        let action = ActionConfig {
+            name: "polite".to_string(),
+            display_name: "丁寧に".to_string(),
+            description: None,
+            tags: vec!["writing".to_string()],
+            prompt_template: "{text}".to_string(),
+            provider: None,
+            model: None,
+            validate: None,
+            privacy: None,
+            pre_hook: None,
+            post_hook: None,
+            script: None,
+        };
+
+        let json = to_value(vec![action_item(&action)]);
+        let item = &json["items"][0];
+        assert_eq!(item["title"], "丁寧に");
+        assert_eq!(item["arg"], "polite");
+        assert_eq!(item["match"], "polite 丁寧に writing");
+        assert!(item.get("mods").is_none());
+    }
+
+    #[test]
+    fn test_result_item() {
+        let json = to_value(vec![result_item("丁寧に", "Line one\nLine two")]);
+        let item = &json["items"][0];
+        assert_eq!(item["title"], "Line one Line two");
+        assert_eq!(item["arg"], "Line one\nLine two");
+        assert_eq!(item["text"]["largetype"], "Line one\nLine two");
+        assert_eq!(item["variables"]["mode"], "copy");
+        assert_eq!(item["mods"]["cmd"]["variables"]["mode"], "paste");
+    }
+
+    #[test]
+    fn test_error_item() {
+        let json = to_value(vec![error_item("Action not found: nope")]);
+        assert_eq!(json["items"][0]["valid"], false);
+        assert!(json["items"][0].get("arg").is_none());
+    }
+}
@@ -0,0 +1,217 @@
+//! LaunchAgent for starting Rephraser at login
+//!
+//! `agent install` writes a plist to `~/Library/LaunchAgents` that runs the
+//! daemon (or the menu bar item) at login and restarts it if it exits, then
+//! loads it with `launchctl`.
+
+use crate::error::{RephraserError, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// launchd label of the agent
+pub const LABEL: &str = "com.github.hirotakainoue.rephraser";
+
+/// What the agent runs
+#[derive(Debug, Clone)]
+pub struct AgentSpec {
+    /// Program and its arguments
+    pub program_arguments: Vec<String>,
+    /// File receiving both stdout and stderr
+    pub log_path: PathBuf,
+    /// Environment variables set for the program, e.g. API keys
+    pub environment: Vec<(String, String)>,
+}
+
+/// State of a loaded agent, as reported by `launchctl list`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AgentStatus {
+    /// Process id while running
+    pub pid: Option<u32>,
+    /// Exit status of the previous run
+    pub last_exit_status: Option<i32>,
+}
+
+/// Where the agent's plist is installed
+pub fn plist_path() -> Result<PathBuf> {
+    dirs::home_dir()
+        .map(|home| {
+            home.join("Library")
+                .join("LaunchAgents")
+                .join(format!("{}.plist", LABEL))
+        })
+        .ok_or_else(|| RephraserError::Config("Could not find home directory".to_string()))
+}
+
+/// Property list for the agent
+pub fn plist(spec: &AgentSpec) -> String {
+    let mut xml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+        "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+        "<plist version=\"1.0\">\n",
+        "<dict>\n",
+    ));
+
+    push_key(&mut xml, "Label");
+    push_string(&mut xml, 1, LABEL);
+
+    push_key(&mut xml, "ProgramArguments");
+    xml.push_str("\t<array>\n");
+    for argument in &spec.program_arguments {
+        push_string(&mut xml, 2, argument);
+    }
+    xml.push_str("\t</array>\n");
+
+    if !spec.environment.is_empty() {
+        push_key(&mut xml, "EnvironmentVariables");
+        xml.push_str("\t<dict>\n");
+        for (name, value) in &spec.environment {
+            xml.push_str(&format!("\t\t<key>{}</key>\n", escape(name)));
+            push_string(&mut xml, 2, value);
+        }
+        xml.push_str("\t</dict>\n");
+    }
+
+    push_key(&mut xml, "RunAtLoad");
+    xml.push_str("\t<true/>\n");
+    push_key(&mut xml, "KeepAlive");
+    xml.push_str("\t<true/>\n");
+    // Some providers close idle connections; don't restart in a tight loop
+    push_key(&mut xml, "ThrottleInterval");
+    xml.push_str("\t<integer>10</integer>\n");
+
+    let log_path = spec.log_path.to_string_lossy();
+    push_key(&mut xml, "StandardOutPath");
+    push_string(&mut xml, 1, &log_path);
+    push_key(&mut xml, "StandardErrorPath");
+    push_string(&mut xml, 1, &log_path);
+
+    xml.push_str("</dict>\n</plist>\n");
+    xml
+}
+
+fn push_key(xml: &mut String, key: &str) {
+    xml.push_str(&format!("\t<key>{}</key>\n", key));
+}
+
+fn push_string(xml: &mut String, depth: usize, value: &str) {
+    xml.push_str(&format!(
+        "{}<string>{}</string>\n",
+        "\t".repeat(depth),
+        escape(value)
+    ));
+}
+
+/// Escape text for an XML element
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Load the agent at `plist`, starting it now and at every login
+pub fn load(plist: &Path) -> Result<()> {
+    launchctl(&["load", "-w", &plist.to_string_lossy()]).map(|_| ())
+}
+
+/// Stop the agent at `plist` and keep it from starting at login
+pub fn unload(plist: &Path) -> Result<()> {
+    launchctl(&["unload", "-w", &plist.to_string_lossy()]).map(|_| ())
+}
+
+/// Status of the agent, or `None` if it is not loaded
+pub fn status() -> Result<Option<AgentStatus>> {
+    let output = Command::new("launchctl")
+        .args(["list", LABEL])
+        .output()
+        .map_err(|e| RephraserError::Other(format!("Failed to execute launchctl: {}", e)))?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(parse_status(&String::from_utf8_lossy(&output.stdout))))
+}
+
+/// Parse the output of `launchctl list LABEL`
+///
+/// The output looks like `{ "PID" = 123; "LastExitStatus" = 0; ... };`
+fn parse_status(output: &str) -> AgentStatus {
+    let value = |key: &str| {
+        output.lines().find_map(|line| {
+            line.trim()
+                .strip_prefix(&format!("\"{}\" = ", key))
+                .map(|rest| rest.trim_end_matches(';').to_string())
+        })
+    };
+
+    AgentStatus {
+        pid: value("PID").and_then(|pid| pid.parse().ok()),
+        last_exit_status: value("LastExitStatus").and_then(|status| status.parse().ok()),
+    }
+}
+
+/// Run launchctl, failing with its output
+fn launchctl(args: &[&str]) -> Result<String> {
+    let output = Command::new("launchctl")
+        .args(args)
+        .output()
+        .map_err(|e| RephraserError::Other(format!("Failed to execute launchctl: {}", e)))?;
+    // `launchctl load` reports some failures on stderr with a zero exit status
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() || !stderr.trim().is_empty() {
+        return Err(RephraserError::Other(format!(
+            "launchctl {} failed: {}",
+            args[0],
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plist() {
+        let spec = AgentSpec {
+            program_arguments: vec!["/opt/rephraser".to_string(), "daemon".to_string()],
+            log_path: PathBuf::from("/tmp/agent.log"),
+            environment: vec![("OPENAI_API_KEY".to_string(), "sk-a&b".to_string())],
+        };
+        let xml = plist(&spec);
+
+        assert!(xml.contains(&format!("<string>{}</string>", LABEL)));
+        assert!(xml.contains(
+            "\t<array>\n\t\t<string>/opt/rephraser</string>\n\t\t<string>daemon</string>\n\t</array>"
+        ));
+        assert!(xml.contains("<key>OPENAI_API_KEY</key>\n\t\t<string>sk-a&amp;b</string>"));
+        assert!(xml.contains("<key>KeepAlive</key>\n\t<true/>"));
+        assert!(xml.contains("<key>StandardErrorPath</key>\n\t<string>/tmp/agent.log</string>"));
+    }
+
+    #[test]
+    fn test_plist_without_environment() {
+        let spec = AgentSpec {
+            program_arguments: vec!["/opt/rephraser".to_string(), "daemon".to_string()],
+            log_path: PathBuf::from("/tmp/agent.log"),
+            environment: Vec::new(),
+        };
+        assert!(!plist(&spec).contains("EnvironmentVariables"));
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let running = "{\n\t\"LimitLoadToSessionType\" = \"Aqua\";\n\t\"Label\" = \"x\";\n\t\"PID\" = 4242;\n\t\"LastExitStatus\" = 0;\n};\n";
+        assert_eq!(
+            parse_status(running),
+            AgentStatus {
+                pid: Some(4242),
+                last_exit_status: Some(0),
+            }
+        );
+
+        let stopped = "{\n\t\"Label\" = \"x\";\n\t\"LastExitStatus\" = 256;\n};\n";
+        assert_eq!(parse_status(stopped).pid, None);
+        assert_eq!(parse_status(stopped).last_exit_status, Some(256));
+    }
+}
@@ -0,0 +1,7 @@
+//! Integrations with launcher and automation apps
+
+pub mod alfred;
+pub mod launchd;
+pub mod quick_action;
+pub mod raycast;
+pub mod url_scheme;
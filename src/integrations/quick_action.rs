@@ -0,0 +1,205 @@
+//! Finder Quick Actions
+//!
+//! `install-quick-action` writes an Automator workflow to
+//! `~/Library/Services` that shows up in Finder's Quick Actions menu for
+//! text files. It passes the selected files to `rephraser rephrase-files`,
+//! which writes each result next to its file.
+
+use crate::error::{RephraserError, Result};
+use crate::output::platform::shell_quote;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Tool that refreshes the Services menu after a workflow is added
+const PBS: &str = "/System/Library/CoreServices/pbs";
+
+/// Where the Quick Action named `menu_name` is installed
+pub fn workflow_path(menu_name: &str) -> Result<PathBuf> {
+    // Slashes would create subdirectories
+    let file_name = format!("{}.workflow", menu_name.replace('/', "-"));
+    dirs::home_dir()
+        .map(|home| home.join("Library").join("Services").join(file_name))
+        .ok_or_else(|| RephraserError::Config("Could not find home directory".to_string()))
+}
+
+/// Shell command run by the workflow, with the selected files as arguments
+pub fn shell_command(exe: &Path, action: &str) -> String {
+    format!(
+        "{} rephrase-files --notify {} \"$@\"",
+        shell_quote(&exe.to_string_lossy()),
+        shell_quote(action)
+    )
+}
+
+/// Write the workflow bundle at `path` and refresh the Services menu
+pub fn install(path: &Path, menu_name: &str, command: &str) -> Result<()> {
+    let contents = path.join("Contents");
+    std::fs::create_dir_all(&contents)?;
+    std::fs::write(contents.join("Info.plist"), info_plist(menu_name))?;
+    std::fs::write(contents.join("document.wflow"), document(command))?;
+
+    // The menu also refreshes on its own, just not right away
+    let _ = Command::new(PBS).arg("-update").output();
+    Ok(())
+}
+
+/// Info.plist declaring the service for text files in Finder
+fn info_plist(menu_name: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>NSServices</key>
+	<array>
+		<dict>
+			<key>NSMenuItem</key>
+			<dict>
+				<key>default</key>
+				<string>{}</string>
+			</dict>
+			<key>NSMessage</key>
+			<string>runWorkflowAsService</string>
+			<key>NSRequiredContext</key>
+			<dict>
+				<key>NSApplicationIdentifier</key>
+				<string>com.apple.finder</string>
+			</dict>
+			<key>NSSendFileTypes</key>
+			<array>
+				<string>public.text</string>
+			</array>
+		</dict>
+	</array>
+</dict>
+</plist>
+"#,
+        escape(menu_name)
+    )
+}
+
+/// Workflow with a single "Run Shell Script" action receiving file paths as
+/// arguments
+fn document(command: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>AMApplicationBuild</key>
+	<string>523</string>
+	<key>AMApplicationVersion</key>
+	<string>2.10</string>
+	<key>AMDocumentVersion</key>
+	<string>2</string>
+	<key>actions</key>
+	<array>
+		<dict>
+			<key>action</key>
+			<dict>
+				<key>AMAccepts</key>
+				<dict>
+					<key>Container</key>
+					<string>List</string>
+					<key>Optional</key>
+					<true/>
+					<key>Types</key>
+					<array>
+						<string>com.apple.cocoa.path</string>
+					</array>
+				</dict>
+				<key>AMActionVersion</key>
+				<string>2.0.3</string>
+				<key>AMApplication</key>
+				<array>
+					<string>Automator</string>
+				</array>
+				<key>AMProvides</key>
+				<dict>
+					<key>Container</key>
+					<string>List</string>
+					<key>Types</key>
+					<array>
+						<string>com.apple.cocoa.string</string>
+					</array>
+				</dict>
+				<key>ActionBundlePath</key>
+				<string>/System/Library/Automator/Run Shell Script.action</string>
+				<key>ActionName</key>
+				<string>Run Shell Script</string>
+				<key>ActionParameters</key>
+				<dict>
+					<key>COMMAND_STRING</key>
+					<string>{}</string>
+					<key>CheckedForUserDefaultShell</key>
+					<true/>
+					<key>inputMethod</key>
+					<integer>1</integer>
+					<key>shell</key>
+					<string>/bin/zsh</string>
+					<key>source</key>
+					<string></string>
+				</dict>
+				<key>BundleIdentifier</key>
+				<string>com.apple.RunShellScript</string>
+				<key>CFBundleVersion</key>
+				<string>2.0.3</string>
+				<key>Class Name</key>
+				<string>RunShellScriptAction</string>
+			</dict>
+		</dict>
+	</array>
+	<key>connectors</key>
+	<dict/>
+	<key>workflowMetaData</key>
+	<dict>
+		<key>serviceApplicationBundleID</key>
+		<string>com.apple.finder</string>
+		<key>serviceApplicationPath</key>
+		<string>/System/Library/CoreServices/Finder.app</string>
+		<key>serviceInputTypeIdentifier</key>
+		<string>com.apple.Automator.fileSystemObject</string>
+		<key>serviceOutputTypeIdentifier</key>
+		<string>com.apple.Automator.nothing</string>
+		<key>serviceProcessesInput</key>
+		<false/>
+		<key>workflowTypeIdentifier</key>
+		<string>com.apple.Automator.servicesMenu</string>
+	</dict>
+</dict>
+</plist>
+"#,
+        escape(command)
+    )
+}
+
+/// Escape text for an XML element
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_command() {
+        assert_eq!(
+            shell_command(Path::new("/opt/my tools/rephraser"), "it's"),
+            r#"'/opt/my tools/rephraser' rephrase-files --notify 'it'\''s' "$@""#
+        );
+    }
+
+    #[test]
+    fn test_workflow() {
+        let plist = info_plist("Rephraser - 要約 & more");
+        assert!(plist.contains("<string>Rephraser - 要約 &amp; more</string>"));
+
+        let document = document("rephraser rephrase-files --notify 'polite' \"$@\" < x");
+        assert!(document
+            .contains("<string>rephraser rephrase-files --notify 'polite' \"$@\" &lt; x</string>"));
+        assert!(document.contains("<string>com.apple.RunShellScript</string>"));
+    }
+}
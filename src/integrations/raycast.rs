@@ -0,0 +1,132 @@
+//! Raycast script commands
+//!
+//! `rephraser raycast generate` writes one script command per action. Each
+//! takes the text as an optional argument, falls back to the clipboard, and
+//! runs `rephrase --format raycast` so the result is copied and summarized
+//! in a single line for Raycast's HUD.
+
+use crate::config::ActionConfig;
+use std::path::Path;
+
+/// Icon shown next to the generated commands
+const ICON: &str = "✎";
+
+/// Raycast package the generated commands are grouped under
+const PACKAGE_NAME: &str = "Rephraser";
+
+/// File name of the script command for an action
+pub fn script_file_name(action: &ActionConfig) -> String {
+    let name: String = action
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("rephraser-{}.sh", name)
+}
+
+/// Script command running `action` with the binary at `exe`
+pub fn script_command(action: &ActionConfig, exe: &Path) -> String {
+    let exe = shell_quote(&exe.to_string_lossy());
+    let name = shell_quote(&action.name);
+    let description = action
+        .description
+        .as_deref()
+        .unwrap_or(&action.display_name);
+
+    format!(
+        r#"#!/bin/bash
+
+# Required parameters:
+# @raycast.schemaVersion 1
+# @raycast.title {title}
+# @raycast.mode silent
+
+# Optional parameters:
+# @raycast.icon {icon}
+# @raycast.packageName {package}
+# @raycast.argument1 {{ "type": "text", "placeholder": "Text (clipboard if empty)", "optional": true }}
+
+# Documentation:
+# @raycast.description {description}
+
+if [ -n "$1" ]; then
+  exec {exe} rephrase {name} --format raycast -- "$1"
+else
+  exec {exe} rephrase {name} --format raycast --from-clipboard
+fi
+"#,
+        title = single_line(&action.display_name),
+        icon = ICON,
+        package = PACKAGE_NAME,
+        description = single_line(description),
+        exe = exe,
+        name = name,
+    )
+}
+
+/// Metadata values must fit on their comment line
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Quote a word for bash
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(name: &str) -> ActionConfig {
+        ActionConfig {
+            name: name.to_string(),
+            display_name: "Make\npolite".to_string(),
+            description: None,
+            tags: Vec::new(),
+            prompt_template: "{text}".to_string(),
+            provider: None,
+            model: None,
+            validate: None,
+            privacy: None,
+            pre_hook: None,
+            post_hook: None,
+            script: None,
+        }
+    }
+
+    #[test]
+    fn test_script_command() {
+        let script = script_command(&action("polite"), Path::new("/opt/my tools/rephraser"));
+
+        assert!(script.starts_with("#!/bin/bash\n"));
+        assert!(script.contains("# @raycast.title Make polite\n"));
+        assert!(script.contains("# @raycast.description Make polite\n"));
+        assert!(script.contains("# @raycast.mode silent\n"));
+        assert!(script.contains(r#""optional": true"#));
+        assert!(script.contains(
+            r#"exec '/opt/my tools/rephraser' rephrase 'polite' --format raycast -- "$1""#
+        ));
+        assert!(script.contains("--format raycast --from-clipboard"));
+    }
+
+    #[test]
+    fn test_script_file_name() {
+        assert_eq!(script_file_name(&action("polite")), "rephraser-polite.sh");
+        assert_eq!(
+            script_file_name(&action("to ja/en")),
+            "rephraser-to-ja-en.sh"
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+}
@@ -0,0 +1,404 @@
+//! `rephraser://` URL scheme
+//!
+//! `rephraser://rephrase?action=polite&text=...` runs an action without any
+//! shell quoting, from Shortcuts' "Open URLs" or any other app. `text` is
+//! required: any web page can open a URL, so it never reads the clipboard.
+//! `output` overrides the configured output method, but only with one that
+//! stays on the machine unless `url_scheme.allow_all_outputs` is set.
+//!
+//! The same request can be made as an [x-callback-url], e.g.
+//! `rephraser://x-callback-url/rephrase?action=polite&text=...&x-success=drafts://...`.
+//! The result is then sent back by opening the `x-success` URL with a
+//! `result` parameter added, or a failure by opening `x-error` with
+//! `errorCode` and `errorMessage`, instead of using the output method. Only
+//! apps whose schemes are listed in `url_scheme.callback_schemes` can be
+//! sent results, and never web sites.
+//!
+//! [x-callback-url]: https://x-callback-url.com/specification/
+//!
+//! A command-line binary cannot receive URLs itself, so `url-handler
+//! install` compiles a small AppleScript applet that declares the scheme
+//! and forwards each URL to `rephraser open-url`.
+
+use crate::config::{OutputMethod, UrlSchemeConfig};
+use crate::error::{RephraserError, Result};
+use crate::output::formatter::escape_applescript_string;
+use reqwest::Url;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// URL scheme handled by Rephraser
+pub const SCHEME: &str = "rephraser";
+
+/// Host of x-callback-url requests
+const X_CALLBACK_HOST: &str = "x-callback-url";
+
+/// Name of the handler applet
+const HANDLER_APP_NAME: &str = "Rephraser URL Handler.app";
+
+/// LaunchServices tool used to register and unregister the applet
+const LSREGISTER: &str = "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister";
+
+/// A request made through a `rephraser://` URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct UrlRequest {
+    pub action: String,
+    pub text: String,
+    pub output: Option<OutputMethod>,
+    /// Set for x-callback-url requests
+    pub callbacks: Option<Callbacks>,
+}
+
+/// Where an x-callback-url request wants its outcome sent
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Callbacks {
+    /// Opened with a `result` parameter on success
+    pub success: Option<String>,
+    /// Opened with `errorCode` and `errorMessage` parameters on failure
+    pub error: Option<String>,
+}
+
+impl UrlRequest {
+    /// Parse a `rephraser://rephrase?...` or
+    /// `rephraser://x-callback-url/rephrase?...` URL
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = Url::parse(url)
+            .map_err(|e| RephraserError::Input(format!("Invalid URL '{}': {}", url, e)))?;
+        if url.scheme() != SCHEME {
+            return Err(RephraserError::Input(format!(
+                "Unsupported URL scheme '{}', expected '{}'",
+                url.scheme(),
+                SCHEME
+            )));
+        }
+        let (command, x_callback) = match url.host_str() {
+            Some(X_CALLBACK_HOST) => (url.path().trim_start_matches('/'), true),
+            other => (other.unwrap_or_default(), false),
+        };
+        if command != "rephrase" {
+            return Err(RephraserError::Input(format!(
+                "Unsupported URL command '{}', expected 'rephrase'",
+                command
+            )));
+        }
+
+        let mut action = None;
+        let mut text = None;
+        let mut output = None;
+        let mut callbacks = Callbacks::default();
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "action" => action = Some(value.into_owned()),
+                "text" => text = Some(value.into_owned()),
+                "output" => output = Some(value.parse().map_err(RephraserError::Input)?),
+                "x-success" => callbacks.success = Some(value.into_owned()),
+                "x-error" => callbacks.error = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            action: action.ok_or_else(|| {
+                RephraserError::Input("URL is missing the 'action' parameter".to_string())
+            })?,
+            text: text.ok_or_else(|| {
+                RephraserError::Input("URL is missing the 'text' parameter".to_string())
+            })?,
+            output,
+            callbacks: x_callback.then_some(callbacks),
+        })
+    }
+
+    /// Check that the request only does what `config` allows URLs to do
+    ///
+    /// # Errors
+    /// * If it asks for an output method other than clipboard, notification
+    ///   or terminal, unless all are allowed
+    /// * If a callback URL is http(s) or its scheme is not allowlisted
+    pub fn check(&self, config: &UrlSchemeConfig) -> Result<()> {
+        if let Some(output) = &self.output {
+            let local = matches!(
+                output,
+                OutputMethod::Clipboard | OutputMethod::Notification | OutputMethod::Terminal
+            );
+            if !local && !config.allow_all_outputs {
+                return Err(RephraserError::Input(
+                    "URLs can only use the clipboard, notification and terminal outputs \
+                     unless url_scheme.allow_all_outputs is set"
+                        .to_string(),
+                ));
+            }
+        }
+
+        let Some(callbacks) = &self.callbacks else {
+            return Ok(());
+        };
+        for url in callbacks.success.iter().chain(&callbacks.error) {
+            check_callback(url, &config.callback_schemes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check that results may be sent to the callback `url`
+fn check_callback(url: &str, allowed: &[String]) -> Result<()> {
+    let parsed = Url::parse(url)
+        .map_err(|e| RephraserError::Input(format!("Invalid callback URL '{}': {}", url, e)))?;
+    let scheme = parsed.scheme();
+    if scheme == "http" || scheme == "https" {
+        return Err(RephraserError::Input(format!(
+            "Callback URL '{}' would send the result to a web site",
+            url
+        )));
+    }
+    if !allowed.iter().any(|s| s.eq_ignore_ascii_case(scheme)) {
+        return Err(RephraserError::Input(format!(
+            "Callback URL scheme '{}' is not in url_scheme.callback_schemes",
+            scheme
+        )));
+    }
+    Ok(())
+}
+
+/// Add query parameters to a callback URL
+///
+/// Values are percent-encoded with `%20` for spaces, which every
+/// x-callback-url app decodes (unlike `+`).
+pub fn callback_url(base: &str, params: &[(&str, &str)]) -> String {
+    let mut url = base.to_string();
+    let mut separator = if base.contains('?') { '&' } else { '?' };
+    for (name, value) in params {
+        url.push(separator);
+        url.push_str(name);
+        url.push('=');
+        url.push_str(&percent_encode(value));
+        separator = '&';
+    }
+    url
+}
+
+/// Percent-encode everything except unreserved characters
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Where `url-handler install` puts the applet
+pub fn handler_app_path() -> Result<PathBuf> {
+    dirs::home_dir()
+        .map(|home| home.join("Applications").join(HANDLER_APP_NAME))
+        .ok_or_else(|| RephraserError::Config("Could not find home directory".to_string()))
+}
+
+/// AppleScript of the applet, forwarding URLs to the binary at `exe`
+pub fn handler_script(exe: &Path) -> String {
+    format!(
+        "on open location theURL\n\tdo shell script quoted form of \"{}\" & \" open-url \" & quoted form of theURL\nend open location",
+        escape_applescript_string(&exe.to_string_lossy())
+    )
+}
+
+/// Build the applet at `app` and register it for the URL scheme
+pub fn install_handler(app: &Path, exe: &Path) -> Result<()> {
+    if let Some(parent) = app.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if app.exists() {
+        std::fs::remove_dir_all(app)?;
+    }
+
+    run(Command::new("osacompile")
+        .arg("-o")
+        .arg(app)
+        .arg("-e")
+        .arg(handler_script(exe)))?;
+
+    let info_plist = app.join("Contents").join("Info.plist");
+    let url_types = format!(
+        r#"[{{"CFBundleURLName":"Rephraser","CFBundleURLSchemes":["{}"]}}]"#,
+        SCHEME
+    );
+    run(Command::new("plutil")
+        .args(["-insert", "CFBundleURLTypes", "-json", &url_types])
+        .arg(&info_plist))?;
+    // Keep the applet out of the Dock while it runs
+    run(Command::new("plutil")
+        .args(["-insert", "LSUIElement", "-bool", "YES"])
+        .arg(&info_plist))?;
+
+    run(Command::new(LSREGISTER).arg("-f").arg(app))
+}
+
+/// Unregister and delete the applet at `app`
+pub fn uninstall_handler(app: &Path) -> Result<()> {
+    if !app.exists() {
+        return Ok(());
+    }
+    run(Command::new(LSREGISTER).arg("-u").arg(app))?;
+    std::fs::remove_dir_all(app)?;
+    Ok(())
+}
+
+/// Run a setup command, failing with its stderr
+fn run(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .output()
+        .map_err(|e| RephraserError::Other(format!("Failed to execute {}: {}", program, e)))?;
+    if !output.status.success() {
+        return Err(RephraserError::Other(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let request =
+            UrlRequest::parse("rephraser://rephrase?action=polite&text=hello%20there%26more")
+                .unwrap();
+        assert_eq!(request.action, "polite");
+        assert_eq!(request.text, "hello there&more");
+        assert_eq!(request.output, None);
+
+        let request =
+            UrlRequest::parse("rephraser://rephrase?action=polite&text=hi&output=notification")
+                .unwrap();
+        assert_eq!(request.output, Some(OutputMethod::Notification));
+        assert_eq!(request.callbacks, None);
+    }
+
+    #[test]
+    fn test_parse_x_callback_url() {
+        let request = UrlRequest::parse(
+            "rephraser://x-callback-url/rephrase?action=polite&text=hi\
+             &x-source=Drafts&x-success=drafts%3A%2F%2Fx-callback-url%2Fcreate",
+        )
+        .unwrap();
+        assert_eq!(request.action, "polite");
+        assert_eq!(
+            request.callbacks,
+            Some(Callbacks {
+                success: Some("drafts://x-callback-url/create".to_string()),
+                error: None,
+            })
+        );
+
+        assert!(UrlRequest::parse("rephraser://x-callback-url/delete?action=polite").is_err());
+    }
+
+    #[test]
+    fn test_never_reads_the_clipboard() {
+        // Without `text`, the clipboard would be sent to x-success
+        assert!(UrlRequest::parse("rephraser://rephrase?action=polite").is_err());
+        assert!(UrlRequest::parse(
+            "rephraser://x-callback-url/rephrase?action=polite&x-success=drafts%3A%2F%2Fcreate"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_check_callbacks() {
+        let config = UrlSchemeConfig {
+            callback_schemes: vec!["drafts".to_string(), "https".to_string()],
+            ..Default::default()
+        };
+        let check = |query: &str| {
+            UrlRequest::parse(&format!(
+                "rephraser://x-callback-url/rephrase?action=polite&text=hi&{}",
+                query
+            ))
+            .unwrap()
+            .check(&config)
+        };
+
+        assert!(check("x-success=drafts%3A%2F%2Fcreate").is_ok());
+        assert!(check("x-success=DRAFTS%3A%2F%2Fcreate").is_ok());
+        // Web sites are rejected even when listed
+        assert!(check("x-success=https%3A%2F%2Fevil.example%2F").is_err());
+        assert!(check("x-error=http%3A%2F%2Fevil.example%2F").is_err());
+        assert!(check("x-success=things%3A%2F%2Fadd").is_err());
+        assert!(check("x-error=things%3A%2F%2Fadd").is_err());
+        assert!(check("x-success=not%20a%20url").is_err());
+
+        // Nothing is allowed until configured
+        let request = UrlRequest::parse(
+            "rephraser://x-callback-url/rephrase?action=polite&text=hi&x-success=drafts%3A%2F%2Fcreate",
+        )
+        .unwrap();
+        assert!(request.check(&UrlSchemeConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_check_output() {
+        let check = |output: &str, config: &UrlSchemeConfig| {
+            UrlRequest::parse(&format!(
+                "rephraser://rephrase?action=polite&text=hi&output={}",
+                output
+            ))
+            .unwrap()
+            .check(config)
+        };
+
+        let config = UrlSchemeConfig::default();
+        for output in ["clipboard", "notification", "terminal"] {
+            assert!(check(output, &config).is_ok(), "{}", output);
+        }
+        for output in ["slack", "discord", "email", "plugin:upload"] {
+            assert!(check(output, &config).is_err(), "{}", output);
+        }
+
+        let config = UrlSchemeConfig {
+            allow_all_outputs: true,
+            ..Default::default()
+        };
+        assert!(check("slack", &config).is_ok());
+    }
+
+    #[test]
+    fn test_callback_url() {
+        assert_eq!(
+            callback_url("drafts://x-callback-url/create", &[("result", "a b&c")]),
+            "drafts://x-callback-url/create?result=a%20b%26c"
+        );
+        assert_eq!(
+            callback_url(
+                "app://done?id=1",
+                &[("errorCode", "4"), ("errorMessage", "é")]
+            ),
+            "app://done?id=1&errorCode=4&errorMessage=%C3%A9"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(UrlRequest::parse("rephraser://rephrase?text=hi").is_err());
+        assert!(UrlRequest::parse("rephraser://delete?action=polite").is_err());
+        assert!(UrlRequest::parse("https://rephrase?action=polite").is_err());
+        assert!(
+            UrlRequest::parse("rephraser://rephrase?action=polite&text=hi&output=fax").is_err()
+        );
+        assert!(UrlRequest::parse("not a url").is_err());
+    }
+
+    #[test]
+    fn test_handler_script() {
+        let script = handler_script(Path::new("/opt/\"odd\"/rephraser"));
+        assert!(script.starts_with("on open location theURL\n"));
+        assert!(script.contains(r#"quoted form of "/opt/\"odd\"/rephraser" & " open-url ""#));
+    }
+}
@@ -2,12 +2,65 @@
 //!
 //! This library provides the core functionality for transforming text using
 //! Large Language Models (LLMs) through customizable actions.
+//!
+//! The `native` feature, on by default, adds everything that needs the
+//! operating system: provider clients, config files, history, output and
+//! the CLI. Without it only the actions, their templates and the mock
+//! client are built, which also works on wasm32.
 
 pub mod actions;
+#[cfg(feature = "native")]
+pub mod api;
+#[cfg(feature = "native")]
+pub mod audit;
+#[cfg(feature = "native")]
+pub mod batch;
+#[cfg(feature = "native")]
+pub mod blocking;
+#[cfg(feature = "native")]
+pub mod cache;
+#[cfg(feature = "native")]
 pub mod cli;
 pub mod config;
+#[cfg(all(unix, feature = "native"))]
+pub mod daemon;
 pub mod error;
+#[cfg(feature = "native")]
+pub mod history;
+#[cfg(feature = "native")]
+pub mod i18n;
+#[cfg(feature = "native")]
+pub mod input;
+#[cfg(feature = "native")]
+pub mod integrations;
 pub mod llm;
+#[cfg(feature = "native")]
+pub mod logging;
+#[cfg(all(target_os = "macos", feature = "menu-bar"))]
+pub mod menubar;
+#[cfg(feature = "native")]
+pub mod metrics;
+#[cfg(feature = "native")]
 pub mod output;
+#[cfg(feature = "native")]
+pub mod pipeline;
+#[cfg(feature = "native")]
+pub mod plugins;
+#[cfg(feature = "native")]
+pub mod privacy;
+#[cfg(feature = "native")]
+pub mod queue;
+#[cfg(feature = "native")]
+pub mod schedule;
+#[cfg(feature = "native")]
+pub mod secrets;
+#[cfg(feature = "native")]
+pub mod shutdown;
+#[cfg(feature = "native")]
+pub mod update;
+#[cfg(feature = "native")]
+pub mod usage;
 
+#[cfg(feature = "native")]
+pub use api::{Observer, Rephrased, Rephraser};
 pub use error::{RephraserError, Result};
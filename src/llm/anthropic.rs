@@ -1,12 +1,18 @@
 //! Anthropic API client
 
 use crate::error::{RephraserError, Result};
-use crate::llm::client::LlmClient;
+use crate::llm::client::{
+    header_map, http_client, http_client_builder, Completion, LlmClient, LlmParameters, TokenUsage,
+};
+use crate::llm::health::check_ping_status;
+use crate::llm::sse::SseParser;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::debug;
 
-const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
 const ANTHROPIC_VERSION: &str = "2023-06-01";
 
 /// Anthropic message in the conversation
@@ -23,6 +29,8 @@ struct MessagesRequest {
     messages: Vec<AnthropicMessage>,
     max_tokens: usize,
     temperature: f32,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 /// Response content block
@@ -38,6 +46,32 @@ struct ResponseContent {
 #[derive(Debug, Deserialize)]
 struct MessagesResponse {
     content: Vec<ResponseContent>,
+    #[serde(default)]
+    usage: Option<MessagesUsage>,
+}
+
+/// Token usage reported by Anthropic
+#[derive(Debug, Deserialize)]
+struct MessagesUsage {
+    input_tokens: usize,
+    output_tokens: usize,
+}
+
+/// Streaming event (only text deltas are of interest)
+#[derive(Debug, Deserialize)]
+struct StreamEvent {
+    #[serde(rename = "type")]
+    event_type: String,
+    #[serde(default)]
+    delta: Option<StreamDelta>,
+    #[serde(default)]
+    error: Option<AnthropicError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    #[serde(default)]
+    text: Option<String>,
 }
 
 /// Anthropic API error response
@@ -61,6 +95,8 @@ pub struct AnthropicClient {
     model: String,
     temperature: f32,
     max_tokens: usize,
+    /// API address, without a trailing slash
+    base_url: String,
 }
 
 impl AnthropicClient {
@@ -71,22 +107,129 @@ impl AnthropicClient {
     /// * `model` - Model name (e.g., "claude-3-sonnet-20240229")
     /// * `temperature` - Temperature parameter (0.0-1.0)
     /// * `max_tokens` - Maximum tokens in response
+    ///
+    /// See [`AnthropicClient::builder`] for the other options.
     pub fn new(api_key: String, model: String, temperature: f32, max_tokens: usize) -> Self {
         Self {
-            client: Client::new(),
+            client: http_client(),
             api_key,
             model,
             temperature,
             max_tokens,
+            base_url: DEFAULT_BASE_URL.to_string(),
         }
     }
+
+    /// Start building a client; the API key and model are required
+    pub fn builder() -> AnthropicClientBuilder {
+        AnthropicClientBuilder::default()
+    }
 }
 
-#[async_trait]
-impl LlmClient for AnthropicClient {
-    async fn complete(&self, prompt: &str) -> Result<String> {
-        // Construct request
-        let request = MessagesRequest {
+/// Builder for [`AnthropicClient`]
+#[derive(Debug, Clone, Default)]
+pub struct AnthropicClientBuilder {
+    api_key: Option<String>,
+    model: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<usize>,
+    timeout: Option<Duration>,
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl AnthropicClientBuilder {
+    /// Anthropic API key
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Model name (e.g., "claude-3-5-sonnet-20241022")
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Temperature parameter (0.0-1.0); 0.7 if not set
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Maximum tokens in response; 500 if not set
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Give up on a request after this long; no limit if not set
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests to this address instead of https://api.anthropic.com/v1, such as a
+    /// proxy or a compatible server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Send these headers with every request, besides the ones the API
+    /// needs; a `User-Agent` replaces the default "rephraser/<version>"
+    pub fn headers<K, V>(mut self, headers: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.extend(
+            headers
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into())),
+        );
+        self
+    }
+
+    /// Build the client
+    ///
+    /// # Errors
+    /// * If the API key or model is missing
+    /// * If a header can't be sent
+    /// * If the HTTP client can't be created
+    pub fn build(self) -> Result<AnthropicClient> {
+        let (Some(api_key), Some(model)) = (self.api_key, self.model) else {
+            return Err(RephraserError::Config(
+                "Anthropic clients need an API key and a model".to_string(),
+            ));
+        };
+
+        let mut client = http_client_builder();
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        if !self.headers.is_empty() {
+            client = client.default_headers(header_map(&self.headers)?);
+        }
+        let defaults = LlmParameters::default();
+        Ok(AnthropicClient {
+            client: client.build()?,
+            api_key,
+            model,
+            temperature: self.temperature.unwrap_or(defaults.temperature),
+            max_tokens: self.max_tokens.unwrap_or(defaults.max_tokens),
+            base_url: self
+                .base_url
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+        })
+    }
+}
+
+impl AnthropicClient {
+    /// Build a messages request for a prompt
+    fn build_request(&self, prompt: &str, stream: bool) -> MessagesRequest {
+        MessagesRequest {
             model: self.model.clone(),
             messages: vec![AnthropicMessage {
                 role: "user".to_string(),
@@ -94,49 +237,160 @@ impl LlmClient for AnthropicClient {
             }],
             max_tokens: self.max_tokens,
             temperature: self.temperature,
-        };
+            stream,
+        }
+    }
+
+    /// Address of an API endpoint
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
 
-        // Send request
+    /// Send a request, mapping error status codes to errors
+    async fn send(&self, request: &MessagesRequest) -> Result<reqwest::Response> {
         let response = self
             .client
-            .post(ANTHROPIC_API_URL)
+            .post(self.url("messages"))
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", ANTHROPIC_VERSION)
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(request)
             .send()
             .await?;
 
         // Check status code
         let status = response.status();
+        debug!(
+            "POST {} (model {}): {}",
+            self.url("messages"),
+            request.model,
+            status
+        );
 
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
 
             // Try to parse structured error
-            let error_msg = if let Ok(err_resp) = serde_json::from_str::<AnthropicErrorResponse>(&error_text) {
-                err_resp.error.message
-            } else {
-                error_text
-            };
+            let error_msg =
+                if let Ok(err_resp) = serde_json::from_str::<AnthropicErrorResponse>(&error_text) {
+                    err_resp.error.message
+                } else {
+                    error_text
+                };
 
             return Err(match status.as_u16() {
-                401 | 403 => RephraserError::LlmAuth(format!("Anthropic authentication failed: {}", error_msg)),
-                429 => RephraserError::LlmRateLimit(format!("Anthropic rate limit exceeded: {}", error_msg)),
-                400 => RephraserError::LlmBadRequest(format!("Anthropic bad request: {}", error_msg)),
-                _ => RephraserError::LlmServiceError(format!("Anthropic API error ({}): {}", status, error_msg)),
+                401 | 403 => RephraserError::LlmAuth(format!(
+                    "Anthropic authentication failed: {}",
+                    error_msg
+                )),
+                429 => RephraserError::LlmRateLimit(format!(
+                    "Anthropic rate limit exceeded: {}",
+                    error_msg
+                )),
+                400 => {
+                    RephraserError::LlmBadRequest(format!("Anthropic bad request: {}", error_msg))
+                }
+                _ => RephraserError::LlmServiceError(format!(
+                    "Anthropic API error ({}): {}",
+                    status, error_msg
+                )),
             });
         }
 
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmClient for AnthropicClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self.complete_with_usage(prompt).await?.text)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        // Construct and send request
+        let request = self.build_request(prompt, false);
+        let response = self.send(&request).await?;
+
         // Parse successful response
         let messages_response: MessagesResponse = response.json().await?;
 
         // Extract text from first content block
-        messages_response
+        let text = messages_response
             .content
             .first()
             .map(|content| content.text.clone())
-            .ok_or_else(|| RephraserError::LlmApi("Anthropic returned no content".to_string()))
+            .ok_or_else(|| RephraserError::LlmApi("Anthropic returned no content".to_string()))?;
+
+        let usage = match messages_response.usage {
+            Some(usage) => TokenUsage {
+                prompt_tokens: usage.input_tokens,
+                completion_tokens: usage.output_tokens,
+            },
+            None => TokenUsage::estimate(prompt, &text),
+        };
+
+        Ok(Completion::new(text, usage))
+    }
+
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        let request = self.build_request(prompt, true);
+        let mut response = self.send(&request).await?;
+
+        let mut parser = SseParser::new();
+        let mut full = String::new();
+
+        while let Some(chunk) = response.chunk().await? {
+            for data in parser.push(&chunk) {
+                let event: StreamEvent = serde_json::from_str(&data)?;
+                match event.event_type.as_str() {
+                    "content_block_delta" => {
+                        if let Some(text) = event.delta.and_then(|d| d.text) {
+                            on_token(&text);
+                            full.push_str(&text);
+                        }
+                    }
+                    "message_stop" => return Ok(Completion::estimate(prompt, full)),
+                    "error" => {
+                        let message = event
+                            .error
+                            .map(|e| e.message)
+                            .unwrap_or_else(|| "Unknown streaming error".to_string());
+                        return Err(RephraserError::LlmServiceError(format!(
+                            "Anthropic stream error: {}",
+                            message
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(Completion::estimate(prompt, full))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let url = self.url(&format!("models/{}", self.model));
+        let response = self
+            .client
+            .get(&url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await?;
+        debug!("GET {}: {}", url, response.status());
+        check_ping_status("Anthropic", &self.model, response.status())
     }
 
     fn provider_name(&self) -> &str {
@@ -162,6 +416,7 @@ mod tests {
             }],
             max_tokens: 500,
             temperature: 0.7,
+            stream: false,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -183,6 +438,18 @@ mod tests {
         assert_eq!(response.content[0].text, "Hello! How can I assist you?");
     }
 
+    #[test]
+    fn test_stream_event_parsing() {
+        let json =
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#;
+        let event: StreamEvent = serde_json::from_str(json).unwrap();
+        assert_eq!(event.event_type, "content_block_delta");
+        assert_eq!(event.delta.unwrap().text.as_deref(), Some("Hi"));
+
+        let ping: StreamEvent = serde_json::from_str(r#"{"type":"ping"}"#).unwrap();
+        assert!(ping.delta.is_none());
+    }
+
     #[test]
     fn test_error_response_parsing() {
         let json = r#"{
@@ -195,4 +462,29 @@ mod tests {
         let err_resp: AnthropicErrorResponse = serde_json::from_str(json).unwrap();
         assert_eq!(err_resp.error.message, "Invalid API key");
     }
+
+    #[tokio::test]
+    async fn test_builder_base_url() {
+        assert!(AnthropicClient::builder().api_key("key").build().is_err());
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/messages")
+            .match_header("x-api-key", "key")
+            .match_header("anthropic-version", ANTHROPIC_VERSION)
+            .with_status(200)
+            .with_body(r#"{"content":[{"type":"text","text":"Hi"}]}"#)
+            .create_async()
+            .await;
+
+        let client = AnthropicClient::builder()
+            .api_key("key")
+            .model("claude-3-5-haiku-latest")
+            .temperature(0.2)
+            .base_url(format!("{}/v1", server.url()))
+            .build()
+            .unwrap();
+        assert_eq!(client.complete("Hello").await.unwrap(), "Hi");
+        mock.assert_async().await;
+    }
 }
@@ -0,0 +1,130 @@
+//! Audit logging of LLM requests
+
+use crate::audit::AuditLog;
+use crate::error::Result;
+use crate::llm::client::{Completion, LlmClient};
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Client wrapper that records every request in an [`AuditLog`]
+///
+/// Requests are refused when the log cannot be written, so no text reaches
+/// a provider unrecorded.
+pub struct AuditedClient {
+    inner: Arc<dyn LlmClient>,
+    log: AuditLog,
+}
+
+impl AuditedClient {
+    /// Wrap `inner`, recording its requests in `log`
+    pub fn new(inner: Arc<dyn LlmClient>, log: AuditLog) -> Self {
+        Self { inner, log }
+    }
+
+    /// Append the outcome of a request to the log
+    fn record(
+        &self,
+        prompt: &str,
+        outcome: std::result::Result<&Completion, &crate::error::RephraserError>,
+        started: Instant,
+    ) -> Result<()> {
+        let (provider, model) = match outcome {
+            Ok(completion) => completion.provider_and_model(self.inner.as_ref()),
+            Err(_) => (self.inner.provider_name(), self.inner.model_name()),
+        };
+        let entry = self.log.entry(
+            provider,
+            model,
+            prompt,
+            outcome.map(|c| c.text.as_str()),
+            started.elapsed().as_millis() as u64,
+        );
+        self.log.append(&entry)
+    }
+}
+
+#[async_trait]
+impl LlmClient for AuditedClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self.complete_with_usage(prompt).await?.text)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        self.log.check_writable()?;
+        let started = Instant::now();
+        let result = self.inner.complete_with_usage(prompt).await;
+        self.record(prompt, result.as_ref(), started)?;
+        result
+    }
+
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        self.log.check_writable()?;
+        let started = Instant::now();
+        let result = self.inner.complete_stream(prompt, on_token).await;
+        self.record(prompt, result.as_ref(), started)?;
+        result
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AuditConfig;
+    use crate::llm::MockLlmClient;
+
+    #[tokio::test]
+    async fn test_records_requests() {
+        let path = std::env::temp_dir().join(format!(
+            "rephraser-audited-client-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let log = AuditLog::from_config(&AuditConfig {
+            enabled: true,
+            path: path.to_string_lossy().into_owned(),
+            ..AuditConfig::default()
+        })
+        .unwrap();
+        let client = AuditedClient::new(Arc::new(MockLlmClient::new()), log);
+
+        let response = client.complete("polite").await.unwrap();
+
+        let line = std::fs::read_to_string(&path).unwrap();
+        let entry: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(entry["provider"], "mock");
+        assert_eq!(entry["prompt"], "polite");
+        assert_eq!(entry["response"], response.as_str());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_without_log() {
+        let log = AuditLog::from_config(&AuditConfig {
+            enabled: true,
+            // A file cannot contain the log
+            path: "/dev/null/audit.jsonl".to_string(),
+            ..AuditConfig::default()
+        })
+        .unwrap();
+        let client = AuditedClient::new(Arc::new(MockLlmClient::new()), log);
+        assert!(client.complete("polite").await.is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Response caching for LLM requests
+
+use crate::cache::ResponseCache;
+use crate::config::models::LlmParameters;
+use crate::error::Result;
+use crate::llm::client::{Completion, LlmClient, TokenUsage};
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// Client wrapper that answers repeated requests from a [`ResponseCache`]
+///
+/// A cached response costs nothing, so it is reported with zero token
+/// usage. A request this client has already answered is sent to the
+/// provider again, so that retrying (e.g. from the dialog) gets a new
+/// response rather than the same cached one. Cache failures are logged and
+/// fall back to the provider.
+pub struct CachedClient {
+    inner: Arc<dyn LlmClient>,
+    cache: ResponseCache,
+    parameters: LlmParameters,
+    refresh: bool,
+    answered: Mutex<HashSet<String>>,
+}
+
+impl CachedClient {
+    /// Wrap `inner`, whose requests use `parameters`
+    ///
+    /// With `refresh`, cached responses are ignored but new responses are
+    /// still stored.
+    pub fn new(
+        inner: Arc<dyn LlmClient>,
+        cache: ResponseCache,
+        parameters: &LlmParameters,
+        refresh: bool,
+    ) -> Self {
+        Self {
+            inner,
+            cache,
+            parameters: parameters.clone(),
+            refresh,
+            answered: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn key(&self, prompt: &str) -> String {
+        ResponseCache::key(
+            self.inner.provider_name(),
+            self.inner.model_name(),
+            &self.parameters,
+            prompt,
+        )
+    }
+
+    /// Cached response for `key`, unless it should be regenerated
+    fn lookup(&self, key: &str) -> Option<String> {
+        let answered = !self.answered.lock().unwrap().insert(key.to_string());
+        if self.refresh || answered {
+            return None;
+        }
+
+        match self.cache.get(key) {
+            Ok(Some(text)) => {
+                debug!("Using cached response {}", key);
+                Some(text)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Failed to read the response cache: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Cache a response, unless another provider answered in place of the
+    /// one the key is for
+    fn store(&self, key: &str, completion: &Completion) {
+        if completion.answered_by.is_some() {
+            debug!("Not caching the fallback's response {}", key);
+            return;
+        }
+        if let Err(e) = self.cache.put(key, &completion.text) {
+            warn!("Failed to write the response cache: {}", e);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmClient for CachedClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self.complete_with_usage(prompt).await?.text)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        let key = self.key(prompt);
+        if let Some(text) = self.lookup(&key) {
+            return Ok(Completion::new(text, TokenUsage::default()));
+        }
+
+        let completion = self.inner.complete_with_usage(prompt).await?;
+        self.store(&key, &completion);
+        Ok(completion)
+    }
+
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        let key = self.key(prompt);
+        if let Some(text) = self.lookup(&key) {
+            on_token(&text);
+            return Ok(Completion::new(text, TokenUsage::default()));
+        }
+
+        let completion = self.inner.complete_stream(prompt, on_token).await?;
+        self.store(&key, &completion);
+        Ok(completion)
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLlmClient;
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rephraser-cached-client-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn cached_client(dir: &Path, refresh: bool) -> CachedClient {
+        CachedClient::new(
+            Arc::new(MockLlmClient::new()),
+            ResponseCache::new(dir, Duration::from_secs(3600), 1 << 20),
+            &LlmParameters::default(),
+            refresh,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_cached_response() {
+        let dir = temp_dir("hit");
+        let completion = cached_client(&dir, false)
+            .complete_with_usage("polite")
+            .await
+            .unwrap();
+        assert!(completion.usage.prompt_tokens > 0);
+
+        // A later run is answered from the cache for free
+        let client = cached_client(&dir, false);
+        let cached = client.complete_with_usage("polite").await.unwrap();
+        assert_eq!(cached.text, completion.text);
+        assert_eq!(cached.usage, TokenUsage::default());
+
+        // Asking the same client again regenerates the response
+        let retried = client.complete_with_usage("polite").await.unwrap();
+        assert!(retried.usage.prompt_tokens > 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_refresh() {
+        let dir = temp_dir("refresh");
+        cached_client(&dir, false).complete("polite").await.unwrap();
+
+        let refreshed = cached_client(&dir, true)
+            .complete_with_usage("polite")
+            .await
+            .unwrap();
+        assert!(refreshed.usage.prompt_tokens > 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_does_not_cache_fallback_answers() {
+        use crate::error::RephraserError;
+        use crate::llm::FallbackClient;
+
+        /// Provider that can't be reached
+        struct Offline;
+
+        #[async_trait]
+        impl LlmClient for Offline {
+            async fn complete(&self, _prompt: &str) -> Result<String> {
+                Err(RephraserError::Offline("api.openai.com".to_string()))
+            }
+
+            fn provider_name(&self) -> &str {
+                "openai"
+            }
+
+            fn model_name(&self) -> &str {
+                "gpt-4o-mini"
+            }
+        }
+
+        let dir = temp_dir("fallback");
+        let cache = || ResponseCache::new(&dir, Duration::from_secs(3600), 1 << 20);
+        let fallback = FallbackClient::new(Arc::new(Offline), Arc::new(MockLlmClient::new()));
+        let client = CachedClient::new(
+            Arc::new(fallback),
+            cache(),
+            &LlmParameters::default(),
+            false,
+        );
+        let completion = client.complete_with_usage("polite").await.unwrap();
+        assert_eq!(
+            completion.answered_by,
+            Some(("mock".to_string(), "mock-model-v1".to_string()))
+        );
+
+        // Nothing was stored under the primary's key
+        assert_eq!(cache().get(&client.key("polite")).unwrap(), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
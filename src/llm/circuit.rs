@@ -0,0 +1,325 @@
+//! Circuit breaker for providers that keep failing
+//!
+//! When a provider is down, every request would otherwise wait for its
+//! connection or timeout, and batches would retry each item on top of that.
+//! After `failure_threshold` consecutive failures the circuit of the
+//! provider opens: requests fail at once with
+//! [`RephraserError::CircuitOpen`] (or go to the offline fallback) for
+//! `cooldown_secs`, after which one is let through to see whether the
+//! provider has recovered. A success closes the circuit again.
+//!
+//! The state is kept in a file next to the config, so that it carries over
+//! between runs started by a hotkey, each of which is a process of its own.
+
+use crate::config::CircuitConfig;
+use crate::error::{RephraserError, Result};
+use crate::llm::client::{Completion, LlmClient};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// State of the circuit of one provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Circuit {
+    /// Failures since the last success
+    failures: u32,
+    /// When the circuit last opened, in seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    opened_at: Option<u64>,
+}
+
+/// Circuits of all providers, saved in a state file
+pub struct CircuitBreaker {
+    path: PathBuf,
+    failure_threshold: u32,
+    cooldown: Duration,
+    /// Serializes updates of the state file within this process
+    lock: Mutex<()>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &CircuitConfig, path: PathBuf) -> Self {
+        Self {
+            path,
+            failure_threshold: config.failure_threshold.max(1),
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Fail if the circuit of `provider` is open
+    ///
+    /// # Errors
+    /// * [`RephraserError::CircuitOpen`] while the provider's cooldown lasts
+    pub fn check(&self, provider: &str) -> Result<()> {
+        let circuits = self.read();
+        let Some(opened_at) = circuits.get(provider).and_then(|circuit| circuit.opened_at) else {
+            return Ok(());
+        };
+
+        let reopens = Duration::from_secs(opened_at) + self.cooldown;
+        match reopens.checked_sub(now()) {
+            Some(retry_in) if !retry_in.is_zero() => Err(RephraserError::CircuitOpen {
+                provider: provider.to_string(),
+                retry_in: Duration::from_secs(retry_in.as_secs().max(1)),
+            }),
+            _ => {
+                debug!("Cooldown of {} is over, trying it again", provider);
+                Ok(())
+            }
+        }
+    }
+
+    /// Record the outcome of a request to `provider`
+    ///
+    /// Only failures that suggest the provider is unavailable count; a bad
+    /// request or API key is the caller's problem, not an outage.
+    pub fn record<T>(&self, provider: &str, result: &Result<T>) {
+        match result {
+            Ok(_) => self.update(provider, false),
+            Err(e) if e.is_retryable() && !e.is_circuit_open() => self.update(provider, true),
+            Err(_) => {}
+        }
+    }
+
+    fn update(&self, provider: &str, failed: bool) {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut circuits = self.read();
+        if !failed {
+            if circuits.remove(provider).is_some() {
+                self.write(&circuits);
+            }
+            return;
+        }
+
+        let circuit = circuits.entry(provider.to_string()).or_default();
+        circuit.failures += 1;
+        if circuit.failures >= self.failure_threshold {
+            warn!(
+                "{} failed {} times in a row; not sending it requests for {:?}",
+                provider, circuit.failures, self.cooldown
+            );
+            circuit.opened_at = Some(now().as_secs());
+        }
+        self.write(&circuits);
+    }
+
+    fn read(&self) -> HashMap<String, Circuit> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn write(&self, circuits: &HashMap<String, Circuit>) {
+        let written = serde_json::to_string(circuits)
+            .map_err(RephraserError::from)
+            .and_then(|content| Ok(fs::write(&self.path, content)?));
+        if let Err(e) = written {
+            debug!(
+                "Could not save circuit state to {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Client wrapper that stops sending requests while the circuit of its
+/// provider is open
+pub struct CircuitClient {
+    inner: Arc<dyn LlmClient>,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl CircuitClient {
+    pub fn new(inner: Arc<dyn LlmClient>, breaker: Arc<CircuitBreaker>) -> Self {
+        Self { inner, breaker }
+    }
+
+    fn provider(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    /// Check the circuit and start tracking a request
+    fn start(&self) -> Result<Attempt<'_>> {
+        self.breaker.check(self.provider())?;
+        Ok(Attempt {
+            client: self,
+            finished: false,
+        })
+    }
+}
+
+/// A request in flight
+///
+/// A request abandoned before it finishes, typically because the timeout
+/// around this client expired, counts as a failure.
+struct Attempt<'a> {
+    client: &'a CircuitClient,
+    finished: bool,
+}
+
+impl Attempt<'_> {
+    fn finish<T>(mut self, result: Result<T>) -> Result<T> {
+        self.finished = true;
+        self.client.breaker.record(self.client.provider(), &result);
+        result
+    }
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.client.breaker.update(self.client.provider(), true);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmClient for CircuitClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        let attempt = self.start()?;
+        attempt.finish(self.inner.complete(prompt).await)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        let attempt = self.start()?;
+        attempt.finish(self.inner.complete_with_usage(prompt).await)
+    }
+
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        let attempt = self.start()?;
+        attempt.finish(self.inner.complete_stream(prompt, on_token).await)
+    }
+
+    /// Health checks always reach the provider, so they show whether it
+    /// has recovered
+    async fn ping(&self) -> Result<()> {
+        self.inner.ping().await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLlmClient;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Client failing every request as if the provider were down
+    #[derive(Default)]
+    struct Down(AtomicUsize);
+
+    #[async_trait]
+    impl LlmClient for Down {
+        async fn complete(&self, _prompt: &str) -> Result<String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(RephraserError::LlmServiceError(
+                "503 Service Unavailable".to_string(),
+            ))
+        }
+
+        fn provider_name(&self) -> &str {
+            "openai"
+        }
+
+        fn model_name(&self) -> &str {
+            "gpt-4o-mini"
+        }
+    }
+
+    fn breaker(name: &str, cooldown_secs: u64) -> Arc<CircuitBreaker> {
+        let path = std::env::temp_dir().join(format!(
+            "rephraser-circuit-{}-{}.json",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let config = CircuitConfig {
+            enabled: true,
+            failure_threshold: 2,
+            cooldown_secs,
+        };
+        Arc::new(CircuitBreaker::new(&config, path))
+    }
+
+    #[tokio::test]
+    async fn test_opens_after_failures() {
+        let breaker = breaker("open", 60);
+        let down = Arc::new(Down::default());
+        let client = CircuitClient::new(
+            Arc::clone(&down) as Arc<dyn LlmClient>,
+            Arc::clone(&breaker),
+        );
+
+        for _ in 0..2 {
+            let err = client.complete("hello").await.unwrap_err();
+            assert!(matches!(err, RephraserError::LlmServiceError(_)));
+        }
+        let err = client.complete("hello").await.unwrap_err();
+        assert!(err.is_circuit_open());
+        assert_eq!(down.0.load(Ordering::SeqCst), 2);
+
+        // Another client (or process) for the same provider sees the state
+        let other = CircuitClient::new(Arc::new(Down::default()), breaker);
+        assert!(other.complete("hello").await.unwrap_err().is_circuit_open());
+    }
+
+    #[tokio::test]
+    async fn test_counts_abandoned_requests() {
+        let breaker = breaker("abandoned", 60);
+        let client = CircuitClient::new(Arc::new(MockLlmClient::new()), Arc::clone(&breaker));
+        // The mock takes longer than this to answer
+        for _ in 0..2 {
+            let request = client.complete("hello");
+            assert!(tokio::time::timeout(Duration::from_millis(1), request)
+                .await
+                .is_err());
+        }
+        assert!(breaker.check("mock").unwrap_err().is_circuit_open());
+    }
+
+    #[test]
+    fn test_closes_after_cooldown_and_success() {
+        let breaker = breaker("close", 0);
+        let failure: Result<()> = Err(RephraserError::Timeout(Duration::from_secs(30)));
+        breaker.record("openai", &failure);
+        breaker.record("openai", &failure);
+        assert!(breaker.check("openai").is_ok());
+
+        breaker.record("openai", &Ok(()));
+        assert_eq!(breaker.read().get("openai"), None);
+
+        // Errors that don't mean an outage don't count
+        let auth: Result<()> = Err(RephraserError::LlmAuth("bad key".to_string()));
+        breaker.record("anthropic", &auth);
+        assert_eq!(breaker.read().get("anthropic"), None);
+    }
+}
@@ -1,7 +1,56 @@
 //! LLM Client trait definition
 
+#[cfg(feature = "native")]
+use crate::error::RephraserError;
 use crate::error::Result;
+use crate::llm::tokens::estimate_tokens;
 use async_trait::async_trait;
+#[cfg(feature = "native")]
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+#[cfg(feature = "native")]
+use std::time::Duration;
+
+/// How long providers get to accept a connection, so that a lost network
+/// fails fast instead of waiting for the whole request to time out
+#[cfg(feature = "native")]
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Sent with every request, so providers and gateways can tell where
+/// requests come from
+#[cfg(feature = "native")]
+pub(crate) const USER_AGENT: &str = concat!("rephraser/", env!("CARGO_PKG_VERSION"));
+
+/// HTTP client for calling provider APIs
+#[cfg(feature = "native")]
+pub(crate) fn http_client() -> reqwest::Client {
+    http_client_builder().build().unwrap_or_default()
+}
+
+/// Builder of [`http_client`], for clients with further options
+#[cfg(feature = "native")]
+pub(crate) fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .user_agent(USER_AGENT)
+}
+
+/// Extra headers to send with every request of a client
+///
+/// # Errors
+/// * If a name or value can't be sent in a header
+#[cfg(feature = "native")]
+pub(crate) fn header_map(headers: &[(String, String)]) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        // Values may hold tokens, so only the name is reported
+        let invalid = || RephraserError::Config(format!("Invalid header: {}", name));
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?;
+        map.insert(name, HeaderValue::from_str(value).map_err(|_| invalid())?);
+    }
+    Ok(map)
+}
 
 /// Core trait for LLM clients
 ///
@@ -23,6 +72,42 @@ pub trait LlmClient: Send + Sync {
     /// * Response parsing errors
     async fn complete(&self, prompt: &str) -> Result<String>;
 
+    /// Send a prompt and receive the completion with its token usage
+    ///
+    /// Providers that don't report usage return an estimate.
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        let text = self.complete(prompt).await?;
+        Ok(Completion::estimate(prompt, text))
+    }
+
+    /// Send a prompt and receive the completion incrementally
+    ///
+    /// `on_token` is called with each piece of text as it arrives. The full
+    /// response is returned at the end. Providers without streaming support
+    /// call `on_token` once with the complete response.
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        let completion = self.complete_with_usage(prompt).await?;
+        on_token(&completion.text);
+        Ok(completion)
+    }
+
+    /// Whether `complete_stream` delivers tokens incrementally
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+
+    /// Check that the provider can be reached and accepts the key and model
+    ///
+    /// Sends a tiny completion by default; providers with a cheaper way to
+    /// check, such as looking up the model, use that instead.
+    async fn ping(&self) -> Result<()> {
+        self.complete("Reply with OK.").await.map(|_| ())
+    }
+
     /// Get the name of this LLM provider (e.g., "openai", "anthropic", "mock")
     fn provider_name(&self) -> &str;
 
@@ -30,6 +115,66 @@ pub trait LlmClient: Send + Sync {
     fn model_name(&self) -> &str;
 }
 
+/// A completion together with its token usage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub text: String,
+    pub usage: TokenUsage,
+    /// Provider and model that answered instead of the client's own, such
+    /// as the offline fallback
+    pub answered_by: Option<(String, String)>,
+}
+
+impl Completion {
+    /// A completion from the client's own provider and model
+    pub fn new(text: String, usage: TokenUsage) -> Self {
+        Self {
+            text,
+            usage,
+            answered_by: None,
+        }
+    }
+
+    /// A completion with its usage estimated from the prompt and response
+    pub fn estimate(prompt: &str, text: String) -> Self {
+        let usage = TokenUsage::estimate(prompt, &text);
+        Self::new(text, usage)
+    }
+
+    /// Provider and model that answered, given the client that returned
+    /// the completion
+    pub fn provider_and_model<'a>(&'a self, client: &'a dyn LlmClient) -> (&'a str, &'a str) {
+        match &self.answered_by {
+            Some((provider, model)) => (provider, model),
+            None => (client.provider_name(), client.model_name()),
+        }
+    }
+}
+
+/// Token counts for a request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+impl TokenUsage {
+    /// Estimate usage from the prompt and response text
+    pub fn estimate(prompt: &str, response: &str) -> Self {
+        Self {
+            prompt_tokens: estimate_tokens(prompt),
+            completion_tokens: estimate_tokens(response),
+        }
+    }
+}
+
+impl AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+    }
+}
+
 /// Parameters for LLM API calls
 #[derive(Debug, Clone)]
 pub struct LlmParameters {
@@ -0,0 +1,161 @@
+//! Local fallback for when the provider can't be reached
+
+use crate::error::Result;
+use crate::llm::client::{Completion, LlmClient};
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Client wrapper that sends requests to a fallback client, such as a local
+/// model, when the machine appears to be offline or the primary's circuit
+/// is open
+///
+/// Other failures are returned as they are. The client is named after the
+/// primary; completions from the fallback say so in
+/// [`Completion::answered_by`], so they are neither cached nor charged at
+/// the primary's prices.
+pub struct FallbackClient {
+    primary: Arc<dyn LlmClient>,
+    fallback: Arc<dyn LlmClient>,
+}
+
+impl FallbackClient {
+    pub fn new(primary: Arc<dyn LlmClient>, fallback: Arc<dyn LlmClient>) -> Self {
+        Self { primary, fallback }
+    }
+
+    fn falls_back(error: &crate::error::RephraserError) -> bool {
+        error.is_offline() || error.is_circuit_open()
+    }
+
+    fn falling_back(&self, error: &crate::error::RephraserError) {
+        warn!(
+            "{}; using {}/{} instead",
+            error,
+            self.fallback.provider_name(),
+            self.fallback.model_name()
+        );
+    }
+
+    /// Mark a completion of the fallback as such
+    fn answered_by_fallback(&self, completion: Completion) -> Completion {
+        Completion {
+            answered_by: Some((
+                self.fallback.provider_name().to_string(),
+                self.fallback.model_name().to_string(),
+            )),
+            ..completion
+        }
+    }
+}
+
+#[async_trait]
+impl LlmClient for FallbackClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        match self.primary.complete(prompt).await {
+            Err(e) if Self::falls_back(&e) => {
+                self.falling_back(&e);
+                self.fallback.complete(prompt).await
+            }
+            result => result,
+        }
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        match self.primary.complete_with_usage(prompt).await {
+            Err(e) if Self::falls_back(&e) => {
+                self.falling_back(&e);
+                let completion = self.fallback.complete_with_usage(prompt).await?;
+                Ok(self.answered_by_fallback(completion))
+            }
+            result => result,
+        }
+    }
+
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        // Being offline shows when connecting, before any token arrives
+        match self.primary.complete_stream(prompt, on_token).await {
+            Err(e) if Self::falls_back(&e) => {
+                self.falling_back(&e);
+                let completion = self.fallback.complete_stream(prompt, on_token).await?;
+                Ok(self.answered_by_fallback(completion))
+            }
+            result => result,
+        }
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.primary.supports_streaming()
+    }
+
+    fn provider_name(&self) -> &str {
+        self.primary.provider_name()
+    }
+
+    fn model_name(&self) -> &str {
+        self.primary.model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::RephraserError;
+    use crate::llm::MockLlmClient;
+
+    /// Client failing every request with a fixed error
+    struct Failing(fn() -> RephraserError);
+
+    #[async_trait]
+    impl LlmClient for Failing {
+        async fn complete(&self, _prompt: &str) -> Result<String> {
+            Err((self.0)())
+        }
+
+        fn provider_name(&self) -> &str {
+            "openai"
+        }
+
+        fn model_name(&self) -> &str {
+            "gpt-4o-mini"
+        }
+    }
+
+    fn client(error: fn() -> RephraserError) -> FallbackClient {
+        FallbackClient::new(Arc::new(Failing(error)), Arc::new(MockLlmClient::new()))
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_when_offline() {
+        let client = client(|| RephraserError::Offline("api.openai.com".to_string()));
+        let completion = client
+            .complete_with_usage("丁寧にしてください")
+            .await
+            .unwrap();
+        assert!(completion.text.contains("お元気でしょうか"));
+        assert_eq!(client.provider_name(), "openai");
+        assert_eq!(
+            completion.provider_and_model(&client),
+            ("mock", "mock-model-v1")
+        );
+
+        let mut tokens = Vec::new();
+        let completion = client
+            .complete_stream("hello", &mut |token| tokens.push(token.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(tokens, [completion.text]);
+        assert!(completion.answered_by.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_keeps_other_errors() {
+        let client = client(|| RephraserError::LlmAuth("bad key".to_string()));
+        let err = client.complete("hello").await.unwrap_err();
+        assert!(matches!(err, RephraserError::LlmAuth(_)));
+    }
+}
@@ -0,0 +1,208 @@
+//! Health checks of the configured providers
+//!
+//! `doctor` and the server's `/health` endpoint ping every configured
+//! provider with a cheap request, such as looking up the model, and report
+//! whether it answered, how long that took and the last error seen.
+
+use crate::config::LlmConfig;
+use crate::error::{RephraserError, Result};
+use crate::llm::LlmClient;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a provider gets to answer a ping
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of pinging one provider
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProviderHealth {
+    pub provider: String,
+    pub model: String,
+    pub ok: bool,
+    /// How long the provider took to answer, if it did
+    pub latency_ms: Option<u64>,
+    /// Most recent failure, kept after later successes
+    pub last_error: Option<String>,
+}
+
+/// Map the status of a ping response to an error, like completions do
+pub(crate) fn check_ping_status(provider: &str, model: &str, status: StatusCode) -> Result<()> {
+    match status {
+        status if status.is_success() => Ok(()),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(RephraserError::LlmAuth(format!(
+            "{} rejected the API key ({})",
+            provider, status
+        ))),
+        StatusCode::NOT_FOUND => Err(RephraserError::LlmBadRequest(format!(
+            "{} has no model named {}",
+            provider, model
+        ))),
+        StatusCode::TOO_MANY_REQUESTS => Err(RephraserError::LlmRateLimit(format!(
+            "{} is rate limiting requests",
+            provider
+        ))),
+        status => Err(RephraserError::LlmServiceError(format!(
+            "{} answered {}",
+            provider, status
+        ))),
+    }
+}
+
+/// Ping `client` once, timing the answer
+pub async fn probe(llm: &LlmConfig, client: Result<Arc<dyn LlmClient>>) -> ProviderHealth {
+    let started = Instant::now();
+    let result = match client {
+        Ok(client) => match tokio::time::timeout(PROBE_TIMEOUT, client.ping()).await {
+            Ok(result) => result,
+            Err(_) => Err(RephraserError::Timeout(PROBE_TIMEOUT)),
+        },
+        Err(e) => Err(e),
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+    ProviderHealth {
+        provider: llm.provider.clone(),
+        model: llm.model.clone(),
+        ok: result.is_ok(),
+        latency_ms: result.is_ok().then_some(latency_ms),
+        last_error: result.err().map(|e| e.to_string()),
+    }
+}
+
+/// Ping every client in `targets` at once
+pub async fn probe_all(
+    targets: Vec<(LlmConfig, Result<Arc<dyn LlmClient>>)>,
+) -> Vec<ProviderHealth> {
+    let probes = targets
+        .into_iter()
+        .map(|(llm, client)| async move { probe(&llm, client).await });
+    futures_util::future::join_all(probes).await
+}
+
+/// Results of the latest checks, refreshed at most once per `max_age`
+///
+/// Used by the server so that frequent `/health` requests don't each send a
+/// request to every provider.
+#[derive(Default)]
+pub struct HealthChecks {
+    latest: Mutex<Option<(Instant, Vec<ProviderHealth>)>>,
+    last_errors: Mutex<HashMap<(String, String), String>>,
+}
+
+impl HealthChecks {
+    /// The latest results, pinging the providers of `targets` again if they
+    /// are older than `max_age`
+    pub async fn check(
+        &self,
+        targets: impl FnOnce() -> Vec<(LlmConfig, Result<Arc<dyn LlmClient>>)>,
+        max_age: Duration,
+    ) -> Vec<ProviderHealth> {
+        let mut latest = self.latest.lock().await;
+        if let Some((checked, results)) = latest.as_ref() {
+            if checked.elapsed() < max_age {
+                return results.clone();
+            }
+        }
+
+        let mut results = probe_all(targets()).await;
+        let mut last_errors = self.last_errors.lock().await;
+        for health in &mut results {
+            let key = (health.provider.clone(), health.model.clone());
+            match &health.last_error {
+                Some(error) => {
+                    last_errors.insert(key, error.clone());
+                }
+                None => health.last_error = last_errors.get(&key).cloned(),
+            }
+        }
+        *latest = Some((Instant::now(), results.clone()));
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::llm::MockLlmClient;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Client whose pings fail until it is told to recover
+    #[derive(Default)]
+    struct Flaky(AtomicBool);
+
+    #[async_trait]
+    impl LlmClient for Flaky {
+        async fn complete(&self, _prompt: &str) -> Result<String> {
+            if self.0.load(Ordering::SeqCst) {
+                Ok("OK".to_string())
+            } else {
+                Err(RephraserError::LlmServiceError("overloaded".to_string()))
+            }
+        }
+
+        fn provider_name(&self) -> &str {
+            "openai"
+        }
+
+        fn model_name(&self) -> &str {
+            "gpt-4o-mini"
+        }
+    }
+
+    #[test]
+    fn test_check_ping_status() {
+        assert!(check_ping_status("OpenAI", "gpt-4o", StatusCode::OK).is_ok());
+        let err = check_ping_status("OpenAI", "gpt-5o", StatusCode::NOT_FOUND).unwrap_err();
+        assert!(err.to_string().contains("gpt-5o"));
+        assert!(matches!(
+            check_ping_status("OpenAI", "gpt-4o", StatusCode::UNAUTHORIZED),
+            Err(RephraserError::LlmAuth(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_probe() {
+        let llm = Config::default().llm;
+        let health = probe(&llm, Ok(Arc::new(MockLlmClient::new()))).await;
+        assert!(health.ok);
+        assert!(health.latency_ms.is_some());
+        assert_eq!(health.last_error, None);
+
+        let missing = Err(RephraserError::Config("no API key".to_string()));
+        let health = probe(&llm, missing).await;
+        assert!(!health.ok);
+        assert_eq!(health.latency_ms, None);
+        assert!(health.last_error.unwrap().contains("no API key"));
+    }
+
+    #[tokio::test]
+    async fn test_checks_keep_last_error() {
+        let checks = HealthChecks::default();
+        let flaky = Arc::new(Flaky::default());
+        let targets = || {
+            let client = Arc::clone(&flaky) as Arc<dyn LlmClient>;
+            vec![(Config::default().llm, Ok(client))]
+        };
+
+        let results = checks.check(targets, Duration::ZERO).await;
+        assert!(!results[0].ok);
+
+        // Cached results are returned until they are older than max_age
+        flaky.0.store(true, Ordering::SeqCst);
+        let cached = checks.check(targets, Duration::from_secs(60)).await;
+        assert_eq!(cached, results);
+
+        let results = checks.check(targets, Duration::ZERO).await;
+        assert!(results[0].ok);
+        assert!(results[0]
+            .last_error
+            .as_ref()
+            .unwrap()
+            .contains("overloaded"));
+    }
+}
@@ -0,0 +1,94 @@
+//! Idempotency keys for requests that may be sent more than once
+//!
+//! When a request fails in a way that leaves it unclear whether the provider
+//! received it, such as a dropped connection or a timeout, retrying it may
+//! generate and bill a second completion. Work that may be retried (batch
+//! items) runs in a [`scope`] with a key of its own, and providers that
+//! support it (OpenAI) send a key derived from it and the request body with
+//! each request, so the provider recognizes a retried request as the same.
+
+use crate::actions::registry::sha256_hex;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Header carrying the key of a request
+pub const HEADER: &str = "Idempotency-Key";
+
+tokio::task_local! {
+    static KEY: String;
+}
+
+/// A key unlike any other, for one piece of work and all its retries
+pub fn new_key() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    sha256_hex(&format!("{}:{}:{}", std::process::id(), nanos, count))[..32].to_string()
+}
+
+/// Run `work` with `key` as the current key, or with none
+pub async fn scope<F: Future>(key: Option<String>, work: F) -> F::Output {
+    match key {
+        Some(key) => KEY.scope(key, work).await,
+        None => work.await,
+    }
+}
+
+/// Key of the scope the current task runs in
+///
+/// Tasks spawned from a scope don't inherit it; pass this to [`scope`] in
+/// the new task instead.
+pub fn current() -> Option<String> {
+    KEY.try_with(Clone::clone).ok()
+}
+
+/// Key for a request with `body` in the current scope
+///
+/// The same request gets the same key on every attempt, while different
+/// requests of one piece of work (such as a guardrail retry with a
+/// corrected prompt) get different keys.
+pub fn request_key(body: &impl Serialize) -> Option<String> {
+    let key = current()?;
+    let body = serde_json::to_string(body).ok()?;
+    Some(sha256_hex(&format!("{}\n{}", key, body))[..32].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_keys() {
+        assert_eq!(request_key(&"prompt"), None);
+        assert_ne!(new_key(), new_key());
+
+        let key = new_key();
+        let (first, retried, other) = scope(Some(key.clone()), async {
+            (
+                request_key(&"prompt"),
+                request_key(&"prompt"),
+                request_key(&"corrected"),
+            )
+        })
+        .await;
+        assert_eq!(first, retried);
+        assert_ne!(first, other);
+        assert_eq!(first.unwrap().len(), 32);
+
+        // Another piece of work sending the same request gets another key
+        let elsewhere = scope(Some(new_key()), async { request_key(&"prompt") }).await;
+        assert_ne!(elsewhere, retried);
+
+        let inherited = scope(Some(key.clone()), async {
+            let key = current();
+            tokio::spawn(scope(key, async { current() })).await.unwrap()
+        })
+        .await;
+        assert_eq!(inherited, Some(key));
+    }
+}
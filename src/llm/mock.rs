@@ -4,6 +4,7 @@ use crate::error::Result;
 use crate::llm::client::LlmClient;
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 /// Mock LLM client that returns predefined responses
 ///
@@ -11,6 +12,7 @@ use std::collections::HashMap;
 pub struct MockLlmClient {
     responses: HashMap<String, String>,
     default_response: String,
+    prompts: Mutex<Vec<String>>,
 }
 
 impl MockLlmClient {
@@ -46,9 +48,15 @@ impl MockLlmClient {
             "要約: このテキストは主要な3つのポイントを含んでいます。".to_string(),
         );
 
+        responses.insert(
+            "proofread".to_string(),
+            r#"{"corrections": []}"#.to_string(),
+        );
+
         Self {
             responses,
             default_response: "[Mock LLM Response] Processed successfully.".to_string(),
+            prompts: Mutex::default(),
         }
     }
 
@@ -62,6 +70,14 @@ impl MockLlmClient {
         self.default_response = response.into();
     }
 
+    /// The prompts received so far, oldest first
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
     /// Extract action name from prompt (simple heuristic)
     fn extract_action(&self, prompt: &str) -> Option<String> {
         // Try to match known action keywords in the prompt
@@ -95,7 +111,13 @@ impl Default for MockLlmClient {
 #[async_trait]
 impl LlmClient for MockLlmClient {
     async fn complete(&self, prompt: &str) -> Result<String> {
+        self.prompts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(prompt.to_string());
+
         // Simulate slight delay
+        #[cfg(feature = "native")]
         tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
 
         // Try to find a matching response
@@ -146,6 +168,7 @@ mod tests {
 
         let result = client.complete("custom action").await.unwrap();
         assert_eq!(result, "Custom response");
+        assert_eq!(client.prompts(), ["custom action"]);
     }
 
     #[test]
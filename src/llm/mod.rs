@@ -1,11 +1,59 @@
 //! LLM client implementations
 
+#[cfg(feature = "native")]
 pub mod anthropic;
+#[cfg(feature = "native")]
+pub mod audit;
+#[cfg(feature = "native")]
+pub mod cache;
+#[cfg(feature = "native")]
+pub mod circuit;
 pub mod client;
+#[cfg(feature = "native")]
+pub mod fallback;
+#[cfg(feature = "native")]
+pub mod health;
+#[cfg(feature = "native")]
+pub mod idempotency;
 pub mod mock;
+#[cfg(feature = "native")]
 pub mod openai;
+#[cfg(feature = "native")]
+pub mod plugin;
+pub mod pricing;
+#[cfg(feature = "native")]
+pub mod privacy;
+#[cfg(feature = "native")]
+pub mod registry;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "native")]
+pub mod sse;
+#[cfg(feature = "native")]
+pub mod timeout;
+pub mod tokens;
 
-pub use anthropic::AnthropicClient;
-pub use client::{LlmClient, LlmParameters};
+#[cfg(feature = "native")]
+pub use anthropic::{AnthropicClient, AnthropicClientBuilder};
+#[cfg(feature = "native")]
+pub use audit::AuditedClient;
+#[cfg(feature = "native")]
+pub use cache::CachedClient;
+#[cfg(feature = "native")]
+pub use circuit::{CircuitBreaker, CircuitClient};
+pub use client::{Completion, LlmClient, LlmParameters, TokenUsage};
+#[cfg(feature = "native")]
+pub use fallback::FallbackClient;
 pub use mock::MockLlmClient;
-pub use openai::OpenAiClient;
+#[cfg(feature = "native")]
+pub use openai::{OpenAiClient, OpenAiClientBuilder};
+#[cfg(feature = "native")]
+pub use plugin::PluginClient;
+#[cfg(feature = "native")]
+pub use privacy::PrivacyClient;
+#[cfg(feature = "native")]
+pub use registry::ClientRegistry;
+#[cfg(feature = "scripting")]
+pub use script::ScriptClient;
+#[cfg(feature = "native")]
+pub use timeout::TimeoutClient;
@@ -1,12 +1,19 @@
 //! OpenAI API client
 
 use crate::error::{RephraserError, Result};
-use crate::llm::client::LlmClient;
+use crate::llm::client::{
+    header_map, http_client, http_client_builder, Completion, LlmClient, LlmParameters, TokenUsage,
+};
+use crate::llm::health::check_ping_status;
+use crate::llm::idempotency;
+use crate::llm::sse::SseParser;
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::debug;
 
-const OPENAI_API_URL: &str = "https://api.openai.com/v1/chat/completions";
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
 
 /// Chat completion request message
 #[derive(Debug, Serialize)]
@@ -22,6 +29,8 @@ struct ChatCompletionRequest {
     messages: Vec<ChatMessage>,
     temperature: f32,
     max_tokens: usize,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
 }
 
 /// OpenAI chat completion response choice
@@ -40,6 +49,32 @@ struct ChatResponseMessage {
 #[derive(Debug, Deserialize)]
 struct ChatCompletionResponse {
     choices: Vec<ChatChoice>,
+    #[serde(default)]
+    usage: Option<ChatUsage>,
+}
+
+/// Token usage reported by OpenAI
+#[derive(Debug, Deserialize)]
+struct ChatUsage {
+    prompt_tokens: usize,
+    completion_tokens: usize,
+}
+
+/// Streaming chunk of a chat completion
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    choices: Vec<ChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    delta: ChunkDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
 }
 
 /// Error response from OpenAI API
@@ -63,6 +98,8 @@ pub struct OpenAiClient {
     model: String,
     temperature: f32,
     max_tokens: usize,
+    /// API address, without a trailing slash
+    base_url: String,
 }
 
 impl OpenAiClient {
@@ -73,22 +110,129 @@ impl OpenAiClient {
     /// * `model` - Model name (e.g., "gpt-4", "gpt-3.5-turbo")
     /// * `temperature` - Temperature parameter (0.0-2.0)
     /// * `max_tokens` - Maximum tokens in response
+    ///
+    /// See [`OpenAiClient::builder`] for the other options.
     pub fn new(api_key: String, model: String, temperature: f32, max_tokens: usize) -> Self {
         Self {
-            client: Client::new(),
+            client: http_client(),
             api_key,
             model,
             temperature,
             max_tokens,
+            base_url: DEFAULT_BASE_URL.to_string(),
         }
     }
+
+    /// Start building a client; the API key and model are required
+    pub fn builder() -> OpenAiClientBuilder {
+        OpenAiClientBuilder::default()
+    }
 }
 
-#[async_trait]
-impl LlmClient for OpenAiClient {
-    async fn complete(&self, prompt: &str) -> Result<String> {
-        // Construct request
-        let request = ChatCompletionRequest {
+/// Builder for [`OpenAiClient`]
+#[derive(Debug, Clone, Default)]
+pub struct OpenAiClientBuilder {
+    api_key: Option<String>,
+    model: Option<String>,
+    temperature: Option<f32>,
+    max_tokens: Option<usize>,
+    timeout: Option<Duration>,
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl OpenAiClientBuilder {
+    /// OpenAI API key
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Model name (e.g., "gpt-4o-mini")
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Temperature parameter (0.0-2.0); 0.7 if not set
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Maximum tokens in response; 500 if not set
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Give up on a request after this long; no limit if not set
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests to this address instead of https://api.openai.com/v1, such as a
+    /// proxy or a compatible server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Send these headers with every request, besides the ones the API
+    /// needs; a `User-Agent` replaces the default "rephraser/<version>"
+    pub fn headers<K, V>(mut self, headers: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.headers.extend(
+            headers
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into())),
+        );
+        self
+    }
+
+    /// Build the client
+    ///
+    /// # Errors
+    /// * If the API key or model is missing
+    /// * If a header can't be sent
+    /// * If the HTTP client can't be created
+    pub fn build(self) -> Result<OpenAiClient> {
+        let (Some(api_key), Some(model)) = (self.api_key, self.model) else {
+            return Err(RephraserError::Config(
+                "OpenAI clients need an API key and a model".to_string(),
+            ));
+        };
+
+        let mut client = http_client_builder();
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        if !self.headers.is_empty() {
+            client = client.default_headers(header_map(&self.headers)?);
+        }
+        let defaults = LlmParameters::default();
+        Ok(OpenAiClient {
+            client: client.build()?,
+            api_key,
+            model,
+            temperature: self.temperature.unwrap_or(defaults.temperature),
+            max_tokens: self.max_tokens.unwrap_or(defaults.max_tokens),
+            base_url: self
+                .base_url
+                .map(|url| url.trim_end_matches('/').to_string())
+                .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+        })
+    }
+}
+
+impl OpenAiClient {
+    /// Build a chat completion request for a prompt
+    fn build_request(&self, prompt: &str, stream: bool) -> ChatCompletionRequest {
+        ChatCompletionRequest {
             model: self.model.clone(),
             messages: vec![ChatMessage {
                 role: "user".to_string(),
@@ -96,48 +240,142 @@ impl LlmClient for OpenAiClient {
             }],
             temperature: self.temperature,
             max_tokens: self.max_tokens,
-        };
+            stream,
+        }
+    }
 
-        // Send request
-        let response = self
+    /// Address of an API endpoint
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    /// Send a request, mapping error status codes to errors
+    async fn send(&self, request: &ChatCompletionRequest) -> Result<reqwest::Response> {
+        let mut builder = self
             .client
-            .post(OPENAI_API_URL)
+            .post(self.url("chat/completions"))
             .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
+            .header("Content-Type", "application/json");
+        if let Some(key) = idempotency::request_key(request) {
+            builder = builder.header(idempotency::HEADER, key);
+        }
+        let response = builder.json(request).send().await?;
 
         // Check status code
         let status = response.status();
+        debug!(
+            "POST {} (model {}): {}",
+            self.url("chat/completions"),
+            request.model,
+            status
+        );
 
         if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
 
             // Try to parse structured error
-            let error_msg = if let Ok(err_resp) = serde_json::from_str::<OpenAiErrorResponse>(&error_text) {
-                err_resp.error.message
-            } else {
-                error_text
-            };
+            let error_msg =
+                if let Ok(err_resp) = serde_json::from_str::<OpenAiErrorResponse>(&error_text) {
+                    err_resp.error.message
+                } else {
+                    error_text
+                };
 
             return Err(match status.as_u16() {
-                401 | 403 => RephraserError::LlmAuth(format!("OpenAI authentication failed: {}", error_msg)),
-                429 => RephraserError::LlmRateLimit(format!("OpenAI rate limit exceeded: {}", error_msg)),
+                401 | 403 => {
+                    RephraserError::LlmAuth(format!("OpenAI authentication failed: {}", error_msg))
+                }
+                429 => RephraserError::LlmRateLimit(format!(
+                    "OpenAI rate limit exceeded: {}",
+                    error_msg
+                )),
                 400 => RephraserError::LlmBadRequest(format!("OpenAI bad request: {}", error_msg)),
-                _ => RephraserError::LlmServiceError(format!("OpenAI API error ({}): {}", status, error_msg)),
+                _ => RephraserError::LlmServiceError(format!(
+                    "OpenAI API error ({}): {}",
+                    status, error_msg
+                )),
             });
         }
 
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmClient for OpenAiClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self.complete_with_usage(prompt).await?.text)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        // Construct and send request
+        let request = self.build_request(prompt, false);
+        let response = self.send(&request).await?;
+
         // Parse successful response
         let completion_response: ChatCompletionResponse = response.json().await?;
 
         // Extract text from first choice
-        completion_response
+        let text = completion_response
             .choices
             .first()
             .map(|choice| choice.message.content.clone())
-            .ok_or_else(|| RephraserError::LlmApi("OpenAI returned no choices".to_string()))
+            .ok_or_else(|| RephraserError::LlmApi("OpenAI returned no choices".to_string()))?;
+
+        let usage = match completion_response.usage {
+            Some(usage) => TokenUsage {
+                prompt_tokens: usage.prompt_tokens,
+                completion_tokens: usage.completion_tokens,
+            },
+            None => TokenUsage::estimate(prompt, &text),
+        };
+
+        Ok(Completion::new(text, usage))
+    }
+
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        let request = self.build_request(prompt, true);
+        let mut response = self.send(&request).await?;
+
+        let mut parser = SseParser::new();
+        let mut full = String::new();
+
+        while let Some(chunk) = response.chunk().await? {
+            for data in parser.push(&chunk) {
+                if data == "[DONE]" {
+                    return Ok(Completion::estimate(prompt, full));
+                }
+                if let Some(token) = parse_stream_chunk(&data)? {
+                    on_token(&token);
+                    full.push_str(&token);
+                }
+            }
+        }
+
+        Ok(Completion::estimate(prompt, full))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let url = self.url(&format!("models/{}", self.model));
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .send()
+            .await?;
+        debug!("GET {}: {}", url, response.status());
+        check_ping_status("OpenAI", &self.model, response.status())
     }
 
     fn provider_name(&self) -> &str {
@@ -149,6 +387,17 @@ impl LlmClient for OpenAiClient {
     }
 }
 
+/// Extract the text delta from a streaming chunk
+fn parse_stream_chunk(data: &str) -> Result<Option<String>> {
+    let chunk: ChatCompletionChunk = serde_json::from_str(data)?;
+    Ok(chunk
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|choice| choice.delta.content)
+        .filter(|content| !content.is_empty()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -163,11 +412,22 @@ mod tests {
             }],
             temperature: 0.7,
             max_tokens: 500,
+            stream: false,
         };
 
         let json = serde_json::to_string(&request).unwrap();
         assert!(json.contains("\"model\":\"gpt-4\""));
         assert!(json.contains("\"temperature\":0.7"));
+        assert!(!json.contains("stream"));
+    }
+
+    #[test]
+    fn test_stream_chunk_parsing() {
+        let data = r#"{"choices":[{"delta":{"content":"Hel"}}]}"#;
+        assert_eq!(parse_stream_chunk(data).unwrap(), Some("Hel".to_string()));
+
+        let role_only = r#"{"choices":[{"delta":{"role":"assistant"}}]}"#;
+        assert_eq!(parse_stream_chunk(role_only).unwrap(), None);
     }
 
     #[test]
@@ -182,7 +442,18 @@ mod tests {
         }"#;
 
         let response: ChatCompletionResponse = serde_json::from_str(json).unwrap();
-        assert_eq!(response.choices[0].message.content, "Hello! How can I help?");
+        assert_eq!(
+            response.choices[0].message.content,
+            "Hello! How can I help?"
+        );
+        assert!(response.usage.is_none());
+
+        let with_usage = r#"{
+            "choices": [{"message": {"role": "assistant", "content": "Hi"}}],
+            "usage": {"prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15}
+        }"#;
+        let response: ChatCompletionResponse = serde_json::from_str(with_usage).unwrap();
+        assert_eq!(response.usage.unwrap().completion_tokens, 3);
     }
 
     #[test]
@@ -197,4 +468,74 @@ mod tests {
         let err_resp: OpenAiErrorResponse = serde_json::from_str(json).unwrap();
         assert_eq!(err_resp.error.message, "Invalid API key");
     }
+
+    #[test]
+    fn test_builder_requires_key_and_model() {
+        assert!(OpenAiClient::builder().model("gpt-4o").build().is_err());
+        let client = OpenAiClient::builder()
+            .api_key("sk-test")
+            .model("gpt-4o")
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.url("models/gpt-4o"),
+            "https://api.openai.com/v1/models/gpt-4o"
+        );
+        assert_eq!(client.max_tokens, 500);
+    }
+
+    #[tokio::test]
+    async fn test_builder_base_url() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/v1/chat/completions")
+            .match_header("authorization", "Bearer sk-test")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"model":"gpt-4o","max_tokens":64}"#.to_string(),
+            ))
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Hi"}}]}"#)
+            .create_async()
+            .await;
+
+        let client = OpenAiClient::builder()
+            .api_key("sk-test")
+            .model("gpt-4o")
+            .max_tokens(64)
+            .timeout(Duration::from_secs(5))
+            .base_url(format!("{}/v1/", server.url()))
+            .build()
+            .unwrap();
+        assert_eq!(client.complete("Hello").await.unwrap(), "Hi");
+        mock.assert_async().await;
+    }
+    #[tokio::test]
+    async fn test_builder_headers() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/chat/completions")
+            .match_header("user-agent", crate::llm::client::USER_AGENT)
+            .match_header("x-gateway-team", "writing")
+            .with_status(200)
+            .with_body(r#"{"choices":[{"message":{"role":"assistant","content":"Hi"}}]}"#)
+            .create_async()
+            .await;
+
+        let client = OpenAiClient::builder()
+            .api_key("sk-test")
+            .model("gpt-4o")
+            .base_url(server.url())
+            .headers([("X-Gateway-Team", "writing")])
+            .build()
+            .unwrap();
+        assert_eq!(client.complete("Hello").await.unwrap(), "Hi");
+        mock.assert_async().await;
+
+        let invalid = OpenAiClient::builder()
+            .api_key("sk-test")
+            .model("gpt-4o")
+            .headers([("X-Bad Name", "value")])
+            .build();
+        assert!(matches!(invalid, Err(RephraserError::Config(_))));
+    }
 }
@@ -0,0 +1,311 @@
+//! Client for provider plugins
+//!
+//! See [`crate::plugins`] for the protocol.
+
+use crate::config::PluginCommand;
+use crate::error::{RephraserError, Result};
+use crate::llm::client::{Completion, LlmClient, LlmParameters, TokenUsage};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+/// Environment variable the provider's API key is passed in, when one is
+/// configured under `[secrets.providers]`
+pub const API_KEY_ENV: &str = "REPHRASER_API_KEY";
+
+/// LLM client backed by a plugin process
+///
+/// The process is started on the first request and reused afterwards.
+/// Requests are sent one at a time.
+pub struct PluginClient {
+    provider: String,
+    model: String,
+    command: PluginCommand,
+    api_key: Option<String>,
+    params: LlmParameters,
+    process: Mutex<Option<PluginProcess>>,
+}
+
+/// A running plugin
+struct PluginProcess {
+    // Kept so the process is killed when the client is dropped
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+    /// A request was abandoned midway (e.g. by a timeout), so the plugin's
+    /// output can no longer be matched to requests
+    desynced: bool,
+}
+
+/// Result of a `complete` request
+#[derive(Debug, Deserialize)]
+struct CompleteResult {
+    text: String,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+impl PluginClient {
+    /// Client for `provider`, implemented by `command`
+    pub fn new(
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        command: PluginCommand,
+        api_key: Option<String>,
+        params: LlmParameters,
+    ) -> Self {
+        Self {
+            provider: provider.into(),
+            model: model.into(),
+            command,
+            api_key,
+            params,
+            process: Mutex::new(None),
+        }
+    }
+
+    fn spawn(&self) -> Result<PluginProcess> {
+        debug!(
+            "Starting plugin '{}' for provider '{}'",
+            self.command.command, self.provider
+        );
+        let mut command = Command::new(&self.command.command);
+        command
+            .args(&self.command.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(api_key) = &self.api_key {
+            command.env(API_KEY_ENV, api_key);
+        }
+
+        let mut child = command.spawn().map_err(|e| {
+            RephraserError::Config(format!(
+                "Failed to start the plugin '{}' for provider '{}': {}",
+                self.command.command, self.provider, e
+            ))
+        })?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        Ok(PluginProcess {
+            _child: child,
+            stdin,
+            stdout: BufReader::new(stdout).lines(),
+            next_id: 1,
+            desynced: false,
+        })
+    }
+
+    /// Send a `complete` request and wait for its result, passing streamed
+    /// tokens to `on_token`
+    async fn request(
+        &self,
+        prompt: &str,
+        stream: bool,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        let mut process = self.process.lock().await;
+        if process.as_ref().is_none_or(|p| p.desynced) {
+            *process = Some(self.spawn()?);
+        }
+        let running = process.as_mut().expect("process was just started");
+
+        let id = running.next_id;
+        running.next_id += 1;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "complete",
+            "params": {
+                "model": self.model,
+                "prompt": prompt,
+                "temperature": self.params.temperature,
+                "max_tokens": self.params.max_tokens,
+                "stream": stream,
+            },
+        });
+
+        // Cleared once the exchange completes; stays set if this future is
+        // dropped midway
+        running.desynced = true;
+        let result = self.exchange(running, id, &request, on_token).await;
+        match &result {
+            Ok(_) | Err(RephraserError::LlmApi(_)) => running.desynced = false,
+            // The plugin died or misbehaved, so start a new one next time
+            Err(_) => *process = None,
+        }
+
+        let result = result?;
+        let usage = result
+            .usage
+            .unwrap_or_else(|| TokenUsage::estimate(prompt, &result.text));
+        Ok(Completion::new(result.text, usage))
+    }
+
+    async fn exchange(
+        &self,
+        process: &mut PluginProcess,
+        id: u64,
+        request: &Value,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<CompleteResult> {
+        let mut line = request.to_string();
+        line.push('\n');
+        process
+            .stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| self.failure(format!("failed to send the request: {}", e)))?;
+        process
+            .stdin
+            .flush()
+            .await
+            .map_err(|e| self.failure(format!("failed to send the request: {}", e)))?;
+
+        loop {
+            let line = process
+                .stdout
+                .next_line()
+                .await
+                .map_err(|e| self.failure(format!("failed to read the response: {}", e)))?
+                .ok_or_else(|| self.failure("exited without responding".to_string()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let message: Value = serde_json::from_str(&line)
+                .map_err(|e| self.failure(format!("sent invalid JSON: {}", e)))?;
+            if message.get("id").is_none() {
+                if message.get("method").and_then(Value::as_str) == Some("token") {
+                    if let Some(text) = message.pointer("/params/text").and_then(Value::as_str) {
+                        on_token(text);
+                    }
+                }
+                continue;
+            }
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                return Err(self.failure("answered with an unexpected id".to_string()));
+            }
+
+            if let Some(error) = message.get("error") {
+                let text = error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error");
+                return Err(RephraserError::LlmApi(format!(
+                    "Plugin for provider '{}': {}",
+                    self.provider, text
+                )));
+            }
+            let result = message.get("result").cloned().unwrap_or(Value::Null);
+            return serde_json::from_value(result)
+                .map_err(|e| self.failure(format!("sent an invalid result: {}", e)));
+        }
+    }
+
+    fn failure(&self, detail: String) -> RephraserError {
+        RephraserError::LlmServiceError(format!(
+            "Plugin for provider '{}' {}",
+            self.provider, detail
+        ))
+    }
+}
+
+#[async_trait]
+impl LlmClient for PluginClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self.complete_with_usage(prompt).await?.text)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        self.request(prompt, false, &mut |_| {}).await
+    }
+
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        let mut streamed = false;
+        let completion = self
+            .request(prompt, true, &mut |token| {
+                streamed = true;
+                on_token(token);
+            })
+            .await?;
+        // Plugins without streaming support only send the result
+        if !streamed {
+            on_token(&completion.text);
+        }
+        Ok(completion)
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    fn provider_name(&self) -> &str {
+        &self.provider
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn client(script: &str) -> PluginClient {
+        PluginClient::new(
+            "shell",
+            "echo-1",
+            PluginCommand {
+                command: "sh".to_string(),
+                args: vec!["-c".to_string(), script.to_string()],
+            },
+            None,
+            LlmParameters::default(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_streams_tokens() {
+        // Answers every request as if it were the first
+        let client = client(
+            r#"while read -r line; do
+                printf '%s\n' '{"jsonrpc":"2.0","method":"token","params":{"text":"Hel"}}'
+                printf '%s\n' '{"jsonrpc":"2.0","method":"token","params":{"text":"lo"}}'
+                printf '%s\n' '{"jsonrpc":"2.0","id":1,"result":{"text":"Hello","usage":{"prompt_tokens":3,"completion_tokens":1}}}'
+            done"#,
+        );
+
+        let mut tokens = Vec::new();
+        let completion = client
+            .complete_stream("Hi", &mut |t| tokens.push(t.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(completion.text, "Hello");
+        assert_eq!(tokens, ["Hel", "lo"]);
+    }
+
+    #[tokio::test]
+    async fn test_reports_errors() {
+        let client = client(
+            r#"read -r line
+            printf '%s\n' '{"jsonrpc":"2.0","id":1,"error":{"code":1,"message":"model not loaded"}}'"#,
+        );
+        let err = client.complete("Hi").await.unwrap_err();
+        assert!(err.to_string().contains("model not loaded"));
+
+        // The plugin has exited, so the next request fails too
+        assert!(client.complete("Hi").await.is_err());
+    }
+}
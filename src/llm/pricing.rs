@@ -0,0 +1,168 @@
+//! Approximate API prices for cost estimates
+//!
+//! Prices are list prices in USD per million tokens and may be outdated;
+//! costs derived from them are estimates.
+
+use crate::llm::client::TokenUsage;
+
+/// Price of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+/// Known model prices, matched by model name prefix (most specific first)
+const PRICES: &[(&str, ModelPrice)] = &[
+    (
+        "gpt-4o-mini",
+        ModelPrice {
+            input: 0.15,
+            output: 0.60,
+        },
+    ),
+    (
+        "gpt-4o",
+        ModelPrice {
+            input: 2.50,
+            output: 10.00,
+        },
+    ),
+    (
+        "gpt-4.1-nano",
+        ModelPrice {
+            input: 0.10,
+            output: 0.40,
+        },
+    ),
+    (
+        "gpt-4.1-mini",
+        ModelPrice {
+            input: 0.40,
+            output: 1.60,
+        },
+    ),
+    (
+        "gpt-4.1",
+        ModelPrice {
+            input: 2.00,
+            output: 8.00,
+        },
+    ),
+    (
+        "gpt-4-turbo",
+        ModelPrice {
+            input: 10.00,
+            output: 30.00,
+        },
+    ),
+    (
+        "gpt-4",
+        ModelPrice {
+            input: 30.00,
+            output: 60.00,
+        },
+    ),
+    (
+        "gpt-3.5-turbo",
+        ModelPrice {
+            input: 0.50,
+            output: 1.50,
+        },
+    ),
+    (
+        "claude-3-5-haiku",
+        ModelPrice {
+            input: 0.80,
+            output: 4.00,
+        },
+    ),
+    (
+        "claude-3-5-sonnet",
+        ModelPrice {
+            input: 3.00,
+            output: 15.00,
+        },
+    ),
+    (
+        "claude-3-7-sonnet",
+        ModelPrice {
+            input: 3.00,
+            output: 15.00,
+        },
+    ),
+    (
+        "claude-sonnet-4",
+        ModelPrice {
+            input: 3.00,
+            output: 15.00,
+        },
+    ),
+    (
+        "claude-opus-4",
+        ModelPrice {
+            input: 15.00,
+            output: 75.00,
+        },
+    ),
+    (
+        "claude-3-opus",
+        ModelPrice {
+            input: 15.00,
+            output: 75.00,
+        },
+    ),
+    (
+        "claude-3-sonnet",
+        ModelPrice {
+            input: 3.00,
+            output: 15.00,
+        },
+    ),
+    (
+        "claude-3-haiku",
+        ModelPrice {
+            input: 0.25,
+            output: 1.25,
+        },
+    ),
+];
+
+/// Look up the price of a model
+pub fn price_for(model: &str) -> Option<ModelPrice> {
+    PRICES
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, price)| *price)
+}
+
+/// Estimate the cost of a request in USD, if the model's price is known
+pub fn estimate_cost(model: &str, usage: TokenUsage) -> Option<f64> {
+    price_for(model).map(|price| {
+        (usage.prompt_tokens as f64 * price.input + usage.completion_tokens as f64 * price.output)
+            / 1_000_000.0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_prefix_matching() {
+        assert_eq!(price_for("gpt-4o-mini-2024-07-18").unwrap().input, 0.15);
+        assert_eq!(price_for("gpt-4o-2024-08-06").unwrap().input, 2.50);
+        assert_eq!(price_for("claude-3-5-haiku-20241022").unwrap().output, 4.00);
+        assert!(price_for("mock-model-v1").is_none());
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        let usage = TokenUsage {
+            prompt_tokens: 1_000_000,
+            completion_tokens: 500_000,
+        };
+        let cost = estimate_cost("gpt-4o-mini", usage).unwrap();
+        assert!((cost - 0.45).abs() < 1e-9);
+    }
+}
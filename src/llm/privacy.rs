@@ -0,0 +1,80 @@
+//! Privacy filtering of LLM requests
+
+use crate::error::Result;
+use crate::llm::client::{Completion, LlmClient};
+use crate::privacy::PrivacyFilter;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Client wrapper that masks sensitive text in prompts and restores it in
+/// responses
+///
+/// A placeholder could be split across streamed tokens, so responses are
+/// never streamed through this client.
+pub struct PrivacyClient {
+    inner: Arc<dyn LlmClient>,
+    filter: PrivacyFilter,
+}
+
+impl PrivacyClient {
+    /// Wrap `inner`, masking its prompts with `filter`
+    pub fn new(inner: Arc<dyn LlmClient>, filter: PrivacyFilter) -> Self {
+        Self { inner, filter }
+    }
+}
+
+#[async_trait]
+impl LlmClient for PrivacyClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self.complete_with_usage(prompt).await?.text)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        let masked = self.filter.mask(prompt);
+        if !masked.replacements.is_empty() {
+            debug!(
+                "Masked {} sensitive values in the prompt",
+                masked.replacements.len()
+            );
+        }
+
+        let completion = self.inner.complete_with_usage(&masked.text).await?;
+        Ok(Completion {
+            text: masked.unmask(&completion.text),
+            ..completion
+        })
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PrivacyConfig;
+    use crate::llm::MockLlmClient;
+
+    #[tokio::test]
+    async fn test_restores_masked_text() {
+        // The mock echoes nothing of the prompt, so answer with a placeholder
+        let mut mock = MockLlmClient::new();
+        mock.set_default_response("Reply to [EMAIL_1] today");
+        let filter = PrivacyFilter::from_config(&PrivacyConfig::default()).unwrap();
+        let mock = Arc::new(mock);
+        let client = PrivacyClient::new(Arc::clone(&mock) as Arc<dyn LlmClient>, filter);
+
+        let response = client.complete("Write to bob@example.com").await.unwrap();
+        assert_eq!(response, "Reply to bob@example.com today");
+        assert!(!client.supports_streaming());
+
+        // The provider only saw the placeholder
+        assert_eq!(mock.prompts(), ["Write to [EMAIL_1]"]);
+    }
+}
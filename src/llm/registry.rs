@@ -0,0 +1,127 @@
+//! Provider clients shared across requests
+//!
+//! Building a provider client creates an HTTP client and looks up its API
+//! key, which may mean asking the Keychain. Batches, the server and the
+//! daemon send many requests with the same settings, so clients are built
+//! once per provider, model and parameters and then reused.
+
+use crate::config::LlmConfig;
+use crate::error::Result;
+use crate::llm::LlmClient;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use tracing::debug;
+
+/// Provider clients built so far, keyed by their serialized configuration
+#[derive(Default)]
+pub struct ClientRegistry {
+    clients: Mutex<HashMap<String, Arc<dyn LlmClient>>>,
+}
+
+impl ClientRegistry {
+    /// Registry shared by everything in this process
+    pub fn global() -> &'static Self {
+        static GLOBAL: OnceLock<ClientRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(Self::default)
+    }
+
+    /// The client for `llm`, built with `build` on first use
+    ///
+    /// Failures are not cached, so a missing API key is looked up again on
+    /// the next call. Clients are built without holding the lock, so a slow
+    /// Keychain prompt doesn't hold up requests for other providers; if two
+    /// requests build the same client at once, both get the first one
+    /// stored.
+    pub fn get(
+        &self,
+        llm: &LlmConfig,
+        build: impl FnOnce(&LlmConfig) -> Result<Arc<dyn LlmClient>>,
+    ) -> Result<Arc<dyn LlmClient>> {
+        let key = serde_json::to_string(llm)?;
+        if let Some(client) = self.lock().get(&key) {
+            return Ok(Arc::clone(client));
+        }
+
+        debug!("Creating client for {}/{}", llm.provider, llm.model);
+        let client = build(llm)?;
+        Ok(Arc::clone(self.lock().entry(key).or_insert(client)))
+    }
+
+    /// Forget all clients, so that they are built again with the current
+    /// configuration and API keys
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// The clients, even if another thread panicked while holding the lock
+    /// (entries are only ever added or removed whole)
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Arc<dyn LlmClient>>> {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::error::RephraserError;
+    use crate::llm::MockLlmClient;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_reuses_clients() {
+        let registry = ClientRegistry::default();
+        let built = AtomicUsize::new(0);
+        let build = |_: &LlmConfig| {
+            built.fetch_add(1, Ordering::SeqCst);
+            Ok(Arc::new(MockLlmClient::new()) as Arc<dyn LlmClient>)
+        };
+
+        let mut llm = Config::default().llm;
+        let first = registry.get(&llm, build).unwrap();
+        assert!(Arc::ptr_eq(&first, &registry.get(&llm, build).unwrap()));
+        assert_eq!(built.load(Ordering::SeqCst), 1);
+
+        // Any difference in the settings gets its own client
+        llm.parameters.temperature = 0.1;
+        assert!(!Arc::ptr_eq(&first, &registry.get(&llm, build).unwrap()));
+        assert_eq!(built.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_builds_outside_the_lock() {
+        let registry = ClientRegistry::default();
+        let mut other = Config::default().llm;
+        other.provider = "anthropic".to_string();
+        let mock = || Arc::new(MockLlmClient::new()) as Arc<dyn LlmClient>;
+
+        // Building one client may use the registry for another
+        let llm = Config::default().llm;
+        let client = registry
+            .get(&llm, |_| {
+                registry.get(&other, |_| Ok(mock()))?;
+                Ok(mock())
+            })
+            .unwrap();
+        assert!(Arc::ptr_eq(
+            &client,
+            &registry.get(&llm, |_| Ok(mock())).unwrap()
+        ));
+
+        registry.clear();
+        assert!(!Arc::ptr_eq(
+            &client,
+            &registry.get(&llm, |_| Ok(mock())).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_does_not_cache_failures() {
+        let registry = ClientRegistry::default();
+        let llm = Config::default().llm;
+        let failed = registry.get(&llm, |_| Err(RephraserError::Config("no key".to_string())));
+        assert!(failed.is_err());
+        let mock = Arc::new(MockLlmClient::new()) as Arc<dyn LlmClient>;
+        assert!(registry.get(&llm, |_| Ok(mock)).is_ok());
+    }
+}
@@ -0,0 +1,205 @@
+//! Actions implemented as Rhai scripts
+//!
+//! A script action's rendered prompt is handed to its script as `text`, and
+//! whatever the script evaluates to becomes the response. The script talks
+//! to the configured LLM through `llm`:
+//!
+//! ```rhai
+//! let summary = llm.complete("Summarize:\n" + text);
+//! if summary.len() > 200 {
+//!     summary = llm.run_action("shorten", summary);
+//! }
+//! summary
+//! ```
+
+use crate::actions::ActionResolver;
+use crate::config::manager::expand_home;
+use crate::config::Config;
+use crate::error::{RephraserError, Result};
+use crate::llm::client::{Completion, LlmClient, TokenUsage};
+use async_trait::async_trait;
+use rhai::{Dynamic, Engine, EvalAltResult, Scope};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+use tracing::{debug, info};
+
+/// Client that answers prompts by running a script
+///
+/// The script's own LLM requests go to `inner`, and their token usage is
+/// reported as the usage of the script run.
+pub struct ScriptClient {
+    inner: Arc<dyn LlmClient>,
+    source: String,
+    config: Arc<Config>,
+}
+
+/// The `llm` object scripts call
+#[derive(Clone)]
+struct ScriptLlm {
+    inner: Arc<dyn LlmClient>,
+    config: Arc<Config>,
+    runtime: Handle,
+    state: Arc<Mutex<RunState>>,
+}
+
+/// Bookkeeping for one script run
+#[derive(Default)]
+struct RunState {
+    calls: usize,
+    usage: TokenUsage,
+}
+
+impl ScriptClient {
+    /// Client running the script at `path` (a leading `~/` is expanded)
+    ///
+    /// `config` supplies the actions the script can run and the limits it
+    /// runs under.
+    ///
+    /// # Errors
+    /// * If the script cannot be read
+    pub fn new(inner: Arc<dyn LlmClient>, path: &str, config: &Config) -> Result<Self> {
+        let path = expand_home(path);
+        let source = std::fs::read_to_string(&path).map_err(|e| {
+            RephraserError::Config(format!("Failed to read script {}: {}", path.display(), e))
+        })?;
+        Ok(Self {
+            inner,
+            source,
+            config: Arc::new(config.clone()),
+        })
+    }
+}
+
+impl ScriptLlm {
+    /// Send a prompt on behalf of the script
+    fn complete(&mut self, prompt: &str) -> std::result::Result<String, Box<EvalAltResult>> {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            if state.calls >= self.config.scripting.max_llm_calls {
+                return Err(format!(
+                    "Script exceeded {} LLM requests",
+                    self.config.scripting.max_llm_calls
+                )
+                .into());
+            }
+            state.calls += 1;
+        }
+
+        let completion = self
+            .runtime
+            .block_on(self.inner.complete_with_usage(prompt))
+            .map_err(|e| e.to_string())?;
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).usage += completion.usage;
+        Ok(completion.text)
+    }
+
+    /// Run another action's prompt on `text`
+    fn run_action(
+        &mut self,
+        action: &str,
+        text: &str,
+    ) -> std::result::Result<String, Box<EvalAltResult>> {
+        let prompt = ActionResolver::new(&self.config)
+            .resolve(action, text)
+            .map_err(|e| e.to_string())?;
+        self.complete(&prompt)
+    }
+}
+
+/// Evaluate a script with `text` and `llm` in scope
+fn run_script(source: &str, text: &str, llm: ScriptLlm, max_operations: u64) -> Result<String> {
+    let mut engine = Engine::new();
+    engine.set_max_operations(max_operations);
+    // Scripts must not write to stdout, which carries results
+    engine.on_print(|message| info!("Script: {}", message));
+    engine.on_debug(|message, _, position| debug!("Script at {}: {}", position, message));
+    engine
+        .register_type_with_name::<ScriptLlm>("Llm")
+        .register_fn("complete", ScriptLlm::complete)
+        .register_fn("run_action", ScriptLlm::run_action);
+
+    let mut scope = Scope::new();
+    scope.push("text", text.to_string());
+    scope.push("llm", llm);
+
+    let result = engine
+        .eval_with_scope::<Dynamic>(&mut scope, source)
+        .map_err(|e| RephraserError::Other(format!("Script failed: {}", e)))?;
+    Ok(if result.is_string() {
+        result.into_string().unwrap_or_default()
+    } else {
+        result.to_string()
+    })
+}
+
+#[async_trait]
+impl LlmClient for ScriptClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        Ok(self.complete_with_usage(prompt).await?.text)
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        let state = Arc::new(Mutex::new(RunState::default()));
+        let llm = ScriptLlm {
+            inner: Arc::clone(&self.inner),
+            config: Arc::clone(&self.config),
+            runtime: Handle::current(),
+            state: Arc::clone(&state),
+        };
+        let source = self.source.clone();
+        let text = prompt.to_string();
+        let max_operations = self.config.scripting.max_operations;
+
+        // The script blocks on its LLM requests, so keep it off the runtime
+        let text =
+            tokio::task::spawn_blocking(move || run_script(&source, &text, llm, max_operations))
+                .await
+                .map_err(|e| RephraserError::Other(format!("Script task failed: {}", e)))??;
+
+        let usage = state.lock().unwrap_or_else(|e| e.into_inner()).usage;
+        Ok(Completion::new(text, usage))
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLlmClient;
+
+    fn script_client(source: &str) -> ScriptClient {
+        let mut mock = MockLlmClient::new();
+        mock.set_default_response("reply");
+        ScriptClient {
+            inner: Arc::new(mock),
+            source: source.to_string(),
+            config: Arc::new(Config::default()),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_script_calls_llm() {
+        let client =
+            script_client(r#"let a = llm.complete(text); a + " / " + llm.complete("again")"#);
+        let completion = client.complete_with_usage("hello").await.unwrap();
+        assert_eq!(completion.text, "reply / reply");
+        assert!(completion.usage.completion_tokens > 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_limits() {
+        let client = script_client(r#"loop { llm.complete(text); }"#);
+        let err = client.complete("hello").await.unwrap_err();
+        assert!(err.to_string().contains("LLM requests"));
+
+        let client = script_client("loop {}");
+        assert!(client.complete("hello").await.is_err());
+    }
+}
@@ -0,0 +1,62 @@
+//! Server-sent events parsing for streaming responses
+
+/// Incremental parser for `text/event-stream` bodies
+///
+/// Feed raw chunks as they arrive; complete `data:` payloads are returned
+/// once their line has been fully received.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    /// Create a new parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk and return the data payloads it completes
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut payloads = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if let Some(data) = line.strip_prefix("data:") {
+                payloads.push(data.trim_start().to_string());
+            }
+        }
+
+        payloads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_lines() {
+        let mut parser = SseParser::new();
+        let payloads = parser.push(b"event: ping\ndata: {\"a\":1}\n\ndata: [DONE]\n");
+        assert_eq!(payloads, vec!["{\"a\":1}", "[DONE]"]);
+    }
+
+    #[test]
+    fn test_split_across_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b"data: hel").is_empty());
+        assert_eq!(parser.push(b"lo\r\n"), vec!["hello"]);
+    }
+
+    #[test]
+    fn test_split_multibyte_character() {
+        let bytes = "data: こんにちは\n".as_bytes();
+        let mut parser = SseParser::new();
+        assert!(parser.push(&bytes[..8]).is_empty());
+        assert_eq!(parser.push(&bytes[8..]), vec!["こんにちは"]);
+    }
+}
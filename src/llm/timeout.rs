@@ -0,0 +1,85 @@
+//! Time limit for LLM requests
+
+use crate::error::{RephraserError, Result};
+use crate::llm::client::{Completion, LlmClient};
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Client wrapper that cancels requests taking longer than a time limit
+///
+/// The limit applies to each request on its own, so a guardrail retry gets
+/// a fresh allowance.
+pub struct TimeoutClient {
+    inner: Arc<dyn LlmClient>,
+    timeout: Duration,
+}
+
+impl TimeoutClient {
+    /// Wrap `inner`, failing its requests with [`RephraserError::Timeout`]
+    /// after `timeout`
+    pub fn new(inner: Arc<dyn LlmClient>, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// Run a request, dropping (and thereby cancelling) it at the time limit
+    async fn limit<T>(&self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::time::timeout(self.timeout, request)
+            .await
+            .map_err(|_| RephraserError::Timeout(self.timeout))?
+    }
+}
+
+#[async_trait]
+impl LlmClient for TimeoutClient {
+    async fn complete(&self, prompt: &str) -> Result<String> {
+        self.limit(self.inner.complete(prompt)).await
+    }
+
+    async fn complete_with_usage(&self, prompt: &str) -> Result<Completion> {
+        self.limit(self.inner.complete_with_usage(prompt)).await
+    }
+
+    async fn complete_stream(
+        &self,
+        prompt: &str,
+        on_token: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<Completion> {
+        self.limit(self.inner.complete_stream(prompt, on_token))
+            .await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn provider_name(&self) -> &str {
+        self.inner.provider_name()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::MockLlmClient;
+
+    #[tokio::test]
+    async fn test_request_within_limit() {
+        let client = TimeoutClient::new(Arc::new(MockLlmClient::new()), Duration::from_secs(5));
+        assert!(client.complete("hello").await.is_ok());
+        assert_eq!(client.provider_name(), "mock");
+    }
+
+    #[tokio::test]
+    async fn test_request_over_limit() {
+        // The mock client takes 100ms per request
+        let client = TimeoutClient::new(Arc::new(MockLlmClient::new()), Duration::from_millis(10));
+        let err = client.complete_with_usage("hello").await.unwrap_err();
+        assert!(matches!(err, RephraserError::Timeout(_)));
+    }
+}
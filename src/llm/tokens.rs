@@ -0,0 +1,26 @@
+//! Rough token count estimation
+//!
+//! Tokenizers differ between providers, so this is only an approximation:
+//! about four characters per token for ASCII text and one token per
+//! character for other scripts (Japanese text is usually close to that).
+
+/// Estimate the number of tokens in a text
+pub fn estimate_tokens(text: &str) -> usize {
+    let ascii = text.chars().filter(char::is_ascii).count();
+    let other = text.chars().count() - ascii;
+
+    ascii.div_ceil(4) + other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("hello world!"), 3);
+        assert_eq!(estimate_tokens("こんにちは"), 5);
+        assert_eq!(estimate_tokens("Hi こんにちは"), 6);
+    }
+}
@@ -0,0 +1,264 @@
+//! Diagnostic logging
+//!
+//! Logs go to stderr so they never mix with results printed on stdout.
+//! `RUST_LOG` takes precedence over the verbosity flags.
+//!
+//! Hotkey scripts, the daemon and the menu bar run without a terminal, so
+//! logs are also appended to `~/.rephraser/logs/rephraser.log` at the level
+//! configured under `[logging]`. The file is rotated once it grows past a
+//! size limit, keeping a few previous files as `rephraser.log.1`, `.2`, ...
+
+use crate::config::{ConfigManager, LoggingConfig};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Name of the current log file
+pub const LOG_FILE_NAME: &str = "rephraser.log";
+
+/// Target of command failures, which are printed on stderr by `main` and
+/// therefore only logged to the file
+const FAILURE_TARGET: &str = "rephraser_failure";
+
+/// Install the global tracing subscriber
+///
+/// # Arguments
+/// * `verbosity` - Number of `-v` flags: 0 shows warnings, 1 adds debug
+///   messages, 2 or more adds trace messages
+pub fn init(verbosity: u8) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(level_directive(verbosity)))
+        .add_directive(
+            format!("{}=off", FAILURE_TARGET)
+                .parse()
+                .expect("failure directive is valid"),
+        );
+
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_target(false)
+        .without_time()
+        .with_filter(filter);
+
+    let _ = tracing_subscriber::registry()
+        .with(stderr)
+        .with(file_layer())
+        .try_init();
+}
+
+/// Record the error a command failed with in the log file
+pub fn log_failure(error: &dyn std::fmt::Display) {
+    tracing::error!(target: FAILURE_TARGET, "{}", error);
+}
+
+/// Layer appending to the log file, if enabled
+///
+/// Problems with the configuration or the log directory are reported on
+/// stderr and disable only the file.
+fn file_layer<S>() -> Option<impl Layer<S>>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    let config_manager = ConfigManager::new().ok()?;
+    // Errors in the config are reported by the command itself
+    let config = config_manager.load().map(|c| c.logging).unwrap_or_default();
+    if !config.file {
+        return None;
+    }
+
+    let filter = match EnvFilter::try_new(file_directive(&config)) {
+        Ok(filter) => filter,
+        Err(e) => {
+            eprintln!(
+                "Warning: invalid [logging] level, not writing a log file: {}",
+                e
+            );
+            return None;
+        }
+    };
+    let writer = match RollingFile::open(
+        &config_manager.log_dir(),
+        config.max_size_mb.saturating_mul(1024 * 1024),
+        config.max_files,
+    ) {
+        Ok(writer) => writer,
+        Err(e) => {
+            eprintln!("Warning: failed to open the log file: {}", e);
+            return None;
+        }
+    };
+
+    Some(
+        tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(false)
+            .with_filter(filter),
+    )
+}
+
+/// Filter directive for a verbosity level (dependencies stay at warn)
+fn level_directive(verbosity: u8) -> &'static str {
+    match verbosity {
+        0 => "warn",
+        1 => "warn,rephraser=debug",
+        _ => "warn,rephraser=trace",
+    }
+}
+
+/// Filter directive for the log file (dependencies stay at warn)
+fn file_directive(config: &LoggingConfig) -> String {
+    let mut directive = format!("warn,rephraser={}", config.level);
+    for (module, level) in &config.modules {
+        directive.push_str(&format!(",{}={}", module, level));
+    }
+    directive
+}
+
+/// Log file that is rotated once it exceeds a size limit
+///
+/// Several processes may append to the same file; each event is written
+/// with a single call, so lines don't interleave.
+#[derive(Clone)]
+pub struct RollingFile {
+    state: Arc<Mutex<RollingState>>,
+}
+
+struct RollingState {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RollingFile {
+    /// Append to `rephraser.log` in `dir`, rotating it beyond `max_bytes`
+    /// and keeping `max_files` previous files
+    pub fn open(dir: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOG_FILE_NAME);
+        let file = open_append(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            state: Arc::new(Mutex::new(RollingState {
+                path,
+                file,
+                size,
+                max_bytes,
+                max_files,
+            })),
+        })
+    }
+}
+
+impl RollingState {
+    /// Shift `rephraser.log.N` up by one, dropping the oldest, and start a
+    /// new current file
+    fn rotate(&mut self) -> io::Result<()> {
+        let numbered = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+
+        if self.max_files == 0 {
+            let _ = fs::remove_file(&self.path);
+        } else {
+            let _ = fs::remove_file(numbered(self.max_files));
+            for n in (1..self.max_files).rev() {
+                let _ = fs::rename(numbered(n), numbered(n + 1));
+            }
+            fs::rename(&self.path, numbered(1))?;
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RollingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.size > 0 && state.size + buf.len() as u64 > state.max_bytes {
+            state.rotate()?;
+        }
+        state.file.write_all(buf)?;
+        state.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.file.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RollingFile {
+    type Writer = RollingFile;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Open a log file for appending, readable only by the user since log
+/// messages may quote prompts
+fn open_append(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_level_directive() {
+        assert_eq!(level_directive(0), "warn");
+        assert_eq!(level_directive(1), "warn,rephraser=debug");
+        assert_eq!(level_directive(5), "warn,rephraser=trace");
+    }
+
+    #[test]
+    fn test_file_directive() {
+        let config = LoggingConfig {
+            level: "info".to_string(),
+            modules: BTreeMap::from([("rephraser::daemon".to_string(), "trace".to_string())]),
+            ..LoggingConfig::default()
+        };
+        assert_eq!(
+            file_directive(&config),
+            "warn,rephraser=info,rephraser::daemon=trace"
+        );
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = std::env::temp_dir().join(format!("rephraser-logs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut log = RollingFile::open(&dir, 10, 2).unwrap();
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read(LOG_FILE_NAME), "fourth\n");
+        assert_eq!(read("rephraser.log.1"), "third\n");
+        assert_eq!(read("rephraser.log.2"), "second\n");
+        assert!(!dir.join("rephraser.log.3").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -1,24 +1,327 @@
 use clap::Parser;
-use rephraser::cli::{Cli, Commands, ConfigCommands};
+use rephraser::cli::{
+    rephrase_actions, ActionCommands, AgentCommands, CacheCommands, Cli, Commands, ConfigCommands,
+    HistoryCommands, LogsCommands, OutputFormat, QueueCommands, RaycastCommands, ScheduleCommands,
+    SecretsCommands, UrlHandlerCommands,
+};
+use rephraser::config::OutputMethod;
 use rephraser::error::Result;
+use std::io::IsTerminal;
+use std::time::Duration;
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let cli = Cli::parse();
+    rephraser::logging::init(cli.global.verbose);
+    rephraser::i18n::init();
 
+    // `version --check` does its own check
+    let update_check = if matches!(cli.command, Commands::Version { .. }) {
+        None
+    } else {
+        rephraser::cli::commands::spawn_update_check(&cli.global)
+    };
+
+    if let Err(e) = run(cli).await {
+        rephraser::logging::log_failure(&e);
+        eprintln!("{}", rephraser::t!("error", error = e));
+        if let Some(hint) = rephraser::i18n::hint(e.code()) {
+            eprintln!("{}", rephraser::t!("error-hint", hint = hint));
+        }
+        std::process::exit(e.exit_code());
+    }
+
+    if let Some(update_check) = update_check {
+        update_check.finish().await;
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
     match cli.command {
-        Commands::Rephrase { action, text } => {
-            rephraser::cli::commands::rephrase(&action, &text).await?;
+        Commands::Rephrase {
+            action,
+            actions,
+            mut input,
+            llm,
+            edit,
+            output,
+            dry_run,
+            json,
+            format,
+        } => {
+            let stdin_piped = !std::io::stdin().is_terminal();
+            let actions = rephrase_actions(action, actions, &mut input, stdin_piped);
+            let output = if edit {
+                Some(OutputMethod::Editor)
+            } else {
+                output
+            };
+            if dry_run {
+                rephraser::cli::commands::dry_run(&actions, &input, &llm).await?;
+            } else if json {
+                rephraser::cli::commands::rephrase_json(&cli.global, &actions, &input, &llm)
+                    .await?;
+            } else if let Some(format) = format {
+                match format {
+                    OutputFormat::Raycast => {
+                        rephraser::cli::commands::rephrase_raycast(
+                            &cli.global,
+                            &actions,
+                            &input,
+                            &llm,
+                        )
+                        .await?;
+                    }
+                    OutputFormat::Alfred => {
+                        rephraser::cli::commands::rephrase_alfred(
+                            &cli.global,
+                            &actions,
+                            &input,
+                            &llm,
+                        )
+                        .await?;
+                    }
+                }
+            } else if let [action] = actions.as_slice() {
+                rephraser::cli::commands::rephrase(&cli.global, action, &input, &llm, output)
+                    .await?;
+            } else {
+                rephraser::cli::commands::rephrase_many(
+                    &cli.global,
+                    &actions,
+                    &input,
+                    &llm,
+                    output,
+                )
+                .await?;
+            }
+        }
+        Commands::Selection { action } => {
+            rephraser::cli::commands::selection(&cli.global, &action).await?;
+        }
+        Commands::Prompt {
+            input,
+            llm,
+            edit,
+            output,
+        } => {
+            let output = if edit {
+                Some(OutputMethod::Editor)
+            } else {
+                output
+            };
+            rephraser::cli::commands::prompt(&cli.global, &input, &llm, output).await?;
+        }
+        Commands::Proofread {
+            input,
+            llm,
+            format,
+            output,
+        } => {
+            rephraser::cli::commands::proofread(&cli.global, &input, &llm, format, output).await?;
+        }
+        Commands::Compare {
+            action,
+            input,
+            models,
+        } => {
+            rephraser::cli::commands::compare(&cli.global, &action, &input, &models).await?;
+        }
+        Commands::Auto {
+            input,
+            llm,
+            edit,
+            output,
+        } => {
+            let output = if edit {
+                Some(OutputMethod::Editor)
+            } else {
+                output
+            };
+            rephraser::cli::commands::auto(&cli.global, &input, &llm, output).await?;
+        }
+        Commands::Watch {
+            action,
+            file,
+            output_file,
+            debounce,
+            llm,
+        } => {
+            rephraser::cli::commands::watch(
+                &cli.global,
+                &action,
+                &file,
+                output_file.as_deref(),
+                Duration::from_millis(debounce),
+                &llm,
+            )
+            .await?;
+        }
+        Commands::Daemon => {
+            rephraser::cli::commands::daemon(&cli.global).await?;
+        }
+        Commands::Serve { host, port } => {
+            rephraser::cli::commands::serve(&cli.global, host.as_deref(), port).await?;
+        }
+        Commands::RephraseFiles {
+            action,
+            files,
+            notify,
+            jobs,
+            llm,
+        } => {
+            rephraser::cli::commands::rephrase_files(
+                &cli.global,
+                &action,
+                &files,
+                jobs,
+                &llm,
+                notify,
+            )
+            .await?;
+        }
+        Commands::Batch {
+            action,
+            file,
+            jobs,
+            llm,
+        } => {
+            rephraser::cli::commands::batch(&cli.global, &action, file.as_deref(), jobs, &llm)
+                .await?;
+        }
+        Commands::WatchClipboard => {
+            rephraser::cli::commands::watch_clipboard(&cli.global).await?;
+        }
+        Commands::MenuBar => {
+            rephraser::cli::commands::menu_bar(&cli.global).await?;
+        }
+        Commands::Mcp => {
+            rephraser::cli::commands::mcp().await?;
+        }
+        Commands::Raycast { subcommand } => match subcommand {
+            RaycastCommands::Generate { dir } => {
+                rephraser::cli::commands::raycast_generate(&cli.global, dir.as_deref()).await?;
+            }
+        },
+        Commands::Shortcut => {
+            rephraser::cli::commands::shortcut(&cli.global).await?;
+        }
+        Commands::OpenUrl { url } => {
+            rephraser::cli::commands::open_url(&cli.global, &url).await?;
+        }
+        Commands::UrlHandler { subcommand } => match subcommand {
+            UrlHandlerCommands::Install => {
+                rephraser::cli::commands::url_handler_install(&cli.global).await?;
+            }
+            UrlHandlerCommands::Uninstall => {
+                rephraser::cli::commands::url_handler_uninstall(&cli.global).await?;
+            }
+        },
+        Commands::InstallQuickAction { action } => {
+            rephraser::cli::commands::install_quick_action(&cli.global, &action).await?;
+        }
+        Commands::Agent { subcommand } => match subcommand {
+            AgentCommands::Install { menu_bar, env } => {
+                rephraser::cli::commands::agent_install(&cli.global, menu_bar, &env).await?;
+            }
+            AgentCommands::Uninstall => {
+                rephraser::cli::commands::agent_uninstall(&cli.global).await?;
+            }
+            AgentCommands::Status => {
+                rephraser::cli::commands::agent_status().await?;
+            }
+        },
+        Commands::Redo {
+            action,
+            llm,
+            output,
+        } => {
+            rephraser::cli::commands::redo(&cli.global, action.as_deref(), &llm, output).await?;
+        }
+        Commands::Undo { paste } => {
+            rephraser::cli::commands::undo(&cli.global, paste).await?;
+        }
+        Commands::History { subcommand, limit } => match subcommand {
+            None => {
+                rephraser::cli::commands::history(&cli.global, limit).await?;
+            }
+            Some(HistoryCommands::Search { query, limit }) => {
+                rephraser::cli::commands::history_search(&cli.global, &query.join(" "), limit)
+                    .await?;
+            }
+            Some(HistoryCommands::Show { id }) => {
+                rephraser::cli::commands::history_show(id).await?;
+            }
+            Some(HistoryCommands::Copy { id }) => {
+                rephraser::cli::commands::history_copy(&cli.global, id).await?;
+            }
+        },
+        Commands::Cost { since, tag, by } => {
+            rephraser::cli::commands::cost(since.as_deref(), tag.as_deref(), by).await?;
+        }
+        Commands::Stats { since, by } => {
+            rephraser::cli::commands::stats(since.as_deref(), by).await?;
+        }
+        Commands::ListActions { long, json, format } => {
+            rephraser::cli::commands::list_actions(&cli.global, long, json, format).await?;
+        }
+        Commands::Logs { subcommand } => match subcommand {
+            LogsCommands::Tail { lines, follow } => {
+                rephraser::cli::commands::logs_tail(lines, follow).await?;
+            }
+        },
+        Commands::Cache { subcommand } => match subcommand {
+            CacheCommands::Stats => {
+                rephraser::cli::commands::cache_stats().await?;
+            }
+            CacheCommands::Clear => {
+                rephraser::cli::commands::cache_clear(&cli.global).await?;
+            }
+        },
+        Commands::Secrets { subcommand } => match subcommand {
+            SecretsCommands::Set { name } => {
+                rephraser::cli::commands::secrets_set(&cli.global, &name).await?;
+            }
+        },
+        Commands::Queue { subcommand } => match subcommand {
+            QueueCommands::List => {
+                rephraser::cli::commands::queue_list().await?;
+            }
+            QueueCommands::Run => {
+                rephraser::cli::commands::queue_run(&cli.global).await?;
+            }
+            QueueCommands::Clear => {
+                rephraser::cli::commands::queue_clear(&cli.global).await?;
+            }
+        },
+        Commands::Schedule { subcommand } => match subcommand {
+            ScheduleCommands::List => {
+                rephraser::cli::commands::schedule_list().await?;
+            }
+            ScheduleCommands::Run { name } => {
+                rephraser::cli::commands::schedule_run(&cli.global, &name).await?;
+            }
+        },
+        Commands::Action { subcommand } => match subcommand {
+            ActionCommands::Browse => {
+                rephraser::cli::commands::action_browse(&cli.global).await?;
+            }
+            ActionCommands::Install { name } => {
+                rephraser::cli::commands::action_install(&cli.global, &name).await?;
+            }
+        },
+        Commands::Doctor => {
+            rephraser::cli::commands::doctor().await?;
         }
-        Commands::ListActions => {
-            rephraser::cli::commands::list_actions().await?;
+        Commands::Version { check } => {
+            rephraser::cli::commands::version(&cli.global, check).await?;
         }
         Commands::Config { subcommand } => match subcommand {
             ConfigCommands::Init => {
-                rephraser::cli::commands::config_init().await?;
+                rephraser::cli::commands::config_init(&cli.global).await?;
             }
             ConfigCommands::Show => {
-                rephraser::cli::commands::config_show().await?;
+                rephraser::cli::commands::config_show(&cli.global).await?;
             }
             ConfigCommands::Set { key, value } => {
                 rephraser::cli::commands::config_set(&key, &value).await?;
@@ -0,0 +1,203 @@
+//! Menu bar status item (macOS only, `menu-bar` feature)
+//!
+//! Shows the configured actions, recent results and a pause toggle in an
+//! NSStatusItem. What the items do is left to a [`MenuBarHandler`].
+
+use crate::error::{RephraserError, Result};
+use objc2::rc::Retained;
+use objc2::runtime::ProtocolObject;
+use objc2::{define_class, msg_send, sel, DefinedClass, MainThreadMarker, MainThreadOnly};
+use objc2_app_kit::{
+    NSApplication, NSApplicationActivationPolicy, NSControlStateValueOff, NSControlStateValueOn,
+    NSMenu, NSMenuDelegate, NSMenuItem, NSStatusBar, NSVariableStatusItemLength,
+};
+use objc2_foundation::{NSObject, NSObjectProtocol, NSString};
+use std::cell::RefCell;
+
+/// Title shown in the menu bar
+const STATUS_TITLE: &str = "✎";
+
+/// Behavior behind the menu items, called on the main thread
+pub trait MenuBarHandler {
+    /// Actions to list, as (name, display name)
+    fn actions(&self) -> Vec<(String, String)>;
+
+    /// Recent results to list, as (label, full output)
+    fn recent(&self) -> Vec<(String, String)>;
+
+    /// Run an action; must not block
+    fn run_action(&self, name: &str);
+
+    /// Copy a recent result to the clipboard
+    fn copy(&self, text: &str);
+
+    fn is_paused(&self) -> bool;
+
+    fn set_paused(&self, paused: bool);
+}
+
+/// What a menu item does when clicked
+enum Command {
+    RunAction(String),
+    Copy(String),
+    TogglePause,
+    Quit,
+}
+
+struct Ivars {
+    handler: Box<dyn MenuBarHandler>,
+    /// Commands of the current menu items, indexed by item tag
+    commands: RefCell<Vec<Command>>,
+}
+
+define_class!(
+    #[unsafe(super(NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[name = "RephraserMenuController"]
+    #[ivars = Ivars]
+    struct MenuController;
+
+    unsafe impl NSObjectProtocol for MenuController {}
+
+    unsafe impl NSMenuDelegate for MenuController {
+        // Rebuilt every time the menu opens, so recent results are current
+        #[unsafe(method(menuNeedsUpdate:))]
+        fn menu_needs_update(&self, menu: &NSMenu) {
+            self.rebuild(menu);
+        }
+    }
+
+    impl MenuController {
+        #[unsafe(method(itemClicked:))]
+        fn item_clicked(&self, sender: &NSMenuItem) {
+            let commands = self.ivars().commands.borrow();
+            let Some(command) = usize::try_from(sender.tag()).ok().and_then(|i| commands.get(i))
+            else {
+                return;
+            };
+
+            let handler = &self.ivars().handler;
+            match command {
+                Command::RunAction(name) => handler.run_action(name),
+                Command::Copy(text) => handler.copy(text),
+                Command::TogglePause => handler.set_paused(!handler.is_paused()),
+                Command::Quit => NSApplication::sharedApplication(self.mtm()).terminate(None),
+            }
+        }
+    }
+);
+
+impl MenuController {
+    fn new(mtm: MainThreadMarker, handler: Box<dyn MenuBarHandler>) -> Retained<Self> {
+        let this = Self::alloc(mtm).set_ivars(Ivars {
+            handler,
+            commands: RefCell::default(),
+        });
+        unsafe { msg_send![super(this), init] }
+    }
+
+    /// Replace the menu's items with the current actions and history
+    fn rebuild(&self, menu: &NSMenu) {
+        let mtm = self.mtm();
+        let handler = &self.ivars().handler;
+        let paused = handler.is_paused();
+        let mut commands = Vec::new();
+
+        menu.removeAllItems();
+
+        menu.addItem(&heading(mtm, "Actions"));
+        for (name, display_name) in handler.actions() {
+            let item = self.item(&display_name, Command::RunAction(name), &mut commands);
+            item.setEnabled(!paused);
+            menu.addItem(&item);
+        }
+
+        let recent = handler.recent();
+        if !recent.is_empty() {
+            menu.addItem(&NSMenuItem::separatorItem(mtm));
+            menu.addItem(&heading(mtm, "Recent"));
+            for (label, output) in recent {
+                menu.addItem(&self.item(&label, Command::Copy(output), &mut commands));
+            }
+        }
+
+        menu.addItem(&NSMenuItem::separatorItem(mtm));
+        let pause = self.item("Pause", Command::TogglePause, &mut commands);
+        pause.setState(if paused {
+            NSControlStateValueOn
+        } else {
+            NSControlStateValueOff
+        });
+        menu.addItem(&pause);
+        menu.addItem(&self.item("Quit Rephraser", Command::Quit, &mut commands));
+
+        *self.ivars().commands.borrow_mut() = commands;
+    }
+
+    /// A clickable item running `command`
+    fn item(
+        &self,
+        title: &str,
+        command: Command,
+        commands: &mut Vec<Command>,
+    ) -> Retained<NSMenuItem> {
+        let item = unsafe {
+            NSMenuItem::initWithTitle_action_keyEquivalent(
+                NSMenuItem::alloc(self.mtm()),
+                &NSString::from_str(title),
+                Some(sel!(itemClicked:)),
+                &NSString::from_str(""),
+            )
+        };
+        unsafe { item.setTarget(Some(self)) };
+        item.setTag(commands.len() as isize);
+        commands.push(command);
+        item
+    }
+}
+
+/// A disabled item labeling a section
+fn heading(mtm: MainThreadMarker, title: &str) -> Retained<NSMenuItem> {
+    let item = unsafe {
+        NSMenuItem::initWithTitle_action_keyEquivalent(
+            NSMenuItem::alloc(mtm),
+            &NSString::from_str(title),
+            None,
+            &NSString::from_str(""),
+        )
+    };
+    item.setEnabled(false);
+    item
+}
+
+/// Show the status item and run the application event loop
+///
+/// Blocks until "Quit" is chosen, which exits the process.
+///
+/// # Errors
+/// Returns an error if not called on the main thread
+pub fn run(handler: Box<dyn MenuBarHandler>) -> Result<()> {
+    let mtm = MainThreadMarker::new().ok_or_else(|| {
+        RephraserError::Other("The menu bar must run on the main thread".to_string())
+    })?;
+
+    let app = NSApplication::sharedApplication(mtm);
+    // No Dock icon or app menu, only the status item
+    app.setActivationPolicy(NSApplicationActivationPolicy::Accessory);
+
+    let controller = MenuController::new(mtm, handler);
+    let menu = NSMenu::new(mtm);
+    menu.setAutoenablesItems(false);
+    menu.setDelegate(Some(ProtocolObject::from_ref(&*controller)));
+    controller.rebuild(&menu);
+
+    let status_item =
+        NSStatusBar::systemStatusBar().statusItemWithLength(NSVariableStatusItemLength);
+    if let Some(button) = status_item.button(mtm) {
+        button.setTitle(&NSString::from_str(STATUS_TITLE));
+    }
+    status_item.setMenu(Some(&menu));
+
+    app.run();
+    Ok(())
+}
@@ -0,0 +1,9 @@
+//! Request count and latency metrics
+//!
+//! Opt-in counterpart to usage tracking for dashboards: totals are kept in
+//! a JSON file and can be exported in the Prometheus text format, either to
+//! a file or from `GET /metrics` in server mode.
+
+pub mod store;
+
+pub use store::{Metrics, MetricsStore, Series};
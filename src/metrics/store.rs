@@ -0,0 +1,242 @@
+//! JSON-backed metrics store
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Upper bounds of the latency histogram buckets, in seconds
+pub const LATENCY_BUCKETS: [f64; 9] = [0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 30.0, 60.0];
+
+/// All recorded metrics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub series: Vec<Series>,
+}
+
+/// Metrics of one provider and action
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Series {
+    pub provider: String,
+    pub action: String,
+    pub requests: u64,
+    pub errors: u64,
+    /// Requests per latency bucket (not cumulative), with one more entry
+    /// than [`LATENCY_BUCKETS`] for slower requests
+    pub latency_buckets: Vec<u64>,
+    pub latency_sum_secs: f64,
+}
+
+impl Metrics {
+    /// Count a request
+    pub fn record(&mut self, provider: &str, action: &str, duration: Duration, success: bool) {
+        let index = match self
+            .series
+            .iter()
+            .position(|s| s.provider == provider && s.action == action)
+        {
+            Some(index) => index,
+            None => {
+                self.series.push(Series {
+                    provider: provider.to_string(),
+                    action: action.to_string(),
+                    ..Series::default()
+                });
+                self.series.len() - 1
+            }
+        };
+
+        let series = &mut self.series[index];
+        let secs = duration.as_secs_f64();
+        series.requests += 1;
+        if !success {
+            series.errors += 1;
+        }
+        series.latency_buckets.resize(LATENCY_BUCKETS.len() + 1, 0);
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| secs <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        series.latency_buckets[bucket] += 1;
+        series.latency_sum_secs += secs;
+    }
+
+    /// Render in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut text = String::new();
+        let labels = |series: &Series| {
+            format!(
+                "provider=\"{}\",action=\"{}\"",
+                escape_label(&series.provider),
+                escape_label(&series.action)
+            )
+        };
+
+        text.push_str("# HELP rephraser_requests_total LLM requests.\n");
+        text.push_str("# TYPE rephraser_requests_total counter\n");
+        for series in &self.series {
+            let _ = writeln!(
+                text,
+                "rephraser_requests_total{{{}}} {}",
+                labels(series),
+                series.requests
+            );
+        }
+
+        text.push_str("# HELP rephraser_request_errors_total Failed LLM requests.\n");
+        text.push_str("# TYPE rephraser_request_errors_total counter\n");
+        for series in &self.series {
+            let _ = writeln!(
+                text,
+                "rephraser_request_errors_total{{{}}} {}",
+                labels(series),
+                series.errors
+            );
+        }
+
+        text.push_str("# HELP rephraser_request_duration_seconds LLM request latency.\n");
+        text.push_str("# TYPE rephraser_request_duration_seconds histogram\n");
+        for series in &self.series {
+            let labels = labels(series);
+            let mut cumulative = 0;
+            for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+                cumulative += series.latency_buckets.get(i).copied().unwrap_or(0);
+                let _ = writeln!(
+                    text,
+                    "rephraser_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                text,
+                "rephraser_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, series.requests
+            );
+            let _ = writeln!(
+                text,
+                "rephraser_request_duration_seconds_sum{{{}}} {}",
+                labels, series.latency_sum_secs
+            );
+            let _ = writeln!(
+                text,
+                "rephraser_request_duration_seconds_count{{{}}} {}",
+                labels, series.requests
+            );
+        }
+        text
+    }
+}
+
+/// Escape a label value for the Prometheus text format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Metrics saved in a JSON file
+pub struct MetricsStore {
+    path: PathBuf,
+}
+
+impl MetricsStore {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// The saved metrics (empty if nothing has been recorded)
+    pub fn load(&self) -> Metrics {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Count a request and save the updated metrics
+    ///
+    /// Returns the updated metrics.
+    ///
+    /// # Errors
+    /// * If the file could not be written
+    pub fn record(
+        &self,
+        provider: &str,
+        action: &str,
+        duration: Duration,
+        success: bool,
+    ) -> Result<Metrics> {
+        let mut metrics = self.load();
+        metrics.record(provider, action, duration, success);
+        write_atomically(&self.path, &serde_json::to_string(&metrics)?)?;
+        Ok(metrics)
+    }
+}
+
+/// Replace `path` with `content` so readers never see a partial file
+///
+/// # Errors
+/// * If the file could not be written
+pub fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // The temporary file must be on the same file system for the rename
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_render() {
+        let mut metrics = Metrics::default();
+        metrics.record("openai", "polite", Duration::from_millis(300), true);
+        metrics.record("openai", "polite", Duration::from_secs(90), false);
+        metrics.record("anthropic", "say \"hi\"", Duration::from_millis(100), true);
+
+        let polite = &metrics.series[0];
+        assert_eq!((polite.requests, polite.errors), (2, 1));
+        assert_eq!(polite.latency_buckets[1], 1);
+        assert_eq!(polite.latency_buckets[LATENCY_BUCKETS.len()], 1);
+
+        let text = metrics.to_prometheus();
+        assert!(text.contains(r#"rephraser_requests_total{provider="openai",action="polite"} 2"#));
+        assert!(text.contains(
+            r#"rephraser_request_duration_seconds_bucket{provider="openai",action="polite",le="0.25"} 0"#
+        ));
+        assert!(text.contains(
+            r#"rephraser_request_duration_seconds_bucket{provider="openai",action="polite",le="60"} 1"#
+        ));
+        assert!(text.contains(
+            r#"rephraser_request_duration_seconds_bucket{provider="openai",action="polite",le="+Inf"} 2"#
+        ));
+        assert!(text.contains(r#"action="say \"hi\"""#));
+    }
+
+    #[test]
+    fn test_store_accumulates() {
+        let dir = std::env::temp_dir().join(format!("rephraser-metrics-{}", std::process::id()));
+        let store = MetricsStore::new(&dir.join("metrics.json"));
+
+        store
+            .record("mock", "polite", Duration::from_secs(1), true)
+            .unwrap();
+        let metrics = store
+            .record("mock", "polite", Duration::from_secs(1), true)
+            .unwrap();
+        assert_eq!(metrics.series[0].requests, 2);
+        assert_eq!(store.load(), metrics);
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,118 @@
+//! Clipboard access
+//!
+//! By default the clipboard is accessed through the platform's clipboard
+//! commands (`pbcopy`/`pbpaste` on macOS). With the `native-clipboard`
+//! feature, the OS clipboard API (NSPasteboard on macOS) is used directly,
+//! avoiding a subprocess per call.
+
+use crate::error::Result;
+
+/// Write text to the clipboard
+///
+/// # Errors
+/// Returns an error if the clipboard could not be written
+pub fn write_text(text: &str) -> Result<()> {
+    backend::write_text(text)
+}
+
+/// Write HTML and plain-text flavors to the clipboard
+///
+/// Apps that understand rich text (Mail, Notes, Pages) paste the HTML,
+/// everything else pastes the plain text.
+///
+/// # Errors
+/// Returns an error if the clipboard could not be written
+pub fn write_html(html: &str, plain: &str) -> Result<()> {
+    backend::write_html(html, plain)
+}
+
+/// Read text from the clipboard
+///
+/// # Errors
+/// Returns an error if the clipboard could not be read
+pub fn read_text() -> Result<String> {
+    backend::read_text()
+}
+
+/// Clear the clipboard
+///
+/// # Errors
+/// Returns an error if the clipboard could not be cleared
+pub fn clear() -> Result<()> {
+    backend::clear()
+}
+
+#[cfg(not(feature = "native-clipboard"))]
+mod backend {
+    use super::*;
+    use crate::output::platform;
+
+    pub fn write_text(text: &str) -> Result<()> {
+        platform::current().write_clipboard(text)
+    }
+
+    pub fn write_html(html: &str, plain: &str) -> Result<()> {
+        platform::current().write_clipboard_html(html, plain)
+    }
+
+    pub fn read_text() -> Result<String> {
+        platform::current().read_clipboard()
+    }
+
+    pub fn clear() -> Result<()> {
+        write_text("")
+    }
+}
+
+#[cfg(feature = "native-clipboard")]
+mod backend {
+    use super::*;
+    use crate::error::RephraserError;
+    use arboard::Clipboard;
+
+    fn open() -> Result<Clipboard> {
+        Clipboard::new()
+            .map_err(|e| RephraserError::Output(format!("Failed to open pasteboard: {}", e)))
+    }
+
+    pub fn write_text(text: &str) -> Result<()> {
+        open()?
+            .set_text(text)
+            .map_err(|e| RephraserError::Output(format!("Failed to write to pasteboard: {}", e)))
+    }
+
+    pub fn write_html(html: &str, plain: &str) -> Result<()> {
+        open()?
+            .set_html(html, Some(plain))
+            .map_err(|e| RephraserError::Output(format!("Failed to write to pasteboard: {}", e)))
+    }
+
+    pub fn read_text() -> Result<String> {
+        match open()?.get_text() {
+            Ok(text) => Ok(text),
+            Err(arboard::Error::ContentNotAvailable) => Ok(String::new()),
+            Err(e) => Err(RephraserError::Input(format!(
+                "Failed to read from pasteboard: {}",
+                e
+            ))),
+        }
+    }
+
+    pub fn clear() -> Result<()> {
+        open()?
+            .clear()
+            .map_err(|e| RephraserError::Output(format!("Failed to clear pasteboard: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore] // Modifies the system clipboard and needs a desktop session
+    fn test_clipboard_round_trip() {
+        write_text("clipboard round trip").unwrap();
+        assert_eq!(read_text().unwrap(), "clipboard round trip");
+    }
+}
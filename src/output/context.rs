@@ -0,0 +1,104 @@
+//! Request context available to output templates
+
+use crate::actions::TemplateEngine;
+use crate::error::Result;
+use chrono::Local;
+
+/// Information about the request that produced an output
+#[derive(Debug, Clone, Default)]
+pub struct OutputContext {
+    /// Action name (e.g., "polite")
+    pub action: String,
+
+    /// Action display name (e.g., "丁寧に")
+    pub action_display_name: String,
+
+    /// LLM provider name (e.g., "openai")
+    pub provider: String,
+
+    /// Model name (e.g., "gpt-4o-mini")
+    pub model: String,
+}
+
+impl OutputContext {
+    /// Build a template engine with the context variables
+    ///
+    /// Sets {action}, {action_display_name}, {provider}, {model}, {date}
+    /// and {time}
+    pub fn template_engine(&self) -> TemplateEngine<'_> {
+        let now = Local::now();
+        let mut engine = TemplateEngine::new();
+        engine
+            .set("action", self.action.as_str())
+            .set("action_display_name", self.action_display_name.as_str())
+            .set("provider", self.provider.as_str())
+            .set("model", self.model.as_str())
+            .set("date", now.format("%Y-%m-%d").to_string())
+            .set("time", now.format("%H:%M").to_string());
+        engine
+    }
+
+    /// Decorate a result with the output template
+    ///
+    /// The template can use {result} plus the context variables. Without a
+    /// template the result is returned unchanged.
+    ///
+    /// # Errors
+    /// * If the template contains unknown variables
+    pub fn wrap(&self, template: Option<&str>, result: String) -> Result<String> {
+        match template {
+            Some(template) => {
+                let mut engine = self.template_engine();
+                engine.set("result", result);
+                engine.render(template)
+            }
+            None => Ok(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> OutputContext {
+        OutputContext {
+            action: "polite".to_string(),
+            action_display_name: "丁寧に".to_string(),
+            provider: "openai".to_string(),
+            model: "gpt-4o-mini".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_wrap_with_template() {
+        let wrapped = context()
+            .wrap(
+                Some("{result}\n\n— rewritten by {model}"),
+                "Hello".to_string(),
+            )
+            .unwrap();
+        assert_eq!(wrapped, "Hello\n\n— rewritten by gpt-4o-mini");
+    }
+
+    #[test]
+    fn test_wrap_result_with_braces() {
+        // Responses with code or JSON must not be read as placeholders
+        let result = "fn main() { println!(\"{model}\") }".to_string();
+        let wrapped = context().wrap(Some("> {result}"), result.clone()).unwrap();
+        assert_eq!(wrapped, format!("> {}", result));
+    }
+
+    #[test]
+    fn test_wrap_without_template() {
+        let wrapped = context().wrap(None, "Hello".to_string()).unwrap();
+        assert_eq!(wrapped, "Hello");
+    }
+
+    #[test]
+    fn test_wrap_unknown_variable() {
+        assert!(context()
+            .wrap(Some("{result} {signature}"), "Hi".to_string())
+            .is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Discord webhook output
+
+use crate::config::DiscordConfig;
+use crate::error::{RephraserError, Result};
+use crate::output::text::fit_chars;
+use crate::output::OutputContext;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Longest text posted in one message
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+/// Post the result to the Discord webhook, one message per chunk
+///
+/// # Errors
+/// Returns an error if:
+/// - The webhook URL is not set
+/// - Discord rejects a message
+pub fn post_to_discord(config: &DiscordConfig, context: &OutputContext, text: &str) -> Result<()> {
+    let url = std::env::var(&config.webhook_url_env).map_err(|_| {
+        RephraserError::Config(format!(
+            "Set '{}' to post to Discord",
+            config.webhook_url_env
+        ))
+    })?;
+    let title = context.template_engine().render(&config.title)?;
+    let messages = build_messages(config, &title, text);
+
+    // The output handler is synchronous and may run inside the async
+    // runtime, where blocking requests are not allowed, so send from a
+    // separate thread
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                let client = reqwest::blocking::Client::new();
+                messages
+                    .iter()
+                    .try_for_each(|message| send(&client, &url, message))
+            })
+            .join()
+            .unwrap_or_else(|_| {
+                Err(RephraserError::Output(
+                    "Discord request panicked".to_string(),
+                ))
+            })
+    })
+}
+
+/// Messages with one embed each; only the first has the title, and the
+/// footer numbers them when there are several
+fn build_messages(config: &DiscordConfig, title: &str, text: &str) -> Vec<Value> {
+    let chunks = split_message(text, MAX_MESSAGE_CHARS);
+    let count = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut embed = json!({ "description": chunk });
+            if i == 0 && !title.is_empty() {
+                embed["title"] = json!(title);
+            }
+            if let Some(color) = config.color {
+                embed["color"] = json!(color);
+            }
+            if count > 1 {
+                embed["footer"] = json!({ "text": format!("{}/{}", i + 1, count) });
+            }
+
+            let mut message = json!({
+                "embeds": [embed],
+                // Never ping anyone mentioned in the result
+                "allowed_mentions": { "parse": [] },
+            });
+            if let Some(username) = &config.username {
+                message["username"] = json!(username);
+            }
+            message
+        })
+        .collect()
+}
+
+/// Split `text` into chunks of at most `limit` characters, breaking at the
+/// last line break or space that fits when there is one, and otherwise
+/// between grapheme clusters
+fn split_message(text: &str, limit: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+    while rest.chars().count() > limit {
+        let hard_end = fit_chars(rest, limit);
+        let head = &rest[..hard_end];
+        let end = head
+            .rfind('\n')
+            .or_else(|| head.rfind(' '))
+            .filter(|&i| i > 0)
+            .unwrap_or(hard_end);
+        chunks.push(rest[..end].trim_end());
+        rest = rest[end..].trim_start();
+    }
+    if !rest.is_empty() || chunks.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+fn send(client: &reqwest::blocking::Client, url: &str, message: &Value) -> Result<()> {
+    let post = || {
+        client
+            .post(url)
+            .json(message)
+            .send()
+            .map_err(|e| RephraserError::Output(format!("Failed to post to Discord: {}", e)))
+    };
+
+    let mut response = post()?;
+    // Long results are posted in quick succession and may hit the rate
+    // limit, which says how long to wait
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let body: Value = response.json().unwrap_or_default();
+        let wait = body["retry_after"].as_f64().unwrap_or(1.0).clamp(0.0, 10.0);
+        std::thread::sleep(Duration::from_secs_f64(wait));
+        response = post()?;
+    }
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().unwrap_or_default();
+        return Err(RephraserError::Output(format!(
+            "Discord rejected the message ({}): {}",
+            status, body
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("short", 10), ["short"]);
+        assert_eq!(split_message("", 10), [""]);
+        assert_eq!(
+            split_message("first line\nsecond one", 15),
+            ["first line", "second one"]
+        );
+        assert_eq!(
+            split_message("あいうえおかきく", 5),
+            ["あいうえお", "かきく"]
+        );
+        // The flag is not split between messages
+        assert_eq!(split_message("abcd🇯🇵", 5), ["abcd", "🇯🇵"]);
+    }
+
+    #[test]
+    fn test_build_messages() {
+        let config = DiscordConfig {
+            username: Some("Rephraser".to_string()),
+            ..DiscordConfig::default()
+        };
+        let text = format!("{}\n{}", "a".repeat(1500), "b".repeat(1500));
+        let messages = build_messages(&config, "丁寧に", &text);
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["username"], "Rephraser");
+        assert_eq!(messages[0]["embeds"][0]["title"], "丁寧に");
+        assert_eq!(messages[0]["embeds"][0]["footer"]["text"], "1/2");
+        assert!(messages[1]["embeds"][0].get("title").is_none());
+        assert_eq!(messages[1]["embeds"][0]["description"], "b".repeat(1500));
+    }
+
+    #[test]
+    fn test_send() {
+        let mut server = mockito::Server::new();
+        let ok = server.mock("POST", "/ok").with_status(204).create();
+        let bad = server
+            .mock("POST", "/bad")
+            .with_status(400)
+            .with_body(r#"{"message":"Cannot send an empty message"}"#)
+            .create();
+
+        let client = reqwest::blocking::Client::new();
+        let message = json!({ "embeds": [] });
+        send(&client, &format!("{}/ok", server.url()), &message).unwrap();
+        let err = send(&client, &format!("{}/bad", server.url()), &message).unwrap_err();
+
+        ok.assert();
+        bad.assert();
+        assert!(err.to_string().contains("empty message"));
+    }
+}
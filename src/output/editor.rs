@@ -0,0 +1,126 @@
+//! Open-in-editor output
+
+use crate::error::{RephraserError, Result};
+use crate::output::{clipboard, undo};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Write text to a temp file and open it for review
+///
+/// With `$VISUAL` or `$EDITOR` set, the editor runs in the foreground and the
+/// edited text is copied to the clipboard once it exits, saving up to
+/// `undo_depth` previous contents for `rephraser undo`. Otherwise the file
+/// is opened in the platform's default text editor.
+///
+/// # Errors
+/// Returns an error if:
+/// - The temp file could not be written or read back
+/// - The editor could not be launched or exited with an error
+pub fn open_in_editor(text: &str, undo_depth: usize) -> Result<()> {
+    let path = temp_path("md");
+    fs::write(&path, text)?;
+
+    match configured_editor() {
+        Some(editor) => {
+            run_editor(&editor, &path)?;
+            let edited = fs::read_to_string(&path)?;
+            let _ = fs::remove_file(&path);
+            undo::stash_clipboard(undo_depth);
+            clipboard::write_text(&edited)
+        }
+        None => open_default(&path),
+    }
+}
+
+/// Get the user's editor command from `$VISUAL` or `$EDITOR`
+fn configured_editor() -> Option<String> {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+/// Split an editor command like "code --wait" into program and arguments
+fn split_editor_command(editor: &str) -> Option<(&str, Vec<&str>)> {
+    let mut parts = editor.split_whitespace();
+    let program = parts.next()?;
+    Some((program, parts.collect()))
+}
+
+fn run_editor(editor: &str, path: &Path) -> Result<()> {
+    let (program, args) = split_editor_command(editor)
+        .ok_or_else(|| RephraserError::Output("Editor command is empty".to_string()))?;
+
+    let status = Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|e| {
+            RephraserError::Output(format!("Failed to launch editor '{}': {}", editor, e))
+        })?;
+
+    if !status.success() {
+        return Err(RephraserError::Output(format!(
+            "Editor exited with status: {}",
+            status
+        )));
+    }
+
+    Ok(())
+}
+
+fn open_default(path: &Path) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("open");
+        c.arg("-t");
+        c
+    } else if cfg!(target_os = "windows") {
+        Command::new("notepad")
+    } else {
+        Command::new("xdg-open")
+    };
+
+    command
+        .arg(path)
+        .spawn()
+        .map_err(|e| RephraserError::Output(format!("Failed to open editor: {}", e)))?;
+
+    Ok(())
+}
+
+/// Build a unique temp file path with the given extension
+pub(crate) fn temp_path(extension: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "rephraser-{}-{}.{}",
+        std::process::id(),
+        nanos,
+        extension
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_editor_command() {
+        assert_eq!(split_editor_command("vim"), Some(("vim", vec![])));
+        assert_eq!(
+            split_editor_command("code --wait"),
+            Some(("code", vec!["--wait"]))
+        );
+        assert_eq!(split_editor_command("  "), None);
+    }
+
+    #[test]
+    fn test_temp_path_is_unique() {
+        assert_ne!(temp_path("md"), temp_path("md"));
+        assert!(temp_path("html").to_string_lossy().ends_with(".html"));
+    }
+}
@@ -0,0 +1,126 @@
+//! Email draft output
+
+use crate::config::EmailConfig;
+use crate::error::{RephraserError, Result};
+use crate::output::formatter::escape_applescript_string;
+use crate::output::platform::open_url;
+use crate::output::OutputContext;
+use std::process::Command;
+
+/// Open a new email draft with the result as its body
+///
+/// Uses Mail.app on macOS unless `mailto` is set, and a mailto: link in the
+/// default mail app elsewhere. The draft is left open for review; nothing
+/// is sent.
+///
+/// # Errors
+/// Returns an error if:
+/// - The subject template is invalid
+/// - The mail app could not be opened
+pub fn compose_email(config: &EmailConfig, context: &OutputContext, text: &str) -> Result<()> {
+    let subject = config
+        .subject
+        .as_deref()
+        .map(|subject| context.template_engine().render(subject))
+        .transpose()?
+        .unwrap_or_default();
+    let to = config.to.as_deref().unwrap_or_default();
+
+    if cfg!(target_os = "macos") && !config.mailto {
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(build_script(to, &subject, text))
+            .output()
+            .map_err(|e| RephraserError::Output(format!("Failed to execute osascript: {}", e)))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(RephraserError::Output(format!(
+                "Failed to create a Mail.app draft: {}",
+                stderr
+            )));
+        }
+        return Ok(());
+    }
+
+    open_url(&mailto_url(to, &subject, text))
+}
+
+/// Build the AppleScript that opens a Mail.app draft
+fn build_script(to: &str, subject: &str, body: &str) -> String {
+    let subject = escape_applescript_string(subject);
+    let body = escape_applescript_string(body);
+    let recipient = if to.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\n    tell draft to make new to recipient at end of to recipients with properties {{address:\"{}\"}}",
+            escape_applescript_string(to)
+        )
+    };
+
+    format!(
+        r#"tell application "Mail"
+    set draft to make new outgoing message with properties {{subject:"{subject}", content:"{body}", visible:true}}{recipient}
+    activate
+end tell"#
+    )
+}
+
+/// Build a mailto: URL (RFC 6068)
+fn mailto_url(to: &str, subject: &str, body: &str) -> String {
+    let mut url = format!("mailto:{}", percent_encode(to));
+    let mut separator = '?';
+    for (name, value) in [("subject", subject), ("body", body)] {
+        if !value.is_empty() {
+            url.push(separator);
+            url.push_str(name);
+            url.push('=');
+            // Line breaks must be CRLF
+            url.push_str(&percent_encode(
+                &value.replace("\r\n", "\n").replace('\n', "\r\n"),
+            ));
+            separator = '&';
+        }
+    }
+    url
+}
+
+/// Percent-encode everything except unreserved characters and `@`
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~@".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mailto_url() {
+        assert_eq!(
+            mailto_url("boss@example.com", "Re: 件名", "Hi,\nthanks & bye"),
+            "mailto:boss@example.com?subject=Re%3A%20%E4%BB%B6%E5%90%8D\
+             &body=Hi%2C%0D%0Athanks%20%26%20bye"
+        );
+        assert_eq!(mailto_url("", "", "Hello"), "mailto:?body=Hello");
+    }
+
+    #[test]
+    fn test_build_script() {
+        let script = build_script("", "Re: \"plan\"", "line 1\nline 2");
+        assert!(script.contains(r#"subject:"Re: \"plan\"""#));
+        assert!(script.contains(r#"content:"line 1\nline 2""#));
+        assert!(!script.contains("to recipient"));
+
+        let script = build_script("boss@example.com", "", "Hi");
+        assert!(script.contains(r#"{address:"boss@example.com"}"#));
+    }
+}
@@ -1,23 +1,82 @@
 //! Output formatting and display
 
-use crate::config::OutputMethod;
+use crate::config::{OutputConfig, OutputMethod, PluginsConfig, SpeechConfig};
 use crate::error::Result;
+use crate::output::platform::Notification;
+use crate::output::{
+    clipboard, discord, editor, email, markdown, note, notes_app, platform, preview, sink, slack,
+    text, undo, OutputContext,
+};
 use std::process::Command;
 
-/// Maximum length for notification text
-const MAX_NOTIFICATION_LENGTH: usize = 200;
+/// Maximum width of notification text, in columns (a CJK character takes two)
+const MAX_NOTIFICATION_WIDTH: usize = 200;
+
+/// Notification subtitle shown when the text was truncated
+const TRUNCATED_SUBTITLE: &str = "Truncated - click to copy the full text";
+
+/// AppleScript that sends Cmd+V to the frontmost application
+const PASTE_SCRIPT: &str =
+    r#"tell application "System Events" to keystroke "v" using command down"#;
+
+/// Action chosen in the interactive result dialog
+#[derive(Debug, Clone, PartialEq)]
+pub enum DialogAction {
+    /// Copy the (possibly edited) text to the clipboard
+    Copy(String),
+    /// Generate a new result
+    Retry,
+    /// Dismiss without output
+    Cancel,
+}
 
 /// Output handler
 ///
-/// Handles different output methods: clipboard, notification, dialog
+/// Handles different output methods: clipboard, notification, dialog, paste,
+/// speak, note, editor, terminal, preview, notes_app, and sink plugins.
+/// Clipboard, notification, note, editor, terminal and preview work on macOS,
+/// Linux and Windows; dialog, paste and notes_app rely on AppleScript and,
+/// like speak, are macOS-only.
 pub struct OutputHandler {
-    method: OutputMethod,
+    config: OutputConfig,
+    context: OutputContext,
+    plugins: PluginsConfig,
 }
 
 impl OutputHandler {
     /// Create a new output handler
     pub fn new(method: OutputMethod) -> Self {
-        Self { method }
+        Self::from_config(&OutputConfig {
+            method,
+            ..Default::default()
+        })
+    }
+
+    /// Create an output handler from the output configuration
+    pub fn from_config(config: &OutputConfig) -> Self {
+        Self {
+            config: config.clone(),
+            context: OutputContext::default(),
+            plugins: PluginsConfig::default(),
+        }
+    }
+
+    /// Set the context of the request that produced the output
+    pub fn with_context(mut self, context: OutputContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Look up sink plugins in `plugins` rather than only on PATH
+    pub fn with_plugins(mut self, plugins: &PluginsConfig) -> Self {
+        self.plugins = plugins.clone();
+        self
+    }
+
+    /// Use a different output method, keeping the other settings
+    pub fn with_method(mut self, method: OutputMethod) -> Self {
+        self.config.method = method;
+        self
     }
 
     /// Handle output based on the configured method
@@ -28,176 +87,375 @@ impl OutputHandler {
     /// # Returns
     /// * `Result<()>` - Success or error
     pub fn handle(&self, text: &str) -> Result<()> {
-        match self.method {
-            OutputMethod::Clipboard => self.copy_to_clipboard(text),
+        match &self.config.method {
+            OutputMethod::Clipboard => {
+                self.copy_to_clipboard(text)?;
+                self.play_completion_sound();
+                Ok(())
+            }
             OutputMethod::Notification => self.show_notification(text),
-            OutputMethod::Dialog => self.show_dialog(text),
+            // Without a request to re-run there's nothing for Retry to do,
+            // so only the commands that can regenerate call show_dialog
+            OutputMethod::Dialog => match self.dialog(text, false)? {
+                DialogAction::Copy(edited) => self.copy_to_clipboard(&edited),
+                DialogAction::Retry | DialogAction::Cancel => Ok(()),
+            },
+            OutputMethod::Paste => self.paste_in_place(text),
+            OutputMethod::Speak => self.speak(text),
+            OutputMethod::Note => {
+                note::append_to_note(&self.config.note, &self.context.action, text)
+            }
+            OutputMethod::Editor => editor::open_in_editor(text, self.config.undo_depth),
+            OutputMethod::Terminal => self.print_to_terminal(text),
+            OutputMethod::Preview => preview::show_preview(text, &self.preview_title()),
+            OutputMethod::NotesApp => {
+                notes_app::send_to_notes(&self.config.notes_app, &self.context, text)
+            }
+            OutputMethod::Slack => slack::post_to_slack(&self.config.slack, &self.context, text),
+            OutputMethod::Discord => {
+                discord::post_to_discord(&self.config.discord, &self.context, text)
+            }
+            OutputMethod::Email => email::compose_email(&self.config.email, &self.context, text),
+            OutputMethod::Plugin(name) => {
+                sink::send_to_sink(&self.plugins, name, &self.context, text)
+            }
         }
     }
 
-    /// Copy text to clipboard using pbcopy
+    /// Copy text to clipboard
+    ///
+    /// Uses the platform's clipboard command, or the native clipboard API
+    /// with the `native-clipboard` feature. The previous contents are saved
+    /// for `rephraser undo`
     ///
     /// # Errors
-    /// Returns an error if:
-    /// - The platform is not macOS
-    /// - pbcopy command is not available
-    /// - The command execution fails
-    fn copy_to_clipboard(&self, text: &str) -> Result<()> {
-        use crate::error::RephraserError;
-        check_macos_platform()?;
-
-        let mut child = Command::new("pbcopy")
-            .stdin(std::process::Stdio::piped())
-            .spawn()
-            .map_err(|e| RephraserError::Output(
-                format!("Failed to spawn pbcopy: {}", e)
-            ))?;
-
-        if let Some(mut stdin) = child.stdin.take() {
-            use std::io::Write;
-            stdin.write_all(text.as_bytes())
-                .map_err(|e| RephraserError::Output(
-                    format!("Failed to write to pbcopy stdin: {}", e)
-                ))?;
-        }
-
-        let status = child.wait()
-            .map_err(|e| RephraserError::Output(
-                format!("Failed to wait for pbcopy: {}", e)
-            ))?;
-
-        if !status.success() {
-            return Err(RephraserError::Output(
-                format!("pbcopy exited with status: {}", status)
-            ));
+    /// Returns an error if the clipboard could not be written
+    pub fn copy_to_clipboard(&self, text: &str) -> Result<()> {
+        undo::stash_clipboard(self.config.undo_depth);
+        if self.config.rich_clipboard {
+            clipboard::write_html(&markdown::to_html(text), text)
+        } else {
+            clipboard::write_text(text)
         }
+    }
 
-        Ok(())
+    /// Show a desktop notification
+    ///
+    /// Displays a system notification with the configured title and subtitle
+    /// (title "Rephraser" by default).
+    /// Text wider than 200 columns (100 CJK characters) will be truncated
+    /// with ellipsis and flagged in the subtitle. On macOS with terminal-notifier installed,
+    /// clicking the notification copies the full text.
+    ///
+    /// # Errors
+    /// Returns an error if the platform's notification command is not
+    /// available or fails
+    fn show_notification(&self, text: &str) -> Result<()> {
+        let truncated = truncate_notification_text(text, MAX_NOTIFICATION_WIDTH);
+        let is_truncated = truncated != text;
+
+        let engine = self.context.template_engine();
+        let title = engine.render(&self.config.notification.title)?;
+        let subtitle = match &self.config.notification.subtitle {
+            Some(template) => Some(engine.render(template)?),
+            None => None,
+        };
+        let subtitle = notification_subtitle(subtitle, is_truncated);
+
+        platform::current().notify(&Notification {
+            title: &title,
+            subtitle: subtitle.as_deref(),
+            body: &truncated,
+            copy_on_click: Some(text),
+        })
     }
 
-    /// Show macOS notification
+    /// Show an interactive macOS dialog
     ///
-    /// Displays a system notification with title "Rephraser".
-    /// Text longer than 200 characters will be truncated with ellipsis.
+    /// Displays a blocking dialog with the text in an editable field and
+    /// "Cancel", "Retry" and "Copy" buttons.
+    ///
+    /// # Returns
+    /// * `Result<DialogAction>` - The button the user pressed, with the
+    ///   (possibly edited) text for "Copy"
     ///
     /// # Errors
     /// Returns an error if:
     /// - The platform is not macOS
     /// - osascript command is not available
     /// - The AppleScript execution fails
-    fn show_notification(&self, text: &str) -> Result<()> {
+    pub fn show_dialog(&self, text: &str) -> Result<DialogAction> {
+        self.dialog(text, true)
+    }
+
+    /// Show the result dialog, with a "Retry" button if `retry` is set
+    fn dialog(&self, text: &str, retry: bool) -> Result<DialogAction> {
         use crate::error::RephraserError;
         check_macos_platform()?;
 
-        // Truncate and escape the text
-        let truncated = truncate_notification_text(text, MAX_NOTIFICATION_LENGTH);
-        // Remove newlines (AppleScript notifications don't support them)
-        let single_line = truncated.replace('\n', " ").replace('\r', " ");
-        let escaped = escape_applescript_string(&single_line);
+        // Escape the text for AppleScript
+        let escaped = escape_applescript_string(text);
+        let buttons = if retry {
+            r#""Cancel", "Retry", "Copy""#
+        } else {
+            r#""Cancel", "Copy""#
+        };
 
-        // Build AppleScript command
+        // Return the pressed button on the first line, the edited text after it
         let script = format!(
-            r#"display notification "{}" with title "Rephraser""#,
-            escaped
+            r#"set r to display dialog "Result:" default answer "{}" with title "Rephraser" buttons {{{}}} default button "Copy" cancel button "Cancel"
+return (button returned of r) & linefeed & (text returned of r)"#,
+            escaped, buttons
         );
 
         let output = Command::new("osascript")
             .arg("-e")
             .arg(&script)
             .output()
-            .map_err(|e| RephraserError::Output(
-                format!("Failed to execute osascript: {}", e)
-            ))?;
+            .map_err(|e| RephraserError::Output(format!("Failed to execute osascript: {}", e)))?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(RephraserError::Output(
-                format!("osascript failed: {}", stderr)
-            ));
+            // -128 is AppleScript's "User canceled" error
+            if stderr.contains("-128") {
+                return Ok(DialogAction::Cancel);
+            }
+            return Err(RephraserError::Output(format!(
+                "osascript dialog failed: {}",
+                stderr
+            )));
         }
 
+        Ok(parse_dialog_output(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    /// Paste text into the frontmost application
+    ///
+    /// Copies the text to the clipboard, then simulates Cmd+V via System Events.
+    /// Requires Accessibility permission for the calling process.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - The platform is not macOS
+    /// - Copying to the clipboard fails
+    /// - The keystroke could not be sent (e.g. missing Accessibility permission)
+    fn paste_in_place(&self, text: &str) -> Result<()> {
+        self.copy_to_clipboard(text)?;
+        paste_clipboard()
+    }
+
+    /// Print text to stdout
+    ///
+    /// Copies the text to the clipboard afterwards when terminal copy is on
+    fn print_to_terminal(&self, text: &str) -> Result<()> {
+        println!("{}", text);
+        self.finish_terminal_output(text)
+    }
+
+    /// Finish terminal output that has already been printed (e.g. streamed)
+    ///
+    /// # Errors
+    /// Returns an error if terminal copy is on and the clipboard write fails
+    pub fn finish_terminal_output(&self, text: &str) -> Result<()> {
+        if self.config.copy_terminal_output {
+            self.copy_to_clipboard(text)?;
+        }
         Ok(())
     }
 
-    /// Show macOS dialog
+    /// Window title for previews
+    fn preview_title(&self) -> String {
+        if self.context.action_display_name.is_empty() {
+            "Rephraser".to_string()
+        } else {
+            format!("Rephraser - {}", self.context.action_display_name)
+        }
+    }
+
+    /// Play the configured completion sound, if any
     ///
-    /// Displays a blocking dialog box with the text and an OK button.
-    /// Long text will be scrollable within the dialog.
+    /// Gives feedback for outputs that are otherwise silent. Failures are
+    /// ignored since the output itself already succeeded.
+    fn play_completion_sound(&self) {
+        let Some(sound) = &self.config.completion_sound else {
+            return;
+        };
+        if check_macos_platform().is_err() {
+            return;
+        }
+
+        let (program, args) = sound_command(sound);
+        let _ = Command::new(program)
+            .args(args)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn();
+    }
+
+    /// Read text aloud using macOS `say`
+    ///
+    /// Blocks until speaking has finished.
     ///
     /// # Errors
     /// Returns an error if:
     /// - The platform is not macOS
-    /// - osascript command is not available
-    /// - The AppleScript execution fails
-    fn show_dialog(&self, text: &str) -> Result<()> {
+    /// - say command is not available
+    /// - The command execution fails
+    fn speak(&self, text: &str) -> Result<()> {
         use crate::error::RephraserError;
         check_macos_platform()?;
 
-        // Escape the text for AppleScript
-        let escaped = escape_applescript_string(text);
+        let mut child = Command::new("say")
+            .args(say_args(&self.config.speech))
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| RephraserError::Output(format!("Failed to spawn say: {}", e)))?;
 
-        // Build AppleScript command with scrollable text
-        // Note: For long text, AppleScript automatically makes dialogs scrollable
-        let script = format!(
-            r#"display dialog "{}" with title "Rephraser" buttons {{"OK"}} default button "OK""#,
-            escaped
-        );
+        // Pass text via stdin so it is never interpreted as an option
+        if let Some(mut stdin) = child.stdin.take() {
+            use std::io::Write;
+            stdin.write_all(text.as_bytes()).map_err(|e| {
+                RephraserError::Output(format!("Failed to write to say stdin: {}", e))
+            })?;
+        }
 
-        let output = Command::new("osascript")
-            .arg("-e")
-            .arg(&script)
-            .output()
-            .map_err(|e| RephraserError::Output(
-                format!("Failed to execute osascript: {}", e)
-            ))?;
+        let status = child
+            .wait()
+            .map_err(|e| RephraserError::Output(format!("Failed to wait for say: {}", e)))?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(RephraserError::Output(
-                format!("osascript dialog failed: {}", stderr)
-            ));
+        if !status.success() {
+            return Err(RephraserError::Output(format!(
+                "say exited with status: {}",
+                status
+            )));
         }
 
         Ok(())
     }
 }
 
+/// Combine the configured subtitle with the truncation hint
+fn notification_subtitle(subtitle: Option<String>, is_truncated: bool) -> Option<String> {
+    match (subtitle, is_truncated) {
+        (Some(subtitle), true) => Some(format!("{} - {}", subtitle, TRUNCATED_SUBTITLE)),
+        (Some(subtitle), false) => Some(subtitle),
+        (None, true) => Some(TRUNCATED_SUBTITLE.to_string()),
+        (None, false) => None,
+    }
+}
+
+/// Build the command that plays a completion sound
+fn sound_command(sound: &str) -> (&'static str, Vec<String>) {
+    if sound.eq_ignore_ascii_case("beep") {
+        ("osascript", vec!["-e".to_string(), "beep".to_string()])
+    } else if sound.contains('/') {
+        ("afplay", vec![sound.to_string()])
+    } else {
+        (
+            "afplay",
+            vec![format!("/System/Library/Sounds/{}.aiff", sound)],
+        )
+    }
+}
+
+/// Build the arguments for the `say` command
+fn say_args(speech: &SpeechConfig) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(voice) = &speech.voice {
+        args.push("-v".to_string());
+        args.push(voice.clone());
+    }
+    if let Some(rate) = speech.rate {
+        args.push("-r".to_string());
+        args.push(rate.to_string());
+    }
+    args
+}
+
+/// Parse the output of the interactive dialog script
+///
+/// The first line is the pressed button, the remainder is the text field
+fn parse_dialog_output(output: &str) -> DialogAction {
+    let output = output.strip_suffix('\n').unwrap_or(output);
+    let (button, text) = output.split_once('\n').unwrap_or((output, ""));
+
+    match button.trim() {
+        "Copy" => DialogAction::Copy(text.to_string()),
+        "Retry" => DialogAction::Retry,
+        _ => DialogAction::Cancel,
+    }
+}
+
 /// Escape a string for safe use in AppleScript
 ///
 /// AppleScript string literals require:
 /// - Backslashes escaped as \\
 /// - Double quotes escaped as \"
-fn escape_applescript_string(text: &str) -> String {
-    text.replace('\\', "\\\\")
-        .replace('"', "\\\"")
+///
+/// Tabs and line breaks (including U+2028 and U+2029) become \t, \n and
+/// \r. Other control characters are dropped: they cannot be written in a
+/// literal, and a NUL would make the osascript argument invalid.
+pub(crate) fn escape_applescript_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\t' => escaped.push_str("\\t"),
+            '\n' | '\u{2028}' | '\u{2029}' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
-/// Truncate text for notification display
+/// Paste the clipboard into the frontmost application by simulating Cmd+V
 ///
-/// If text exceeds max length, truncate and add ellipsis
-fn truncate_notification_text(text: &str, max: usize) -> String {
-    if text.len() <= max {
-        text.to_string()
-    } else {
-        // Find a safe truncation point (avoid cutting multi-byte chars)
-        let mut truncate_at = max.saturating_sub(3); // Reserve space for "..."
-        while truncate_at > 0 && !text.is_char_boundary(truncate_at) {
-            truncate_at -= 1;
-        }
-        format!("{}...", &text[..truncate_at])
+/// # Errors
+/// Returns an error if the keystroke could not be sent (e.g. missing
+/// Accessibility permission)
+pub fn paste_clipboard() -> Result<()> {
+    use crate::error::RephraserError;
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(PASTE_SCRIPT)
+        .output()
+        .map_err(|e| RephraserError::Output(format!("Failed to execute osascript: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RephraserError::Output(format!(
+            "Failed to paste (check Accessibility permissions): {}",
+            stderr
+        )));
     }
+
+    Ok(())
+}
+
+/// Truncate text for notification display
+///
+/// If text is wider than `max_width` columns, truncate it between grapheme
+/// clusters and add ellipsis
+fn truncate_notification_text(text: &str, max_width: usize) -> String {
+    text::truncate_to_width(text, max_width, "...")
 }
 
 /// Check if the current platform is macOS
 ///
 /// Returns an error if not on macOS
-fn check_macos_platform() -> Result<()> {
+pub(crate) fn check_macos_platform() -> Result<()> {
     #[cfg(not(target_os = "macos"))]
     {
         use crate::error::RephraserError;
-        return Err(RephraserError::Output(
-            "Output methods are only supported on macOS".to_string()
-        ));
+        Err(RephraserError::Output(
+            "Output methods are only supported on macOS".to_string(),
+        ))
     }
     #[cfg(target_os = "macos")]
     Ok(())
@@ -244,7 +502,7 @@ mod tests {
 
     #[test]
     #[cfg(target_os = "macos")]
-    #[ignore] // Requires manual interaction (user must click OK)
+    #[ignore] // Requires manual interaction (user must press a button)
     fn test_dialog_handler() {
         let handler = OutputHandler::new(OutputMethod::Dialog);
 
@@ -258,11 +516,73 @@ mod tests {
     }
 
     #[test]
-    fn test_escape_applescript_string() {
+    #[cfg(target_os = "macos")]
+    #[ignore] // Sends Cmd+V to the frontmost app and needs Accessibility permission
+    fn test_paste_handler() {
+        let handler = OutputHandler::new(OutputMethod::Paste);
+        let result = handler.handle("test paste");
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_notification_subtitle() {
+        assert_eq!(notification_subtitle(None, false), None);
+        assert_eq!(
+            notification_subtitle(None, true).as_deref(),
+            Some(TRUNCATED_SUBTITLE)
+        );
+        assert_eq!(
+            notification_subtitle(Some("gpt-4o".to_string()), false).as_deref(),
+            Some("gpt-4o")
+        );
+        assert!(notification_subtitle(Some("gpt-4o".to_string()), true)
+            .unwrap()
+            .starts_with("gpt-4o - "));
+    }
+
+    #[test]
+    fn test_sound_command() {
+        assert_eq!(sound_command("beep").0, "osascript");
+        assert_eq!(
+            sound_command("Glass"),
+            (
+                "afplay",
+                vec!["/System/Library/Sounds/Glass.aiff".to_string()]
+            )
+        );
+        assert_eq!(
+            sound_command("/tmp/done.wav"),
+            ("afplay", vec!["/tmp/done.wav".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_say_args() {
+        assert!(say_args(&SpeechConfig::default()).is_empty());
+
+        let speech = SpeechConfig {
+            voice: Some("Kyoko".to_string()),
+            rate: Some(180),
+        };
+        assert_eq!(say_args(&speech), vec!["-v", "Kyoko", "-r", "180"]);
+    }
+
+    #[test]
+    fn test_parse_dialog_output() {
+        assert_eq!(
+            parse_dialog_output("Copy\nedited\ntext\n"),
+            DialogAction::Copy("edited\ntext".to_string())
+        );
         assert_eq!(
-            escape_applescript_string("simple text"),
-            "simple text"
+            parse_dialog_output("Retry\nold text\n"),
+            DialogAction::Retry
         );
+        assert_eq!(parse_dialog_output(""), DialogAction::Cancel);
+    }
+
+    #[test]
+    fn test_escape_applescript_string() {
+        assert_eq!(escape_applescript_string("simple text"), "simple text");
         assert_eq!(
             escape_applescript_string("text with \"quotes\""),
             "text with \\\"quotes\\\""
@@ -275,25 +595,63 @@ mod tests {
             escape_applescript_string("mixed: \"path\\file\""),
             "mixed: \\\"path\\\\file\\\""
         );
+        assert_eq!(
+            escape_applescript_string("a\tb\r\nc\u{2028}d"),
+            "a\\tb\\r\\nc\\nd"
+        );
+    }
+
+    #[test]
+    fn test_escape_applescript_adversarial() {
+        // Nothing may end the literal or leave a character osascript rejects
+        let inputs = [
+            "\" & do shell script \"touch /tmp/pwned\" & \"",
+            "\\\" & quit & \"",
+            "nul\0byte",
+            "bell\u{7}esc\u{1b}[31mdel\u{7f}",
+            "c1\u{85}\u{9b}controls",
+            "trailing backslash\\",
+        ];
+        for input in inputs {
+            let escaped = escape_applescript_string(input);
+            assert!(!escaped.chars().any(char::is_control), "{:?}", escaped);
+
+            // Every quote is preceded by an odd number of backslashes
+            let mut backslashes = 0;
+            for c in escaped.chars() {
+                match c {
+                    '\\' => backslashes += 1,
+                    '"' => {
+                        assert_eq!(backslashes % 2, 1, "{:?}", escaped);
+                        backslashes = 0;
+                    }
+                    _ => backslashes = 0,
+                }
+            }
+            assert_eq!(backslashes % 2, 0, "{:?}", escaped);
+        }
+        assert_eq!(escape_applescript_string("nul\0byte"), "nulbyte");
     }
 
     #[test]
     fn test_truncate_notification_text() {
-        assert_eq!(
-            truncate_notification_text("short", 100),
-            "short"
-        );
+        assert_eq!(truncate_notification_text("short", 100), "short");
 
         let long_text = "a".repeat(250);
         let truncated = truncate_notification_text(&long_text, 200);
         assert_eq!(truncated.len(), 200);
         assert!(truncated.ends_with("..."));
 
-        // Test with multi-byte characters (Japanese)
-        let japanese = "こんにちは".repeat(50); // ~150 chars (3 bytes each)
+        // Japanese characters are two columns wide
+        let japanese = "こんにちは".repeat(50);
         let truncated = truncate_notification_text(&japanese, 200);
-        assert!(truncated.len() <= 200);
+        assert_eq!(truncated.chars().count(), 98 + 3);
         assert!(truncated.ends_with("..."));
+
+        // Emoji sequences are kept whole
+        let family = "👨‍👩‍👧".repeat(150);
+        let truncated = truncate_notification_text(&family, 200);
+        assert_eq!(truncated, format!("{}...", "👨‍👩‍👧".repeat(98)));
     }
 
     #[test]
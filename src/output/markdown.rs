@@ -0,0 +1,79 @@
+//! Markdown rendering for rich clipboard output
+
+use pulldown_cmark::{html, Options, Parser};
+
+/// Render Markdown to an HTML fragment suitable for the clipboard
+///
+/// The fragment declares UTF-8 so that Japanese text survives pasting into
+/// apps that otherwise assume a legacy encoding.
+pub fn to_html(markdown: &str) -> String {
+    format!("<meta charset=\"utf-8\">{}", render_body(markdown))
+}
+
+/// Render Markdown to a bare HTML fragment (no charset declaration)
+pub fn to_body(markdown: &str) -> String {
+    render_body(markdown)
+}
+
+/// Render Markdown to a standalone, styled HTML document for previewing
+pub fn to_document(markdown: &str, title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        PREVIEW_STYLE,
+        render_body(markdown)
+    )
+}
+
+/// Stylesheet for preview documents (follows the system light/dark mode)
+const PREVIEW_STYLE: &str = "body{font:15px/1.6 -apple-system,'Hiragino Sans',sans-serif;\
+max-width:720px;margin:2em auto;padding:0 1em;color:#222;background:#fff}\
+pre,code{font-family:Menlo,monospace;background:#f4f4f4;border-radius:4px}\
+pre{padding:.8em;overflow:auto}table{border-collapse:collapse}\
+td,th{border:1px solid #ccc;padding:.3em .6em}\
+@media (prefers-color-scheme:dark){body{color:#ddd;background:#1e1e1e}\
+pre,code{background:#2b2b2b}}";
+
+fn render_body(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let parser = Parser::new_ext(markdown, options);
+    let mut body = String::new();
+    html::push_html(&mut body, parser);
+    body
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_html() {
+        let html = to_html("# 見出し\n\n- **bold** item\n");
+        assert!(html.starts_with("<meta charset=\"utf-8\">"));
+        assert!(html.contains("<h1>見出し</h1>"));
+        assert!(html.contains("<li><strong>bold</strong> item</li>"));
+    }
+
+    #[test]
+    fn test_to_document() {
+        let doc = to_document("*hi*", "A <b> title");
+        assert!(doc.starts_with("<!DOCTYPE html>"));
+        assert!(doc.contains("<title>A &lt;b&gt; title</title>"));
+        assert!(doc.contains("<em>hi</em>"));
+    }
+
+    #[test]
+    fn test_plain_text_becomes_paragraph() {
+        assert!(to_html("hello").contains("<p>hello</p>"));
+    }
+}
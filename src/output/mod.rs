@@ -1,5 +1,22 @@
 //! Output module
 
+pub mod clipboard;
+pub mod context;
+pub mod discord;
+pub mod editor;
+pub mod email;
 pub mod formatter;
+pub mod markdown;
+pub mod note;
+pub mod notes_app;
+pub mod platform;
+pub mod preview;
+pub mod progress;
+pub mod sink;
+pub mod slack;
+pub mod text;
+pub mod undo;
 
-pub use formatter::OutputHandler;
+pub use context::OutputContext;
+pub use formatter::{DialogAction, OutputHandler};
+pub use platform::Platform;
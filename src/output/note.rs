@@ -0,0 +1,130 @@
+//! Append-to-note output
+
+use crate::actions::TemplateEngine;
+use crate::config::manager::expand_home;
+use crate::config::NoteConfig;
+use crate::error::{RephraserError, Result};
+use chrono::{DateTime, Local};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Resolve the note path for the given time
+///
+/// # Errors
+/// * If the path template contains unknown variables
+pub fn note_path(config: &NoteConfig, now: &DateTime<Local>) -> Result<PathBuf> {
+    let path = date_variables(now).render(&config.path)?;
+    Ok(expand_home(&path))
+}
+
+/// Format a note entry with its header
+///
+/// # Errors
+/// * If the header template contains unknown variables
+pub fn format_entry(
+    config: &NoteConfig,
+    action: &str,
+    text: &str,
+    now: &DateTime<Local>,
+) -> Result<String> {
+    let mut engine = date_variables(now);
+    engine
+        .set("time", now.format("%H:%M").to_string())
+        .set("action", action);
+    let header = engine.render(&config.header)?;
+
+    Ok(format!("\n{}\n\n{}\n", header, text.trim_end()))
+}
+
+/// Append a result to the configured note, creating it if needed
+///
+/// # Errors
+/// Returns an error if:
+/// - The path or header template is invalid
+/// - The note could not be created or written
+pub fn append_to_note(config: &NoteConfig, action: &str, text: &str) -> Result<()> {
+    let now = Local::now();
+    let path = note_path(config, &now)?;
+    let entry = format_entry(config, action, text, &now)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| RephraserError::Output(format!("Failed to open note {:?}: {}", path, e)))?;
+
+    file.write_all(entry.as_bytes())
+        .map_err(|e| RephraserError::Output(format!("Failed to write note {:?}: {}", path, e)))?;
+
+    Ok(())
+}
+
+fn date_variables(now: &DateTime<Local>) -> TemplateEngine<'static> {
+    let mut engine = TemplateEngine::new();
+    engine
+        .set("date", now.format("%Y-%m-%d").to_string())
+        .set("year", now.format("%Y").to_string())
+        .set("month", now.format("%m").to_string())
+        .set("day", now.format("%d").to_string());
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn fixed_time() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 3, 9, 14, 5, 0).unwrap()
+    }
+
+    #[test]
+    fn test_note_path() {
+        let config = NoteConfig {
+            path: "/notes/{year}/{date}.md".to_string(),
+            ..Default::default()
+        };
+        let path = note_path(&config, &fixed_time()).unwrap();
+        assert_eq!(path, PathBuf::from("/notes/2024/2024-03-09.md"));
+    }
+
+    #[test]
+    fn test_format_entry() {
+        let entry =
+            format_entry(&NoteConfig::default(), "polite", "本文\n", &fixed_time()).unwrap();
+        assert_eq!(entry, "\n## 14:05 polite\n\n本文\n");
+    }
+
+    #[test]
+    fn test_unknown_variable() {
+        let config = NoteConfig {
+            path: "/notes/{week}.md".to_string(),
+            ..Default::default()
+        };
+        assert!(note_path(&config, &fixed_time()).is_err());
+    }
+
+    #[test]
+    fn test_append_to_note() {
+        let dir = std::env::temp_dir().join(format!("rephraser-note-{}", std::process::id()));
+        let config = NoteConfig {
+            path: dir.join("{date}.md").to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+
+        append_to_note(&config, "polite", "first").unwrap();
+        append_to_note(&config, "summarize", "second").unwrap();
+
+        let path = note_path(&config, &Local::now()).unwrap();
+        let content = fs::read_to_string(path).unwrap();
+        assert!(content.contains("polite\n\nfirst"));
+        assert!(content.contains("summarize\n\nsecond"));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
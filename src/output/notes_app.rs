@@ -0,0 +1,70 @@
+//! Notes.app output
+
+use crate::config::NotesAppConfig;
+use crate::error::{RephraserError, Result};
+use crate::output::formatter::{check_macos_platform, escape_applescript_string};
+use crate::output::{markdown, OutputContext};
+use std::process::Command;
+
+/// Create a note in Notes.app, or append to it if it already exists
+///
+/// # Errors
+/// Returns an error if:
+/// - The platform is not macOS
+/// - The title template is invalid
+/// - The AppleScript execution fails
+pub fn send_to_notes(config: &NotesAppConfig, context: &OutputContext, text: &str) -> Result<()> {
+    check_macos_platform()?;
+
+    let title = context.template_engine().render(&config.title)?;
+    let script = build_script(&config.folder, &title, &markdown::to_body(text));
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(&script)
+        .output()
+        .map_err(|e| RephraserError::Output(format!("Failed to execute osascript: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RephraserError::Output(format!(
+            "Failed to write to Notes.app: {}",
+            stderr
+        )));
+    }
+
+    Ok(())
+}
+
+/// Build the AppleScript that creates or appends to the note
+fn build_script(folder: &str, title: &str, body_html: &str) -> String {
+    let folder = escape_applescript_string(folder);
+    let title = escape_applescript_string(title);
+    let body = escape_applescript_string(body_html);
+
+    format!(
+        r#"tell application "Notes"
+    if not (exists folder "{folder}") then make new folder with properties {{name:"{folder}"}}
+    set targetFolder to folder "{folder}"
+    if exists note "{title}" of targetFolder then
+        set targetNote to note "{title}" of targetFolder
+        set body of targetNote to (body of targetNote) & "<br>" & "{body}"
+    else
+        make new note at targetFolder with properties {{name:"{title}", body:"<h1>{title}</h1>" & "{body}"}}
+    end if
+end tell"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_script_escapes_values() {
+        let script = build_script("Work \"notes\"", "Daily", "<p>a \\ b</p>");
+        assert!(script.contains(r#"folder "Work \"notes\"""#));
+        assert!(script.contains(r#"note "Daily" of targetFolder"#));
+        assert!(script.contains(r#""<p>a \\ b</p>""#));
+    }
+}
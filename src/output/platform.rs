@@ -0,0 +1,508 @@
+//! Platform integration for clipboard and notifications
+//!
+//! Each supported OS implements [`Platform`] using its standard command-line
+//! tools, so the clipboard and notification output methods work beyond macOS.
+
+use crate::config::ConfigManager;
+use crate::error::{RephraserError, Result};
+use crate::output::formatter::escape_applescript_string;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long the text of a notification that was never clicked is kept
+const CLICK_TEXT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Platform-specific clipboard and notification operations
+pub trait Platform: Send + Sync {
+    /// Platform name (e.g., "macos", "linux", "windows")
+    fn name(&self) -> &str;
+
+    /// Write text to the system clipboard
+    fn write_clipboard(&self, text: &str) -> Result<()>;
+
+    /// Write both an HTML and a plain-text flavor to the system clipboard
+    ///
+    /// Platforms without multi-flavor support fall back to plain text
+    fn write_clipboard_html(&self, html: &str, plain: &str) -> Result<()> {
+        let _ = html;
+        self.write_clipboard(plain)
+    }
+
+    /// Read text from the system clipboard
+    fn read_clipboard(&self) -> Result<String>;
+
+    /// Show a desktop notification
+    fn notify(&self, notification: &Notification) -> Result<()>;
+}
+
+/// A desktop notification
+#[derive(Debug, Clone, Default)]
+pub struct Notification<'a> {
+    pub title: &'a str,
+    pub subtitle: Option<&'a str>,
+
+    /// Body text, already truncated to a notification-friendly length
+    pub body: &'a str,
+
+    /// Text copied to the clipboard when the notification is clicked, where
+    /// the platform supports it
+    pub copy_on_click: Option<&'a str>,
+}
+
+impl Notification<'_> {
+    /// Body with the subtitle prepended, for platforms without subtitles
+    fn body_with_subtitle(&self) -> String {
+        match self.subtitle {
+            Some(subtitle) => format!("{}\n{}", subtitle, self.body),
+            None => self.body.to_string(),
+        }
+    }
+}
+
+/// Get the platform implementation for the current OS
+pub fn current() -> &'static dyn Platform {
+    #[cfg(target_os = "macos")]
+    {
+        &MacOs
+    }
+    #[cfg(target_os = "windows")]
+    {
+        &Windows
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        &Linux
+    }
+}
+
+/// macOS: pbcopy/pbpaste and AppleScript notifications
+pub struct MacOs;
+
+impl Platform for MacOs {
+    fn name(&self) -> &str {
+        "macos"
+    }
+
+    fn write_clipboard(&self, text: &str) -> Result<()> {
+        run_with_stdin("pbcopy", &[], text)
+    }
+
+    fn write_clipboard_html(&self, html: &str, plain: &str) -> Result<()> {
+        let hex: String = html.bytes().map(|b| format!("{:02X}", b)).collect();
+        let script = format!(
+            r#"set the clipboard to {{«class HTML»:«data HTML{}», string:"{}"}}"#,
+            hex,
+            escape_applescript_string(plain)
+        );
+        run_status("osascript", &["-e", &script])
+    }
+
+    fn read_clipboard(&self) -> Result<String> {
+        run_capture("pbpaste", &[])
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        // Remove newlines (notifications don't support them)
+        let single_line = notification.body.replace(['\n', '\r'], " ");
+
+        // terminal-notifier supports click actions; fall back to AppleScript
+        if let Some(text) = notification.copy_on_click {
+            match notify_clickable(notification, &single_line, text) {
+                Err(RephraserError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+                result => return result,
+            }
+        }
+
+        let mut script = format!(
+            r#"display notification "{}" with title "{}""#,
+            escape_applescript_string(&single_line),
+            escape_applescript_string(notification.title)
+        );
+        if let Some(subtitle) = notification.subtitle {
+            script.push_str(&format!(
+                r#" subtitle "{}""#,
+                escape_applescript_string(subtitle)
+            ));
+        }
+        run_status("osascript", &["-e", &script])
+    }
+}
+
+/// Linux: wl-copy/wl-paste on Wayland, xclip on X11, notify-send
+pub struct Linux;
+
+impl Linux {
+    fn is_wayland() -> bool {
+        std::env::var_os("WAYLAND_DISPLAY").is_some()
+    }
+}
+
+impl Platform for Linux {
+    fn name(&self) -> &str {
+        "linux"
+    }
+
+    fn write_clipboard(&self, text: &str) -> Result<()> {
+        let (program, args) = linux_copy_command(Self::is_wayland());
+        run_with_stdin(program, args, text)
+    }
+
+    fn read_clipboard(&self) -> Result<String> {
+        let (program, args) = linux_paste_command(Self::is_wayland());
+        run_capture(program, args)
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        run_status(
+            "notify-send",
+            &[notification.title, &notification.body_with_subtitle()],
+        )
+    }
+}
+
+/// Windows: PowerShell clipboard cmdlets and a tray balloon notification
+pub struct Windows;
+
+/// Shows a balloon tip; title and body are passed via environment variables
+/// to avoid quoting issues
+const WINDOWS_NOTIFY_SCRIPT: &str = "Add-Type -AssemblyName System.Windows.Forms; \
+$n = New-Object System.Windows.Forms.NotifyIcon; \
+$n.Icon = [System.Drawing.SystemIcons]::Information; \
+$n.Visible = $true; \
+$n.ShowBalloonTip(5000, $env:REPHRASER_TITLE, $env:REPHRASER_BODY, 'None'); \
+Start-Sleep -Seconds 5; $n.Dispose()";
+
+/// Copies stdin to the clipboard
+///
+/// Like [`WINDOWS_PASTE_SCRIPT`], it switches the console to UTF-8 first:
+/// PowerShell otherwise reads stdin and writes stdout in the console's OEM
+/// code page, which garbles Japanese and most other non-ASCII text.
+const WINDOWS_COPY_SCRIPT: &str = "[Console]::InputEncoding = [Console]::OutputEncoding = \
+[Text.Encoding]::UTF8; \
+Set-Clipboard -Value ([Console]::In.ReadToEnd())";
+
+/// Prints the clipboard
+const WINDOWS_PASTE_SCRIPT: &str = "[Console]::InputEncoding = [Console]::OutputEncoding = \
+[Text.Encoding]::UTF8; \
+Get-Clipboard -Raw";
+
+impl Platform for Windows {
+    fn name(&self) -> &str {
+        "windows"
+    }
+
+    fn write_clipboard(&self, text: &str) -> Result<()> {
+        run_with_stdin(
+            "powershell",
+            &["-NoProfile", "-Command", WINDOWS_COPY_SCRIPT],
+            text,
+        )
+    }
+
+    fn read_clipboard(&self) -> Result<String> {
+        run_capture(
+            "powershell",
+            &["-NoProfile", "-Command", WINDOWS_PASTE_SCRIPT],
+        )
+    }
+
+    fn notify(&self, notification: &Notification) -> Result<()> {
+        let status = Command::new("powershell")
+            .args(["-NoProfile", "-Command", WINDOWS_NOTIFY_SCRIPT])
+            .env("REPHRASER_TITLE", notification.title)
+            .env("REPHRASER_BODY", notification.body_with_subtitle())
+            .stdout(Stdio::null())
+            .status()
+            .map_err(|e| RephraserError::Output(format!("Failed to execute powershell: {}", e)))?;
+
+        if !status.success() {
+            return Err(RephraserError::Output(format!(
+                "powershell exited with status: {}",
+                status
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Show a notification via terminal-notifier that copies `text` when clicked
+///
+/// Returns an `Io` error of kind `NotFound` when terminal-notifier is not
+/// installed.
+fn notify_clickable(notification: &Notification, body: &str, text: &str) -> Result<()> {
+    // The full text is handed over via a file, so it does not need escaping
+    let path = write_click_text(&ConfigManager::new()?.notification_dir(), text)?;
+    let execute = format!(
+        "/usr/bin/pbcopy < {}; rm -f {}",
+        shell_quote(&path.to_string_lossy()),
+        shell_quote(&path.to_string_lossy())
+    );
+
+    let mut command = Command::new("terminal-notifier");
+    command
+        .args(["-title", notification.title])
+        .args(["-message", body])
+        .args(["-execute", &execute]);
+    if let Some(subtitle) = notification.subtitle {
+        command.args(["-subtitle", subtitle]);
+    }
+
+    let output = command.output().inspect_err(|_| {
+        let _ = std::fs::remove_file(&path);
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RephraserError::Output(format!(
+            "terminal-notifier failed: {}",
+            stderr
+        )));
+    }
+
+    Ok(())
+}
+
+/// Write the text a notification copies when clicked to a new file in
+/// `dir`, readable by the user only
+///
+/// Clicking removes the file; those of notifications that were never
+/// clicked are removed by a later notification once they are older than
+/// [`CLICK_TEXT_TTL`].
+fn write_click_text(dir: &Path, text: &str) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    remove_stale(dir, CLICK_TEXT_TTL);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let path = dir.join(format!("{}-{}.txt", std::process::id(), nanos));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(&path)?.write_all(text.as_bytes())?;
+    Ok(path)
+}
+
+/// Remove the files in `dir` last modified more than `ttl` ago
+fn remove_stale(dir: &Path, ttl: Duration) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > ttl));
+        if stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Open a URL with the app registered for its scheme
+///
+/// # Errors
+/// * If the opener could not be run or failed
+pub fn open_url(url: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        // `start` would treat the `&` between parameters as a command separator
+        let mut c = Command::new("rundll32");
+        c.arg("url.dll,FileProtocolHandler");
+        c
+    } else {
+        Command::new("xdg-open")
+    };
+
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .arg(url)
+        .status()
+        .map_err(|e| RephraserError::Output(format!("Failed to execute {}: {}", program, e)))?;
+
+    if !status.success() {
+        return Err(RephraserError::Output(format!(
+            "{} exited with status: {}",
+            program, status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Quote a string for safe use as a single POSIX shell word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Clipboard write command for Linux
+fn linux_copy_command(wayland: bool) -> (&'static str, &'static [&'static str]) {
+    if wayland {
+        ("wl-copy", &[])
+    } else {
+        ("xclip", &["-selection", "clipboard"])
+    }
+}
+
+/// Clipboard read command for Linux
+fn linux_paste_command(wayland: bool) -> (&'static str, &'static [&'static str]) {
+    if wayland {
+        ("wl-paste", &["--no-newline"])
+    } else {
+        ("xclip", &["-selection", "clipboard", "-o"])
+    }
+}
+
+/// Run a command, writing `input` to its stdin
+fn run_with_stdin(program: &str, args: &[&str], input: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| RephraserError::Output(format!("Failed to spawn {}: {}", program, e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).map_err(|e| {
+            RephraserError::Output(format!("Failed to write to {} stdin: {}", program, e))
+        })?;
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| RephraserError::Output(format!("Failed to wait for {}: {}", program, e)))?;
+
+    if !status.success() {
+        return Err(RephraserError::Output(format!(
+            "{} exited with status: {}",
+            program, status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Run a command and capture its stdout
+fn run_capture(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| RephraserError::Input(format!("Failed to execute {}: {}", program, e)))?;
+
+    if !output.status.success() {
+        return Err(RephraserError::Input(format!(
+            "{} exited with status: {}",
+            program, output.status
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run a command, failing with its stderr if it does not succeed
+fn run_status(program: &str, args: &[&str]) -> Result<()> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| RephraserError::Output(format!("Failed to execute {}: {}", program, e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(RephraserError::Output(format!(
+            "{} failed: {}",
+            program, stderr
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_platform() {
+        let expected = if cfg!(target_os = "macos") {
+            "macos"
+        } else if cfg!(target_os = "windows") {
+            "windows"
+        } else {
+            "linux"
+        };
+        assert_eq!(current().name(), expected);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/tmp/a b.txt"), "'/tmp/a b.txt'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn test_windows_clipboard_is_utf8() {
+        let utf8 = "[Console]::InputEncoding = [Console]::OutputEncoding = [Text.Encoding]::UTF8;";
+        for script in [WINDOWS_COPY_SCRIPT, WINDOWS_PASTE_SCRIPT] {
+            assert!(script.starts_with(utf8), "{}", script);
+        }
+    }
+
+    #[test]
+    fn test_write_click_text() {
+        let dir = std::env::temp_dir().join(format!("rephraser-click-{}", std::process::id()));
+        let path = write_click_text(&dir, "こんにちは").unwrap();
+        assert!(path.starts_with(&dir));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "こんにちは");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // The text of a notification that was never clicked goes once stale
+        let stale = SystemTime::now() - CLICK_TEXT_TTL - Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(stale)
+            .unwrap();
+        let next = write_click_text(&dir, "next").unwrap();
+        assert!(!path.exists());
+        assert!(next.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_body_with_subtitle() {
+        let notification = Notification {
+            title: "Rephraser",
+            subtitle: Some("Truncated"),
+            body: "text",
+            copy_on_click: None,
+        };
+        assert_eq!(notification.body_with_subtitle(), "Truncated\ntext");
+    }
+
+    #[test]
+    fn test_linux_clipboard_commands() {
+        assert_eq!(linux_copy_command(true).0, "wl-copy");
+        assert_eq!(
+            linux_copy_command(false),
+            ("xclip", &["-selection", "clipboard"][..])
+        );
+        assert_eq!(linux_paste_command(true).0, "wl-paste");
+        assert!(linux_paste_command(false).1.contains(&"-o"));
+    }
+}
@@ -0,0 +1,50 @@
+//! Markdown preview output
+
+use crate::error::{RephraserError, Result};
+use crate::output::editor::temp_path;
+use crate::output::markdown;
+use std::fs;
+use std::process::{Command, Stdio};
+
+/// Render text as Markdown and open it in a preview window
+///
+/// On macOS the rendered HTML opens in a QuickLook panel; elsewhere it opens
+/// in the default browser.
+///
+/// # Errors
+/// Returns an error if:
+/// - The temp file could not be written
+/// - The preview could not be opened
+pub fn show_preview(text: &str, title: &str) -> Result<()> {
+    let path = temp_path("html");
+    fs::write(&path, markdown::to_document(text, title))?;
+
+    let mut command = if cfg!(target_os = "macos") {
+        let mut c = Command::new("qlmanage");
+        c.arg("-p");
+        c
+    } else if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    } else {
+        Command::new("xdg-open")
+    };
+
+    // qlmanage blocks until the panel is closed and is chatty on stdout
+    let status = command
+        .arg(&path)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| RephraserError::Output(format!("Failed to open preview: {}", e)))?;
+
+    if !status.success() {
+        return Err(RephraserError::Output(format!(
+            "Preview exited with status: {}",
+            status
+        )));
+    }
+
+    Ok(())
+}
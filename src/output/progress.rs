@@ -0,0 +1,104 @@
+//! Progress display while waiting for the LLM
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::IsTerminal;
+use std::time::Duration;
+
+/// Spinner shown on stderr during a long-running call
+///
+/// Does nothing when disabled or when stdout is not a TTY (e.g. when run
+/// from Automator or piped). The spinner is cleared when dropped.
+pub struct Spinner {
+    bar: Option<ProgressBar>,
+}
+
+impl Spinner {
+    /// Start a spinner with the given message
+    ///
+    /// # Arguments
+    /// * `message` - Text shown next to the spinner
+    /// * `enabled` - Set to false to suppress the spinner (e.g. `--quiet`)
+    pub fn start(message: impl Into<String>, enabled: bool) -> Self {
+        if !enabled || !std::io::stdout().is_terminal() {
+            return Self { bar: None };
+        }
+
+        let bar = ProgressBar::new_spinner();
+        if let Ok(style) = ProgressStyle::with_template("{spinner} {msg} ({elapsed})") {
+            bar.set_style(style);
+        }
+        bar.set_message(message.into());
+        bar.enable_steady_tick(Duration::from_millis(100));
+
+        Self { bar: Some(bar) }
+    }
+
+    /// Whether the spinner is being displayed
+    pub fn is_visible(&self) -> bool {
+        self.bar.is_some()
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+/// Progress bar on stderr counting finished items of a batch
+///
+/// Hidden under the same conditions as [`Spinner`]. Cleared when dropped.
+pub struct ProgressCounter {
+    bar: Option<ProgressBar>,
+}
+
+impl ProgressCounter {
+    /// Start a progress bar for `total` items
+    pub fn start(message: impl Into<String>, total: usize, enabled: bool) -> Self {
+        if !enabled || !std::io::stdout().is_terminal() {
+            return Self { bar: None };
+        }
+
+        let bar = ProgressBar::new(total as u64);
+        if let Ok(style) = ProgressStyle::with_template("{msg} [{bar:30}] {pos}/{len} ({elapsed})")
+        {
+            bar.set_style(style.progress_chars("=> "));
+        }
+        bar.set_message(message.into());
+        bar.enable_steady_tick(Duration::from_millis(100));
+
+        Self { bar: Some(bar) }
+    }
+
+    /// Count one more finished item
+    pub fn inc(&self) {
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+    }
+}
+
+impl Drop for ProgressCounter {
+    fn drop(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_spinner_is_hidden() {
+        let spinner = Spinner::start("Waiting", false);
+        assert!(!spinner.is_visible());
+
+        let progress = ProgressCounter::start("Waiting", 3, false);
+        progress.inc();
+        assert!(progress.bar.is_none());
+    }
+}
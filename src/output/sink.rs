@@ -0,0 +1,152 @@
+//! Output to sink plugins
+//!
+//! See [`crate::plugins`] for the protocol.
+
+use crate::config::PluginsConfig;
+use crate::error::{RephraserError, Result};
+use crate::output::OutputContext;
+use crate::plugins::{PluginRegistry, SINK_PREFIX};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+/// Send the result to the sink plugin `name`
+///
+/// # Errors
+/// Returns an error if:
+/// - No plugin is configured or found on PATH for `name`
+/// - The plugin can't be started, or exits without answering
+/// - The plugin answers with an error
+pub fn send_to_sink(
+    plugins: &PluginsConfig,
+    name: &str,
+    context: &OutputContext,
+    text: &str,
+) -> Result<()> {
+    let plugin = PluginRegistry::from_config(plugins)
+        .sink(name)
+        .ok_or_else(|| {
+            RephraserError::Config(format!(
+                "No sink plugin '{}': add it under [plugins.sinks] or put {}{} on PATH",
+                name, SINK_PREFIX, name
+            ))
+        })?;
+
+    let mut child = Command::new(&plugin.command)
+        .args(&plugin.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| {
+            RephraserError::Output(format!(
+                "Failed to start the sink plugin '{}': {}",
+                plugin.command, e
+            ))
+        })?;
+
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "sink",
+        "params": {
+            "text": text,
+            "action": context.action,
+            "action_display_name": context.action_display_name,
+            "provider": context.provider,
+            "model": context.model,
+        },
+    });
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    writeln!(stdin, "{}", request)?;
+    // Closing stdin tells plugins that read to the end that nothing follows
+    drop(stdin);
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let response = read_response(BufReader::new(stdout));
+    let _ = child.wait();
+    response.map_err(|message| {
+        RephraserError::Output(format!("Sink plugin '{}' failed: {}", name, message))
+    })
+}
+
+/// Wait for the answer to request 1, skipping other messages
+fn read_response(reader: impl BufRead) -> std::result::Result<(), String> {
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if message.get("id") != Some(&json!(1)) {
+            continue;
+        }
+        return match message.get("error") {
+            Some(error) => Err(error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error")
+                .to_string()),
+            None => Ok(()),
+        };
+    }
+    Err("exited without answering".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_response() {
+        let answered = "{\"jsonrpc\":\"2.0\",\"method\":\"log\"}\nnot json\n\
+                        {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{}}\n";
+        assert_eq!(read_response(answered.as_bytes()), Ok(()));
+
+        let failed = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"vault locked"}}"#;
+        assert_eq!(
+            read_response(failed.as_bytes()),
+            Err("vault locked".to_string())
+        );
+
+        assert!(read_response("".as_bytes()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send_to_sink() {
+        let dir = std::env::temp_dir().join(format!("rephraser-sink-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let received = dir.join("received");
+        let plugins = PluginsConfig {
+            sinks: [(
+                "file".to_string(),
+                crate::config::PluginCommand {
+                    command: "sh".to_string(),
+                    args: vec![
+                        "-c".to_string(),
+                        format!(
+                            "cat > '{}'; echo '{{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{{}}}}'",
+                            received.display()
+                        ),
+                    ],
+                },
+            )]
+            .into(),
+            discover: false,
+            ..PluginsConfig::default()
+        };
+        let context = OutputContext {
+            action: "polite".to_string(),
+            ..OutputContext::default()
+        };
+
+        send_to_sink(&plugins, "file", &context, "こんにちは").unwrap();
+        let request: Value =
+            serde_json::from_str(&std::fs::read_to_string(&received).unwrap()).unwrap();
+        assert_eq!(request["method"], "sink");
+        assert_eq!(request["params"]["text"], "こんにちは");
+        assert_eq!(request["params"]["action"], "polite");
+
+        assert!(send_to_sink(&plugins, "missing", &context, "hi").is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
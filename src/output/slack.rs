@@ -0,0 +1,160 @@
+//! Slack output
+
+use crate::config::SlackConfig;
+use crate::error::{RephraserError, Result};
+use crate::output::text::fit_chars;
+use crate::output::OutputContext;
+use serde_json::{json, Value};
+
+/// Endpoint for posting messages with a bot token
+const POST_MESSAGE_URL: &str = "https://slack.com/api/chat.postMessage";
+
+/// Maximum length of the text in one section block
+const MAX_SECTION_CHARS: usize = 3000;
+
+/// Post the result to Slack
+///
+/// Uses the bot token if its variable is set, and the webhook otherwise.
+///
+/// # Errors
+/// Returns an error if:
+/// - Neither the bot token nor the webhook URL is set
+/// - A bot token is used without a channel
+/// - Slack rejects the message
+pub fn post_to_slack(config: &SlackConfig, context: &OutputContext, text: &str) -> Result<()> {
+    let header = context.template_engine().render(&config.context)?;
+    let mut payload = build_payload(&header, text);
+
+    let token = std::env::var(&config.token_env).ok();
+    let target = match token {
+        Some(token) => {
+            let channel = config.channel.as_deref().ok_or_else(|| {
+                RephraserError::Config(
+                    "Set `channel` under [output.slack] to post with a bot token".to_string(),
+                )
+            })?;
+            payload["channel"] = json!(channel);
+            Target::Api(token)
+        }
+        None => Target::Webhook(std::env::var(&config.webhook_url_env).map_err(|_| {
+            RephraserError::Config(format!(
+                "Set '{}' or '{}' to post to Slack",
+                config.webhook_url_env, config.token_env
+            ))
+        })?),
+    };
+
+    // The output handler is synchronous and may run inside the async
+    // runtime, where blocking requests are not allowed, so send from a
+    // separate thread
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| send(&target, POST_MESSAGE_URL, &payload))
+            .join()
+            .unwrap_or_else(|_| Err(RephraserError::Output("Slack request panicked".to_string())))
+    })
+}
+
+/// Where a message is posted
+enum Target {
+    /// Incoming webhook URL
+    Webhook(String),
+    /// Bot token for the Web API
+    Api(String),
+}
+
+/// Message with the context line and the result, split into section blocks
+/// short enough for Slack
+fn build_payload(header: &str, text: &str) -> Value {
+    let mut blocks = vec![json!({
+        "type": "context",
+        "elements": [{ "type": "mrkdwn", "text": header }],
+    })];
+    let mut rest = text;
+    while !rest.is_empty() {
+        let (chunk, tail) = rest.split_at(fit_chars(rest, MAX_SECTION_CHARS));
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": chunk },
+        }));
+        rest = tail;
+    }
+
+    json!({
+        // Shown in notifications and clients without block support
+        "text": text,
+        "blocks": blocks,
+    })
+}
+
+fn send(target: &Target, api_url: &str, payload: &Value) -> Result<()> {
+    let client = reqwest::blocking::Client::new();
+    let request = match target {
+        Target::Webhook(url) => client.post(url),
+        Target::Api(token) => client.post(api_url).bearer_auth(token),
+    };
+
+    let response = request
+        .json(payload)
+        .send()
+        .map_err(|e| RephraserError::Output(format!("Failed to post to Slack: {}", e)))?;
+    let status = response.status();
+    let body = response.text().unwrap_or_default();
+    if !status.is_success() {
+        return Err(RephraserError::Output(format!(
+            "Slack rejected the message ({}): {}",
+            status, body
+        )));
+    }
+
+    // The Web API reports errors in the body of a 200 response
+    if let Target::Api(_) = target {
+        let result: Value = serde_json::from_str(&body).unwrap_or_default();
+        if result["ok"] != json!(true) {
+            let error = result["error"].as_str().unwrap_or("unknown error");
+            return Err(RephraserError::Output(format!(
+                "Slack rejected the message: {}",
+                error
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_payload() {
+        let long = "あ".repeat(MAX_SECTION_CHARS + 1);
+        let payload = build_payload("Rephraser · 要約", &long);
+
+        let blocks = payload["blocks"].as_array().unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0]["elements"][0]["text"], "Rephraser · 要約");
+        assert_eq!(
+            blocks[1]["text"]["text"].as_str().unwrap().chars().count(),
+            MAX_SECTION_CHARS
+        );
+        assert_eq!(blocks[2]["text"]["text"], "あ");
+        assert_eq!(payload["text"], long.as_str());
+    }
+
+    #[test]
+    fn test_api_errors() {
+        let mut server = mockito::Server::new();
+        let mock = server
+            .mock("POST", "/api/chat.postMessage")
+            .match_header("authorization", "Bearer xoxb-test")
+            .with_body(r#"{"ok":false,"error":"channel_not_found"}"#)
+            .create();
+
+        let url = format!("{}/api/chat.postMessage", server.url());
+        let target = Target::Api("xoxb-test".to_string());
+        let err = send(&target, &url, &json!({ "text": "hi" })).unwrap_err();
+
+        mock.assert();
+        assert!(err.to_string().contains("channel_not_found"));
+    }
+}
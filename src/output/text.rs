@@ -0,0 +1,236 @@
+//! Grapheme-aware text measuring and truncation
+//!
+//! Cutting text at an arbitrary character can split what is displayed as a
+//! single symbol: an emoji built from several code points (👨‍👩‍👧, 👍🏽, 🇯🇵,
+//! 1️⃣), a letter with combining marks, or a kana with a combining voiced
+//! mark. Everything here cuts between extended grapheme clusters instead.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+const ZWJ: char = '\u{200D}';
+
+/// Emoji variation selector, requesting the emoji presentation
+const EMOJI_PRESENTATION: char = '\u{FE0F}';
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// Columns a grapheme cluster takes in a terminal or a notification
+fn grapheme_width(grapheme: &str) -> usize {
+    let Some(first) = grapheme.chars().next() else {
+        return 0;
+    };
+    // Emoji sequences and flags are shown as one wide symbol
+    if is_regional_indicator(first) || grapheme.contains([EMOJI_PRESENTATION, ZWJ]) {
+        return 2;
+    }
+    first.width().unwrap_or(0)
+}
+
+/// Display width of `text`, counting wide (e.g., CJK) characters and emoji
+/// as two columns
+pub fn width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Break `text` into lines no wider than `max_width` columns
+///
+/// Lines are broken after the last space that fits or, in text without
+/// spaces such as Japanese, between grapheme clusters. A cluster wider
+/// than `max_width` gets a line of its own.
+pub fn wrap(text: &str, max_width: usize) -> Vec<&str> {
+    let mut lines = Vec::new();
+    for mut rest in text.lines() {
+        while width(rest) > max_width {
+            let mut used = 0;
+            let mut end = 0;
+            for grapheme in rest.graphemes(true) {
+                used += grapheme_width(grapheme);
+                if used > max_width && end > 0 {
+                    break;
+                }
+                end += grapheme.len();
+            }
+            // A space right after the line ends it as well as one within
+            let breakable = if rest[end..].starts_with(' ') {
+                end + 1
+            } else {
+                end
+            };
+            match rest[..breakable].rfind(' ') {
+                Some(space) if space > 0 => {
+                    lines.push(&rest[..space]);
+                    rest = &rest[space + 1..];
+                }
+                _ => {
+                    lines.push(&rest[..end]);
+                    rest = &rest[end..];
+                }
+            }
+        }
+        lines.push(rest);
+    }
+    lines
+}
+
+/// Shorten `text` to at most `max_width` columns, ending with `ellipsis`
+/// when shortened
+pub fn truncate_to_width(text: &str, max_width: usize, ellipsis: &str) -> String {
+    if width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let available = max_width.saturating_sub(width(ellipsis));
+    let mut used = 0;
+    let mut end = 0;
+    for grapheme in text.graphemes(true) {
+        used += grapheme_width(grapheme);
+        if used > available {
+            break;
+        }
+        end += grapheme.len();
+    }
+    format!("{}{}", &text[..end], ellipsis)
+}
+
+/// Byte length of the longest start of `text` made of whole grapheme
+/// clusters and at most `max_chars` characters
+///
+/// Only if the first cluster alone is longer than that is it cut, at
+/// `max_chars` characters, so that the result is never empty for non-empty
+/// text.
+pub fn fit_chars(text: &str, max_chars: usize) -> usize {
+    let mut chars = 0;
+    let mut end = 0;
+    for grapheme in text.graphemes(true) {
+        chars += grapheme.chars().count();
+        if chars > max_chars {
+            break;
+        }
+        end += grapheme.len();
+    }
+
+    if end == 0 && max_chars > 0 {
+        return text
+            .char_indices()
+            .nth(max_chars)
+            .map_or(text.len(), |(i, _)| i);
+    }
+    end
+}
+
+/// Where to end a chunk of at most `max_chars` characters at the start of
+/// `text`, as a byte length
+///
+/// Ends after the last line break that fits, unless that would make the
+/// chunk less than half as long as it could be; otherwise between grapheme
+/// clusters.
+pub fn chunk_end(text: &str, max_chars: usize) -> usize {
+    let end = fit_chars(text, max_chars);
+    if end == text.len() {
+        return end;
+    }
+    match text[..end].rfind('\n') {
+        Some(newline) if newline + 1 >= end / 2 => newline + 1,
+        _ => end,
+    }
+}
+
+/// Split `text` into chunks of at most `max_chars` characters, as
+/// [`chunk_end`] does
+pub fn chunks(text: &str, max_chars: usize) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let (chunk, tail) = rest.split_at(chunk_end(rest, max_chars));
+        rest = tail;
+        Some(chunk)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("the quick brown fox", 10), ["the quick", "brown fox"]);
+        assert_eq!(
+            wrap("明日の会議に遅れます", 8),
+            ["明日の会", "議に遅れ", "ます"]
+        );
+        assert_eq!(wrap("first\n\nsecond", 10), ["first", "", "second"]);
+        assert_eq!(wrap("abcdefgh", 3), ["abc", "def", "gh"]);
+        // A cluster wider than the line still makes progress
+        assert_eq!(wrap("👍🏽x", 1), ["👍🏽", "x"]);
+    }
+
+    #[test]
+    fn test_graphemes() {
+        let text = "e\u{301}👨‍👩‍👧👍🏽🇯🇵🇺🇸1️⃣か\u{3099}한\u{1100}\u{1161}\r\nx";
+        let clusters: Vec<&str> = text.graphemes(true).collect();
+        assert_eq!(
+            clusters,
+            [
+                "e\u{301}",
+                "👨‍👩‍👧",
+                "👍🏽",
+                "🇯🇵",
+                "🇺🇸",
+                "1️⃣",
+                "か\u{3099}",
+                "한",
+                "\u{1100}\u{1161}",
+                "\r\n",
+                "x"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_width() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width("こんにちは"), 10);
+        assert_eq!(width("👨‍👩‍👧🇯🇵"), 4);
+        assert_eq!(width("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("short", 10, "..."), "short");
+        assert_eq!(truncate_to_width("abcdefghij", 8, "..."), "abcde...");
+        // A wide character that would straddle the limit is left out
+        assert_eq!(truncate_to_width("あいうえお", 8, "..."), "あい...");
+        assert_eq!(truncate_to_width("ab👨‍👩‍👧cd", 5, "..."), "ab...");
+        assert_eq!(truncate_to_width("👨‍👩‍👧👨‍👩‍👧👨‍👩‍👧", 5, "..."), "👨‍👩‍👧...");
+    }
+
+    #[test]
+    fn test_fit_chars() {
+        assert_eq!(fit_chars("abc", 5), 3);
+        assert_eq!(fit_chars("abc", 2), 2);
+        // The flag is two characters and does not fit in one
+        assert_eq!(fit_chars("a🇯🇵", 2), 1);
+        assert_eq!(fit_chars("🇯🇵", 1), "🇯🇵".char_indices().nth(1).unwrap().0);
+        assert_eq!(fit_chars("", 3), 0);
+    }
+
+    #[test]
+    fn test_chunks() {
+        let text = "line one\nline two\nabcdefghijklmnop";
+        assert_eq!(
+            chunks(text, 12).collect::<Vec<_>>(),
+            ["line one\n", "line two\n", "abcdefghijkl", "mnop"]
+        );
+        // A line break early in the chunk is not worth a short chunk
+        assert_eq!(
+            chunks("a\nbcdefgh", 6).collect::<Vec<_>>(),
+            ["a\nbcde", "fgh"]
+        );
+        assert_eq!(chunks("", 6).count(), 0);
+    }
+}
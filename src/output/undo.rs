@@ -0,0 +1,142 @@
+//! Previous clipboard contents, restored by `rephraser undo`
+//!
+//! Before Rephraser overwrites the clipboard, the text on it is pushed onto
+//! a small stack stored next to the config file. Each `undo` pops the most
+//! recent entry back onto the clipboard.
+
+use crate::config::ConfigManager;
+use crate::error::Result;
+use crate::output::clipboard;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Stack of previous clipboard contents, most recent last
+pub struct UndoStack {
+    path: PathBuf,
+    depth: usize,
+}
+
+impl UndoStack {
+    /// Stack stored at `path`, keeping at most `depth` entries
+    pub fn new(path: &Path, depth: usize) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            depth,
+        }
+    }
+
+    /// Stack stored in the config directory
+    pub fn open_default(depth: usize) -> Result<Self> {
+        Ok(Self::new(&ConfigManager::new()?.undo_path(), depth))
+    }
+
+    /// Save `text`, dropping the oldest entries beyond the depth
+    ///
+    /// Empty text and a repeat of the most recent entry are not saved.
+    pub fn push(&self, text: &str) -> Result<()> {
+        if self.depth == 0 || text.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = self.load()?;
+        if entries.last().map(String::as_str) == Some(text) {
+            return Ok(());
+        }
+        entries.push(text.to_string());
+        let excess = entries.len().saturating_sub(self.depth);
+        entries.drain(..excess);
+        self.save(&entries)
+    }
+
+    /// Remove and return the most recent entry
+    pub fn pop(&self) -> Result<Option<String>> {
+        let mut entries = self.load()?;
+        let text = entries.pop();
+        if text.is_some() {
+            self.save(&entries)?;
+        }
+        Ok(text)
+    }
+
+    fn load(&self) -> Result<Vec<String>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the entries, readable only by the user since clipboard
+    /// contents may be private
+    fn save(&self, entries: &[String]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string(entries)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&self.path, std::fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(())
+    }
+}
+
+/// Save the current clipboard text before it is overwritten
+///
+/// Best-effort like history: failures are logged and never stop the output.
+/// Nothing is saved when the clipboard holds no text.
+pub fn stash_clipboard(depth: usize) {
+    if depth == 0 {
+        return;
+    }
+    let text = match clipboard::read_text() {
+        Ok(text) => text,
+        Err(e) => {
+            debug!("Not saving the clipboard for undo: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = UndoStack::open_default(depth).and_then(|stack| stack.push(&text)) {
+        warn!("Failed to save the clipboard for undo: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_pop() {
+        let path = std::env::temp_dir().join(format!("rephraser-undo-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let stack = UndoStack::new(&path, 2);
+
+        assert_eq!(stack.pop().unwrap(), None);
+
+        stack.push("first").unwrap();
+        stack.push("second").unwrap();
+        stack.push("second").unwrap();
+        stack.push("").unwrap();
+        stack.push("third").unwrap();
+
+        // "first" was dropped to stay within the depth
+        assert_eq!(stack.pop().unwrap().as_deref(), Some("third"));
+        assert_eq!(stack.pop().unwrap().as_deref(), Some("second"));
+        assert_eq!(stack.pop().unwrap(), None);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let path = std::env::temp_dir().join(format!(
+            "rephraser-undo-disabled-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        UndoStack::new(&path, 0).push("text").unwrap();
+        assert!(!path.exists());
+    }
+}
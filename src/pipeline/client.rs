@@ -0,0 +1,281 @@
+//! Provider clients for running actions
+
+use crate::audit::AuditLog;
+use crate::cache::ResponseCache;
+use crate::config::{Config, ConfigManager, LlmConfig};
+#[cfg(unix)]
+use crate::daemon::{server::ClientFactory, DaemonClient};
+use crate::error::{RephraserError, Result};
+use crate::llm::{
+    AnthropicClient, AuditedClient, CachedClient, CircuitBreaker, CircuitClient, ClientRegistry,
+    FallbackClient, LlmClient, LlmParameters, MockLlmClient, OpenAiClient, PluginClient,
+    PrivacyClient, TimeoutClient,
+};
+use crate::plugins::{PluginRegistry, PROVIDER_PREFIX};
+use crate::privacy::PrivacyFilter;
+use crate::secrets;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::debug;
+
+/// Bytes in a megabyte, for the cache size limit
+pub(crate) const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Create an LLM client based on configuration
+///
+/// Requests go through the daemon when it is running and enabled, are
+/// recorded in the audit log if it is enabled, have sensitive text masked if
+/// privacy filtering is enabled, and are answered from the response cache
+/// when possible. For script actions, the script sends the requests
+pub(crate) fn create_llm_client(config: &Config) -> Result<Arc<dyn LlmClient>> {
+    let client = match daemon_client(config) {
+        Some(client) => client,
+        None => create_provider_client(&config.llm, config)?,
+    };
+
+    let client: Arc<dyn LlmClient> = match config.offline.fallback_llm(&config.llm) {
+        Some(fallback) => Arc::new(FallbackClient::new(
+            client,
+            create_provider_client(&fallback, config)?,
+        )),
+        None => client,
+    };
+
+    let client: Arc<dyn LlmClient> = match config.llm.parameters.timeout_secs {
+        Some(secs) => Arc::new(TimeoutClient::new(client, Duration::from_secs(secs))),
+        None => client,
+    };
+
+    // Audit inside the cache: cached answers send nothing to the provider
+    let client: Arc<dyn LlmClient> = if config.audit.enabled {
+        Arc::new(AuditedClient::new(
+            client,
+            AuditLog::from_config(&config.audit)?,
+        ))
+    } else {
+        client
+    };
+
+    // Mask outside the audit log so it records what was actually sent
+    let client: Arc<dyn LlmClient> = if config.privacy.enabled {
+        Arc::new(PrivacyClient::new(
+            client,
+            PrivacyFilter::from_config(&config.privacy)?,
+        ))
+    } else {
+        client
+    };
+
+    let client: Arc<dyn LlmClient> = if config.cache.enabled {
+        Arc::new(CachedClient::new(
+            client,
+            response_cache(&ConfigManager::new()?, config),
+            &config.llm.parameters,
+            config.cache.refresh,
+        ))
+    } else {
+        client
+    };
+
+    // Scripts run outside the cache so each of their requests is cached
+    match &config.scripting.script {
+        Some(script) => script_client(client, script, config),
+        None => Ok(client),
+    }
+}
+
+/// Wrap `client` in the script of a script action
+#[cfg(feature = "scripting")]
+fn script_client(
+    client: Arc<dyn LlmClient>,
+    script: &str,
+    config: &Config,
+) -> Result<Arc<dyn LlmClient>> {
+    Ok(Arc::new(crate::llm::ScriptClient::new(
+        client, script, config,
+    )?))
+}
+
+#[cfg(not(feature = "scripting"))]
+fn script_client(
+    _client: Arc<dyn LlmClient>,
+    _script: &str,
+    _config: &Config,
+) -> Result<Arc<dyn LlmClient>> {
+    Err(RephraserError::Config(
+        "Script actions require building with the `scripting` feature".to_string(),
+    ))
+}
+
+/// The response cache with the configured limits
+pub(crate) fn response_cache(config_manager: &ConfigManager, config: &Config) -> ResponseCache {
+    ResponseCache::new(
+        &config_manager.cache_dir(),
+        // Saturate so that a huge limit means "never" instead of overflowing
+        Duration::from_secs(config.cache.ttl_hours.saturating_mul(60 * 60)),
+        config.cache.max_size_mb.saturating_mul(BYTES_PER_MB),
+    )
+}
+
+/// Client that calls the provider `llm` is configured for directly
+///
+/// Clients are shared by every request in the process with the same
+/// settings, so batches and the server build each one once.
+fn create_provider_client(llm: &LlmConfig, config: &Config) -> Result<Arc<dyn LlmClient>> {
+    ClientRegistry::global().get(llm, |llm| {
+        build_provider_client(llm, config).map(|client| circuit_client(client, config))
+    })
+}
+
+/// Wrap `client` in the circuit breaker, unless it is disabled
+fn circuit_client(client: Arc<dyn LlmClient>, config: &Config) -> Arc<dyn LlmClient> {
+    if !config.circuit.enabled {
+        return client;
+    }
+    match ConfigManager::new() {
+        Ok(manager) => {
+            let breaker = CircuitBreaker::new(&config.circuit, manager.circuit_path());
+            Arc::new(CircuitClient::new(client, Arc::new(breaker)))
+        }
+        Err(_) => client,
+    }
+}
+
+/// Provider clients for every distinct LLM configuration in `config`
+///
+/// Covers the configured LLM, the providers and models actions override it
+/// with and the offline fallback, for health checks. Clients that can't be
+/// built, such as for lack of an API key, are returned as errors.
+pub(crate) fn provider_clients(config: &Config) -> Vec<(LlmConfig, Result<Arc<dyn LlmClient>>)> {
+    let mut llms = vec![config.llm.clone()];
+    for action in &config.actions {
+        if action.provider.is_none() && action.model.is_none() {
+            continue;
+        }
+        let mut llm = config.llm.clone();
+        if let Some(provider) = &action.provider {
+            llm.set_provider(provider);
+        }
+        if let Some(model) = &action.model {
+            llm.model = model.clone();
+        }
+        llms.push(llm);
+    }
+    llms.extend(config.offline.fallback_llm(&config.llm));
+
+    let mut seen = std::collections::HashSet::new();
+    llms.retain(|llm| seen.insert((llm.provider.clone(), llm.model.clone())));
+    llms.into_iter()
+        .map(|llm| {
+            let client = create_provider_client(&llm, config);
+            (llm, client)
+        })
+        .collect()
+}
+
+/// Check that a client for the configured provider can be built, such as
+/// that its API key is set
+pub(crate) fn check_provider(config: &Config) -> Result<()> {
+    create_provider_client(&config.llm, config).map(|_| ())
+}
+
+/// Build a client that calls the provider `llm` is configured for directly
+///
+/// Providers that are not built in are looked up among the plugins. API
+/// keys and plugins come from `config`.
+fn build_provider_client(llm: &LlmConfig, config: &Config) -> Result<Arc<dyn LlmClient>> {
+    let secrets = &config.secrets;
+    debug!(
+        "Using provider '{}' with model '{}'",
+        llm.provider, llm.model
+    );
+    let client: Arc<dyn LlmClient> = match llm.provider.as_str() {
+        "openai" => {
+            let api_key = secrets::api_key(secrets, llm)?;
+
+            Arc::new(
+                OpenAiClient::builder()
+                    .api_key(api_key)
+                    .model(&llm.model)
+                    .temperature(llm.parameters.temperature)
+                    .max_tokens(llm.parameters.max_tokens)
+                    .headers(&llm.headers)
+                    .build()?,
+            )
+        }
+        "anthropic" => {
+            let api_key = secrets::api_key(secrets, llm)?;
+
+            Arc::new(
+                AnthropicClient::builder()
+                    .api_key(api_key)
+                    .model(&llm.model)
+                    .temperature(llm.parameters.temperature)
+                    .max_tokens(llm.parameters.max_tokens)
+                    .headers(&llm.headers)
+                    .build()?,
+            )
+        }
+        "mock" => Arc::new(MockLlmClient::new()),
+        provider => {
+            let Some(command) = PluginRegistry::from_config(&config.plugins).provider(provider)
+            else {
+                return Err(RephraserError::Config(format!(
+                    "Unknown provider: {} (no plugin is configured for it and no {}{} is on PATH)",
+                    provider, PROVIDER_PREFIX, provider
+                )));
+            };
+            // Plugins get a key only if one is configured explicitly
+            let api_key = if secrets.providers.contains_key(provider) {
+                Some(secrets::api_key(secrets, llm)?)
+            } else {
+                None
+            };
+
+            Arc::new(PluginClient::new(
+                provider,
+                llm.model.clone(),
+                command,
+                api_key,
+                LlmParameters {
+                    temperature: llm.parameters.temperature,
+                    max_tokens: llm.parameters.max_tokens,
+                },
+            ))
+        }
+    };
+
+    Ok(client)
+}
+
+/// Factory the daemon creates provider clients with, using the daemon's own
+/// secret stores and plugins
+///
+/// The daemon keeps its own registry of the clients it builds.
+#[cfg(unix)]
+pub(crate) fn provider_client_factory(config: Config) -> Arc<ClientFactory> {
+    Arc::new(move |llm: &LlmConfig| {
+        build_provider_client(llm, &config).map(|client| circuit_client(client, &config))
+    })
+}
+
+/// Client for the running daemon, if it is enabled and listening
+#[cfg(unix)]
+fn daemon_client(config: &Config) -> Option<Arc<dyn LlmClient>> {
+    if !config.daemon.enabled {
+        return None;
+    }
+
+    let socket_path = ConfigManager::new().ok()?.socket_path();
+    let client = DaemonClient::connect(&socket_path, &config.llm)?;
+    debug!(
+        "Delegating LLM requests to the daemon at {}",
+        socket_path.display()
+    );
+    Some(Arc::new(client))
+}
+
+#[cfg(not(unix))]
+fn daemon_client(_config: &Config) -> Option<Arc<dyn LlmClient>> {
+    None
+}
@@ -0,0 +1,464 @@
+//! Running actions against a provider
+//!
+//! Every front end runs actions the same way: the CLI, the server, the
+//! daemon's clients and the library API ([`crate::api`]). An action's
+//! configuration is resolved with per-run overrides, its prompt built and
+//! passed through its hooks, the request sent through the configured client
+//! stack (daemon, fallback, audit log, privacy filter, cache, script) with
+//! guardrails enforced, and the run recorded in the history, usage database
+//! and metrics. Spinners, printing and delivering the result are left to
+//! the front end.
+
+mod client;
+mod record;
+
+#[cfg(unix)]
+pub(crate) use client::provider_client_factory;
+pub(crate) use client::{
+    check_provider, create_llm_client, provider_clients, response_cache, BYTES_PER_MB,
+};
+pub(crate) use record::record_usage;
+
+use crate::actions::guardrails::complete_validated;
+use crate::actions::hooks::Hooks;
+use crate::actions::{proofread, ActionResolver, ActionSelector};
+use crate::api::Observer;
+use crate::config::{Config, ValidationRules};
+use crate::error::Result;
+use crate::llm::{Completion, LlmClient};
+use crate::output::OutputContext;
+use record::record_history;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Per-run overrides of the LLM settings
+///
+/// Set from the CLI's `--provider`, `--model` and so on, or from the fields
+/// of a request to the server.
+#[derive(Debug, Clone, Default)]
+pub struct LlmOverrides {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    /// Seconds each attempt of a request may take
+    pub timeout: Option<u64>,
+    /// Neither use nor store cached responses
+    pub no_cache: bool,
+    /// Generate a fresh response even if one is cached, replacing it
+    pub refresh: bool,
+    /// Project or client the spend is attributed to
+    pub tag: Option<String>,
+    /// Tone for the actions, from [tones]
+    pub tone: Option<String>,
+}
+
+/// Configure the LLM for an action
+///
+/// The action's own provider, model and privacy setting take precedence
+/// over the config defaults, and per-run overrides take precedence over both.
+/// The action's script, if any, is set up to run
+pub(crate) fn configure_llm(config: &mut Config, action: &str, llm: &LlmOverrides) {
+    let action_llm = config.actions.iter().find(|a| a.name == action).map(|a| {
+        (
+            a.provider.clone(),
+            a.model.clone(),
+            a.privacy,
+            a.script.clone(),
+        )
+    });
+
+    if let Some((provider, model, privacy, script)) = action_llm {
+        config.scripting.script = script;
+        if let Some(provider) = provider {
+            config.llm.set_provider(&provider);
+        }
+        if let Some(model) = model {
+            config.llm.model = model;
+        }
+        if let Some(privacy) = privacy {
+            config.privacy.enabled = privacy;
+        }
+    }
+
+    apply_llm_overrides(config, llm);
+}
+
+/// Apply per-run LLM overrides to the loaded configuration
+pub(crate) fn apply_llm_overrides(config: &mut Config, llm: &LlmOverrides) {
+    if let Some(provider) = &llm.provider {
+        config.llm.set_provider(provider);
+    }
+    if let Some(model) = &llm.model {
+        config.llm.model = model.clone();
+    }
+    if let Some(temperature) = llm.temperature {
+        config.llm.parameters.temperature = temperature;
+    }
+    if let Some(timeout) = llm.timeout {
+        config.llm.parameters.timeout_secs = Some(timeout);
+    }
+    if llm.no_cache {
+        config.cache.enabled = false;
+    }
+    if llm.refresh {
+        config.cache.refresh = true;
+    }
+    if let Some(tag) = &llm.tag {
+        config.usage.tag = Some(tag.clone());
+    }
+    if let Some(tone) = &llm.tone {
+        config.tones.default = Some(tone.clone());
+    }
+}
+
+/// Action name recorded for ad-hoc prompts
+pub(crate) const AD_HOC_ACTION: &str = "prompt";
+
+/// Name under which proofreading runs are recorded
+pub(crate) const PROOFREAD_ACTION: &str = "proofread";
+
+/// A prompt ready to be sent, with the action and input it came from
+pub(crate) struct Request {
+    pub(crate) action: String,
+    pub(crate) text: String,
+    pub(crate) prompt: String,
+    pub(crate) rules: Option<ValidationRules>,
+    pub(crate) hooks: Hooks,
+}
+
+impl Request {
+    /// Resolve an action's prompt and guardrails for the given text
+    ///
+    /// The text is passed through the action's pre-hook first
+    pub(crate) async fn for_action(config: &Config, action: &str, text: String) -> Result<Self> {
+        let hooks = Hooks::for_action(config, action);
+        let text = hooks.before(action, text).await?;
+        let resolver = ActionResolver::new(config);
+        let prompt = resolver.resolve(action, &text)?;
+        let rules = resolver
+            .find_action(action)
+            .and_then(|a| a.validate.clone());
+
+        Ok(Self {
+            action: action.to_string(),
+            text,
+            prompt,
+            rules,
+            hooks,
+        })
+    }
+
+    /// Send the text as-is, bypassing the action system
+    pub(crate) fn ad_hoc(text: String) -> Self {
+        Self {
+            action: AD_HOC_ACTION.to_string(),
+            prompt: text.clone(),
+            text,
+            rules: None,
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// Ask for the corrections to the text rather than a rewrite
+    pub(crate) fn proofread(text: String) -> Self {
+        Self {
+            action: PROOFREAD_ACTION.to_string(),
+            prompt: proofread::prompt(&text),
+            text,
+            rules: Some(proofread::rules()),
+            hooks: Hooks::default(),
+        }
+    }
+}
+
+/// An action with its configuration, request and client, ready to be sent
+pub(crate) struct PreparedAction {
+    pub(crate) config: Config,
+    pub(crate) request: Request,
+    pub(crate) client: Arc<dyn LlmClient>,
+}
+
+impl PreparedAction {
+    /// Resolve `action` on `text`, with its own provider and model overrides
+    /// and then `llm`
+    pub(crate) async fn new(
+        base_config: &Config,
+        action: &str,
+        text: &str,
+        llm: &LlmOverrides,
+    ) -> Result<Self> {
+        let mut config = base_config.clone();
+        configure_llm(&mut config, action, llm);
+        let request = Request::for_action(&config, action, text.to_string()).await?;
+        let client = create_llm_client(&config)?;
+        Ok(Self {
+            config,
+            request,
+            client,
+        })
+    }
+
+    /// Send the request and pass the response to the post-hook
+    ///
+    /// With `on_token`, the response is streamed as in [`complete_request`].
+    pub(crate) async fn run(
+        self,
+        on_token: Option<&mut (dyn for<'t> FnMut(&'t str) + Send)>,
+    ) -> Result<ActionRun> {
+        let (completion, duration) =
+            complete_request(&self.config, self.client.as_ref(), &self.request, on_token).await?;
+        self.request
+            .hooks
+            .after(&self.request.action, &completion.text)
+            .await;
+
+        Ok(ActionRun {
+            action: self.request.action,
+            config: self.config,
+            client: self.client,
+            completion,
+            duration,
+        })
+    }
+}
+
+/// A completed action
+pub(crate) struct ActionRun {
+    pub(crate) action: String,
+    pub(crate) config: Config,
+    pub(crate) client: Arc<dyn LlmClient>,
+    pub(crate) completion: Completion,
+    pub(crate) duration: Duration,
+}
+
+impl ActionRun {
+    /// Provider and model that answered, such as the offline fallback
+    /// rather than the configured provider
+    pub(crate) fn provider_and_model(&self) -> (&str, &str) {
+        self.completion.provider_and_model(self.client.as_ref())
+    }
+
+    /// The response decorated with the output template, if any
+    pub(crate) fn output(&self) -> Result<String> {
+        let (provider, model) = self.provider_and_model();
+        let context = OutputContext {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            ..output_context(&self.config, self.client.as_ref(), &self.action)
+        };
+        context.wrap(
+            self.config.output.template.as_deref(),
+            self.completion.text.clone(),
+        )
+    }
+}
+
+/// Ask the LLM which action fits `text` and prepare that action
+///
+/// The question is sent with `client` and recorded under `auto`. The chosen
+/// action gets its own client, with its provider, model, privacy setting
+/// and script and then `llm`, as when it is run by name.
+pub(crate) async fn choose_action(
+    config: &Config,
+    client: &dyn LlmClient,
+    text: &str,
+    llm: &LlmOverrides,
+) -> Result<PreparedAction> {
+    let selector = ActionSelector::new(&config.actions);
+    let prompt = selector.build_prompt(text)?;
+    let started = Instant::now();
+    let answer = client.complete_with_usage(&prompt).await;
+    record_usage(config, "auto", client, answer.as_ref(), started.elapsed());
+    let action = selector.parse_choice(&answer?.text)?.name.clone();
+    PreparedAction::new(config, &action, text, llm).await
+}
+
+/// Run one action, as an item of a batch, a request to the server or a call
+/// of the library API
+pub(crate) async fn run(
+    config: &Config,
+    action: &str,
+    text: &str,
+    llm: &LlmOverrides,
+) -> Result<ActionRun> {
+    PreparedAction::new(config, action, text, llm)
+        .await?
+        .run(None)
+        .await
+}
+
+/// Run one action, reporting its progress to `observer`
+///
+/// Tokens are passed to the observer as they arrive, as in
+/// [`complete_request`]. The response and errors are left to the caller to
+/// report.
+pub(crate) async fn observe(
+    base_config: &Config,
+    action: &str,
+    text: &str,
+    llm: &LlmOverrides,
+    observer: &dyn Observer,
+) -> Result<ActionRun> {
+    let mut config = base_config.clone();
+    configure_llm(&mut config, action, llm);
+    let request = Request::for_action(&config, action, text.to_string()).await?;
+    observer.on_prompt_built(action, &request.prompt);
+    let client = create_llm_client(&config)?;
+    observer.on_request_start(action, client.provider_name(), client.model_name());
+
+    let mut on_token = |token: &str| observer.on_token(action, token);
+    PreparedAction {
+        config,
+        request,
+        client,
+    }
+    .run(Some(&mut on_token))
+    .await
+}
+
+/// Send a request and record it in the usage database, metrics and history
+///
+/// Every way of running an action goes through here. With `on_token`, the
+/// response is streamed to it as it arrives; guardrails need the complete
+/// response, so for requests with validation rules it gets the validated
+/// response in one piece.
+pub(crate) async fn complete_request(
+    config: &Config,
+    client: &dyn LlmClient,
+    request: &Request,
+    on_token: Option<&mut (dyn for<'t> FnMut(&'t str) + Send)>,
+) -> Result<(Completion, Duration)> {
+    let started = Instant::now();
+    let result = match on_token {
+        Some(on_token) if request.rules.is_none() => {
+            client.complete_stream(&request.prompt, on_token).await
+        }
+        on_token => complete_validated(client, &request.prompt, request.rules.as_ref())
+            .await
+            .inspect(|completion| {
+                if let Some(on_token) = on_token {
+                    on_token(&completion.text);
+                }
+            }),
+    };
+    let duration = started.elapsed();
+    record_usage(config, &request.action, client, result.as_ref(), duration);
+    let completion = result?;
+    tracing::info!("Action '{}' completed in {:?}", request.action, duration);
+    record_history(config, client, request, &completion, duration);
+
+    Ok((completion, duration))
+}
+
+/// Build the output context for an action run
+pub(crate) fn output_context(
+    config: &Config,
+    client: &dyn LlmClient,
+    action: &str,
+) -> OutputContext {
+    let display_name = config
+        .actions
+        .iter()
+        .find(|a| a.name == action)
+        .map(|a| a.display_name.clone())
+        .unwrap_or_else(|| action.to_string());
+
+    OutputContext {
+        action: action.to_string(),
+        action_display_name: display_name,
+        provider: client.provider_name().to_string(),
+        model: client.model_name().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{ClientRegistry, MockLlmClient};
+    use async_trait::async_trait;
+
+    /// Client giving the same answer to every prompt
+    struct Answer(&'static str);
+
+    #[async_trait]
+    impl LlmClient for Answer {
+        async fn complete(&self, _prompt: &str) -> Result<String> {
+            Ok(self.0.to_string())
+        }
+
+        fn provider_name(&self) -> &str {
+            "answer"
+        }
+
+        fn model_name(&self) -> &str {
+            "answer"
+        }
+    }
+
+    fn config() -> Config {
+        let mut config = Config::mock();
+        config.cache.enabled = false;
+        config
+    }
+
+    #[tokio::test]
+    async fn test_chosen_action_uses_its_privacy_setting() {
+        let mut config = config();
+        config.llm.model = "auto-privacy".to_string();
+        config.privacy.enabled = false;
+        let polite = config.actions.iter_mut().find(|a| a.name == "polite");
+        polite.unwrap().privacy = Some(true);
+
+        // Requests for the chosen action reach this provider client
+        let provider = Arc::new(MockLlmClient::new());
+        let registered = Arc::clone(&provider) as Arc<dyn LlmClient>;
+        ClientRegistry::global()
+            .get(&config.llm, |_| Ok(registered))
+            .unwrap();
+
+        let prepared = choose_action(
+            &config,
+            &Answer("polite"),
+            "Write to bob@example.com",
+            &LlmOverrides::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(prepared.request.action, "polite");
+        prepared.run(None).await.unwrap();
+
+        let prompts = provider.prompts();
+        assert_eq!(prompts.len(), 1);
+        assert!(prompts[0].contains("[EMAIL_1]"));
+        assert!(!prompts[0].contains("bob@example.com"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_chosen_action_runs_its_script() {
+        let path = std::env::temp_dir().join(format!("rephraser-auto-{}.rhai", std::process::id()));
+        std::fs::write(&path, r#""scripted""#).unwrap();
+        let mut config = config();
+        let mut action = config.actions[0].clone();
+        action.name = "scripted".to_string();
+        action.script = Some(path.display().to_string());
+        config.actions.push(action);
+
+        let prepared = choose_action(
+            &config,
+            &Answer("scripted"),
+            "hello",
+            &LlmOverrides::default(),
+        )
+        .await;
+        std::fs::remove_file(&path).unwrap();
+
+        #[cfg(feature = "scripting")]
+        assert_eq!(
+            prepared.unwrap().run(None).await.unwrap().completion.text,
+            "scripted"
+        );
+        // Without scripting, the action can't run rather than skip its script
+        #[cfg(not(feature = "scripting"))]
+        assert!(prepared.err().unwrap().to_string().contains("scripting"));
+    }
+}
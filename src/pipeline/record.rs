@@ -0,0 +1,128 @@
+//! Recording runs in the history, usage database and metrics
+//!
+//! All of it is best-effort: failures are logged and never abort a run.
+
+use super::Request;
+use crate::config::manager::expand_home;
+use crate::config::{Config, ConfigManager};
+use crate::error::RephraserError;
+use crate::history::{HistoryStore, NewEntry};
+use crate::llm::pricing::estimate_cost;
+use crate::llm::{Completion, LlmClient, TokenUsage};
+use crate::metrics::store::write_atomically;
+use crate::metrics::MetricsStore;
+use crate::usage::{UsageRecord, UsageStore};
+use std::time::Duration;
+use tracing::warn;
+
+/// Record a transformation in the history database, if enabled
+///
+/// History is best-effort: failures never abort the transformation
+pub(super) fn record_history(
+    config: &Config,
+    client: &dyn LlmClient,
+    request: &Request,
+    completion: &Completion,
+    duration: Duration,
+) {
+    if !config.history.enabled {
+        return;
+    }
+
+    let Ok(config_manager) = ConfigManager::new() else {
+        return;
+    };
+    let (provider, model) = completion.provider_and_model(client);
+    let recorded = HistoryStore::open(&config_manager.history_path()).and_then(|store| {
+        store.record(&NewEntry {
+            action: &request.action,
+            input: &request.text,
+            output: &completion.text,
+            provider,
+            model,
+            prompt: &request.prompt,
+            cost_usd: estimate_cost(model, completion.usage),
+            duration,
+        })
+    });
+    if let Err(e) = recorded {
+        warn!("Failed to record history: {}", e);
+    }
+}
+
+/// Record an LLM request in the usage database and the metrics, if enabled
+///
+/// Like history, usage tracking is best-effort. A completion is recorded
+/// under the provider and model that gave it, such as the offline fallback.
+pub(crate) fn record_usage(
+    config: &Config,
+    action: &str,
+    client: &dyn LlmClient,
+    outcome: std::result::Result<&Completion, &RephraserError>,
+    duration: Duration,
+) {
+    let (provider, model) = match outcome {
+        Ok(completion) => completion.provider_and_model(client),
+        Err(_) => (client.provider_name(), client.model_name()),
+    };
+    record_metrics(config, action, provider, outcome.is_ok(), duration);
+    if !config.usage.enabled {
+        return;
+    }
+
+    let Ok(config_manager) = ConfigManager::new() else {
+        return;
+    };
+    let (usage, error) = match outcome {
+        Ok(completion) => (completion.usage, None),
+        Err(e) => (TokenUsage::default(), Some(e.to_string())),
+    };
+    let record = UsageRecord {
+        action,
+        provider,
+        model,
+        usage,
+        cost_usd: error
+            .is_none()
+            .then(|| estimate_cost(model, usage))
+            .flatten(),
+        duration,
+        error: error.as_deref(),
+        tag: config.usage.tag.as_deref(),
+    };
+
+    let recorded =
+        UsageStore::open(&config_manager.usage_path()).and_then(|store| store.record(&record));
+    if let Err(e) = recorded {
+        warn!("Failed to record usage: {}", e);
+    }
+}
+
+/// Count an LLM request in the metrics, if enabled
+///
+/// Also rewrites the Prometheus text file, if one is configured
+fn record_metrics(
+    config: &Config,
+    action: &str,
+    provider: &str,
+    success: bool,
+    duration: Duration,
+) {
+    if !config.metrics.enabled {
+        return;
+    }
+
+    let Ok(config_manager) = ConfigManager::new() else {
+        return;
+    };
+    let store = MetricsStore::new(&config_manager.metrics_path());
+    let recorded = store
+        .record(provider, action, duration, success)
+        .and_then(|metrics| match &config.metrics.textfile {
+            Some(path) => write_atomically(&expand_home(path), &metrics.to_prometheus()),
+            None => Ok(()),
+        });
+    if let Err(e) = recorded {
+        warn!("Failed to record metrics: {}", e);
+    }
+}
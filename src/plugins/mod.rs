@@ -0,0 +1,30 @@
+//! Providers and output sinks implemented by external programs
+//!
+//! A provider plugin is any executable speaking JSON-RPC 2.0 on stdin and
+//! stdout, one message per line. Rephraser starts it on first use and sends
+//! one `complete` request per prompt:
+//!
+//! ```text
+//! -> {"jsonrpc":"2.0","id":1,"method":"complete","params":{"model":"...","prompt":"...",
+//!     "temperature":0.7,"max_tokens":500,"stream":true}}
+//! <- {"jsonrpc":"2.0","method":"token","params":{"text":"Hel"}}       (optional)
+//! <- {"jsonrpc":"2.0","id":1,"result":{"text":"Hello",
+//!     "usage":{"prompt_tokens":12,"completion_tokens":2}}}               (usage optional)
+//! ```
+//!
+//! Failures are reported with a JSON-RPC `error` object. The plugin keeps
+//! running between requests and is stopped when Rephraser exits.
+//!
+//! A sink plugin receives results delivered with the `plugin:<name>` output
+//! method. It is started for each result, gets a single `sink` request and
+//! may exit once it has answered:
+//!
+//! ```text
+//! -> {"jsonrpc":"2.0","id":1,"method":"sink","params":{"text":"...","action":"polite",
+//!     "action_display_name":"...","provider":"openai","model":"..."}}
+//! <- {"jsonrpc":"2.0","id":1,"result":{}}
+//! ```
+
+pub mod registry;
+
+pub use registry::{PluginRegistry, PROVIDER_PREFIX, SINK_PREFIX};
@@ -0,0 +1,130 @@
+//! Lookup of plugins by name
+
+use crate::config::manager::expand_home;
+use crate::config::{PluginCommand, PluginsConfig};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Prefix of provider plugin executables found on PATH
+pub const PROVIDER_PREFIX: &str = "rephraser-provider-";
+
+/// Prefix of sink plugin executables found on PATH
+pub const SINK_PREFIX: &str = "rephraser-sink-";
+
+/// Plugins from the config and, optionally, PATH
+pub struct PluginRegistry {
+    providers: Vec<(String, PluginCommand)>,
+    sinks: Vec<(String, PluginCommand)>,
+    search_path: Option<Vec<PathBuf>>,
+}
+
+impl PluginRegistry {
+    /// Registry of the plugins configured under `[plugins]`
+    pub fn from_config(config: &PluginsConfig) -> Self {
+        let search_path = config.discover.then(|| {
+            std::env::var_os("PATH")
+                .map(|path| std::env::split_paths(&path).collect())
+                .unwrap_or_default()
+        });
+        let entries = |plugins: &BTreeMap<String, PluginCommand>| {
+            plugins
+                .iter()
+                .map(|(name, command)| (name.clone(), command.clone()))
+                .collect()
+        };
+        Self {
+            providers: entries(&config.providers),
+            sinks: entries(&config.sinks),
+            search_path,
+        }
+    }
+
+    /// Program implementing the provider `name`, if any
+    ///
+    /// Configured plugins take precedence over those found on PATH.
+    pub fn provider(&self, name: &str) -> Option<PluginCommand> {
+        self.find(&self.providers, PROVIDER_PREFIX, name)
+    }
+
+    /// Program receiving results for the sink `name`, if any
+    ///
+    /// Configured plugins take precedence over those found on PATH.
+    pub fn sink(&self, name: &str) -> Option<PluginCommand> {
+        self.find(&self.sinks, SINK_PREFIX, name)
+    }
+
+    fn find(
+        &self,
+        configured: &[(String, PluginCommand)],
+        prefix: &str,
+        name: &str,
+    ) -> Option<PluginCommand> {
+        if let Some((_, command)) = configured.iter().find(|(n, _)| n == name) {
+            return Some(PluginCommand {
+                command: expand_home(&command.command).to_string_lossy().into_owned(),
+                args: command.args.clone(),
+            });
+        }
+
+        let file_name = format!("{}{}", prefix, name);
+        self.search_path
+            .iter()
+            .flatten()
+            .map(|dir| dir.join(&file_name))
+            .find(|path| is_executable(path))
+            .map(|path| PluginCommand {
+                command: path.to_string_lossy().into_owned(),
+                args: Vec::new(),
+            })
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.with_extension("exe").is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_provider() {
+        let registry = PluginRegistry::from_config(&PluginsConfig {
+            providers: BTreeMap::from([(
+                "llama".to_string(),
+                PluginCommand {
+                    command: "/opt/llama/plugin".to_string(),
+                    args: vec!["--fast".to_string()],
+                },
+            )]),
+            sinks: BTreeMap::from([(
+                "obsidian".to_string(),
+                PluginCommand {
+                    command: "/opt/obsidian/sink".to_string(),
+                    args: Vec::new(),
+                },
+            )]),
+            discover: false,
+        });
+
+        let command = registry.provider("llama").unwrap();
+        assert_eq!(command.command, "/opt/llama/plugin");
+        assert_eq!(command.args, ["--fast"]);
+        assert!(registry.provider("unknown").is_none());
+
+        // Providers and sinks are looked up separately
+        assert_eq!(
+            registry.sink("obsidian").unwrap().command,
+            "/opt/obsidian/sink"
+        );
+        assert!(registry.sink("llama").is_none());
+    }
+}
@@ -0,0 +1,172 @@
+//! Placeholder substitution for emails, phone numbers, API keys and custom
+//! patterns
+//!
+//! Each distinct match is replaced with a numbered placeholder such as
+//! `[EMAIL_1]` before the prompt leaves the machine. The placeholders in the
+//! response are then replaced with the original text again.
+
+use crate::config::{PrivacyConfig, PrivacyDetector};
+use crate::error::{RephraserError, Result};
+use regex::Regex;
+
+/// Email addresses
+const EMAIL_PATTERN: &str = r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}";
+
+/// Phone numbers written with separators, e.g. "03-1234-5678",
+/// "+1 415 555 0100" or "(415) 555-0100"
+const PHONE_PATTERN: &str =
+    r"(?:\+\d{1,3}[\s-]?)?(?:\(\d{1,4}\)\s?|\d{1,4}[\s-])\d{2,4}[\s-]\d{3,4}\b";
+
+/// API keys and tokens with well-known prefixes (OpenAI, Anthropic, AWS,
+/// GitHub, Slack, Google)
+const API_KEY_PATTERN: &str = r"\b(?:sk-[A-Za-z0-9_-]{20,}|AKIA[0-9A-Z]{16}|gh[pousr]_[A-Za-z0-9]{36,}|xox[abprs]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35})";
+
+/// Detects sensitive text and swaps it for placeholders
+pub struct PrivacyFilter {
+    /// Placeholder label and pattern, tried in order
+    rules: Vec<(String, Regex)>,
+}
+
+/// Text with sensitive parts replaced, and what they were replaced with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Masked {
+    pub text: String,
+    /// Placeholder and original text
+    pub replacements: Vec<(String, String)>,
+}
+
+impl PrivacyDetector {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Email => "EMAIL",
+            Self::Phone => "PHONE",
+            Self::ApiKey => "SECRET",
+        }
+    }
+
+    fn pattern(self) -> &'static str {
+        match self {
+            Self::Email => EMAIL_PATTERN,
+            Self::Phone => PHONE_PATTERN,
+            Self::ApiKey => API_KEY_PATTERN,
+        }
+    }
+}
+
+impl PrivacyFilter {
+    /// Filter with the detectors and custom patterns of `config`
+    ///
+    /// # Errors
+    /// * If a custom pattern is not a valid regular expression
+    pub fn from_config(config: &PrivacyConfig) -> Result<Self> {
+        let mut rules = Vec::new();
+        for detector in &config.detect {
+            let re = Regex::new(detector.pattern()).expect("built-in patterns are valid");
+            rules.push((detector.label().to_string(), re));
+        }
+        for pattern in &config.patterns {
+            let re = Regex::new(pattern).map_err(|e| {
+                RephraserError::Config(format!("Invalid privacy pattern '{}': {}", pattern, e))
+            })?;
+            rules.push(("REDACTED".to_string(), re));
+        }
+        Ok(Self { rules })
+    }
+
+    /// Replace every match with a placeholder
+    ///
+    /// The same text always gets the same placeholder, so the model can
+    /// still tell that two mentions refer to one thing.
+    pub fn mask(&self, text: &str) -> Masked {
+        let mut masked = Masked {
+            text: text.to_string(),
+            replacements: Vec::new(),
+        };
+
+        for (label, re) in &self.rules {
+            let prefix = format!("[{}_", label);
+            let replaced = re.replace_all(&masked.text, |caps: &regex::Captures| {
+                let original = &caps[0];
+                if let Some((placeholder, _)) =
+                    masked.replacements.iter().find(|(_, o)| o == original)
+                {
+                    return placeholder.clone();
+                }
+                // Custom patterns share a label, so count across rules
+                let count = masked
+                    .replacements
+                    .iter()
+                    .filter(|(p, _)| p.starts_with(&prefix))
+                    .count();
+                let placeholder = format!("{}{}]", prefix, count + 1);
+                masked
+                    .replacements
+                    .push((placeholder.clone(), original.to_string()));
+                placeholder
+            });
+            masked.text = replaced.into_owned();
+        }
+
+        masked
+    }
+}
+
+impl Masked {
+    /// Put the original text back in place of the placeholders in `response`
+    pub fn unmask(&self, response: &str) -> String {
+        self.replacements
+            .iter()
+            .fold(response.to_string(), |text, (placeholder, original)| {
+                text.replace(placeholder, original)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(patterns: &[&str]) -> PrivacyFilter {
+        PrivacyFilter::from_config(&PrivacyConfig {
+            enabled: true,
+            patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            ..PrivacyConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_mask_and_unmask() {
+        let text = "Mail bob@example.com or call 03-1234-5678; bob@example.com has key sk-abcdefghijklmnopqrstuvwx";
+        let masked = filter(&[]).mask(text);
+
+        assert_eq!(
+            masked.text,
+            "Mail [EMAIL_1] or call [PHONE_1]; [EMAIL_1] has key [SECRET_1]"
+        );
+        assert_eq!(masked.replacements.len(), 3);
+        assert_eq!(
+            masked.unmask("Contact [EMAIL_1] at [PHONE_1]"),
+            "Contact bob@example.com at 03-1234-5678"
+        );
+    }
+
+    #[test]
+    fn test_phone_pattern() {
+        let masked = filter(&[]).mask("+1 415 555 0100, (415) 555-0100, 2024-06-01, 12:30");
+        assert_eq!(masked.text, "[PHONE_1], [PHONE_2], 2024-06-01, 12:30");
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let masked =
+            filter(&[r"ACME-\d+", r"Project \w+"]).mask("Ticket ACME-42 of Project Falcon");
+        assert_eq!(masked.text, "Ticket [REDACTED_1] of [REDACTED_2]");
+
+        let invalid = PrivacyFilter::from_config(&PrivacyConfig {
+            patterns: vec!["(".to_string()],
+            ..PrivacyConfig::default()
+        });
+        assert!(invalid.is_err());
+    }
+}
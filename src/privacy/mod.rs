@@ -0,0 +1,5 @@
+//! Masking of sensitive text before it is sent to a provider
+
+pub mod filter;
+
+pub use filter::{Masked, PrivacyFilter};
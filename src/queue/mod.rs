@@ -0,0 +1,8 @@
+//! Offline queue of rephrase jobs
+//!
+//! Jobs that fail because the network or provider is unavailable are saved
+//! and run later by `rephraser queue run`, or periodically by the daemon.
+
+pub mod store;
+
+pub use store::{QueueStore, QueuedJob};
@@ -0,0 +1,148 @@
+//! Directory-backed job queue
+//!
+//! Each job is a JSON file, so jobs queued by concurrent invocations never
+//! conflict.
+
+use crate::error::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A rephrase job waiting to be run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub action: String,
+    /// Input as given, before any pre-hook
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    pub queued_at: DateTime<Local>,
+}
+
+/// Queue of jobs kept in a directory
+pub struct QueueStore {
+    dir: PathBuf,
+}
+
+impl QueueStore {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    /// Add a job to the queue
+    ///
+    /// # Errors
+    /// * If the job could not be written
+    pub fn push(&self, job: &QueuedJob) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let stem = format!(
+            "{}-{}",
+            job.queued_at.format("%Y%m%d%H%M%S%3f"),
+            std::process::id()
+        );
+        let path = self.dir.join(format!("{}.json", stem));
+        fs::write(&path, serde_json::to_string_pretty(job)?)?;
+
+        #[cfg(unix)]
+        {
+            // Jobs contain the text being transformed
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        }
+        Ok(path)
+    }
+
+    /// Queued jobs, oldest first, with the files they are stored in
+    ///
+    /// Files that cannot be read are skipped.
+    ///
+    /// # Errors
+    /// * If the queue directory could not be read
+    pub fn jobs(&self) -> Result<Vec<(PathBuf, QueuedJob)>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut paths: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        // File names start with the time the job was queued
+        paths.sort();
+
+        Ok(paths
+            .into_iter()
+            .filter_map(|path| {
+                let job = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|content| serde_json::from_str(&content).ok())?;
+                Some((path, job))
+            })
+            .collect())
+    }
+
+    /// Remove a job once it has been run
+    ///
+    /// # Errors
+    /// * If the file could not be removed
+    pub fn remove(&self, path: &Path) -> Result<()> {
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Remove every queued job, returning how many there were
+    ///
+    /// # Errors
+    /// * If a file could not be removed
+    pub fn clear(&self) -> Result<usize> {
+        let jobs = self.jobs()?;
+        for (path, _) in &jobs {
+            self.remove(path)?;
+        }
+        Ok(jobs.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_list_in_order() {
+        let dir = std::env::temp_dir().join(format!("rephraser-queue-{}", std::process::id()));
+        let store = QueueStore::new(&dir);
+        assert!(store.jobs().unwrap().is_empty());
+
+        let job = |action: &str, queued_at| QueuedJob {
+            action: action.to_string(),
+            text: "こんにちは".to_string(),
+            provider: None,
+            model: Some("gpt-4o".to_string()),
+            temperature: None,
+            queued_at,
+        };
+        let now = Local::now();
+        store.push(&job("second", now)).unwrap();
+        store
+            .push(&job("first", now - chrono::Duration::seconds(5)))
+            .unwrap();
+
+        let jobs = store.jobs().unwrap();
+        let actions: Vec<&str> = jobs.iter().map(|(_, job)| job.action.as_str()).collect();
+        assert_eq!(actions, ["first", "second"]);
+        assert_eq!(jobs[1].1, job("second", now));
+
+        store.remove(&jobs[0].0).unwrap();
+        assert_eq!(store.clear().unwrap(), 1);
+        assert!(store.jobs().unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,248 @@
+//! Cron expressions
+//!
+//! Supports the five standard fields (minute, hour, day of month, month,
+//! day of week) with `*`, lists, ranges, steps and English month and
+//! weekday names, plus the `@hourly`, `@daily`, `@weekly`, `@monthly` and
+//! `@yearly` shorthands.
+
+use crate::error::{RephraserError, Result};
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+
+/// How far ahead `next_after` looks before giving up
+const SEARCH_LIMIT_DAYS: i64 = 366 * 5;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed cron expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    /// Bit 0 is Sunday
+    weekdays: u64,
+    /// Whether the day-of-month and day-of-week fields are both restricted,
+    /// in which case a day matching either one matches
+    either_day: bool,
+}
+
+impl Cron {
+    /// Parse a cron expression
+    ///
+    /// # Errors
+    /// * If the expression is malformed
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid(expression, "expected 5 fields"));
+        };
+
+        let weekdays = parse_field(weekday, 0, 7, &WEEKDAY_NAMES, 0)
+            .map_err(|reason| invalid(expression, &reason))?;
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[], 0).map_err(|r| invalid(expression, &r))?,
+            hours: parse_field(hour, 0, 23, &[], 0).map_err(|r| invalid(expression, &r))?,
+            days: parse_field(day, 1, 31, &[], 1).map_err(|r| invalid(expression, &r))?,
+            months: parse_field(month, 1, 12, &MONTH_NAMES, 1)
+                .map_err(|r| invalid(expression, &r))?,
+            // 7 is another name for Sunday
+            weekdays: (weekdays | (weekdays >> 7)) & 0x7f,
+            either_day: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    /// Whether the expression matches the minute of `time`
+    pub fn matches(&self, time: &NaiveDateTime) -> bool {
+        let bit = |set: u64, value: u32| set & (1 << value) != 0;
+        let day = bit(self.days, time.day());
+        let weekday = bit(self.weekdays, time.weekday().num_days_from_sunday());
+        let day_matches = if self.either_day {
+            day || weekday
+        } else {
+            day && weekday
+        };
+
+        day_matches
+            && bit(self.minutes, time.minute())
+            && bit(self.hours, time.hour())
+            && bit(self.months, time.month())
+    }
+
+    /// The first matching minute after `time`
+    ///
+    /// Returns `None` if nothing matches within the next few years (e.g.,
+    /// "0 0 31 2 *").
+    pub fn next_after(&self, time: &NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = start + Duration::days(SEARCH_LIMIT_DAYS);
+        let mut candidate = start;
+        while candidate < limit {
+            if self.matches(&candidate) {
+                return Some(candidate);
+            }
+            // Skip whole days and hours that cannot match
+            candidate = if !self.matches_day(&candidate) {
+                candidate.date().succ_opt()?.and_hms_opt(0, 0, 0)?
+            } else if self.hours & (1 << candidate.hour()) == 0 {
+                candidate.with_minute(0)? + Duration::hours(1)
+            } else {
+                candidate + Duration::minutes(1)
+            };
+        }
+        None
+    }
+
+    fn matches_day(&self, time: &NaiveDateTime) -> bool {
+        let midnight = time.date().and_hms_opt(0, 0, 0).unwrap_or(*time);
+        Self {
+            minutes: u64::MAX,
+            hours: u64::MAX,
+            ..self.clone()
+        }
+        .matches(&midnight)
+    }
+}
+
+/// Parse one field into a bit set of the values it allows
+///
+/// `names` are accepted in place of numbers, the first name standing for
+/// `first_name`
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    first_name: u32,
+) -> std::result::Result<u64, String> {
+    let value = |text: &str| -> std::result::Result<u32, String> {
+        let lower = text.to_ascii_lowercase();
+        let value = match names.iter().position(|name| *name == lower) {
+            Some(index) => index as u32 + first_name,
+            None => text
+                .parse()
+                .map_err(|_| format!("'{}' is not a number", text))?,
+        };
+        if !(min..=max).contains(&value) {
+            return Err(format!("{} is not between {} and {}", value, min, max));
+        }
+        Ok(value)
+    };
+
+    let mut set = 0;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("invalid step in '{}'", item))?;
+                (range, step)
+            }
+            None => (item, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // "5/15" means from 5 to the end
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let single = value(range)?;
+                    (single, single)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("invalid range '{}'", range));
+        }
+        for v in (start..=end).step_by(step as usize) {
+            set |= 1 << v;
+        }
+    }
+    Ok(set)
+}
+
+fn invalid(expression: &str, reason: &str) -> RephraserError {
+    RephraserError::Config(format!(
+        "Invalid cron expression '{}': {}",
+        expression, reason
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_weekdays_at_six() {
+        let cron = Cron::parse("0 18 * * mon-fri").unwrap();
+        // 2026-10-16 is a Friday
+        assert!(cron.matches(&at(2026, 10, 16, 18, 0)));
+        assert!(!cron.matches(&at(2026, 10, 16, 18, 1)));
+        assert!(!cron.matches(&at(2026, 10, 17, 18, 0)));
+        assert_eq!(
+            cron.next_after(&at(2026, 10, 16, 18, 0)),
+            Some(at(2026, 10, 19, 18, 0))
+        );
+    }
+
+    #[test]
+    fn test_steps_lists_and_shorthands() {
+        let cron = Cron::parse("*/15 9,17 * * *").unwrap();
+        assert!(cron.matches(&at(2026, 1, 1, 17, 45)));
+        assert!(!cron.matches(&at(2026, 1, 1, 17, 50)));
+
+        // Either day field matches when both are restricted
+        let cron = Cron::parse("0 0 1 * sun").unwrap();
+        assert!(cron.matches(&at(2026, 10, 1, 0, 0)));
+        assert!(cron.matches(&at(2026, 10, 18, 0, 0)));
+        assert!(!cron.matches(&at(2026, 10, 19, 0, 0)));
+
+        assert_eq!(
+            Cron::parse("@weekly").unwrap(),
+            Cron::parse("0 0 * * 7").unwrap()
+        );
+        assert_eq!(
+            Cron::parse("0 0 31 2 *")
+                .unwrap()
+                .next_after(&at(2026, 1, 1, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        for expression in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* * * foo *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(Cron::parse(expression).is_err(), "{}", expression);
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! Recurring jobs run by the daemon
+//!
+//! Each `[[schedules]]` entry applies an action to a file or fixed text on a
+//! cron schedule and delivers the result through an output method.
+
+pub mod cron;
+
+pub use cron::Cron;
@@ -0,0 +1,259 @@
+//! Passphrase-encrypted secrets file
+//!
+//! The file holds a JSON object of secret names and values, encrypted with
+//! ChaCha20-Poly1305 under a key derived from the passphrase with
+//! PBKDF2-HMAC-SHA256. The passphrase is read from an environment variable so
+//! unattended runs can unlock the file.
+
+use crate::config::manager::expand_home;
+use crate::config::SecretsConfig;
+use crate::error::{RephraserError, Result};
+use crate::secrets::SecretStore;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::digest::{digest, SHA256};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::Write;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Format version written to new files
+const FORMAT_VERSION: u32 = 1;
+
+/// PBKDF2 iterations for new files (fewer in tests, which would otherwise
+/// take seconds in debug builds)
+const KDF_ITERATIONS: u32 = if cfg!(test) { 1_000 } else { 600_000 };
+
+const SALT_LEN: usize = 16;
+
+const KEY_LEN: usize = 32;
+
+/// What a key is derived from: the salt, the iterations and a digest of the
+/// passphrase
+type KeyInputs = (Vec<u8>, u32, Vec<u8>);
+
+/// Layout of the file on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct SealedFile {
+    version: u32,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Secrets kept in an encrypted file
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    passphrase_env: String,
+}
+
+impl EncryptedFileStore {
+    /// Store at `path`, unlocked with the passphrase in `passphrase_env`
+    pub fn new(path: &Path, passphrase_env: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            passphrase_env: passphrase_env.into(),
+        }
+    }
+
+    /// Store configured under `[secrets]`
+    pub fn from_config(config: &SecretsConfig) -> Self {
+        Self::new(&expand_home(&config.file), &config.passphrase_env)
+    }
+
+    /// Add or replace a secret, creating the file if needed
+    pub fn set(&self, name: &str, value: &str) -> Result<()> {
+        let passphrase = self.passphrase()?;
+        let mut secrets = if self.path.exists() {
+            self.read(&passphrase)?
+        } else {
+            BTreeMap::new()
+        };
+        secrets.insert(name.to_string(), value.to_string());
+        self.write(&passphrase, &secrets)
+    }
+
+    fn passphrase(&self) -> Result<String> {
+        std::env::var(&self.passphrase_env).map_err(|_| {
+            RephraserError::Config(format!(
+                "Set the passphrase of {} in the environment variable '{}'",
+                self.path.display(),
+                self.passphrase_env
+            ))
+        })
+    }
+
+    fn read(&self, passphrase: &str) -> Result<BTreeMap<String, String>> {
+        let content = fs::read_to_string(&self.path).map_err(|e| {
+            RephraserError::Config(format!("Failed to read {}: {}", self.path.display(), e))
+        })?;
+        let sealed: SealedFile = serde_json::from_str(&content)?;
+        if sealed.version != FORMAT_VERSION {
+            return Err(RephraserError::Config(format!(
+                "Unsupported secrets file version {} in {}",
+                sealed.version,
+                self.path.display()
+            )));
+        }
+
+        let corrupted = || {
+            RephraserError::Config(format!(
+                "Failed to decrypt {}: wrong passphrase or corrupted file",
+                self.path.display()
+            ))
+        };
+        let decode = |field: &str| BASE64.decode(field).map_err(|_| corrupted());
+        let salt = decode(&sealed.salt)?;
+        let nonce =
+            Nonce::try_assume_unique_for_key(&decode(&sealed.nonce)?).map_err(|_| corrupted())?;
+        let mut data = decode(&sealed.ciphertext)?;
+
+        let key = derive_key(passphrase, &salt, sealed.iterations)?;
+        let plaintext = key
+            .open_in_place(nonce, Aad::empty(), &mut data)
+            .map_err(|_| corrupted())?;
+        Ok(serde_json::from_slice(plaintext)?)
+    }
+
+    fn write(&self, passphrase: &str, secrets: &BTreeMap<String, String>) -> Result<()> {
+        let rng = SystemRandom::new();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rng.fill(&mut salt)
+            .and_then(|_| rng.fill(&mut nonce))
+            .map_err(|_| RephraserError::Other("Failed to generate random bytes".to_string()))?;
+
+        let key = derive_key(passphrase, &salt, KDF_ITERATIONS)?;
+        let mut data = serde_json::to_vec(secrets)?;
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+            .map_err(|_| RephraserError::Other("Failed to encrypt the secrets".to_string()))?;
+
+        let sealed = SealedFile {
+            version: FORMAT_VERSION,
+            iterations: KDF_ITERATIONS,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(&data),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write then rename, so a failed write never loses existing secrets
+        let partial = self
+            .path
+            .with_extension(format!("partial.{}", std::process::id()));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // Readable by the owner only from the start, not just once written
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&partial)?
+            .write_all(serde_json::to_string_pretty(&sealed)?.as_bytes())?;
+        fs::rename(&partial, &self.path)?;
+        Ok(())
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn get(&self, name: &str) -> Result<String> {
+        let mut secrets = self.read(&self.passphrase()?)?;
+        secrets.remove(name).ok_or_else(|| {
+            RephraserError::Config(format!(
+                "Secret '{}' not found in {} (add it with `rephraser secrets set {}`)",
+                name,
+                self.path.display(),
+                name
+            ))
+        })
+    }
+}
+
+/// Encryption key for a passphrase and salt
+///
+/// Deriving a key takes hundreds of thousands of iterations, so keys are
+/// kept for the rest of the process and each secret looked up after the
+/// first is decrypted without deriving again.
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey> {
+    static KEYS: OnceLock<Mutex<HashMap<KeyInputs, [u8; KEY_LEN]>>> = OnceLock::new();
+
+    let inputs = (
+        salt.to_vec(),
+        iterations,
+        digest(&SHA256, passphrase.as_bytes()).as_ref().to_vec(),
+    );
+    let keys = KEYS.get_or_init(Default::default);
+    let cached = keys
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&inputs)
+        .copied();
+    let key = match cached {
+        Some(key) => key,
+        None => {
+            let rounds = NonZeroU32::new(iterations)
+                .ok_or_else(|| RephraserError::Config("Invalid secrets file".to_string()))?;
+            let mut key = [0u8; KEY_LEN];
+            pbkdf2::derive(
+                pbkdf2::PBKDF2_HMAC_SHA256,
+                rounds,
+                salt,
+                passphrase.as_bytes(),
+                &mut key,
+            );
+            keys.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(inputs, key);
+            key
+        }
+    };
+
+    let key = UnboundKey::new(&CHACHA20_POLY1305, &key).expect("key has the right length");
+    Ok(LessSafeKey::new(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let id = std::process::id();
+        let path = std::env::temp_dir().join(format!("rephraser-secrets-{}.enc", id));
+        let _ = fs::remove_file(&path);
+        let var = format!("REPHRASER_TEST_PASSPHRASE_{}", id);
+        std::env::set_var(&var, "correct horse");
+        let store = EncryptedFileStore::new(&path, &var);
+
+        store.set("openai", "sk-one").unwrap();
+        store.set("anthropic", "sk-two").unwrap();
+        assert_eq!(store.get("openai").unwrap(), "sk-one");
+        assert_eq!(store.get("anthropic").unwrap(), "sk-two");
+        assert!(store.get("missing").is_err());
+        assert!(!fs::read_to_string(&path).unwrap().contains("sk-one"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::env::set_var(&var, "wrong");
+        let err = store.get("openai").unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"));
+
+        std::env::remove_var(&var);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,7 @@
+//! Stores that provider API keys are read from
+
+pub mod encrypted;
+pub mod store;
+
+pub use encrypted::EncryptedFileStore;
+pub use store::{api_key, store_for, EnvStore, KeychainStore, OnePasswordStore, SecretStore};
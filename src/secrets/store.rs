@@ -0,0 +1,177 @@
+//! Secret stores and the lookup of provider API keys
+//!
+//! Each provider's key lives in the store selected under
+//! `[secrets.providers]`: an environment variable, the macOS Keychain, the
+//! encrypted secrets file or 1Password. Providers without an entry read the
+//! environment variable named by `api_key_env`.
+
+use crate::config::{LlmConfig, SecretBackend, SecretsConfig};
+use crate::error::{RephraserError, Result};
+use crate::secrets::EncryptedFileStore;
+use std::process::Command;
+
+/// A place secrets can be read from
+pub trait SecretStore: Send + Sync {
+    /// Read the secret called `name`
+    ///
+    /// # Errors
+    /// * If the secret does not exist or the store cannot be read
+    fn get(&self, name: &str) -> Result<String>;
+}
+
+/// Environment variables
+pub struct EnvStore;
+
+impl SecretStore for EnvStore {
+    fn get(&self, name: &str) -> Result<String> {
+        std::env::var(name).map_err(|_| {
+            RephraserError::Config(format!("Environment variable '{}' not found", name))
+        })
+    }
+}
+
+/// Generic passwords in the macOS Keychain, looked up by account
+pub struct KeychainStore {
+    service: String,
+}
+
+impl KeychainStore {
+    /// Keychain items of `service`
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+}
+
+impl SecretStore for KeychainStore {
+    fn get(&self, name: &str) -> Result<String> {
+        let output = Command::new("security")
+            .args([
+                "find-generic-password",
+                "-s",
+                &self.service,
+                "-a",
+                name,
+                "-w",
+            ])
+            .output()
+            .map_err(|e| RephraserError::Config(format!("Failed to run security: {}", e)))?;
+
+        if !output.status.success() {
+            return Err(RephraserError::Config(format!(
+                "Keychain item '{}' of service '{}' not found (add it with `security \
+                 add-generic-password -s {} -a {} -w`)",
+                name, self.service, self.service, name
+            )));
+        }
+        command_output(output.stdout)
+    }
+}
+
+/// Items read with the 1Password CLI (`op`), named by `op://` references
+pub struct OnePasswordStore;
+
+impl SecretStore for OnePasswordStore {
+    fn get(&self, name: &str) -> Result<String> {
+        let output = Command::new("op")
+            .args(["read", "--no-newline", name])
+            .output()
+            .map_err(|e| {
+                RephraserError::Config(format!("Failed to run the 1Password CLI (op): {}", e))
+            })?;
+
+        if !output.status.success() {
+            return Err(RephraserError::Config(format!(
+                "Failed to read '{}' from 1Password: {}",
+                name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        command_output(output.stdout)
+    }
+}
+
+/// Secret printed by a command, without the trailing newline
+fn command_output(stdout: Vec<u8>) -> Result<String> {
+    let secret = String::from_utf8(stdout)
+        .map_err(|_| RephraserError::Config("Secret is not valid UTF-8".to_string()))?;
+    Ok(secret.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Store of the given kind, set up from `config`
+pub fn store_for(config: &SecretsConfig, backend: SecretBackend) -> Box<dyn SecretStore> {
+    match backend {
+        SecretBackend::Env => Box::new(EnvStore),
+        SecretBackend::Keychain => Box::new(KeychainStore::new(&config.keychain_service)),
+        SecretBackend::EncryptedFile => Box::new(EncryptedFileStore::from_config(config)),
+        SecretBackend::OnePassword => Box::new(OnePasswordStore),
+    }
+}
+
+/// API key of the provider `llm` is configured for
+///
+/// # Errors
+/// * If the key cannot be read from its store
+/// * If a 1Password entry has no `name`
+pub fn api_key(config: &SecretsConfig, llm: &LlmConfig) -> Result<String> {
+    let Some(secret) = config.providers.get(&llm.provider) else {
+        return EnvStore.get(&llm.api_key_env);
+    };
+
+    let name = match (&secret.name, secret.store) {
+        (Some(name), _) => name.clone(),
+        (None, SecretBackend::Env) => llm.api_key_env.clone(),
+        (None, SecretBackend::Keychain | SecretBackend::EncryptedFile) => llm.provider.clone(),
+        (None, SecretBackend::OnePassword) => {
+            return Err(RephraserError::Config(format!(
+                "[secrets.providers.{}] needs the op:// reference of the key as `name`",
+                llm.provider
+            )))
+        }
+    };
+    store_for(config, secret.store).get(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, SecretRef};
+
+    #[test]
+    fn test_api_key_from_env() {
+        let var = format!("REPHRASER_TEST_KEY_{}", std::process::id());
+        std::env::set_var(&var, "sk-test");
+
+        let mut config = Config::default();
+        config.llm.api_key_env = var.clone();
+        assert_eq!(api_key(&config.secrets, &config.llm).unwrap(), "sk-test");
+
+        // An env entry without a name also uses `api_key_env`
+        config.secrets.providers.insert(
+            config.llm.provider.clone(),
+            SecretRef {
+                store: SecretBackend::Env,
+                name: None,
+            },
+        );
+        assert_eq!(api_key(&config.secrets, &config.llm).unwrap(), "sk-test");
+
+        std::env::remove_var(&var);
+        assert!(api_key(&config.secrets, &config.llm).is_err());
+    }
+
+    #[test]
+    fn test_one_password_needs_reference() {
+        let mut config = Config::default();
+        config.secrets.providers.insert(
+            config.llm.provider.clone(),
+            SecretRef {
+                store: SecretBackend::OnePassword,
+                name: None,
+            },
+        );
+        let err = api_key(&config.secrets, &config.llm).unwrap_err();
+        assert!(err.to_string().contains("op://"));
+    }
+}
@@ -0,0 +1,134 @@
+//! Graceful shutdown of long-running modes
+//!
+//! The daemon, `serve` and the watch modes stop on SIGINT (Ctrl-C) or
+//! SIGTERM, which launchd sends when an agent is unloaded. They stop taking
+//! new work and give requests in flight up to [`DRAIN_TIMEOUT`] to finish,
+//! so that their results are delivered and their history and usage records
+//! written, before cleaning up after themselves.
+
+use crate::error::Result;
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// How long requests in flight get to finish once a signal arrives
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Listens for the signals that stop long-running modes
+///
+/// Signals are caught from creation on, so one arriving while a request is
+/// running is not lost.
+pub struct Signals {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    stopping: bool,
+}
+
+impl Signals {
+    /// Start catching SIGINT and SIGTERM
+    ///
+    /// # Errors
+    /// * If the signal handlers can't be installed
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            interrupt: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())?,
+            #[cfg(unix)]
+            terminate: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?,
+            stopping: false,
+        })
+    }
+
+    /// Wait for the next signal
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        tokio::select! {
+            _ = self.interrupt.recv() => info!("Interrupted, shutting down"),
+            _ = self.terminate.recv() => info!("Terminated, shutting down"),
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = tokio::signal::ctrl_c().await;
+            info!("Interrupted, shutting down");
+        }
+        self.stopping = true;
+    }
+
+    /// Whether a signal has arrived
+    pub fn stopping(&self) -> bool {
+        self.stopping
+    }
+
+    /// Run `work` to completion, unless a signal arrives meanwhile and it
+    /// then takes longer than [`DRAIN_TIMEOUT`]
+    ///
+    /// Returns `None` if the work was abandoned. Check [`Signals::stopping`]
+    /// afterwards to learn whether to stop.
+    pub async fn finish<T>(&mut self, work: impl Future<Output = T>) -> Option<T> {
+        tokio::pin!(work);
+        tokio::select! {
+            output = &mut work => return Some(output),
+            _ = self.recv() => {}
+        }
+
+        let output = tokio::time::timeout(DRAIN_TIMEOUT, work).await.ok();
+        if output.is_none() {
+            warn!(
+                "Abandoned a request still running after {:?}",
+                DRAIN_TIMEOUT
+            );
+        }
+        output
+    }
+}
+
+/// Wait for the tasks in flight to finish, aborting any still running after
+/// [`DRAIN_TIMEOUT`]
+pub async fn drain(tasks: &mut JoinSet<()>) {
+    if tasks.is_empty() {
+        return;
+    }
+
+    info!("Waiting for {} requests in flight", tasks.len());
+    let drained = tokio::time::timeout(DRAIN_TIMEOUT, async {
+        while tasks.join_next().await.is_some() {}
+    })
+    .await;
+    if drained.is_err() {
+        warn!(
+            "Abandoning {} requests still running after {:?}",
+            tasks.len(),
+            DRAIN_TIMEOUT
+        );
+        tasks.abort_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain() {
+        let mut tasks = JoinSet::new();
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        tasks.spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let _ = sender.send(());
+        });
+        drain(&mut tasks).await;
+        assert!(receiver.await.is_ok());
+        assert!(tasks.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_finish_without_signal() {
+        let mut signals = Signals::new().unwrap();
+        assert_eq!(signals.finish(async { 1 }).await, Some(1));
+        assert!(!signals.stopping());
+    }
+}
@@ -0,0 +1,208 @@
+//! Checks for newer releases
+//!
+//! The latest release is looked up at most once per check interval and
+//! remembered in a state file, so most runs only read that file.
+
+use crate::config::UpdatesConfig;
+use crate::error::{RephraserError, Result};
+use crate::t;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::debug;
+
+/// Version of this build
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How long the result of a check is trusted
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Time allowed for looking up the latest release
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the last check found
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UpdateState {
+    /// When the last check ran, in seconds since the Unix epoch
+    pub checked_at: u64,
+
+    /// Latest released version, without a leading "v"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest: Option<String>,
+}
+
+impl UpdateState {
+    /// The latest version, if it is newer than this build
+    pub fn newer_version(&self) -> Option<&str> {
+        self.latest
+            .as_deref()
+            .filter(|latest| compare_versions(latest, CURRENT_VERSION) == Ordering::Greater)
+    }
+}
+
+/// Release fields used from the releases API
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+/// Update checker
+pub struct UpdateChecker {
+    url: String,
+    state_path: PathBuf,
+}
+
+impl UpdateChecker {
+    /// Create a checker that remembers its results in `state_path`
+    pub fn new(config: &UpdatesConfig, state_path: PathBuf) -> Self {
+        Self {
+            url: config.url.clone(),
+            state_path,
+        }
+    }
+
+    /// What the last check found (empty if there has been none)
+    pub fn state(&self) -> UpdateState {
+        fs::read_to_string(&self.state_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Whether the last check is older than the check interval
+    pub fn is_due(&self) -> bool {
+        let checked_at = UNIX_EPOCH + Duration::from_secs(self.state().checked_at);
+        SystemTime::now()
+            .duration_since(checked_at)
+            .map_or(true, |elapsed| elapsed >= CHECK_INTERVAL)
+    }
+
+    /// Look up the latest release and remember it
+    ///
+    /// # Errors
+    /// * If the release could not be fetched
+    pub async fn check(&self) -> Result<UpdateState> {
+        debug!("Checking for updates at {}", self.url);
+        // Record the attempt first so a failing check is not retried on
+        // every run
+        let mut state = UpdateState {
+            checked_at: now_secs(),
+            latest: self.state().latest,
+        };
+        let _ = self.write_state(&state);
+
+        state.latest = Some(self.fetch_latest().await?);
+        // A state write failure should not hide the result
+        let _ = self.write_state(&state);
+        Ok(state)
+    }
+
+    async fn fetch_latest(&self) -> Result<String> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            // Required by the GitHub API
+            .user_agent(format!("rephraser/{}", CURRENT_VERSION))
+            .build()?;
+        let response = client.get(&self.url).send().await?;
+        if !response.status().is_success() {
+            return Err(RephraserError::Other(format!(
+                "Update check failed with status {}",
+                response.status()
+            )));
+        }
+
+        let release: Release = response.json().await?;
+        Ok(release.tag_name.trim_start_matches('v').to_string())
+    }
+
+    fn write_state(&self, state: &UpdateState) -> Result<()> {
+        if let Some(parent) = self.state_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.state_path, serde_json::to_string(state)?)?;
+        Ok(())
+    }
+}
+
+/// One-line notice about a newer version
+pub fn notice(latest: &str) -> String {
+    t!(
+        "update-available",
+        latest = latest,
+        current = CURRENT_VERSION
+    )
+}
+
+/// Compare dotted version numbers, ignoring pre-release and build suffixes
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+    (0..a.len().max(b.len()))
+        .map(|i| {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            x.cmp(&y)
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.2.0", "0.1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("v1.10.0", "1.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0.0-beta.1", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("0.0.9", "0.1.0"), Ordering::Less);
+    }
+
+    #[tokio::test]
+    async fn test_check_remembers_latest() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/latest")
+            .with_body(r#"{"tag_name":"v99.0.0","name":"Rephraser 99"}"#)
+            .create_async()
+            .await;
+
+        let dir = std::env::temp_dir().join(format!("rephraser-update-{}", std::process::id()));
+        let checker = UpdateChecker::new(
+            &UpdatesConfig {
+                check: true,
+                url: format!("{}/latest", server.url()),
+            },
+            dir.join("update-check.json"),
+        );
+        assert!(checker.is_due());
+
+        let state = checker.check().await.unwrap();
+        mock.assert_async().await;
+        assert_eq!(state.newer_version(), Some("99.0.0"));
+        assert_eq!(checker.state(), state);
+        assert!(!checker.is_due());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
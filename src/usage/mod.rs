@@ -0,0 +1,5 @@
+//! Token usage, spend and performance tracking
+
+pub mod store;
+
+pub use store::{CostRow, Grouping, PerformanceRow, UsageRecord, UsageStore};
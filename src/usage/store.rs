@@ -0,0 +1,455 @@
+//! SQLite-backed usage store
+//!
+//! Records one row per LLM request (no text content) so that spend and
+//! performance can be reported later.
+
+use crate::error::{RephraserError, Result};
+use crate::history::store::migration;
+use crate::llm::TokenUsage;
+use chrono::{Duration as DateDuration, NaiveDate};
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A request to be recorded
+#[derive(Debug, Clone)]
+pub struct UsageRecord<'a> {
+    pub action: &'a str,
+    pub provider: &'a str,
+    pub model: &'a str,
+    pub usage: TokenUsage,
+    /// Estimated cost in USD, if the model's price is known
+    pub cost_usd: Option<f64>,
+    pub duration: Duration,
+    /// Error message for failed requests
+    pub error: Option<&'a str>,
+    /// Project or client the request is attributed to
+    pub tag: Option<&'a str>,
+}
+
+/// How cost report rows are grouped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    Day,
+    Model,
+    Provider,
+    Action,
+    Tag,
+}
+
+impl Grouping {
+    /// Column heading for the group key
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Day => "Day",
+            Self::Model => "Model",
+            Self::Provider => "Provider",
+            Self::Action => "Action",
+            Self::Tag => "Tag",
+        }
+    }
+
+    /// SQL expression for the group key
+    fn key_expression(self) -> &'static str {
+        match self {
+            Self::Day => "substr(created_at, 1, 10)",
+            Self::Model => "provider || '/' || model",
+            Self::Provider => "provider",
+            Self::Action => "action",
+            Self::Tag => "COALESCE(tag, '(untagged)')",
+        }
+    }
+}
+
+impl FromStr for Grouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Self::Day),
+            "model" => Ok(Self::Model),
+            "provider" => Ok(Self::Provider),
+            "action" => Ok(Self::Action),
+            "tag" => Ok(Self::Tag),
+            _ => Err(format!(
+                "unknown grouping '{}', expected day, model, provider, action or tag",
+                s
+            )),
+        }
+    }
+}
+
+/// One row of a cost report
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostRow {
+    pub key: String,
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// One row of a performance report
+///
+/// Latency percentiles only cover successful requests and are `None` when
+/// the group has none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerformanceRow {
+    pub key: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub p50_ms: Option<u64>,
+    pub p90_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+}
+
+impl PerformanceRow {
+    /// Fraction of requests that failed (0.0-1.0)
+    pub fn failure_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Usage database
+pub struct UsageStore {
+    conn: Connection,
+}
+
+impl UsageStore {
+    /// Open (and create if needed) the usage database at `path`
+    ///
+    /// # Errors
+    /// * If the parent directory cannot be created
+    /// * If the database cannot be opened or initialized
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut conn = Connection::open(path)?;
+        // Concurrent requests (e.g. `compare`) write from several connections
+        conn.busy_timeout(Duration::from_secs(2))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at TEXT NOT NULL,
+                action TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL,
+                completion_tokens INTEGER NOT NULL,
+                cost_usd REAL,
+                duration_ms INTEGER NOT NULL,
+                error TEXT
+            );",
+        )?;
+
+        Self::migrate(&mut conn)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Bring an existing database up to the current schema
+    ///
+    /// Steps are numbered, and the last one run is stored in
+    /// `PRAGMA user_version`.
+    fn migrate(conn: &mut Connection) -> Result<()> {
+        // Attribution of requests to projects or clients
+        migration(conn, 1, "ALTER TABLE usage ADD COLUMN tag TEXT;")?;
+
+        Ok(())
+    }
+
+    /// Record a request
+    pub fn record(&self, record: &UsageRecord) -> Result<()> {
+        let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        self.conn.execute(
+            "INSERT INTO usage
+                (created_at, action, provider, model, prompt_tokens, completion_tokens,
+                 cost_usd, duration_ms, error, tag)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                created_at,
+                record.action,
+                record.provider,
+                record.model,
+                record.usage.prompt_tokens as i64,
+                record.usage.completion_tokens as i64,
+                record.cost_usd,
+                record.duration.as_millis() as i64,
+                record.error,
+                record.tag,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Summarize token usage and spend, grouped by day, model, provider,
+    /// action or tag
+    ///
+    /// With `since`, only requests on or after that date are included; with
+    /// `tag`, only requests with that tag
+    pub fn cost_summary(
+        &self,
+        since: Option<NaiveDate>,
+        tag: Option<&str>,
+        grouping: Grouping,
+    ) -> Result<Vec<CostRow>> {
+        let since = since.map(|date| date.format("%Y-%m-%d").to_string());
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {key} AS key, COUNT(*), SUM(prompt_tokens), SUM(completion_tokens),
+                    SUM(COALESCE(cost_usd, 0))
+             FROM usage
+             WHERE (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR tag = ?2)
+             GROUP BY key
+             ORDER BY key",
+            key = grouping.key_expression()
+        ))?;
+
+        let rows = statement
+            .query_map(params![since, tag], |row| {
+                Ok(CostRow {
+                    key: row.get(0)?,
+                    requests: row.get::<_, i64>(1)? as u64,
+                    prompt_tokens: row.get::<_, i64>(2)? as u64,
+                    completion_tokens: row.get::<_, i64>(3)? as u64,
+                    cost_usd: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+    }
+
+    /// Report request counts, failure rates and latency percentiles, grouped
+    /// by day, model, provider or action
+    ///
+    /// With `since`, only requests on or after that date are included
+    pub fn performance_summary(
+        &self,
+        since: Option<NaiveDate>,
+        grouping: Grouping,
+    ) -> Result<Vec<PerformanceRow>> {
+        let since = since.map(|date| date.format("%Y-%m-%d").to_string());
+        let mut statement = self.conn.prepare(&format!(
+            "SELECT {key} AS key, duration_ms, error IS NOT NULL
+             FROM usage
+             WHERE ?1 IS NULL OR created_at >= ?1
+             ORDER BY key, duration_ms",
+            key = grouping.key_expression()
+        ))?;
+
+        let samples = statement
+            .query_map(params![since], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)? as u64,
+                    row.get::<_, bool>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // Rows arrive sorted by key, then duration, so each group's
+        // successful durations are already in ascending order
+        let mut rows: Vec<PerformanceRow> = Vec::new();
+        let mut durations: Vec<u64> = Vec::new();
+        for (key, duration_ms, failed) in samples {
+            if rows.last().map(|row| &row.key) != Some(&key) {
+                if let Some(row) = rows.last_mut() {
+                    fill_percentiles(row, &durations);
+                }
+                durations.clear();
+                rows.push(PerformanceRow {
+                    key,
+                    requests: 0,
+                    failures: 0,
+                    p50_ms: None,
+                    p90_ms: None,
+                    p99_ms: None,
+                });
+            }
+
+            let row = rows.last_mut().expect("a row was just pushed");
+            row.requests += 1;
+            if failed {
+                row.failures += 1;
+            } else {
+                durations.push(duration_ms);
+            }
+        }
+        if let Some(row) = rows.last_mut() {
+            fill_percentiles(row, &durations);
+        }
+
+        Ok(rows)
+    }
+}
+
+/// Set a row's latency percentiles from its sorted successful durations
+fn fill_percentiles(row: &mut PerformanceRow, sorted: &[u64]) {
+    row.p50_ms = percentile(sorted, 50);
+    row.p90_ms = percentile(sorted, 90);
+    row.p99_ms = percentile(sorted, 99);
+}
+
+/// Nearest-rank percentile of an ascending slice
+fn percentile(sorted: &[u64], pct: usize) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (pct * sorted.len()).div_ceil(100).max(1);
+    Some(sorted[rank - 1])
+}
+
+/// Parse a `--since` value: a date ("2024-06-01") or a number of days ("7d")
+pub fn parse_since(value: &str, today: NaiveDate) -> Result<NaiveDate> {
+    if let Some(days) = value.strip_suffix('d') {
+        let days: i64 = days
+            .parse()
+            .map_err(|_| RephraserError::Input(format!("Invalid number of days: {}", value)))?;
+        return Ok(today - DateDuration::days(days));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        RephraserError::Input(format!(
+            "Invalid date '{}', expected YYYY-MM-DD or a number of days like 7d",
+            value
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record<'a>(action: &'a str, model: &'a str, cost_usd: Option<f64>) -> UsageRecord<'a> {
+        UsageRecord {
+            action,
+            provider: "openai",
+            model,
+            usage: TokenUsage {
+                prompt_tokens: 100,
+                completion_tokens: 20,
+            },
+            cost_usd,
+            duration: Duration::from_millis(500),
+            error: None,
+            tag: None,
+        }
+    }
+
+    #[test]
+    fn test_cost_summary() {
+        let path =
+            std::env::temp_dir().join(format!("rephraser-usage-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = UsageStore::open(&path).unwrap();
+
+        store
+            .record(&record("polite", "gpt-4o-mini", Some(0.001)))
+            .unwrap();
+        store
+            .record(&record("polite", "gpt-4o", Some(0.01)))
+            .unwrap();
+        store.record(&record("summarize", "gpt-4o", None)).unwrap();
+
+        let by_action = store.cost_summary(None, None, Grouping::Action).unwrap();
+        assert_eq!(by_action.len(), 2);
+        assert_eq!(by_action[0].key, "polite");
+        assert_eq!(by_action[0].requests, 2);
+        assert_eq!(by_action[0].prompt_tokens, 200);
+        assert!((by_action[0].cost_usd - 0.011).abs() < 1e-9);
+
+        let by_model = store.cost_summary(None, None, Grouping::Model).unwrap();
+        assert_eq!(by_model[0].key, "openai/gpt-4o");
+
+        let mut tagged = record("polite", "gpt-4o", Some(0.02));
+        tagged.tag = Some("client-acme");
+        store.record(&tagged).unwrap();
+        let by_tag = store.cost_summary(None, None, Grouping::Tag).unwrap();
+        assert_eq!(by_tag.len(), 2);
+        assert_eq!(by_tag[0].key, "(untagged)");
+        assert_eq!(by_tag[0].requests, 3);
+        assert_eq!(by_tag[1].key, "client-acme");
+        let acme = store
+            .cost_summary(None, Some("client-acme"), Grouping::Model)
+            .unwrap();
+        assert_eq!(acme.len(), 1);
+        assert!((acme[0].cost_usd - 0.02).abs() < 1e-9);
+
+        let tomorrow = chrono::Local::now().date_naive() + DateDuration::days(1);
+        assert!(store
+            .cost_summary(Some(tomorrow), None, Grouping::Day)
+            .unwrap()
+            .is_empty());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_performance_summary() {
+        let path =
+            std::env::temp_dir().join(format!("rephraser-perf-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = UsageStore::open(&path).unwrap();
+
+        for ms in [300, 100, 200, 400] {
+            let mut request = record("polite", "gpt-4o-mini", None);
+            request.duration = Duration::from_millis(ms);
+            store.record(&request).unwrap();
+        }
+        let mut failed = record("polite", "gpt-4o-mini", None);
+        failed.duration = Duration::from_millis(5);
+        failed.error = Some("rate limited");
+        store.record(&failed).unwrap();
+        let mut only_failure = record("summarize", "gpt-4o", None);
+        only_failure.error = Some("timeout");
+        store.record(&only_failure).unwrap();
+
+        let rows = store.performance_summary(None, Grouping::Action).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].key, "polite");
+        assert_eq!(rows[0].requests, 5);
+        assert_eq!(rows[0].failures, 1);
+        assert!((rows[0].failure_rate() - 0.2).abs() < 1e-9);
+        assert_eq!(rows[0].p50_ms, Some(200));
+        assert_eq!(rows[0].p90_ms, Some(400));
+        assert_eq!(rows[1].p50_ms, None);
+
+        let by_provider = store.performance_summary(None, Grouping::Provider).unwrap();
+        assert_eq!(by_provider.len(), 1);
+        assert_eq!(by_provider[0].requests, 6);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[], 50), None);
+        assert_eq!(percentile(&[7], 99), Some(7));
+        let values: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&values, 50), Some(50));
+        assert_eq!(percentile(&values, 99), Some(99));
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 10).unwrap();
+        assert_eq!(
+            parse_since("7d", today).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 6, 3).unwrap()
+        );
+        assert_eq!(
+            parse_since("2024-01-31", today).unwrap(),
+            NaiveDate::from_ymd_opt(2024, 1, 31).unwrap()
+        );
+        assert!(parse_since("last week", today).is_err());
+    }
+}